@@ -0,0 +1,9 @@
+#include <stdio.h>
+
+#include "component.h"
+
+void exports____USERNAME_SNAKE_______COMPONENT_SNAKE_______COMPONENT_SNAKE____greet(component_string_t *name) {
+  printf("hello %.*s!\n", (int)name->len, (const char *)name->ptr);
+  // Args of exported functions are owned by the callee.
+  component_string_free(name);
+}
@@ -0,0 +1,3 @@
+module ___COMPONENT___
+
+go 1.24
@@ -0,0 +1,16 @@
+package main
+
+import (
+	"fmt"
+
+	component "___COMPONENT___/internal/___USERNAME___/___COMPONENT___/___COMPONENT___"
+)
+
+func init() {
+	component.Exports.Greet = func(name string) {
+		fmt.Printf("hello %s!\n", name)
+	}
+}
+
+// main is required by TinyGo, but is not called in a component.
+func main() {}
@@ -0,0 +1,6 @@
+from wit_world import exports
+
+
+class ___COMPONENT_PASCAL___(exports.___COMPONENT_PASCAL___):
+    def greet(self, name: str) -> None:
+        print(f"hello {name}!")
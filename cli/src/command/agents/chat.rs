@@ -0,0 +1,82 @@
+use crate::auth::Auth;
+use crate::tui::ops;
+use eyre::{OptionExt, bail};
+use std::io::Read;
+
+#[derive(Debug)]
+pub(super) struct ChatArgs {
+    /// The env name of the agent.
+    name: String,
+    /// The message, or none to read it from stdin.
+    message: Option<String>,
+}
+
+impl ChatArgs {
+    pub fn parse(args: impl Iterator<Item = String>) -> eyre::Result<Self> {
+        let mut name = None;
+        let mut words = Vec::new();
+        for arg in args {
+            match arg.as_str() {
+                "--help" | "-h" | "help" if name.is_none() => {
+                    print_help();
+                    std::process::exit(0);
+                }
+                _ if name.is_none() => name = Some(arg),
+                _ => words.push(arg),
+            }
+        }
+        let name = name.ok_or_eyre("missing agent name")?;
+        // A message of `-` is read from stdin, as is a missing one.
+        let message = match words.join(" ") {
+            m if m.is_empty() || m == "-" => None,
+            m => Some(m),
+        };
+        Ok(Self { name, message })
+    }
+
+    pub async fn execute(&self) -> eyre::Result<()> {
+        let Some(data) = ops::inspect_environment_sync(&self.name) else {
+            bail!(
+                "agent {} not found locally (pull it with: asterai env pull {})",
+                self.name,
+                self.name
+            );
+        };
+        let namespace = Auth::read_user_or_fallback_namespace();
+        let agent = ops::load_agent(&self.name, &namespace, &data)?;
+        let message = match &self.message {
+            Some(message) => message.clone(),
+            None => {
+                let mut message = String::new();
+                std::io::stdin().read_to_string(&mut message)?;
+                message.trim().to_owned()
+            }
+        };
+        if message.is_empty() {
+            bail!("missing message");
+        }
+        if let Some(reply) = ops::call_converse(&message, &agent).await? {
+            println!("{reply}");
+        }
+        Ok(())
+    }
+}
+
+fn print_help() {
+    println!(
+        r#"Send one message to an agent and print its reply, without the TUI.
+
+The agent remembers the conversation, as it does in the agents TUI.
+
+Usage: asterai agents chat <name> [message]
+
+The message is read from stdin if it is missing or `-`.
+The agent's approval policies apply, and tool calls needing
+approval are denied, as there is no one to approve them.
+
+Examples:
+  asterai agents chat asterbot "Summarize my notes from today"
+  echo "What did we talk about yesterday?" | asterai agents chat asterbot
+"#
+    );
+}
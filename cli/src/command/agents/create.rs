@@ -0,0 +1,267 @@
+use crate::tui::app::{
+    AgentSpec, CUSTOM_KEY_VAR, CUSTOM_MODEL_PREFIX, CUSTOM_URL_VAR, DEFAULT_CUSTOM_URL,
+    DEFAULT_TOOLS, PROVIDERS, Provider, default_user_name, provider_by_id, sanitize_bot_name,
+};
+use crate::tui::ops;
+use eyre::{Context, OptionExt, bail, eyre};
+use serde::Deserialize;
+use std::fs;
+use std::path::PathBuf;
+
+/// The ID of the custom provider, an OpenAI-compatible endpoint.
+const CUSTOM_PROVIDER_ID: &str = "custom";
+
+#[derive(Debug)]
+pub(super) struct CreateArgs {
+    /// The agent file to read the settings from, which flags override.
+    from_file: Option<PathBuf>,
+    settings: AgentSettings,
+    /// The API key, or none to read it from the provider's env var.
+    api_key: Option<String>,
+}
+
+/// The settings of an agent, from flags or from an agent TOML file.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct AgentSettings {
+    name: Option<String>,
+    user_name: Option<String>,
+    provider: Option<String>,
+    model: Option<String>,
+    /// The base URL of the custom provider.
+    base_url: Option<String>,
+    /// The tools, or none for the default ones.
+    tools: Option<Vec<String>>,
+    #[serde(default)]
+    allowed_dirs: Vec<String>,
+}
+
+impl CreateArgs {
+    pub fn parse(args: impl Iterator<Item = String>) -> eyre::Result<Self> {
+        let mut from_file = None;
+        let mut settings = AgentSettings::default();
+        let mut api_key = None;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = |flag: &str| args.next().ok_or_eyre(format!("{flag} requires a value"));
+            match arg.as_str() {
+                "--from-file" | "-f" => from_file = Some(PathBuf::from(value(&arg)?)),
+                "--user-name" => settings.user_name = Some(value(&arg)?),
+                "--provider" | "-p" => settings.provider = Some(value(&arg)?),
+                "--model" | "-m" => settings.model = Some(value(&arg)?),
+                "--base-url" => settings.base_url = Some(value(&arg)?),
+                "--api-key" => api_key = Some(value(&arg)?),
+                "--tool" | "-t" => settings.tools.get_or_insert_default().push(value(&arg)?),
+                "--allow-dir" | "-d" => settings.allowed_dirs.push(value(&arg)?),
+                "--help" | "-h" | "help" => {
+                    print_help();
+                    std::process::exit(0);
+                }
+                other if other.starts_with('-') => bail!("unknown flag: {}", other),
+                _ if settings.name.is_none() => settings.name = Some(arg),
+                other => bail!("unexpected argument: {}", other),
+            }
+        }
+        Ok(Self {
+            from_file,
+            settings,
+            api_key,
+        })
+    }
+
+    pub async fn execute(&self) -> eyre::Result<()> {
+        let spec = self.spec().await?;
+        let env_name = sanitize_bot_name(&spec.bot_name);
+        if ops::inspect_environment_sync(&env_name).is_some() {
+            bail!("agent {env_name} already exists");
+        }
+        let agent = ops::provision_agent(&spec, |current, total, message| {
+            println!("[{current}/{total}] {message}");
+            Ok(())
+        })
+        .await?;
+        println!(
+            "created agent {} ({}:{})\n\
+             Chat with it with: asterai agents chat {} \"hello\"",
+            agent.bot_name, agent.namespace, agent.env_name, agent.env_name
+        );
+        Ok(())
+    }
+
+    /// Resolves the settings of the file and flags into what to provision.
+    async fn spec(&self) -> eyre::Result<AgentSpec> {
+        let settings = match &self.from_file {
+            Some(path) => read_agent_file(path)?.overridden_by(&self.settings),
+            None => self.settings.clone(),
+        };
+        let bot_name = settings.name.clone().ok_or_eyre("missing agent name")?;
+        let provider_id = match (&settings.provider, &settings.model) {
+            (Some(provider), _) => provider.clone(),
+            (None, Some(model)) if model.contains('/') => {
+                model.split('/').next().unwrap_or_default().to_owned()
+            }
+            (None, _) if settings.base_url.is_some() => CUSTOM_PROVIDER_ID.to_owned(),
+            (None, _) => bail!("missing --provider ({})", provider_ids().join(", ")),
+        };
+        let (provider, model, provider_vars) = match provider_id == CUSTOM_PROVIDER_ID {
+            true => {
+                let (model, vars) = self.custom_provider(&settings).await?;
+                (CUSTOM_PROVIDER_ID.to_owned(), model, vars)
+            }
+            false => {
+                let provider = provider_by_id(&provider_id).ok_or_else(|| {
+                    eyre!(
+                        "unknown provider {provider_id} (expected one of: {})",
+                        provider_ids().join(", ")
+                    )
+                })?;
+                let (model, vars) = self.listed_provider(provider, &provider_id, &settings)?;
+                (provider.0.to_owned(), model, vars)
+            }
+        };
+        let allowed_dirs = settings
+            .allowed_dirs
+            .iter()
+            .map(|dir| {
+                fs::canonicalize(dir)
+                    .map(|path| path.to_string_lossy().to_string())
+                    .wrap_err_with(|| format!("invalid allowed dir {dir}"))
+            })
+            .collect::<eyre::Result<Vec<String>>>()?;
+        Ok(AgentSpec {
+            bot_name,
+            user_name: settings.user_name.unwrap_or_else(default_user_name),
+            provider,
+            model,
+            provider_vars,
+            tools: settings
+                .tools
+                .unwrap_or_else(|| DEFAULT_TOOLS.iter().map(|t| t.to_string()).collect()),
+            allowed_dirs,
+        })
+    }
+
+    /// Resolves the model and vars of a provider of `PROVIDERS`.
+    fn listed_provider(
+        &self,
+        provider: &Provider,
+        provider_id: &str,
+        settings: &AgentSettings,
+    ) -> eyre::Result<(String, Vec<(String, String)>)> {
+        let (_, key_var, models) = provider;
+        // The first model is the recommended one.
+        let model = match &settings.model {
+            Some(model) if model.contains('/') => model.clone(),
+            Some(model) => format!("{provider_id}/{model}"),
+            None => models[0].0.to_owned(),
+        };
+        let api_key = self
+            .api_key
+            .clone()
+            .or_else(|| std::env::var(key_var).ok())
+            .ok_or_else(|| eyre!("missing API key: pass --api-key or set {key_var}"))?;
+        Ok((model, vec![(key_var.to_string(), api_key)]))
+    }
+
+    /// Resolves the model and vars of the custom provider,
+    /// listing the models of the endpoint if no model is given.
+    async fn custom_provider(
+        &self,
+        settings: &AgentSettings,
+    ) -> eyre::Result<(String, Vec<(String, String)>)> {
+        let base_url = match &settings.base_url {
+            Some(url) => ops::normalize_custom_url(url),
+            None => DEFAULT_CUSTOM_URL.to_owned(),
+        };
+        let api_key = self
+            .api_key
+            .clone()
+            .or_else(|| std::env::var(CUSTOM_KEY_VAR).ok())
+            .filter(|key| !key.is_empty());
+        let model = match &settings.model {
+            Some(model) => model
+                .strip_prefix(CUSTOM_MODEL_PREFIX)
+                .unwrap_or(model)
+                .to_owned(),
+            None => ops::fetch_custom_models(&base_url, api_key.as_deref())
+                .await
+                .wrap_err_with(|| format!("failed to list the models at {base_url}"))?
+                .remove(0),
+        };
+        let mut vars = vec![(CUSTOM_URL_VAR.to_owned(), base_url)];
+        if let Some(api_key) = api_key {
+            vars.push((CUSTOM_KEY_VAR.to_owned(), api_key));
+        }
+        Ok((format!("{CUSTOM_MODEL_PREFIX}{model}"), vars))
+    }
+}
+
+impl AgentSettings {
+    /// Returns these settings with those set in `other` replacing them.
+    fn overridden_by(self, other: &AgentSettings) -> Self {
+        let allowed_dirs = match other.allowed_dirs.is_empty() {
+            true => self.allowed_dirs,
+            false => other.allowed_dirs.clone(),
+        };
+        Self {
+            name: other.name.clone().or(self.name),
+            user_name: other.user_name.clone().or(self.user_name),
+            provider: other.provider.clone().or(self.provider),
+            model: other.model.clone().or(self.model),
+            base_url: other.base_url.clone().or(self.base_url),
+            tools: other.tools.clone().or(self.tools),
+            allowed_dirs,
+        }
+    }
+}
+
+fn read_agent_file(path: &PathBuf) -> eyre::Result<AgentSettings> {
+    let content = fs::read_to_string(path)
+        .wrap_err_with(|| format!("failed to read agent file {}", path.display()))?;
+    toml::from_str(&content).wrap_err_with(|| format!("invalid agent file {}", path.display()))
+}
+
+/// The IDs of the providers, e.g. `anthropic`.
+fn provider_ids() -> Vec<&'static str> {
+    PROVIDERS
+        .iter()
+        .filter_map(|(_, _, models)| models.first()?.0.split('/').next())
+        .chain(std::iter::once(CUSTOM_PROVIDER_ID))
+        .collect()
+}
+
+fn print_help() {
+    println!(
+        r#"Create an agent without the agents TUI, e.g. in CI or dotfile setups.
+
+Usage: asterai agents create <name> [options]
+
+Options:
+  -f, --from-file <path>   Read the settings from an agent TOML file; flags override it
+  -p, --provider <id>      The LLM provider: anthropic, openai, google or custom
+  -m, --model <model>      The model, e.g. claude-sonnet-4-6 (default: the recommended one,
+                           or the first one served by a custom endpoint)
+      --api-key <key>      The API key (default: the provider's env var, e.g. ANTHROPIC_KEY,
+                           or CUSTOM_LLM_KEY for a custom endpoint)
+      --base-url <url>     The base URL of a custom OpenAI-compatible endpoint
+                           (default: http://localhost:11434/v1)
+      --user-name <name>   What the agent calls you
+  -t, --tool <component>   A tool to enable, repeatable (default: the default tools)
+  -d, --allow-dir <dir>    A directory the agent may access, repeatable
+  -h, --help               Show this help message
+
+The agent file has the same settings as the flags, without the API key:
+
+  name = "Asterbot"
+  provider = "anthropic"
+  model = "claude-sonnet-4-6"
+  tools = ["asterbot:soul", "asterbot:memory"]
+  allowed-dirs = ["/home/me/notes"]
+
+Examples:
+  asterai agents create asterbot --provider anthropic --tool asterbot:memory
+  asterai agents create local --provider custom --base-url http://localhost:8080/v1
+  asterai agents create --from-file agent.toml
+"#
+    );
+}
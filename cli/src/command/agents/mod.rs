@@ -0,0 +1,69 @@
+use crate::command::agents::chat::ChatArgs;
+use crate::command::agents::create::CreateArgs;
+use eyre::{bail, eyre};
+use std::str::FromStr;
+use strum_macros::EnumString;
+
+mod chat;
+mod create;
+
+#[derive(Debug)]
+pub struct AgentsArgs {
+    /// The action, or none to launch the agents TUI.
+    action: Option<AgentsAction>,
+    create_args: Option<CreateArgs>,
+    chat_args: Option<ChatArgs>,
+}
+
+#[derive(Debug, Copy, Clone, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum AgentsAction {
+    Create,
+    Chat,
+}
+
+impl AgentsArgs {
+    pub fn parse(mut args: impl Iterator<Item = String>) -> eyre::Result<Self> {
+        let none_args = Self {
+            action: None,
+            create_args: None,
+            chat_args: None,
+        };
+        let Some(action_string) = args.next() else {
+            return Ok(none_args);
+        };
+        let action =
+            AgentsAction::from_str(&action_string).map_err(|_| eyre!("unknown agents action"))?;
+        let command_args = match action {
+            AgentsAction::Create => Self {
+                action: Some(action),
+                create_args: Some(CreateArgs::parse(args)?),
+                ..none_args
+            },
+            AgentsAction::Chat => Self {
+                action: Some(action),
+                chat_args: Some(ChatArgs::parse(args)?),
+                ..none_args
+            },
+        };
+        Ok(command_args)
+    }
+
+    pub async fn execute(&self) -> eyre::Result<()> {
+        match self.action {
+            None => crate::tui::run().await,
+            Some(AgentsAction::Create) => {
+                let Some(args) = &self.create_args else {
+                    bail!("no create args");
+                };
+                args.execute().await
+            }
+            Some(AgentsAction::Chat) => {
+                let Some(args) = &self.chat_args else {
+                    bail!("no chat args");
+                };
+                args.execute().await
+            }
+        }
+    }
+}
@@ -7,7 +7,9 @@ use crate::version_resolver::ComponentRef;
 use asterai_runtime::component::binary::ComponentBinary;
 use asterai_runtime::component::{Component, ComponentId};
 use asterai_runtime::environment::Environment;
+use asterai_runtime::runtime::handles::HandleTable;
 use eyre::{OptionExt, bail};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::str::FromStr;
 
@@ -17,10 +19,30 @@ pub(super) struct CallArgs {
     is_local_project: bool,
     function: String,
     function_args: Vec<String>,
+    /// Mock files of imported interfaces, by interface.
+    mocks: HashMap<String, String>,
 }
 
 impl CallArgs {
     pub fn parse(mut args: impl Iterator<Item = String>) -> eyre::Result<Self> {
+        let mut mocks = HashMap::new();
+        let mut positional = Vec::new();
+        while let Some(arg) = args.next() {
+            if arg != "--mock" {
+                positional.push(arg);
+                continue;
+            }
+            let mock = args
+                .next()
+                .ok_or_eyre("--mock requires <interface>=<file>")?;
+            let Some((interface, path)) = mock.split_once('=') else {
+                bail!("invalid mock {mock}, expected <interface>=<file>");
+            };
+            // Mock paths of environments are absolute.
+            let path = std::env::current_dir()?.join(path);
+            mocks.insert(interface.to_owned(), path.to_string_lossy().into_owned());
+        }
+        let mut args = positional.into_iter();
         let component_str = args.next().ok_or_eyre(
             "missing component reference \
              (e.g., namespace:name, namespace:name@version, or . for current dir)",
@@ -37,6 +59,7 @@ impl CallArgs {
             is_local_project,
             function,
             function_args,
+            mocks,
         })
     }
 
@@ -75,6 +98,7 @@ impl CallArgs {
             "0.0.0".to_string(),
         );
         environment.components.insert(comp_id_str, version);
+        environment.mocks = self.mocks.clone();
         for (key, value) in std::env::vars() {
             environment.vars.insert(key, value);
         }
@@ -88,6 +112,7 @@ impl CallArgs {
             &comp_id,
             self.function.clone(),
             &self.function_args,
+            &HandleTable::default(),
         )
         .await?;
         if let Some(output) = result {
@@ -97,58 +122,29 @@ impl CallArgs {
     }
 
     async fn execute_local(&self, allow_dirs: &[PathBuf]) -> eyre::Result<()> {
-        let cwd = std::env::current_dir()?;
-        let lang = language::detect(&cwd)
-            .ok_or_eyre("current directory is not a recognised component project")?;
-        let component_wasm_path = lang.get_component_wasm_path(&cwd)?;
-        if !component_wasm_path.exists() {
-            bail!(
-                "component not built yet (expected {}). Run: asterai component build",
-                component_wasm_path.display()
-            );
-        }
-        let package_wasm_path = lang.get_package_wasm_path(&cwd);
-        if !package_wasm_path.exists() {
-            bail!(
-                "package.wasm not found (expected {}). Run: asterai component build",
-                package_wasm_path.display()
-            );
-        }
-        let pkg_bytes = std::fs::read(&package_wasm_path)?;
-        let pkg_name = parse_package_name(&pkg_bytes)?;
-        let version = pkg_name
-            .version
-            .as_ref()
-            .ok_or_eyre("package.wasm has no version")?;
-        let comp_ref = format!("{}:{}@{}", pkg_name.namespace, pkg_name.name, version);
-        let component = Component::from_str(&comp_ref)
-            .map_err(|e| eyre::eyre!("invalid component reference: {e}"))?;
+        let binary = load_local_project()?;
+        let component = binary.component().clone();
         let comp_id = component.id();
-        let comp_id_str = format!("{}:{}", pkg_name.namespace, pkg_name.name);
-        let component_bytes = std::fs::read(&component_wasm_path)?;
-        let mut binary = ComponentBinary::from_component_bytes(component, component_bytes)?;
-        binary.apply_package_docs(&pkg_bytes)?;
         let mut environment = Environment::new(
-            pkg_name.namespace.clone(),
+            component.namespace().to_owned(),
             "component-call".to_string(),
             "0.0.0".to_string(),
         );
         environment
             .components
-            .insert(comp_id_str, version.to_string());
+            .insert(comp_id.to_string(), component.version().to_string());
+        environment.mocks = self.mocks.clone();
         for (key, value) in std::env::vars() {
             environment.vars.insert(key, value);
         }
-        println!(
-            "calling {}:{} function {}",
-            pkg_name.namespace, pkg_name.name, self.function,
-        );
+        println!("calling {comp_id} function {}", self.function);
         let mut runtime = build_runtime_with(environment, allow_dirs, vec![binary]).await?;
         let result = call_on_runtime(
             &mut runtime,
             &comp_id,
             self.function.clone(),
             &self.function_args,
+            &HandleTable::default(),
         )
         .await?;
         if let Some(output) = result {
@@ -158,6 +154,41 @@ impl CallArgs {
     }
 }
 
+/// Loads the built component of the project in the current directory,
+/// with docs from its WIT package.
+pub(super) fn load_local_project() -> eyre::Result<ComponentBinary> {
+    let cwd = std::env::current_dir()?;
+    let lang = language::detect(&cwd)
+        .ok_or_eyre("current directory is not a recognised component project")?;
+    let component_wasm_path = lang.get_component_wasm_path(&cwd)?;
+    if !component_wasm_path.exists() {
+        bail!(
+            "component not built yet (expected {}). Run: asterai component build",
+            component_wasm_path.display()
+        );
+    }
+    let package_wasm_path = lang.get_package_wasm_path(&cwd);
+    if !package_wasm_path.exists() {
+        bail!(
+            "package.wasm not found (expected {}). Run: asterai component build",
+            package_wasm_path.display()
+        );
+    }
+    let pkg_bytes = std::fs::read(&package_wasm_path)?;
+    let pkg_name = parse_package_name(&pkg_bytes)?;
+    let version = pkg_name
+        .version
+        .as_ref()
+        .ok_or_eyre("package.wasm has no version")?;
+    let comp_ref = format!("{}:{}@{}", pkg_name.namespace, pkg_name.name, version);
+    let component = Component::from_str(&comp_ref)
+        .map_err(|e| eyre::eyre!("invalid component reference: {e}"))?;
+    let component_bytes = std::fs::read(&component_wasm_path)?;
+    let mut binary = ComponentBinary::from_component_bytes(component, component_bytes)?;
+    binary.apply_package_docs(&pkg_bytes)?;
+    Ok(binary)
+}
+
 impl ComponentArgs {
     pub async fn call(&self) -> eyre::Result<()> {
         let args = self.call_args.as_ref().ok_or_eyre("no call args")?;
@@ -21,7 +21,7 @@ impl InitArgs {
         let mut args = args.peekable();
         while let Some(arg) = args.next() {
             match arg.as_str() {
-                "-l" | "--language" => {
+                "-l" | "--lang" | "--language" => {
                     let value = args.next().ok_or_else(|| {
                         eyre::eyre!(
                             "--language requires a value. \
@@ -93,6 +93,7 @@ fn extract_template(
     let namespace_camel = kebab_to_camel(namespace);
     let component_snake = component_name.replace('-', "_");
     let component_camel = kebab_to_camel(component_name);
+    let component_pascal = capitalize(&component_camel);
     fs::create_dir_all(dst).wrap_err_with(|| format!("failed to create directory: {:?}", dst))?;
     for file in template.files() {
         let mut file_path = dst.join(file.path());
@@ -109,6 +110,7 @@ fn extract_template(
                 .replace("___COMPONENT___", component_name)
                 .replace("___COMPONENT_SNAKE___", &component_snake)
                 .replace("___COMPONENT_CAMEL___", &component_camel)
+                .replace("___COMPONENT_PASCAL___", &component_pascal)
                 .into_bytes(),
             Err(_) => file.contents().to_vec(),
         };
@@ -143,6 +145,15 @@ fn kebab_to_camel(s: &str) -> String {
     result
 }
 
+/// Converts the first character of a string to uppercase.
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
 /// Validates that a name is a valid WIT identifier (kebab-case).
 fn validate_wit_identifier(name: &str) -> eyre::Result<()> {
     if name.is_empty() {
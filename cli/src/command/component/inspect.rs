@@ -0,0 +1,175 @@
+use crate::command::component::ComponentArgs;
+use crate::command::component::call::load_local_project;
+use crate::command::component::precompile::find_local_component;
+use crate::version_resolver::ComponentRef;
+use asterai_runtime::component::binary::ComponentBinary;
+use asterai_runtime::component::wit::{
+    ComponentFunction, ComponentInterface, short_interface_name,
+};
+use eyre::{OptionExt, bail};
+use serde_json::{Value, json};
+
+#[derive(Debug)]
+pub(super) struct InspectArgs {
+    /// Component to inspect, or `None` for the project in the current directory.
+    component_ref: Option<ComponentRef>,
+    /// Print function definitions as JSON instead of text.
+    is_json: bool,
+}
+
+impl InspectArgs {
+    pub fn parse(args: impl Iterator<Item = String>) -> eyre::Result<Self> {
+        let mut component_str: Option<String> = None;
+        let mut is_json = false;
+        for arg in args {
+            match arg.as_str() {
+                "--json" => {
+                    is_json = true;
+                }
+                "--help" | "-h" | "help" => {
+                    print_help();
+                    std::process::exit(0);
+                }
+                other => {
+                    if other.starts_with('-') {
+                        bail!("unknown flag: {}", other);
+                    }
+                    if component_str.is_some() {
+                        bail!("unexpected argument: {}", other);
+                    }
+                    component_str = Some(other.to_owned());
+                }
+            }
+        }
+        let component_str = component_str.ok_or_eyre(
+            "missing component reference\n\n\
+             Usage: asterai component inspect <namespace:name[@version]>\n\
+             Example: asterai component inspect asterai:fs",
+        )?;
+        let component_ref = match component_str == "." {
+            true => None,
+            false => Some(ComponentRef::parse(&component_str)?),
+        };
+        Ok(Self {
+            component_ref,
+            is_json,
+        })
+    }
+
+    fn execute(&self) -> eyre::Result<()> {
+        let binary = match &self.component_ref {
+            Some(component_ref) => find_local_component(component_ref)?,
+            None => load_local_project()?,
+        };
+        let functions = exported_functions(&binary);
+        if self.is_json {
+            let definitions: Vec<Value> = functions
+                .iter()
+                .map(|(name, f)| function_definition(name, f))
+                .collect();
+            let document = json!({
+                "component": binary.component().to_string(),
+                "functions": definitions,
+            });
+            println!("{}", serde_json::to_string_pretty(&document)?);
+            return Ok(());
+        }
+        println!("component {}", binary.component());
+        if let Some(docs) = binary.wit().world_docs() {
+            println!("{}", docs.trim());
+        }
+        if functions.is_empty() {
+            println!("functions: (none)");
+            return Ok(());
+        }
+        println!("functions:");
+        for (name, function) in &functions {
+            print_function(name, function);
+        }
+        Ok(())
+    }
+}
+
+impl ComponentArgs {
+    pub fn inspect(&self) -> eyre::Result<()> {
+        let args = self.inspect_args.as_ref().ok_or_eyre("no inspect args")?;
+        args.execute()
+    }
+}
+
+/// Returns the exported functions with the names they are called by,
+/// e.g. `fs/read` for interface functions.
+fn exported_functions(binary: &ComponentBinary) -> Vec<(String, ComponentFunction)> {
+    let mut functions: Vec<(String, ComponentFunction)> = binary
+        .world_functions()
+        .into_iter()
+        .map(|f| (f.name.clone(), f))
+        .collect();
+    for interface in binary.exported_interfaces() {
+        let interface_name = short_interface_name(&interface.name);
+        for function in interface.functions {
+            functions.push((format!("{interface_name}/{}", function.name), function));
+        }
+    }
+    functions
+}
+
+/// Builds a function definition in the shape of an LLM tool definition,
+/// with the return value's schema alongside.
+fn function_definition(name: &str, function: &ComponentFunction) -> Value {
+    json!({
+        "name": name,
+        "description": function.docs,
+        "parameters": function.params_json_schema(),
+        "output": function.return_json_schema,
+    })
+}
+
+fn print_function(name: &str, function: &ComponentFunction) {
+    let params: Vec<String> = function
+        .params
+        .iter()
+        .map(|p| format!("{}: {}", p.name, p.type_name))
+        .collect();
+    let return_suffix = match &function.return_type_name {
+        Some(type_name) => format!(" -> {type_name}"),
+        None => String::new(),
+    };
+    println!(" - {name}({}){return_suffix}", params.join(", "));
+    if let Some(docs) = &function.docs {
+        for line in docs.trim().lines() {
+            println!("     {line}");
+        }
+    }
+    for param in &function.params {
+        println!("     {}: {}", param.name, param.json_schema);
+    }
+    if let Some(schema) = &function.return_json_schema {
+        println!("     returns: {schema}");
+    }
+}
+
+fn print_help() {
+    println!(
+        r#"Show the functions a component exports, with JSON Schemas of their
+parameters and return values.
+
+The schemas describe the JSON accepted by `component call` and the call
+API, and --json prints each function in the shape of an LLM tool definition.
+
+Usage: asterai component inspect <namespace:name[@version]> [options]
+
+Arguments:
+  <namespace:name[@version]>  Local component reference, or . for the current project
+
+Options:
+  --json               Print function definitions as JSON
+  -h, --help           Show this help message
+
+Examples:
+  asterai component inspect asterai:fs
+  asterai component inspect asterai:fs@1.0.0 --json
+  asterai component inspect .
+"#
+    );
+}
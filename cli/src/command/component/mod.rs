@@ -3,9 +3,12 @@ use crate::command::component::build::BuildArgs;
 use crate::command::component::call::CallArgs;
 use crate::command::component::delete::DeleteArgs;
 use crate::command::component::init::InitArgs;
+use crate::command::component::inspect::InspectArgs;
 use crate::command::component::pkg::PkgArgs;
+use crate::command::component::precompile::PrecompileArgs;
 use crate::command::component::pull::PullArgs;
 use crate::command::component::push::PushArgs;
+use crate::command::component::test::TestArgs;
 use eyre::{bail, eyre};
 use std::path::PathBuf;
 use std::str::FromStr;
@@ -15,10 +18,13 @@ pub mod build;
 pub(crate) mod call;
 pub mod delete;
 pub mod init;
+pub(crate) mod inspect;
 pub mod list;
 pub mod pkg;
+pub(crate) mod precompile;
 pub mod pull;
 pub mod push;
+mod test;
 
 #[derive(Debug)]
 pub struct ComponentArgs {
@@ -26,11 +32,14 @@ pub struct ComponentArgs {
     #[allow(dead_code)]
     build_args: Option<BuildArgs>,
     pkg_args: Option<PkgArgs>,
+    precompile_args: Option<PrecompileArgs>,
     pull_args: Option<PullArgs>,
     push_args: Option<PushArgs>,
     init_args: Option<InitArgs>,
+    inspect_args: Option<InspectArgs>,
     delete_args: Option<DeleteArgs>,
     call_args: Option<CallArgs>,
+    test_args: Option<TestArgs>,
     pub api_endpoint: String,
     pub registry_endpoint: String,
     pub allow_dirs: Vec<PathBuf>,
@@ -42,11 +51,14 @@ pub enum ComponentAction {
     Build,
     Call,
     Init,
+    Inspect,
     Ls,
     Pkg,
+    Precompile,
     Pull,
     Push,
     Rm,
+    Test,
 }
 
 impl ComponentArgs {
@@ -67,11 +79,14 @@ impl ComponentArgs {
             action,
             build_args: None,
             pkg_args: None,
+            precompile_args: None,
             pull_args: None,
             push_args: None,
             init_args: None,
+            inspect_args: None,
             delete_args: None,
             call_args: None,
+            test_args: None,
             api_endpoint,
             registry_endpoint,
             allow_dirs,
@@ -89,11 +104,19 @@ impl ComponentArgs {
                 init_args: Some(InitArgs::parse(args)?),
                 ..none_args
             },
+            ComponentAction::Inspect => Self {
+                inspect_args: Some(InspectArgs::parse(args)?),
+                ..none_args
+            },
             ComponentAction::Ls => none_args,
             ComponentAction::Pkg => Self {
                 pkg_args: Some(PkgArgs::parse(args)?),
                 ..none_args
             },
+            ComponentAction::Precompile => Self {
+                precompile_args: Some(PrecompileArgs::parse(args)?),
+                ..none_args
+            },
             ComponentAction::Pull => Self {
                 pull_args: Some(PullArgs::parse(args)?),
                 ..none_args
@@ -106,6 +129,10 @@ impl ComponentArgs {
                 delete_args: Some(DeleteArgs::parse(args)?),
                 ..none_args
             },
+            ComponentAction::Test => Self {
+                test_args: Some(TestArgs::parse(args)?),
+                ..none_args
+            },
         };
         Ok(command_args)
     }
@@ -121,12 +148,18 @@ impl ComponentArgs {
             ComponentAction::Init => {
                 self.init()?;
             }
+            ComponentAction::Inspect => {
+                self.inspect()?;
+            }
             ComponentAction::Ls => {
                 self.list().await?;
             }
             ComponentAction::Pkg => {
                 self.pkg().await?;
             }
+            ComponentAction::Precompile => {
+                self.precompile().await?;
+            }
             ComponentAction::Pull => {
                 self.pull().await?;
             }
@@ -136,6 +169,9 @@ impl ComponentArgs {
             ComponentAction::Rm => {
                 self.delete()?;
             }
+            ComponentAction::Test => {
+                self.test().await?;
+            }
         }
         Ok(())
     }
@@ -0,0 +1,142 @@
+use crate::command::component::ComponentArgs;
+use crate::config::ARTIFACTS_DIR;
+use crate::local_store::LocalStore;
+use crate::version_resolver::ComponentRef;
+use asterai_runtime::component::binary::ComponentBinary;
+use asterai_runtime::resource::ResourceId;
+use asterai_runtime::resource::metadata::ResourceKind;
+use eyre::{OptionExt, bail, eyre};
+use std::io::Write;
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub(super) struct PrecompileArgs {
+    /// Component to precompile, or `None` for all local components.
+    component_ref: Option<ComponentRef>,
+}
+
+impl PrecompileArgs {
+    pub fn parse(args: impl Iterator<Item = String>) -> eyre::Result<Self> {
+        let mut component_ref: Option<ComponentRef> = None;
+        let mut is_all = false;
+        for arg in args {
+            match arg.as_str() {
+                "--all" | "-a" => {
+                    is_all = true;
+                }
+                "--help" | "-h" | "help" => {
+                    print_help();
+                    std::process::exit(0);
+                }
+                other => {
+                    if other.starts_with('-') {
+                        bail!("unknown flag: {}", other);
+                    }
+                    if component_ref.is_some() {
+                        bail!("unexpected argument: {}", other);
+                    }
+                    component_ref = Some(ComponentRef::parse(other)?);
+                }
+            }
+        }
+        if is_all && component_ref.is_some() {
+            bail!("--all cannot be combined with a component reference");
+        }
+        if !is_all && component_ref.is_none() {
+            bail!(
+                "missing component reference\n\n\
+                 Usage: asterai component precompile <namespace:name[@version]>\n\
+                 Example: asterai component precompile asterai:fs"
+            );
+        }
+        Ok(Self { component_ref })
+    }
+
+    async fn execute(&self) -> eyre::Result<()> {
+        let components = match &self.component_ref {
+            Some(component_ref) => vec![find_local_component(component_ref)?],
+            None => LocalStore::list_components(),
+        };
+        precompile_components(&components).await
+    }
+}
+
+impl ComponentArgs {
+    pub async fn precompile(&self) -> eyre::Result<()> {
+        let args = self
+            .precompile_args
+            .as_ref()
+            .ok_or_eyre("no precompile args")?;
+        args.execute().await
+    }
+}
+
+/// Precompiles each component into its local store directory,
+/// skipping those with up-to-date artifacts.
+pub(crate) async fn precompile_components(components: &[ComponentBinary]) -> eyre::Result<()> {
+    let mut compiled_count = 0;
+    for component in components {
+        print!("precompiling {}...", component.component());
+        std::io::stdout().flush().ok();
+        match component.precompile().await? {
+            true => {
+                compiled_count += 1;
+                println!(" done.");
+            }
+            false => println!(" up to date."),
+        }
+    }
+    println!(
+        "precompiled {} of {} component(s)",
+        compiled_count,
+        components.len()
+    );
+    Ok(())
+}
+
+pub(super) fn find_local_component(component_ref: &ComponentRef) -> eyre::Result<ComponentBinary> {
+    let path = match &component_ref.version {
+        Some(version) => ARTIFACTS_DIR
+            .join(&component_ref.namespace)
+            .join(format!("{}@{}", component_ref.name, version)),
+        None => {
+            let id = format!("{}:{}", component_ref.namespace, component_ref.name);
+            let id = ResourceId::from_str(&id).map_err(|e| eyre!(e))?;
+            LocalStore::find_path(&id, ResourceKind::Component)?
+        }
+    };
+    if !path.join("component.wasm").exists() {
+        bail!(
+            "component '{}:{}' not found locally (pull it first)",
+            component_ref.namespace,
+            component_ref.name
+        );
+    }
+    LocalStore::parse_component(&path)
+}
+
+fn print_help() {
+    println!(
+        r#"Precompile a local component ahead of time.
+
+Stores natively compiled artifacts next to the component in the local store,
+so that running environments with it does not need to compile it again.
+Artifacts are reused only by the same asterai build and engine settings,
+and are otherwise recompiled on demand.
+
+Usage: asterai component precompile <namespace:name[@version]> [options]
+
+Arguments:
+  <namespace:name[@version]>   Component to precompile (default: latest local version)
+
+Options:
+  -a, --all             Precompile all local components
+  -h, --help            Show this help message
+
+Examples:
+  asterai component precompile asterai:fs
+  asterai component precompile asterai:fs@0.1.0
+  asterai component precompile --all
+"#
+    );
+}
@@ -0,0 +1,445 @@
+use crate::command::component::ComponentArgs;
+use crate::command::component::call::load_local_project;
+use crate::command::env::call::call_json_on_runtime;
+use crate::runtime::build_runtime_with;
+use asterai_runtime::component::binary::ComponentBinary;
+use asterai_runtime::environment::Environment;
+use asterai_runtime::runtime::handles::HandleTable;
+use eyre::{Context, OptionExt, bail};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The directory of test files, relative to the project directory.
+const TESTS_DIR: &str = "tests";
+
+#[derive(Debug)]
+pub(super) struct TestArgs {
+    /// Test files to run, or none for all files in the tests directory.
+    files: Vec<PathBuf>,
+    /// Whether to write the outputs of tests to their golden files
+    /// instead of comparing them.
+    should_bless: bool,
+}
+
+/// A file of tests, which run in order on one runtime
+/// until a call fails.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct TestFile {
+    /// Environment variables of the runtime.
+    #[serde(default)]
+    vars: HashMap<String, String>,
+    /// Directories the component may access, relative to the project directory.
+    #[serde(default)]
+    allow_dirs: Vec<PathBuf>,
+    /// Mock files of imported interfaces, by interface,
+    /// relative to the project directory.
+    #[serde(default)]
+    mocks: HashMap<String, PathBuf>,
+    #[serde(default, rename = "test")]
+    tests: Vec<TestCase>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct TestCase {
+    name: String,
+    /// The function to call, e.g. `greeter/greet`.
+    function: String,
+    /// Positional args as an array, or named args as a table.
+    #[serde(default)]
+    args: Option<toml::Value>,
+    /// The expected output. Without it or `error`,
+    /// the output is compared to the test's golden file.
+    output: Option<toml::Value>,
+    /// Text the error of the call is expected to contain.
+    error: Option<String>,
+}
+
+/// What a test expects its call to return.
+enum Expected {
+    Output(Value),
+    Error(String),
+    /// The output in the golden file at the path, if it exists.
+    Golden(PathBuf, Option<Value>),
+}
+
+impl TestArgs {
+    pub fn parse(args: impl Iterator<Item = String>) -> eyre::Result<Self> {
+        let mut files = Vec::new();
+        let mut should_bless = false;
+        for arg in args {
+            match arg.as_str() {
+                "--bless" => should_bless = true,
+                "--help" | "-h" | "help" => {
+                    print_help();
+                    std::process::exit(0);
+                }
+                other if other.starts_with('-') => bail!("unknown flag: {other}"),
+                other => files.push(PathBuf::from(other)),
+            }
+        }
+        Ok(Self {
+            files,
+            should_bless,
+        })
+    }
+
+    async fn execute(&self) -> eyre::Result<()> {
+        let cwd = std::env::current_dir().wrap_err("failed to get current directory")?;
+        let files = match self.files.is_empty() {
+            true => find_test_files(&cwd.join(TESTS_DIR))?,
+            false => self.files.clone(),
+        };
+        if files.is_empty() {
+            bail!(
+                "no test files found in {TESTS_DIR}/ \
+                 (see: asterai component test --help)"
+            );
+        }
+        let binary = load_local_project()?;
+        let mut failed = Vec::new();
+        let mut passed = 0;
+        for path in &files {
+            let test_file = read_test_file(path)?;
+            println!(
+                "\nrunning {} test(s) in {}",
+                test_file.tests.len(),
+                path.display()
+            );
+            let results = self.run_file(&cwd, path, test_file, &binary).await?;
+            for (name, result) in results {
+                match result {
+                    Ok(()) => passed += 1,
+                    Err(message) => failed.push((name, message)),
+                }
+            }
+        }
+        if !failed.is_empty() {
+            println!("\nfailures:");
+        }
+        for (name, message) in &failed {
+            println!("\n---- {name} ----\n{message}");
+        }
+        println!("\ntest result: {} passed, {} failed", passed, failed.len());
+        if !failed.is_empty() {
+            bail!("{} test(s) failed", failed.len());
+        }
+        Ok(())
+    }
+
+    /// Runs the tests of a file, returning the result of each by name.
+    async fn run_file(
+        &self,
+        cwd: &Path,
+        path: &Path,
+        test_file: TestFile,
+        binary: &ComponentBinary,
+    ) -> eyre::Result<Vec<(String, Result<(), String>)>> {
+        let component = binary.component();
+        let comp_id = component.id();
+        // A throwaway environment with only the component, the file's vars and mocks.
+        let mut environment = Environment::new(
+            component.namespace().to_owned(),
+            "component-test".to_string(),
+            "0.0.0".to_string(),
+        );
+        environment
+            .components
+            .insert(comp_id.to_string(), component.version().to_string());
+        environment.vars = test_file.vars;
+        environment.mocks = test_file
+            .mocks
+            .iter()
+            .map(|(interface, path)| {
+                let path = cwd.join(path).to_string_lossy().into_owned();
+                (interface.clone(), path)
+            })
+            .collect();
+        let allow_dirs = test_file
+            .allow_dirs
+            .iter()
+            .map(|dir| {
+                cwd.join(dir)
+                    .canonicalize()
+                    .wrap_err_with(|| format!("allowed directory {dir:?} not found"))
+            })
+            .collect::<eyre::Result<Vec<_>>>()?;
+        // Tests of a file share a runtime, and the handles returned in it.
+        let mut runtime_opt = None;
+        let mut results = Vec::with_capacity(test_file.tests.len());
+        for test in test_file.tests {
+            let expected = test.expected(path)?;
+            let args = match &test.args {
+                Some(args) => serde_json::to_value(args)?,
+                None => Value::Array(Vec::new()),
+            };
+            let (runtime, handles) = match &mut runtime_opt {
+                Some((runtime, handles)) => (runtime, &*handles),
+                None => {
+                    let runtime =
+                        build_runtime_with(environment.clone(), &allow_dirs, vec![binary.clone()])
+                            .await?;
+                    let (runtime, handles) = runtime_opt.insert((runtime, HandleTable::default()));
+                    (runtime, &*handles)
+                }
+            };
+            let result =
+                call_json_on_runtime(runtime, &comp_id, test.function.clone(), &args, handles)
+                    .await;
+            // A trap leaves the store unusable, so the next test gets a new runtime.
+            if result.is_err() {
+                runtime_opt = None;
+            }
+            let outcome = self.check(expected, result);
+            let status = match &outcome {
+                Ok(()) => "ok",
+                Err(_) => "FAILED",
+            };
+            println!("test {} ... {status}", test.name);
+            results.push((test.name, outcome));
+        }
+        Ok(results)
+    }
+
+    /// Checks the result of a test's call against what it expects,
+    /// blessing its golden file instead if enabled.
+    fn check(&self, expected: Expected, result: eyre::Result<Value>) -> Result<(), String> {
+        match (expected, result) {
+            (Expected::Output(expected), Ok(actual)) => compare(&expected, &actual),
+            (Expected::Output(_), Err(e)) => Err(format!("the call failed: {e:#}")),
+            (Expected::Error(expected), Err(e)) => {
+                let message = format!("{e:#}");
+                match message.contains(&expected) {
+                    true => Ok(()),
+                    false => Err(format!(
+                        "expected an error containing {expected:?}, got: {message}"
+                    )),
+                }
+            }
+            (Expected::Error(expected), Ok(actual)) => Err(format!(
+                "expected an error containing {expected:?}, but the call returned:\n{}",
+                to_pretty_json(&actual)
+            )),
+            (Expected::Golden(_, _), Err(e)) => Err(format!("the call failed: {e:#}")),
+            (Expected::Golden(path, _), Ok(actual)) if self.should_bless => {
+                write_golden_file(&path, &actual).map_err(|e| format!("{e:#}"))
+            }
+            (Expected::Golden(path, None), Ok(_)) => Err(format!(
+                "golden file {} not found (write it with: asterai component test --bless)",
+                path.display()
+            )),
+            (Expected::Golden(_, Some(expected)), Ok(actual)) => compare(&expected, &actual),
+        }
+    }
+}
+
+impl ComponentArgs {
+    pub async fn test(&self) -> eyre::Result<()> {
+        let args = self.test_args.as_ref().ok_or_eyre("no test args")?;
+        args.execute().await
+    }
+}
+
+impl TestCase {
+    fn expected(&self, test_file_path: &Path) -> eyre::Result<Expected> {
+        match (&self.output, &self.error) {
+            (Some(_), Some(_)) => bail!(
+                "test {} expects both an output and an error, expected only one",
+                self.name
+            ),
+            (Some(output), None) => Ok(Expected::Output(serde_json::to_value(output)?)),
+            (None, Some(error)) => Ok(Expected::Error(error.clone())),
+            (None, None) => {
+                let path = golden_file_path(test_file_path, &self.name);
+                let output = match path.exists() {
+                    true => {
+                        let content = fs::read_to_string(&path)
+                            .wrap_err_with(|| format!("failed to read {path:?}"))?;
+                        let output = serde_json::from_str(&content)
+                            .wrap_err_with(|| format!("invalid JSON in {path:?}"))?;
+                        Some(output)
+                    }
+                    false => None,
+                };
+                Ok(Expected::Golden(path, output))
+            }
+        }
+    }
+}
+
+/// Returns the `.toml` files in `dir`, sorted.
+fn find_test_files(dir: &Path) -> eyre::Result<Vec<PathBuf>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .wrap_err_with(|| format!("failed to read {dir:?}"))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn read_test_file(path: &Path) -> eyre::Result<TestFile> {
+    let content =
+        fs::read_to_string(path).wrap_err_with(|| format!("failed to read test file {path:?}"))?;
+    let test_file: TestFile =
+        toml::from_str(&content).wrap_err_with(|| format!("invalid test file {path:?}"))?;
+    // Tests share a golden file if their names have the same slug,
+    // so such names are rejected along with duplicate ones.
+    let mut names_by_slug: HashMap<String, &str> = HashMap::new();
+    for test in &test_file.tests {
+        if let Some(other) = names_by_slug.insert(slug(&test.name), &test.name) {
+            match other == test.name {
+                true => bail!("test {:?} is defined more than once in {path:?}", test.name),
+                false => bail!(
+                    "tests {other:?} and {:?} in {path:?} would share a golden file, rename one",
+                    test.name
+                ),
+            }
+        }
+    }
+    Ok(test_file)
+}
+
+/// The golden file of a test, e.g. `tests/greet/greets-by-name.json`
+/// for the test "greets by name" in `tests/greet.toml`.
+fn golden_file_path(test_file_path: &Path, test_name: &str) -> PathBuf {
+    test_file_path
+        .with_extension("")
+        .join(format!("{}.json", slug(test_name)))
+}
+
+/// The name of a test as a file name, e.g. `greets-by-name`.
+fn slug(test_name: &str) -> String {
+    test_name
+        .chars()
+        .map(
+            |c| match c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                true => c.to_ascii_lowercase(),
+                false => '-',
+            },
+        )
+        .collect()
+}
+
+fn write_golden_file(path: &Path, output: &Value) -> eyre::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).wrap_err_with(|| format!("failed to create {parent:?}"))?;
+    }
+    fs::write(path, format!("{}\n", to_pretty_json(output)))
+        .wrap_err_with(|| format!("failed to write {path:?}"))?;
+    println!("blessed {}", path.display());
+    Ok(())
+}
+
+fn to_pretty_json(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
+}
+
+/// Compares an output to the expected one, failing with a diff of
+/// their pretty-printed JSON if they differ.
+fn compare(expected: &Value, actual: &Value) -> Result<(), String> {
+    if expected == actual {
+        return Ok(());
+    }
+    let expected = to_pretty_json(expected);
+    let actual = to_pretty_json(actual);
+    let expected_lines: Vec<&str> = expected.lines().collect();
+    let actual_lines: Vec<&str> = actual.lines().collect();
+    Err(format!(
+        "output differs (- expected, + actual):\n{}",
+        diff_lines(&expected_lines, &actual_lines)
+    ))
+}
+
+/// A line diff of `a` and `b`, based on their longest common subsequence.
+fn diff_lines(a: &[&str], b: &[&str]) -> String {
+    // lcs[i][j] is the length of the LCS of a[i..] and b[j..].
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = match a[i] == b[j] {
+                true => lcs[i + 1][j + 1] + 1,
+                false => lcs[i + 1][j].max(lcs[i][j + 1]),
+            };
+        }
+    }
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            lines.push(format!("  {}", a[i]));
+            i += 1;
+            j += 1;
+        } else if j < b.len() && (i == a.len() || lcs[i][j + 1] > lcs[i + 1][j]) {
+            lines.push(format!("+ {}", b[j]));
+            j += 1;
+        } else {
+            lines.push(format!("- {}", a[i]));
+            i += 1;
+        }
+    }
+    lines.join("\n")
+}
+
+fn print_help() {
+    println!(
+        r#"Run the tests of the component project in the current directory.
+
+Tests are declared in TOML files, by default all files in tests/.
+The tests of a file run in order on one runtime, with only the
+component and its dependencies, and the vars, directories and mocks
+of the file. Mocked interfaces are not pulled from the registry.
+After a call fails, the next test runs on a new runtime.
+
+Usage: asterai component test [files...] [options]
+
+Options:
+  --bless       Write the outputs of tests without an expected output
+                or error to their golden files, instead of comparing them
+  -h, --help    Show this help message
+
+Test file format:
+  vars = {{ GREETING = "hello" }}    # Environment variables (optional)
+  allow-dirs = ["fixtures"]        # Directories the component may access (optional)
+  mocks = {{ "asterai:llm/llm" = "tests/llm.toml" }}  # Mock files of imports (optional)
+
+  [[test]]
+  name = "greets by name"
+  function = "greeter/greet"
+  args = ["world"]                 # Positional args, or a table of named args
+  output = "hello world!"          # The expected output, as JSON
+
+  [[test]]
+  name = "rejects empty names"
+  function = "greeter/greet"
+  args = {{ name = "" }}
+  error = "empty name"             # Text the error of the call should contain
+
+Tests with neither an output nor an error compare the output to a
+golden JSON file, e.g. tests/greet/greets-by-name.json, which --bless writes.
+
+Mock file format (JSON or TOML), with responses by function:
+  [[prompt]]
+  args = ["hello*", "*"]           # Pattern of positional or named args (optional)
+  output = "hi there"              # The returned value, as JSON
+
+  [[prompt]]
+  output = "I don't know"
+
+A call returns the output of the first response whose args match.
+In patterns, "*" matches any value and other strings with * are globs.
+
+Examples:
+  asterai component test
+  asterai component test tests/greet.toml
+  asterai component test --bless
+"#
+    );
+}
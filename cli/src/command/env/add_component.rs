@@ -2,11 +2,17 @@ use crate::command::env::EnvArgs;
 use crate::local_store::LocalStore;
 use crate::registry::RegistryClient;
 use asterai_runtime::component::Component;
-use eyre::OptionExt;
+use asterai_runtime::component::binary::ComponentBinary;
+use asterai_runtime::component::config::ConfigVar;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+use eyre::{OptionExt, bail};
+use std::io::{IsTerminal, Write};
 use std::str::FromStr;
 
 impl EnvArgs {
-    pub async fn add_component(&self) -> eyre::Result<()> {
+    /// Adds the component to the environment, returning the added component.
+    pub async fn add_component(&self) -> eyre::Result<ComponentBinary> {
         let resource_id = self.resource_id()?;
         let component_ref = self
             .component_ref
@@ -28,6 +34,106 @@ impl EnvArgs {
             .map_err(|_| eyre::eyre!("environment '{}' not found locally", resource_id))?;
         environment.add_component(&component);
         LocalStore::write_environment(&environment)?;
+        LocalStore::fetch_component_version(&component)
+    }
+
+    /// Prompts for the required config vars of `component` that are not set
+    /// in the environment, or lists them if stdin is not a terminal.
+    pub fn prompt_config(&self, component: &ComponentBinary) -> eyre::Result<()> {
+        let resource_id = self.resource_id()?;
+        let mut environment = LocalStore::fetch_environment(&resource_id)?;
+        let unset: Vec<&ConfigVar> = component
+            .config()
+            .iter()
+            .filter(|var| environment.get_var(&var.name).is_none())
+            .collect();
+        let (required, optional): (Vec<&ConfigVar>, Vec<&ConfigVar>) = unset
+            .into_iter()
+            .partition(|var| var.is_required && var.default.is_none());
+        if !optional.is_empty() {
+            let names: Vec<&str> = optional.iter().map(|var| var.name.as_str()).collect();
+            println!("optional config: {}", names.join(", "));
+        }
+        if required.is_empty() {
+            return Ok(());
+        }
+        let id = component.component().id();
+        if !std::io::stdin().is_terminal() {
+            let names: Vec<&str> = required.iter().map(|var| var.name.as_str()).collect();
+            println!(
+                "{id} requires config: {}\n\
+                 Set it with: asterai env set-var {} --var NAME=VALUE",
+                names.join(", "),
+                environment.name()
+            );
+            return Ok(());
+        }
+        println!("{id} requires config (leave empty to set it later):");
+        for var in required {
+            if let Some(value) = prompt_var(var)? {
+                environment.set_var(var.name.clone(), value);
+            }
+        }
+        LocalStore::write_environment(&environment)?;
         Ok(())
     }
 }
+
+/// Prompts for the value of `var` until it is valid or left empty.
+fn prompt_var(var: &ConfigVar) -> eyre::Result<Option<String>> {
+    if let Some(description) = &var.description {
+        println!("  {description}");
+    }
+    loop {
+        print!("  {}: ", var.name);
+        std::io::stdout().flush()?;
+        let value = match var.is_secret {
+            true => read_hidden_line()?,
+            false => {
+                let mut line = String::new();
+                std::io::stdin().read_line(&mut line)?;
+                line.trim().to_owned()
+            }
+        };
+        if value.is_empty() {
+            println!("  {} not set: env run will fail until it is set", var.name);
+            return Ok(None);
+        }
+        match var.validate(&value) {
+            Ok(()) => return Ok(Some(value)),
+            Err(e) => println!("  {} {e}", var.name),
+        }
+    }
+}
+
+/// Reads a line from the terminal without echoing it.
+fn read_hidden_line() -> eyre::Result<String> {
+    enable_raw_mode()?;
+    let result = read_keys_until_enter();
+    disable_raw_mode()?;
+    println!();
+    result
+}
+
+fn read_keys_until_enter() -> eyre::Result<String> {
+    let mut line = String::new();
+    loop {
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Enter => return Ok(line),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                bail!("cancelled")
+            }
+            KeyCode::Char(c) => line.push(c),
+            KeyCode::Backspace => {
+                line.pop();
+            }
+            _ => {}
+        }
+    }
+}
@@ -1,13 +1,19 @@
 use crate::command::env::EnvArgs;
 use crate::local_store::LocalStore;
 use crate::runtime::build_runtime;
+use crate::trace;
+use asterai_runtime::component::function_interface::ComponentFunctionInterface;
 use asterai_runtime::component::function_name::ComponentFunctionName;
 use asterai_runtime::component::{ComponentId, PackageName, Version};
-use asterai_runtime::runtime::parsing::{ValExt, json_value_to_val, parse_primitive};
+use asterai_runtime::runtime::handles::HandleTable;
+use asterai_runtime::runtime::parsing::{
+    ValExt, cli_arg_to_json, json_args_to_vals, named_json_args,
+};
 use asterai_runtime::runtime::{ComponentRuntime, Val};
 use eyre::{OptionExt, bail};
+use serde_json::Value;
 use std::str::FromStr;
-use wit_parser::{Resolve, TypeDef, TypeDefKind};
+use wit_parser::{Resolve, TypeDef};
 
 impl EnvArgs {
     pub async fn call(&self) -> eyre::Result<()> {
@@ -38,17 +44,65 @@ impl EnvArgs {
         let environment = LocalStore::fetch_environment(&resource_id)
             .map_err(|_| eyre::eyre!("environment '{}' not found locally", resource_id))?;
         let mut runtime = build_runtime(environment, &self.allow_dirs).await?;
-        call_on_runtime(&mut runtime, &comp_id, function_string, &self.function_args).await
+        let result = call_on_runtime(
+            &mut runtime,
+            &comp_id,
+            function_string,
+            &self.function_args,
+            &HandleTable::default(),
+        )
+        .await;
+        trace::flush().await;
+        result
     }
 }
 
 /// Find a function on a runtime, parse inputs, call it, and return formatted output.
+/// Handles in args and output refer to values of `handles`.
 pub(crate) async fn call_on_runtime(
     runtime: &mut ComponentRuntime,
     comp_id: &ComponentId,
     function_string: String,
     function_args: &[String],
+    handles: &HandleTable,
 ) -> eyre::Result<Option<String>> {
+    let (function, resolve) = find_function(runtime, comp_id, function_string)?;
+    let inputs = parse_inputs_from_string_args(function_args, &function.inputs, &resolve, handles)?;
+    let output = call_with_inputs(runtime, function, &inputs, handles).await?;
+    Ok(output.map(|json| match json {
+        Value::String(s) => s,
+        other => other.to_string(),
+    }))
+}
+
+/// Find a function on a runtime, call it with args given as a JSON array
+/// of positional args or a JSON object of named args, and return its
+/// output as JSON, which is `null` if the function returns nothing.
+/// Handles in args and output refer to values of `handles`.
+pub(crate) async fn call_json_on_runtime(
+    runtime: &mut ComponentRuntime,
+    comp_id: &ComponentId,
+    function_string: String,
+    args: &Value,
+    handles: &HandleTable,
+) -> eyre::Result<Value> {
+    let (function, resolve) = find_function(runtime, comp_id, function_string)?;
+    let json_args = match args {
+        Value::Array(args) => args.clone(),
+        Value::Object(args) => named_json_args(args, &function.inputs)?,
+        _ => bail!("expected a JSON array or object of arguments"),
+    };
+    let inputs = json_args_to_vals(&json_args, &function.inputs, &resolve, handles)?;
+    let output = call_with_inputs(runtime, function, &inputs, handles).await?;
+    Ok(output.unwrap_or(Value::Null))
+}
+
+/// Finds a function on a runtime, with the WIT `Resolve` of its component.
+fn find_function(
+    runtime: &ComponentRuntime,
+    comp_id: &ComponentId,
+    function_string: String,
+) -> eyre::Result<(ComponentFunctionInterface, Resolve)> {
     let (function_name, package_name_opt) = parse_function_string_into_parts(function_string)?;
     let function = runtime
         .find_function(comp_id, &function_name, package_name_opt)?
@@ -56,18 +110,22 @@ pub(crate) async fn call_on_runtime(
     let resolve = runtime
         .resolve_for(comp_id)
         .ok_or_eyre("component not found")?;
-    let inputs = parse_inputs_from_string_args(function_args, &function.inputs, &resolve)?;
-    let output_opt = runtime.call_function(function, &inputs).await?;
-    if let Some(output) = output_opt
-        && let Some(function_output) = output.function_output_opt
-    {
-        let json = function_output.value.val.try_into_json_value();
-        return Ok(json.map(|j| match j {
-            serde_json::Value::String(s) => s,
-            other => other.to_string(),
-        }));
-    }
-    Ok(None)
+    function.check_is_json_callable(&resolve)?;
+    Ok((function, resolve))
+}
+
+/// Calls a function, returning its output as JSON if it has one.
+async fn call_with_inputs(
+    runtime: &mut ComponentRuntime,
+    function: ComponentFunctionInterface,
+    inputs: &[Val],
+    handles: &HandleTable,
+) -> eyre::Result<Option<Value>> {
+    let output_opt = runtime.call_function(function, inputs).await?;
+    let Some(function_output) = output_opt.and_then(|o| o.function_output_opt) else {
+        return Ok(None);
+    };
+    Ok(Some(function_output.value.val.into_json_value(handles)))
 }
 
 fn parse_function_string_into_parts(
@@ -110,130 +168,91 @@ fn parse_function_string_into_parts(
     ))
 }
 
+/// Converts CLI args to the values of the params of a function.
+/// Args are positional, named as `--<param>=<value>`, or read with
+/// `--args-file <path>` as a JSON array or object, from stdin if `-`.
 fn parse_inputs_from_string_args(
     args: &[String],
-    expected_inputs: &[(String, TypeDef)],
+    params: &[(String, TypeDef)],
     resolve: &Resolve,
+    handles: &HandleTable,
 ) -> eyre::Result<Vec<Val>> {
-    if args.len() != expected_inputs.len() {
-        bail!(
-            "expected {} argument(s), got {}",
-            expected_inputs.len(),
-            args.len()
-        );
-    }
-    args.iter()
-        .zip(expected_inputs.iter())
-        .map(|(arg, (name, type_def))| {
-            parse_arg(arg, type_def, resolve)
-                .map_err(|e| eyre::eyre!("failed to parse argument '{name}': {e}"))
-        })
-        .collect()
+    let args_file = match args {
+        [flag, path] if flag == "--args-file" => Some(path.as_str()),
+        [arg] => arg.strip_prefix("--args-file="),
+        _ => None,
+    };
+    let json_args = match args_file {
+        Some(path) => read_args_file(path, params)?,
+        None => cli_args_to_json(args, params, resolve)?,
+    };
+    json_args_to_vals(&json_args, params, resolve, handles)
 }
 
-fn parse_arg(arg: &str, type_def: &TypeDef, resolve: &Resolve) -> eyre::Result<Val> {
-    match &type_def.kind {
-        TypeDefKind::Type(ty) => parse_primitive(strip_quotes(arg), ty),
-        TypeDefKind::Record(record) => {
-            let json: serde_json::Value = serde_json::from_str(arg)
-                .map_err(|e| eyre::eyre!("expected JSON for record: {e}"))?;
-            let serde_json::Value::Object(map) = json else {
-                bail!("expected JSON object for record");
-            };
-            let fields = record
-                .fields
-                .iter()
-                .map(|field| {
-                    let value = map
-                        .get(&field.name)
-                        .ok_or_else(|| eyre::eyre!("missing field '{}'", field.name))?;
-                    let val = json_value_to_val(value, &field.ty, resolve)?;
-                    Ok((field.name.clone(), val))
-                })
-                .collect::<eyre::Result<Vec<_>>>()?;
-            Ok(Val::Record(fields))
-        }
-        TypeDefKind::List(ty) => {
-            let json: serde_json::Value = serde_json::from_str(arg)
-                .map_err(|e| eyre::eyre!("expected JSON for list: {e}"))?;
-            let serde_json::Value::Array(arr) = json else {
-                bail!("expected JSON array for list");
-            };
-            let vals = arr
-                .iter()
-                .map(|v| json_value_to_val(v, ty, resolve))
-                .collect::<eyre::Result<Vec<_>>>()?;
-            Ok(Val::List(vals))
-        }
-        TypeDefKind::Tuple(tuple) => {
-            let json: serde_json::Value = serde_json::from_str(arg)
-                .map_err(|e| eyre::eyre!("expected JSON for tuple: {e}"))?;
-            let serde_json::Value::Array(arr) = json else {
-                bail!("expected JSON array for tuple");
-            };
-            if arr.len() != tuple.types.len() {
-                bail!(
-                    "tuple has {} elements, got {}",
-                    tuple.types.len(),
-                    arr.len()
-                );
-            }
-            let vals = arr
-                .iter()
-                .zip(tuple.types.iter())
-                .map(|(v, ty)| json_value_to_val(v, ty, resolve))
-                .collect::<eyre::Result<Vec<_>>>()?;
-            Ok(Val::Tuple(vals))
-        }
-        TypeDefKind::Enum(e) => {
-            let arg = strip_quotes(arg);
-            let is_valid = e.cases.iter().any(|c| c.name == arg);
-            if !is_valid {
-                let cases: Vec<&str> = e.cases.iter().map(|c| c.name.as_str()).collect();
-                bail!("invalid enum value '{arg}', expected one of: {cases:?}");
-            }
-            Ok(Val::Enum(arg.to_owned()))
-        }
-        TypeDefKind::Option(ty) => {
-            let arg_stripped = strip_quotes(arg);
-            if arg_stripped == "null" || arg_stripped == "none" {
-                return Ok(Val::Option(None));
+fn cli_args_to_json(
+    args: &[String],
+    params: &[(String, TypeDef)],
+    resolve: &Resolve,
+) -> eyre::Result<Vec<Value>> {
+    if args
+        .iter()
+        .any(|a| a == "--args-file" || a.starts_with("--args-file="))
+    {
+        bail!("--args-file cannot be combined with other arguments");
+    }
+    let is_named = args.iter().any(|a| named_arg(a).is_some());
+    if !is_named {
+        // Extra args are kept so that the count error reports them.
+        let json_args = args
+            .iter()
+            .enumerate()
+            .map(|(i, arg)| match params.get(i) {
+                Some((_, type_def)) => cli_arg_to_json(arg, type_def, resolve),
+                None => Value::String(arg.clone()),
+            })
+            .collect();
+        return Ok(json_args);
+    }
+    let mut named = serde_json::Map::new();
+    for (i, arg) in args.iter().enumerate() {
+        let (name, value) = match named_arg(arg) {
+            Some(named) => named,
+            None => {
+                let (name, _) = params.get(i).ok_or_eyre("too many positional arguments")?;
+                (name.as_str(), arg.as_str())
             }
-            let inner = json_value_to_val(&serde_json::from_str(arg)?, ty, resolve)?;
-            Ok(Val::Option(Some(Box::new(inner))))
-        }
-        TypeDefKind::Flags(flags) => {
-            let json: serde_json::Value = serde_json::from_str(arg)
-                .map_err(|e| eyre::eyre!("expected JSON for flags: {e}"))?;
-            let serde_json::Value::Array(arr) = json else {
-                bail!("expected JSON array for flags");
-            };
-            let names = arr
-                .iter()
-                .map(|v| match v {
-                    serde_json::Value::String(s) => {
-                        let is_valid = flags.flags.iter().any(|f| f.name == *s);
-                        if !is_valid {
-                            bail!("invalid flag '{s}'");
-                        }
-                        Ok(s.clone())
-                    }
-                    _ => bail!("expected string for flag name"),
-                })
-                .collect::<eyre::Result<Vec<_>>>()?;
-            Ok(Val::Flags(names))
-        }
-        _ => {
-            bail!("unsupported type: {:#?}", type_def.kind);
+        };
+        let json = match params.iter().find(|(n, _)| n == name) {
+            Some((_, type_def)) => cli_arg_to_json(value, type_def, resolve),
+            None => Value::String(value.to_owned()),
+        };
+        if named.insert(name.to_owned(), json).is_some() {
+            bail!("argument '{name}' given more than once");
         }
     }
+    named_json_args(&named, params)
 }
 
-/// Strips surrounding double quotes from a string if present.
-fn strip_quotes(s: &str) -> &str {
-    s.strip_prefix('"')
-        .and_then(|s| s.strip_suffix('"'))
-        .unwrap_or(s)
+/// Splits a `--<param>=<value>` arg into the param name and value.
+fn named_arg(arg: &str) -> Option<(&str, &str)> {
+    arg.strip_prefix("--")?.split_once('=')
+}
+
+/// Reads args from a JSON array of positional args,
+/// or a JSON object of named args, in a file or stdin.
+fn read_args_file(path: &str, params: &[(String, TypeDef)]) -> eyre::Result<Vec<Value>> {
+    let content = match path {
+        "-" => std::io::read_to_string(std::io::stdin())?,
+        path => std::fs::read_to_string(path)
+            .map_err(|e| eyre::eyre!("failed to read args file '{path}': {e}"))?,
+    };
+    let json = serde_json::from_str(&content)
+        .map_err(|e| eyre::eyre!("invalid JSON in args file '{path}': {e}"))?;
+    match json {
+        Value::Array(args) => Ok(args),
+        Value::Object(args) => named_json_args(&args, params),
+        _ => bail!("expected a JSON array or object of arguments in '{path}'"),
+    }
 }
 
 fn package_name_from_str(package_name_str: &str) -> eyre::Result<PackageName> {
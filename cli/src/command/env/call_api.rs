@@ -1,15 +1,23 @@
 use asterai_runtime::component::ComponentId;
 use asterai_runtime::component::function_name::ComponentFunctionName;
-use asterai_runtime::runtime::ComponentRuntime;
+use asterai_runtime::runtime::cron::CronManager;
+use asterai_runtime::runtime::handles::HandleTable;
 use asterai_runtime::runtime::http::HttpRouteTable;
-use asterai_runtime::runtime::parsing::{ValExt, json_value_to_val_typedef};
+use asterai_runtime::runtime::parsing::{
+    JSON_MAPPING_VERSION, JsonOptions, ValExt, json_args_to_vals,
+};
+use asterai_runtime::runtime::ws::WsManager;
+use asterai_runtime::runtime::{ComponentRuntime, Val};
 use axum::extract::State;
 use axum::response::IntoResponse;
 use hyper::StatusCode;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
+use uuid::Uuid;
 
 pub const RUNTIME_SECRET_ENV: &str = "ASTERAI_RUNTIME_SECRET";
 
@@ -17,8 +25,114 @@ pub const RUNTIME_SECRET_ENV: &str = "ASTERAI_RUNTIME_SECRET";
 pub struct AppState {
     pub route_table: Arc<HttpRouteTable>,
     pub runtime: Arc<Mutex<ComponentRuntime>>,
-    /// If set, `/v1/...` routes require `Authorization: Bearer <secret>`.
+    /// If set, `/v1/...` routes, `/metrics` and `/openapi.json` require
+    /// `Authorization: Bearer <secret>`.
     pub runtime_secret: Option<String>,
+    pub ws_manager: Option<Arc<WsManager>>,
+    pub cron_manager: Option<Arc<CronManager>>,
+    /// OpenAPI document of the call API, served at `/openapi.json`.
+    pub openapi: Arc<serde_json::Value>,
+    /// Sessions of callers holding handles.
+    pub sessions: HandleSessions,
+}
+
+/// Sessions idle for longer than this are ended, releasing their handles.
+const SESSION_IDLE_TTL: Duration = Duration::from_secs(30 * 60);
+/// Number of sessions beyond which the least recently used ones are ended.
+const MAX_SESSIONS: usize = 1024;
+/// Interval at which idle sessions are ended.
+const SESSION_EVICTION_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Handle tables of sessions, by session ID. IDs are random, so that
+/// handles are only valid for the caller they were returned to.
+#[derive(Clone, Default)]
+pub struct HandleSessions {
+    sessions: Arc<std::sync::Mutex<HashMap<String, Session>>>,
+}
+
+struct Session {
+    handles: HandleTable,
+    last_used: Instant,
+}
+
+impl HandleSessions {
+    /// Starts a session holding the handles of `table`, returning its ID.
+    pub fn insert(&self, table: HandleTable) -> String {
+        let id = Uuid::new_v4().to_string();
+        let session = Session {
+            handles: table,
+            last_used: Instant::now(),
+        };
+        self.sessions.lock().unwrap().insert(id.clone(), session);
+        id
+    }
+
+    /// Returns the handles of a session, marking it as used.
+    pub fn get(&self, id: &str) -> Option<HandleTable> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let session = sessions.get_mut(id)?;
+        session.last_used = Instant::now();
+        Some(session.handles.clone())
+    }
+
+    /// Ends a session, returning its handles for release.
+    pub fn remove(&self, id: &str) -> Option<HandleTable> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .remove(id)
+            .map(|session| session.handles)
+    }
+
+    /// Ends the sessions idle for longer than [`SESSION_IDLE_TTL`], and the
+    /// least recently used ones beyond [`MAX_SESSIONS`], returning their
+    /// handles for release.
+    pub fn evict(&self) -> Vec<HandleTable> {
+        self.evict_at(Instant::now())
+    }
+
+    fn evict_at(&self, now: Instant) -> Vec<HandleTable> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let mut ids: Vec<String> = sessions
+            .iter()
+            .filter(|(_, s)| now.saturating_duration_since(s.last_used) > SESSION_IDLE_TTL)
+            .map(|(id, _)| id.clone())
+            .collect();
+        let excess = (sessions.len() - ids.len()).saturating_sub(MAX_SESSIONS);
+        if excess > 0 {
+            let mut active: Vec<(&String, Instant)> = sessions
+                .iter()
+                .filter(|(id, _)| !ids.contains(id))
+                .map(|(id, s)| (id, s.last_used))
+                .collect();
+            active.sort_by_key(|(_, last_used)| *last_used);
+            let oldest = active.into_iter().take(excess).map(|(id, _)| id.clone());
+            ids.extend(oldest.collect::<Vec<_>>());
+        }
+        ids.iter()
+            .filter_map(|id| sessions.remove(id))
+            .map(|session| session.handles)
+            .collect()
+    }
+
+    /// Periodically ends idle sessions, passing their handles to `release`.
+    pub fn spawn_eviction<F, Fut>(&self, release: F)
+    where
+        F: Fn(Vec<HandleTable>) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send,
+    {
+        let sessions = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(SESSION_EVICTION_INTERVAL);
+            loop {
+                interval.tick().await;
+                let evicted = sessions.evict();
+                if !evicted.is_empty() {
+                    release(evicted).await;
+                }
+            }
+        });
+    }
 }
 
 #[derive(Deserialize)]
@@ -34,19 +148,121 @@ struct CallResponse {
     output: Option<serde_json::Value>,
 }
 
+/// Query of call requests.
+#[derive(Deserialize, Default)]
+pub struct CallQuery {
+    /// Encoding of `u64` and `s64` values in the output.
+    #[serde(default)]
+    int64: Int64Encoding,
+}
+
+#[derive(Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Int64Encoding {
+    #[default]
+    Number,
+    String,
+}
+
+/// Response header with the version of the JSON mapping of values.
+const JSON_MAPPING_HEADER: &str = "asterai-json-mapping";
+/// Header with the session whose handles a call uses. Calls with the
+/// value [`NEW_SESSION`] start one, returned in this header. Handles
+/// returned by calls without a session are released when they end.
+const SESSION_HEADER: &str = "asterai-session";
+const NEW_SESSION: &str = "new";
+
+/// Body of a call to a function given in the path.
+#[derive(Deserialize)]
+pub struct FunctionCallRequest {
+    #[serde(default)]
+    args: Vec<serde_json::Value>,
+}
+
 pub async fn handle_call(
     State(state): State<AppState>,
     axum::extract::Path((env_ns, env_name)): axum::extract::Path<(String, String)>,
+    axum::extract::Query(query): axum::extract::Query<CallQuery>,
     headers: axum::http::HeaderMap,
     axum::Json(body): axum::Json<CallRequest>,
 ) -> impl IntoResponse {
+    respond_to_call(&state, &env_ns, &env_name, &query, &headers, body).await
+}
+
+/// Calls the function at
+/// `/v1/environment/{ns}/{name}/call/{component-ns}/{component-name}/{function}`,
+/// which has one route per function in the OpenAPI document.
+pub async fn handle_function_call(
+    State(state): State<AppState>,
+    axum::extract::Path((env_ns, env_name, comp_ns, comp_name, function)): axum::extract::Path<(
+        String,
+        String,
+        String,
+        String,
+        String,
+    )>,
+    axum::extract::Query(query): axum::extract::Query<CallQuery>,
+    headers: axum::http::HeaderMap,
+    axum::Json(body): axum::Json<FunctionCallRequest>,
+) -> impl IntoResponse {
+    let body = CallRequest {
+        component: format!("{comp_ns}:{comp_name}"),
+        function,
+        args: body.args,
+    };
+    respond_to_call(&state, &env_ns, &env_name, &query, &headers, body).await
+}
+
+async fn respond_to_call(
+    state: &AppState,
+    env_ns: &str,
+    env_name: &str,
+    query: &CallQuery,
+    headers: &axum::http::HeaderMap,
+    body: CallRequest,
+) -> axum::response::Response {
     if let Some(secret) = &state.runtime_secret
-        && !check_bearer_token(&headers, secret)
+        && !check_bearer_token(headers, secret)
     {
         return (StatusCode::UNAUTHORIZED, "unauthorized").into_response();
     }
-    match handle_call_inner(&state, &env_ns, &env_name, body).await {
-        Ok(response) => (StatusCode::OK, axum::Json(response)).into_response(),
+    let options = JsonOptions {
+        is_int64_as_string: query.int64 == Int64Encoding::String,
+    };
+    let session_id = headers
+        .get(SESSION_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_owned);
+    let (session_id, handles) = match session_id.as_deref() {
+        Some(NEW_SESSION) => {
+            let handles = HandleTable::default();
+            (Some(state.sessions.insert(handles.clone())), handles)
+        }
+        Some(id) => match state.sessions.get(id) {
+            Some(handles) => (Some(id.to_owned()), handles),
+            None => return (StatusCode::NOT_FOUND, "session not found").into_response(),
+        },
+        None => (None, HandleTable::default()),
+    };
+    let result = handle_call_inner(state, env_ns, env_name, body, &handles, options).await;
+    if session_id.is_none() && !handles.is_empty() {
+        release_tables(state, vec![handles]).await;
+    }
+    match result {
+        Ok(response) => {
+            let mut response = (
+                StatusCode::OK,
+                [(JSON_MAPPING_HEADER, JSON_MAPPING_VERSION.to_string())],
+                axum::Json(response),
+            )
+                .into_response();
+            if let Some(id) = session_id
+                && let Ok(value) = id.parse()
+            {
+                response.headers_mut().insert(SESSION_HEADER, value);
+            }
+            response
+        }
         Err(e) => {
             let msg = format!("{e:#}");
             let status = match msg.contains("not found") {
@@ -63,6 +279,8 @@ async fn handle_call_inner(
     env_ns: &str,
     env_name: &str,
     body: CallRequest,
+    handles: &HandleTable,
+    options: JsonOptions,
 ) -> eyre::Result<CallResponse> {
     if env_ns != state.route_table.env_namespace() || env_name != state.route_table.env_name() {
         eyre::bail!("environment {env_ns}:{env_name} not found");
@@ -83,27 +301,97 @@ async fn handle_call_inner(
     let resolve = runtime
         .resolve_for(&comp_id)
         .ok_or_else(|| eyre::eyre!("component '{}' not found", body.component))?;
-    if body.args.len() != function.inputs.len() {
-        eyre::bail!(
-            "expected {} argument(s), got {}",
-            function.inputs.len(),
-            body.args.len()
-        );
-    }
-    let inputs = body
-        .args
-        .iter()
-        .zip(function.inputs.iter())
-        .map(|(arg, (_name, type_def))| json_value_to_val_typedef(arg, type_def, &resolve))
-        .collect::<eyre::Result<Vec<_>>>()?;
+    function.check_is_json_callable(&resolve)?;
+    let inputs = json_args_to_vals(&body.args, &function.inputs, &resolve, handles)?;
     let output_opt = runtime.call_function(function, &inputs).await?;
     let output = output_opt
         .and_then(|o| o.function_output_opt)
-        .and_then(|o| o.value.val.try_into_json_value());
+        .map(|o| o.value.val.into_json_value_with(handles, options));
     Ok(CallResponse { output })
 }
 
-fn check_bearer_token(headers: &axum::http::HeaderMap, expected: &str) -> bool {
+/// Ends a session, releasing its handles.
+pub async fn handle_end_session(
+    State(state): State<AppState>,
+    axum::extract::Path((env_ns, env_name, session_id)): axum::extract::Path<(
+        String,
+        String,
+        String,
+    )>,
+    headers: axum::http::HeaderMap,
+) -> impl IntoResponse {
+    if let Some(secret) = &state.runtime_secret
+        && !check_bearer_token(&headers, secret)
+    {
+        return (StatusCode::UNAUTHORIZED, "unauthorized").into_response();
+    }
+    if env_ns != state.route_table.env_namespace() || env_name != state.route_table.env_name() {
+        return (StatusCode::NOT_FOUND, "environment not found").into_response();
+    }
+    let Some(handles) = state.sessions.remove(&session_id) else {
+        return (StatusCode::NOT_FOUND, "session not found").into_response();
+    };
+    release(&state, handles.drain()).await
+}
+
+/// Releases one handle of a session.
+pub async fn handle_release_handle(
+    State(state): State<AppState>,
+    axum::extract::Path((env_ns, env_name, session_id, handle)): axum::extract::Path<(
+        String,
+        String,
+        String,
+        u64,
+    )>,
+    headers: axum::http::HeaderMap,
+) -> impl IntoResponse {
+    if let Some(secret) = &state.runtime_secret
+        && !check_bearer_token(&headers, secret)
+    {
+        return (StatusCode::UNAUTHORIZED, "unauthorized").into_response();
+    }
+    if env_ns != state.route_table.env_namespace() || env_name != state.route_table.env_name() {
+        return (StatusCode::NOT_FOUND, "environment not found").into_response();
+    }
+    let Some(handles) = state.sessions.get(&session_id) else {
+        return (StatusCode::NOT_FOUND, "session not found").into_response();
+    };
+    let Some(val) = handles.remove(handle) else {
+        return (StatusCode::NOT_FOUND, "handle not found").into_response();
+    };
+    release(&state, vec![val]).await
+}
+
+async fn release(state: &AppState, vals: Vec<Val>) -> axum::response::Response {
+    let mut runtime = state.runtime.lock().await;
+    match runtime.release_handles(vals).await {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")).into_response(),
+    }
+}
+
+/// Releases the handles of ended sessions, logging failures.
+pub async fn release_tables(state: &AppState, tables: Vec<HandleTable>) {
+    let vals = tables.iter().flat_map(HandleTable::drain).collect();
+    let mut runtime = state.runtime.lock().await;
+    if let Err(e) = runtime.release_handles(vals).await {
+        eprintln!("failed to release handles: {e:#}");
+    }
+}
+
+pub async fn handle_openapi(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+) -> impl IntoResponse {
+    if let Some(secret) = &state.runtime_secret
+        && !check_bearer_token(&headers, secret)
+    {
+        return (StatusCode::UNAUTHORIZED, "unauthorized").into_response();
+    }
+    axum::Json(state.openapi.as_ref().clone()).into_response()
+}
+
+pub(super) fn check_bearer_token(headers: &axum::http::HeaderMap, expected: &str) -> bool {
     let Some(value) = headers.get(axum::http::header::AUTHORIZATION) else {
         return false;
     };
@@ -113,3 +401,38 @@ fn check_bearer_token(headers: &axum::http::HeaderMap, expected: &str) -> bool {
     let token = value.strip_prefix("Bearer ").unwrap_or(value);
     token == expected
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idle_sessions_are_evicted() {
+        let sessions = HandleSessions::default();
+        let handles = HandleTable::default();
+        handles.insert(Val::U32(1));
+        let id = sessions.insert(handles);
+        assert!(sessions.evict_at(Instant::now()).is_empty());
+        let later = Instant::now() + SESSION_IDLE_TTL + Duration::from_secs(1);
+        let evicted = sessions.evict_at(later);
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].drain(), vec![Val::U32(1)]);
+        assert!(sessions.get(&id).is_none());
+    }
+
+    #[test]
+    fn test_least_recently_used_sessions_beyond_cap_are_evicted() {
+        let sessions = HandleSessions::default();
+        let first = sessions.insert(HandleTable::default());
+        let ids: Vec<String> = (0..MAX_SESSIONS)
+            .map(|_| sessions.insert(HandleTable::default()))
+            .collect();
+        // Using the first session makes it the most recently used.
+        std::thread::sleep(Duration::from_millis(1));
+        assert!(sessions.get(&first).is_some());
+        assert_eq!(sessions.evict().len(), 1);
+        assert!(sessions.get(&first).is_some());
+        let remaining = ids.iter().filter(|id| sessions.get(id).is_some());
+        assert_eq!(remaining.count(), MAX_SESSIONS - 1);
+    }
+}
@@ -84,6 +84,10 @@ impl CpArgs {
         );
         new_env.components = source_env.components.clone();
         new_env.vars = source_env.vars.clone();
+        new_env.wiring = source_env.wiring.clone();
+        new_env.interceptors = source_env.interceptors.clone();
+        new_env.mcp_servers = source_env.mcp_servers.clone();
+        new_env.mocks = source_env.mocks.clone();
         // Write to local storage.
         LocalStore::write_environment(&new_env)?;
         println!(
@@ -1,6 +1,10 @@
 use crate::command::env::EnvArgs;
 use crate::local_store::LocalStore;
+use asterai_runtime::component::Component;
+use asterai_runtime::component::binary::ComponentBinary;
+use asterai_runtime::component::config::ConfigVar;
 use std::collections::HashMap;
+use std::str::FromStr;
 
 #[derive(Debug, Clone)]
 pub struct InspectData {
@@ -8,6 +12,14 @@ pub struct InspectData {
     pub components: Vec<String>,
     pub vars: Vec<String>,
     pub var_values: HashMap<String, String>,
+    /// Wired imports, formatted as "consumer: interface -> provider".
+    pub wiring: Vec<String>,
+    /// Interceptors, formatted as "interface: interceptor, ...".
+    pub interceptors: Vec<String>,
+    /// MCP servers, formatted as "component: command or url".
+    pub mcp_servers: Vec<String>,
+    /// Mocks, formatted as "interface: mock file".
+    pub mocks: Vec<String>,
 }
 
 impl EnvArgs {
@@ -36,6 +48,64 @@ impl EnvArgs {
                 println!(" - {var}");
             }
         }
+        self.print_config()?;
+        if !data.wiring.is_empty() {
+            println!("wiring:");
+            for wire in &data.wiring {
+                println!(" - {wire}");
+            }
+        }
+        if !data.interceptors.is_empty() {
+            println!("interceptors:");
+            for interceptor in &data.interceptors {
+                println!(" - {interceptor}");
+            }
+        }
+        if !data.mcp_servers.is_empty() {
+            println!("mcp servers:");
+            for server in &data.mcp_servers {
+                println!(" - {server}");
+            }
+        }
+        if !data.mocks.is_empty() {
+            println!("mocks:");
+            for mock in &data.mocks {
+                println!(" - {mock}");
+            }
+        }
+        Ok(())
+    }
+
+    /// Prints the config vars declared by the locally stored components
+    /// of the environment, with their status in the environment.
+    fn print_config(&self) -> eyre::Result<()> {
+        let env = LocalStore::fetch_environment(&self.resource_id()?)?;
+        let mut components: Vec<ComponentBinary> = env
+            .component_refs()
+            .iter()
+            .filter_map(|r| Component::from_str(r).ok())
+            .filter_map(|c| LocalStore::fetch_component_version(&c).ok())
+            .filter(|c| !c.config().is_empty())
+            .collect();
+        if components.is_empty() {
+            return Ok(());
+        }
+        components.sort_by_key(|c| c.component().to_string());
+        println!("config:");
+        for component in &components {
+            println!(" {}", component.component().id());
+            for var in component.config() {
+                println!(
+                    "  - {}{}: {}",
+                    var.name,
+                    format_attributes(var),
+                    format_status(var, &env.vars)
+                );
+                if let Some(description) = &var.description {
+                    println!("    {description}");
+                }
+            }
+        }
         Ok(())
     }
 
@@ -54,11 +124,79 @@ impl EnvArgs {
             .collect();
         let mut vars: Vec<String> = var_values.keys().cloned().collect();
         vars.sort();
+        let mut wiring: Vec<String> = env
+            .wiring
+            .iter()
+            .flat_map(|(consumer, providers)| {
+                providers.iter().map(move |(interface, provider)| {
+                    format!("{consumer}: {interface} -> {provider}")
+                })
+            })
+            .collect();
+        wiring.sort();
+        let mut interceptors: Vec<String> = env
+            .interceptors
+            .iter()
+            .map(|(interface, chain)| format!("{interface}: {}", chain.join(", ")))
+            .collect();
+        interceptors.sort();
+        let mut mcp_servers: Vec<String> = env
+            .mcp_servers
+            .iter()
+            .map(|(component, config)| match &config.url {
+                Some(url) => format!("{component}: {url}"),
+                None => format!("{component}: {}", config.command.join(" ")),
+            })
+            .collect();
+        mcp_servers.sort();
+        let mut mocks: Vec<String> = env
+            .mocks
+            .iter()
+            .map(|(interface, path)| format!("{interface}: {path}"))
+            .collect();
+        mocks.sort();
         Ok(Some(InspectData {
             display_ref: env.display_ref(),
             components,
             vars,
             var_values,
+            wiring,
+            interceptors,
+            mcp_servers,
+            mocks,
         }))
     }
 }
+
+/// Formats the attributes of a config var, e.g. " (required, secret)".
+pub fn format_attributes(var: &ConfigVar) -> String {
+    let mut attributes = Vec::new();
+    if var.is_required {
+        attributes.push("required".to_owned());
+    }
+    if var.is_secret {
+        attributes.push("secret".to_owned());
+    }
+    if let Some(default) = &var.default {
+        attributes.push(format!("default {default}"));
+    }
+    if let Some(pattern) = &var.pattern {
+        attributes.push(format!("pattern {pattern}"));
+    }
+    match attributes.is_empty() {
+        true => String::new(),
+        false => format!(" ({})", attributes.join(", ")),
+    }
+}
+
+/// The status of a config var in an environment, e.g. "using default".
+pub fn format_status(var: &ConfigVar, vars: &HashMap<String, String>) -> String {
+    if let Some(problem) = var.check(vars) {
+        return problem;
+    }
+    match (vars.contains_key(&var.name), &var.default) {
+        (true, _) => "set".to_owned(),
+        (false, Some(_)) => "using default".to_owned(),
+        (false, None) => "not set".to_owned(),
+    }
+}
@@ -0,0 +1,595 @@
+use crate::command::env::call_api::{HandleSessions, RUNTIME_SECRET_ENV, check_bearer_token};
+use crate::command::resource_or_id::ResourceOrIdArg;
+use crate::local_store::LocalStore;
+use crate::runtime::build_runtime;
+use crate::trace;
+use asterai_runtime::component::binary::ComponentBinary;
+use asterai_runtime::component::function_interface::ComponentFunctionInterface;
+use asterai_runtime::component::json_schema::{
+    is_optional_param, params_json_schema, type_def_json_schema,
+};
+use asterai_runtime::component::{ComponentId, PackageName};
+use asterai_runtime::environment::Environment;
+use asterai_runtime::resource::ResourceId;
+use asterai_runtime::runtime::ComponentRuntime;
+use asterai_runtime::runtime::handles::HandleTable;
+use asterai_runtime::runtime::parsing::{ValExt, json_args_to_vals, named_json_args};
+use axum::extract::State;
+use axum::response::IntoResponse;
+use eyre::{OptionExt, bail, eyre};
+use hyper::StatusCode;
+use serde_json::{Map, Value, json};
+use std::collections::HashSet;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::io::AsyncBufReadExt;
+use tokio::sync::Mutex;
+
+/// MCP protocol versions this server speaks, newest first.
+const PROTOCOL_VERSIONS: [&str; 3] = ["2025-06-18", "2025-03-26", "2024-11-05"];
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Header of the streamable HTTP transport with the session of a client,
+/// which holds the handles returned to it.
+const SESSION_HEADER: &str = "mcp-session-id";
+
+#[derive(Debug)]
+pub struct McpArgs {
+    /// Environment reference (name or namespace:name[@version]).
+    env_ref: ResourceOrIdArg,
+    /// Serve over streamable HTTP instead of stdio.
+    is_http: bool,
+    host: String,
+    port: u16,
+}
+
+impl McpArgs {
+    pub fn parse(args: impl Iterator<Item = String>) -> eyre::Result<Self> {
+        let mut env_ref: Option<ResourceOrIdArg> = None;
+        let mut is_http = false;
+        let mut host = "127.0.0.1".to_string();
+        let mut port: u16 = 8080;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--http" => {
+                    is_http = true;
+                }
+                "--host" => {
+                    host = args.next().ok_or_eyre("--host requires a value")?;
+                }
+                "--port" | "-p" => {
+                    let value = args.next().ok_or_eyre("--port requires a value")?;
+                    port = value
+                        .parse()
+                        .map_err(|_| eyre!("invalid port: {}", value))?;
+                }
+                "--help" | "-h" | "help" => {
+                    print_help();
+                    std::process::exit(0);
+                }
+                other => {
+                    if other.starts_with('-') {
+                        bail!("unknown flag: {}", other);
+                    }
+                    if env_ref.is_some() {
+                        bail!("unexpected argument: {}", other);
+                    }
+                    env_ref = Some(ResourceOrIdArg::from_str(other).unwrap());
+                }
+            }
+        }
+        let env_ref = env_ref.ok_or_eyre(
+            "missing environment reference\n\n\
+             Usage: asterai env mcp <name>\n\
+             Example: asterai env mcp my-env",
+        )?;
+        Ok(Self {
+            env_ref,
+            is_http,
+            host,
+            port,
+        })
+    }
+
+    pub async fn execute(&self, allow_dirs: &[std::path::PathBuf]) -> eyre::Result<()> {
+        // Over stdio, stdout carries the protocol, so it is taken before
+        // anything else can write to it.
+        let protocol_out = match self.is_http {
+            true => None,
+            false => Some(take_stdout()),
+        };
+        let resource_id = ResourceId::from_str(&self.env_ref.with_local_namespace_fallback())
+            .map_err(|e| eyre!(e))?;
+        let environment = LocalStore::fetch_environment(&resource_id)
+            .map_err(|_| eyre!("environment '{}' not found locally", resource_id))?;
+        let runtime = build_runtime(environment.clone(), allow_dirs).await?;
+        let server = Arc::new(McpServer::new(&environment, runtime));
+        eprintln!(
+            "serving {} tool(s) of environment {} over MCP",
+            server.tools.len(),
+            environment.display_ref()
+        );
+        let result = match protocol_out {
+            Some(out) => serve_stdio(&server, out).await,
+            None => self.serve_http(server).await,
+        };
+        trace::flush().await;
+        result
+    }
+
+    async fn serve_http(&self, server: Arc<McpServer>) -> eyre::Result<()> {
+        let addr: SocketAddr = format!("{}:{}", self.host, self.port).parse()?;
+        let runtime_secret = std::env::var(RUNTIME_SECRET_ENV).ok();
+        if runtime_secret.is_some() {
+            eprintln!("MCP authentication enabled ({RUNTIME_SECRET_ENV} is set)");
+        }
+        let state = HttpState {
+            server,
+            runtime_secret,
+            sessions: HandleSessions::default(),
+        };
+        let eviction_server = state.server.clone();
+        state.sessions.spawn_eviction(move |tables| {
+            let server = eviction_server.clone();
+            async move {
+                for handles in &tables {
+                    server.release(handles).await;
+                }
+            }
+        });
+        let app = axum::Router::new()
+            .route(
+                "/mcp",
+                axum::routing::post(handle_mcp_post)
+                    .get(handle_mcp_get)
+                    .delete(handle_mcp_delete),
+            )
+            .with_state(state);
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        eprintln!("listening on http://{addr}/mcp");
+        tokio::select! {
+            result = axum::serve(listener, app) => result?,
+            result = tokio::signal::ctrl_c() => result?,
+        }
+        Ok(())
+    }
+}
+
+/// An exported component function served as an MCP tool.
+struct Tool {
+    name: String,
+    component_id: ComponentId,
+    function: ComponentFunctionInterface,
+    /// The tool as listed by `tools/list`.
+    definition: Value,
+}
+
+struct McpServer {
+    name: String,
+    version: String,
+    tools: Vec<Tool>,
+    runtime: Mutex<ComponentRuntime>,
+}
+
+impl McpServer {
+    fn new(environment: &Environment, runtime: ComponentRuntime) -> Self {
+        let tools = build_tools(&runtime.component_interfaces());
+        Self {
+            name: format!("{}:{}", environment.namespace(), environment.name()),
+            version: environment.version().to_string(),
+            tools,
+            runtime: Mutex::new(runtime),
+        }
+    }
+
+    /// Handles a JSON-RPC message of a client whose handles are held
+    /// in `handles`, returning the response to send if the message
+    /// was a request.
+    async fn handle_message(&self, message: Value, handles: &HandleTable) -> Option<Value> {
+        let Some(method) = message.get("method").and_then(Value::as_str) else {
+            // Responses from the client need no reply.
+            if message.get("result").is_some() || message.get("error").is_some() {
+                return None;
+            }
+            return Some(error_response(
+                Value::Null,
+                INVALID_REQUEST,
+                "invalid request",
+            ));
+        };
+        // Notifications have no ID and need no reply.
+        let id = message.get("id")?.clone();
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+        let result = match method {
+            "initialize" => Ok(self.initialize(&params)),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(self.list_tools()),
+            "tools/call" => self.call_tool(&params, handles).await,
+            _ => Err((METHOD_NOT_FOUND, format!("method not found: {method}"))),
+        };
+        let response = match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => error_response(id, code, &message),
+        };
+        Some(response)
+    }
+
+    fn initialize(&self, params: &Value) -> Value {
+        let requested = params.get("protocolVersion").and_then(Value::as_str);
+        let protocol_version = requested
+            .filter(|v| PROTOCOL_VERSIONS.contains(v))
+            .unwrap_or(PROTOCOL_VERSIONS[0]);
+        json!({
+            "protocolVersion": protocol_version,
+            "capabilities": { "tools": { "listChanged": false } },
+            "serverInfo": { "name": self.name, "version": self.version },
+        })
+    }
+
+    fn list_tools(&self) -> Value {
+        let tools: Vec<&Value> = self.tools.iter().map(|t| &t.definition).collect();
+        json!({ "tools": tools })
+    }
+
+    /// Calls a tool. Failed calls are reported in the result, so that
+    /// the model can see the error, rather than as protocol errors.
+    async fn call_tool(
+        &self,
+        params: &Value,
+        handles: &HandleTable,
+    ) -> Result<Value, (i64, String)> {
+        let name = params
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or((INVALID_PARAMS, "missing tool name".to_owned()))?;
+        let tool = self
+            .tools
+            .iter()
+            .find(|t| t.name == name)
+            .ok_or_else(|| (INVALID_PARAMS, format!("unknown tool: {name}")))?;
+        let empty = Map::new();
+        let arguments = params
+            .get("arguments")
+            .and_then(Value::as_object)
+            .unwrap_or(&empty);
+        let result = match self.call_function(tool, arguments, handles).await {
+            Ok(None) => json!({ "content": [], "isError": false }),
+            Ok(Some(output)) => {
+                let text = match output {
+                    Value::String(s) => s,
+                    other => other.to_string(),
+                };
+                json!({ "content": [{ "type": "text", "text": text }], "isError": false })
+            }
+            Err(e) => json!({
+                "content": [{ "type": "text", "text": format!("{e:#}") }],
+                "isError": true,
+            }),
+        };
+        Ok(result)
+    }
+
+    async fn call_function(
+        &self,
+        tool: &Tool,
+        arguments: &Map<String, Value>,
+        handles: &HandleTable,
+    ) -> eyre::Result<Option<Value>> {
+        let mut runtime = self.runtime.lock().await;
+        let resolve = runtime
+            .resolve_for(&tool.component_id)
+            .ok_or_else(|| eyre!("component '{}' not found", tool.component_id))?;
+        let args = named_json_args(arguments, &tool.function.inputs)?;
+        let inputs = json_args_to_vals(&args, &tool.function.inputs, &resolve, handles)?;
+        let output = runtime
+            .call_function(tool.function.clone(), &inputs)
+            .await?
+            .and_then(|o| o.function_output_opt)
+            .map(|o| o.value.val.into_json_value(handles));
+        Ok(output)
+    }
+
+    /// Releases the handles of a client that is gone.
+    async fn release(&self, handles: &HandleTable) {
+        let mut runtime = self.runtime.lock().await;
+        if let Err(e) = runtime.release_handles(handles.drain()).await {
+            eprintln!("failed to release handles: {e:#}");
+        }
+    }
+}
+
+/// Lists the exported functions of the components as tools,
+/// except for those called by the host, such as HTTP handlers,
+/// and those that cannot be called with JSON.
+/// Functions whose tool names collide get a numeric suffix, e.g.
+/// `asterai_fs__fs_read_2`, in the order of their components and functions.
+fn build_tools(components: &[ComponentBinary]) -> Vec<Tool> {
+    let mut components = components.to_vec();
+    components.sort_by_key(|c| c.component().id().to_string());
+    let mut tools = Vec::new();
+    let mut names = HashSet::new();
+    for binary in &components {
+        let resolve = binary.wit().resolve();
+        let component_id = binary.component().id();
+        let mut functions = binary.get_functions();
+        functions.retain(|f| {
+            !is_host_export(&f.package_name) && f.check_is_json_callable(resolve).is_ok()
+        });
+        functions.sort_by_key(|f| f.name.to_string());
+        for function in functions {
+            let name = tool_name(&component_id.to_string(), &function.name.to_string());
+            let name = (1..)
+                .map(|n| match n {
+                    1 => name.clone(),
+                    n => format!("{name}_{n}"),
+                })
+                .find(|name| names.insert(name.clone()))
+                .unwrap();
+            let input_schema =
+                params_json_schema(function.inputs.iter().map(|(name, type_def)| {
+                    (
+                        name.as_str(),
+                        type_def_json_schema(resolve, type_def),
+                        is_optional_param(type_def),
+                    )
+                }));
+            let mut definition = json!({
+                "name": name,
+                "title": format!("{component_id} {}", function.name),
+                "inputSchema": input_schema,
+            });
+            if let Some(docs) = &function.docs {
+                definition["description"] = json!(docs);
+            }
+            tools.push(Tool {
+                name,
+                component_id: component_id.clone(),
+                function,
+                definition,
+            });
+        }
+    }
+    tools
+}
+
+/// Whether a function is of an interface the host calls components
+/// through, such as `wasi:http/incoming-handler` or interceptors.
+fn is_host_export(package_name: &PackageName) -> bool {
+    package_name.namespace == "wasi"
+        || (package_name.namespace == "asterai" && package_name.name.starts_with("host"))
+}
+
+/// Derives a tool name such as `asterai_fs__fs_read`, as MCP clients
+/// commonly only accept letters, digits, `_` and `-` in tool names.
+fn tool_name(component_id: &str, function: &str) -> String {
+    let sanitize = |s: &str| -> String {
+        s.chars()
+            .map(|c| match c.is_ascii_alphanumeric() || c == '-' {
+                true => c,
+                false => '_',
+            })
+            .collect()
+    };
+    format!("{}__{}", sanitize(component_id), sanitize(function))
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+/// Serves newline-delimited JSON-RPC messages from stdin until it closes.
+async fn serve_stdio(server: &McpServer, mut out: Box<dyn Write + Send>) -> eyre::Result<()> {
+    // The process serves a single client.
+    let handles = HandleTable::default();
+    let mut lines = tokio::io::BufReader::new(tokio::io::stdin()).lines();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => server.handle_message(message, &handles).await,
+            Err(e) => Some(error_response(Value::Null, PARSE_ERROR, &e.to_string())),
+        };
+        let Some(response) = response else {
+            continue;
+        };
+        writeln!(out, "{response}")?;
+        out.flush()?;
+    }
+    server.release(&handles).await;
+    Ok(())
+}
+
+/// Returns a writer to the process's stdout, and points stdout at
+/// stderr so that other output, e.g. from components, cannot corrupt
+/// the protocol.
+#[cfg(unix)]
+fn take_stdout() -> Box<dyn Write + Send> {
+    use std::os::fd::{AsRawFd, FromRawFd};
+    std::io::stdout().flush().ok();
+    let stdout_fd = std::io::stdout().as_raw_fd();
+    let stderr_fd = std::io::stderr().as_raw_fd();
+    let protocol_fd = unsafe { libc::dup(stdout_fd) };
+    if protocol_fd < 0 {
+        return Box::new(std::io::stdout());
+    }
+    unsafe {
+        libc::dup2(stderr_fd, stdout_fd);
+    }
+    Box::new(unsafe { std::fs::File::from_raw_fd(protocol_fd) })
+}
+
+#[cfg(not(unix))]
+fn take_stdout() -> Box<dyn Write + Send> {
+    Box::new(std::io::stdout())
+}
+
+#[derive(Clone)]
+struct HttpState {
+    server: Arc<McpServer>,
+    /// If set, requests require `Authorization: Bearer <secret>`.
+    runtime_secret: Option<String>,
+    /// Sessions of clients, started by `initialize` requests.
+    sessions: HandleSessions,
+}
+
+/// Handles a message of the streamable HTTP transport,
+/// answering requests with a single JSON response.
+async fn handle_mcp_post(
+    State(state): State<HttpState>,
+    headers: axum::http::HeaderMap,
+    body: bytes::Bytes,
+) -> axum::response::Response {
+    if let Some(response) = reject_request(&state, &headers) {
+        return response;
+    }
+    let message: Value = match serde_json::from_slice(&body) {
+        Ok(message) => message,
+        Err(e) => {
+            let response = error_response(Value::Null, PARSE_ERROR, &e.to_string());
+            return (StatusCode::BAD_REQUEST, axum::Json(response)).into_response();
+        }
+    };
+    let is_initialize = message.get("method").and_then(Value::as_str) == Some("initialize");
+    let session_id = headers
+        .get(SESSION_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_owned);
+    let (session_id, handles) = match (is_initialize, session_id) {
+        (true, _) => {
+            let handles = HandleTable::default();
+            (Some(state.sessions.insert(handles.clone())), handles)
+        }
+        (false, Some(id)) => match state.sessions.get(&id) {
+            Some(handles) => (Some(id), handles),
+            None => return (StatusCode::NOT_FOUND, "session not found").into_response(),
+        },
+        // Handles of clients without a session only live for the request.
+        (false, None) => (None, HandleTable::default()),
+    };
+    let response = state.server.handle_message(message, &handles).await;
+    if session_id.is_none() {
+        state.server.release(&handles).await;
+    }
+    let mut response = match response {
+        Some(response) => axum::Json(response).into_response(),
+        None => StatusCode::ACCEPTED.into_response(),
+    };
+    if let Some(id) = session_id
+        && let Ok(value) = id.parse()
+    {
+        response.headers_mut().insert(SESSION_HEADER, value);
+    }
+    response
+}
+
+/// Ends the session of a client, releasing its handles.
+async fn handle_mcp_delete(
+    State(state): State<HttpState>,
+    headers: axum::http::HeaderMap,
+) -> axum::response::Response {
+    if let Some(response) = reject_request(&state, &headers) {
+        return response;
+    }
+    let Some(id) = headers.get(SESSION_HEADER).and_then(|v| v.to_str().ok()) else {
+        return (StatusCode::BAD_REQUEST, "missing session").into_response();
+    };
+    let Some(handles) = state.sessions.remove(id) else {
+        return (StatusCode::NOT_FOUND, "session not found").into_response();
+    };
+    state.server.release(&handles).await;
+    StatusCode::NO_CONTENT.into_response()
+}
+
+/// Returns the response rejecting an HTTP request,
+/// if its origin or bearer token is not allowed.
+fn reject_request(
+    state: &HttpState,
+    headers: &axum::http::HeaderMap,
+) -> Option<axum::response::Response> {
+    // Browsers send an origin, which must be checked
+    // to prevent DNS rebinding attacks.
+    let origin = headers
+        .get(axum::http::header::ORIGIN)
+        .and_then(|v| v.to_str().ok());
+    if let Some(origin) = origin
+        && !is_local_origin(origin)
+    {
+        return Some((StatusCode::FORBIDDEN, "forbidden origin").into_response());
+    }
+    if let Some(secret) = &state.runtime_secret
+        && !check_bearer_token(headers, secret)
+    {
+        return Some((StatusCode::UNAUTHORIZED, "unauthorized").into_response());
+    }
+    None
+}
+
+/// The server sends no messages of its own, so it offers no event stream.
+async fn handle_mcp_get() -> impl IntoResponse {
+    StatusCode::METHOD_NOT_ALLOWED
+}
+
+fn is_local_origin(origin: &str) -> bool {
+    let authority = origin
+        .split_once("://")
+        .map(|(_, rest)| rest)
+        .unwrap_or(origin);
+    let authority = authority.split('/').next().unwrap_or_default();
+    let host = match authority.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or_default(),
+        None => authority.split(':').next().unwrap_or_default(),
+    };
+    matches!(host, "localhost" | "127.0.0.1" | "::1")
+}
+
+fn print_help() {
+    println!(
+        r#"Serve an environment over the Model Context Protocol (MCP).
+
+Each exported component function is listed as a tool, with its WIT
+docs as the description and a JSON Schema of its parameters as the
+input schema. Tool names have the form <namespace>_<name>__<function>.
+
+By default, messages are exchanged over stdin and stdout, for MCP
+clients that launch the server. With --http, the streamable HTTP
+transport is served at /mcp instead.
+
+Resources returned by tools are held by the server as handles, which
+later tool calls of the same client can take. Over HTTP, clients keep
+them in the session started by `initialize` until they end it. Sessions
+idle for 30 minutes are ended, as are the least recently used ones
+beyond 1024 sessions.
+
+Usage: asterai env mcp <name> [options]
+
+Arguments:
+  <[namespace:]name[@version]>  Environment reference
+
+Options:
+  --http               Serve over streamable HTTP instead of stdio
+  --host <host>        HTTP server host (default: 127.0.0.1)
+  -p, --port <port>    HTTP server port (default: 8080)
+  -h, --help           Show this help message
+
+Environment variables:
+  ASTERAI_RUNTIME_SECRET  If set, HTTP requests require
+                          Authorization: Bearer <secret>
+
+Examples:
+  asterai env mcp my-env
+  asterai env mcp my-env --http -p 3001
+"#
+    );
+}
@@ -1,10 +1,13 @@
 use crate::command::common_flags::extract_common_flags;
 use crate::command::env::cp::CpArgs;
 use crate::command::env::delete::DeleteArgs;
+use crate::command::env::mcp::McpArgs;
+use crate::command::env::openapi::OpenApiArgs;
 use crate::command::env::pull::PullArgs;
 use crate::command::env::push::PushArgs;
 use crate::command::env::run::RunArgs;
 use crate::command::env::set_var::SetVarArgs;
+use crate::command::env::trace::TraceArgs;
 use crate::command::resource_or_id::ResourceOrIdArg;
 use crate::version_resolver::ComponentRef;
 use asterai_runtime::resource::ResourceId;
@@ -21,11 +24,15 @@ mod edit;
 pub(crate) mod init;
 pub(crate) mod inspect;
 pub(crate) mod list;
+mod mcp;
+mod openapi;
+mod precompile;
 pub(crate) mod pull;
 pub(crate) mod push;
 pub(crate) mod remove_component;
 mod run;
 pub(crate) mod set_var;
+mod trace;
 
 pub struct EnvArgs {
     action: EnvAction,
@@ -40,6 +47,9 @@ pub struct EnvArgs {
     pull_args: Option<PullArgs>,
     delete_args: Option<DeleteArgs>,
     cp_args: Option<CpArgs>,
+    trace_args: Option<TraceArgs>,
+    openapi_args: Option<OpenApiArgs>,
+    mcp_args: Option<McpArgs>,
     should_open_editor: bool,
     pub api_endpoint: String,
     pub registry_endpoint: String,
@@ -62,6 +72,10 @@ pub enum EnvAction {
     Rm,
     Edit,
     Cp,
+    Precompile,
+    Trace,
+    Openapi,
+    Mcp,
 }
 
 impl EnvArgs {
@@ -98,6 +112,9 @@ impl EnvArgs {
                 pull_args: None,
                 delete_args: None,
                 cp_args: None,
+                trace_args: None,
+                openapi_args: None,
+                mcp_args: None,
                 should_open_editor: false,
                 api_endpoint,
                 registry_endpoint,
@@ -125,13 +142,16 @@ impl EnvArgs {
                     pull_args: None,
                     delete_args: None,
                     cp_args: None,
+                    trace_args: None,
+                    openapi_args: None,
+                    mcp_args: None,
                     should_open_editor,
                     api_endpoint,
                     registry_endpoint,
                     allow_dirs: allow_dirs.clone(),
                 }
             }
-            action @ (EnvAction::Inspect | EnvAction::Edit) => Self {
+            action @ (EnvAction::Inspect | EnvAction::Edit | EnvAction::Precompile) => Self {
                 action,
                 env_resource_or_id: Some(parse_env_name_or_id()?),
                 component_arg: None,
@@ -144,6 +164,9 @@ impl EnvArgs {
                 pull_args: None,
                 delete_args: None,
                 cp_args: None,
+                trace_args: None,
+                openapi_args: None,
+                mcp_args: None,
                 should_open_editor: false,
                 api_endpoint,
                 registry_endpoint,
@@ -167,6 +190,9 @@ impl EnvArgs {
                     pull_args: None,
                     delete_args: None,
                     cp_args: None,
+                    trace_args: None,
+                    openapi_args: None,
+                    mcp_args: None,
                     should_open_editor: false,
                     api_endpoint,
                     registry_endpoint,
@@ -192,6 +218,9 @@ impl EnvArgs {
                     pull_args: None,
                     delete_args: None,
                     cp_args: None,
+                    trace_args: None,
+                    openapi_args: None,
+                    mcp_args: None,
                     should_open_editor: false,
                     api_endpoint,
                     registry_endpoint,
@@ -217,6 +246,9 @@ impl EnvArgs {
                     pull_args: None,
                     delete_args: None,
                     cp_args: None,
+                    trace_args: None,
+                    openapi_args: None,
+                    mcp_args: None,
                     should_open_editor: false,
                     api_endpoint,
                     registry_endpoint,
@@ -236,6 +268,9 @@ impl EnvArgs {
                 pull_args: None,
                 delete_args: None,
                 cp_args: None,
+                trace_args: None,
+                openapi_args: None,
+                mcp_args: None,
                 should_open_editor: false,
                 api_endpoint,
                 registry_endpoint,
@@ -254,6 +289,9 @@ impl EnvArgs {
                 pull_args: None,
                 delete_args: None,
                 cp_args: None,
+                trace_args: None,
+                openapi_args: None,
+                mcp_args: None,
                 should_open_editor: false,
                 api_endpoint,
                 registry_endpoint,
@@ -272,6 +310,9 @@ impl EnvArgs {
                 pull_args: None,
                 delete_args: None,
                 cp_args: None,
+                trace_args: None,
+                openapi_args: None,
+                mcp_args: None,
                 should_open_editor: false,
                 api_endpoint,
                 registry_endpoint,
@@ -290,6 +331,9 @@ impl EnvArgs {
                 pull_args: Some(PullArgs::parse(args)?),
                 delete_args: None,
                 cp_args: None,
+                trace_args: None,
+                openapi_args: None,
+                mcp_args: None,
                 should_open_editor: false,
                 api_endpoint,
                 registry_endpoint,
@@ -308,6 +352,9 @@ impl EnvArgs {
                 pull_args: None,
                 delete_args: Some(DeleteArgs::parse(args)?),
                 cp_args: None,
+                trace_args: None,
+                openapi_args: None,
+                mcp_args: None,
                 should_open_editor: false,
                 api_endpoint,
                 registry_endpoint,
@@ -326,6 +373,72 @@ impl EnvArgs {
                 pull_args: None,
                 delete_args: None,
                 cp_args: Some(CpArgs::parse(args)?),
+                trace_args: None,
+                openapi_args: None,
+                mcp_args: None,
+                should_open_editor: false,
+                api_endpoint,
+                registry_endpoint,
+                allow_dirs: allow_dirs.clone(),
+            },
+            EnvAction::Trace => Self {
+                action,
+                env_resource_or_id: None,
+                component_arg: None,
+                component_ref: None,
+                function: None,
+                function_args: vec![],
+                run_args: None,
+                set_var_args: None,
+                push_args: None,
+                pull_args: None,
+                delete_args: None,
+                cp_args: None,
+                trace_args: Some(TraceArgs::parse(args)?),
+                openapi_args: None,
+                mcp_args: None,
+                should_open_editor: false,
+                api_endpoint,
+                registry_endpoint,
+                allow_dirs: allow_dirs.clone(),
+            },
+            EnvAction::Openapi => Self {
+                action,
+                env_resource_or_id: None,
+                component_arg: None,
+                component_ref: None,
+                function: None,
+                function_args: vec![],
+                run_args: None,
+                set_var_args: None,
+                push_args: None,
+                pull_args: None,
+                delete_args: None,
+                cp_args: None,
+                trace_args: None,
+                openapi_args: Some(OpenApiArgs::parse(args)?),
+                mcp_args: None,
+                should_open_editor: false,
+                api_endpoint,
+                registry_endpoint,
+                allow_dirs: allow_dirs.clone(),
+            },
+            EnvAction::Mcp => Self {
+                action,
+                env_resource_or_id: None,
+                component_arg: None,
+                component_ref: None,
+                function: None,
+                function_args: vec![],
+                run_args: None,
+                set_var_args: None,
+                push_args: None,
+                pull_args: None,
+                delete_args: None,
+                cp_args: None,
+                trace_args: None,
+                openapi_args: None,
+                mcp_args: Some(McpArgs::parse(args)?),
                 should_open_editor: false,
                 api_endpoint,
                 registry_endpoint,
@@ -358,7 +471,8 @@ impl EnvArgs {
                 self.call().await?;
             }
             EnvAction::AddComponent => {
-                self.add_component().await?;
+                let component = self.add_component().await?;
+                self.prompt_config(&component)?;
             }
             EnvAction::RemoveComponent => {
                 self.remove_component().await?;
@@ -381,6 +495,18 @@ impl EnvArgs {
             EnvAction::Cp => {
                 self.cp()?;
             }
+            EnvAction::Precompile => {
+                self.precompile().await?;
+            }
+            EnvAction::Trace => {
+                self.trace()?;
+            }
+            EnvAction::Openapi => {
+                self.openapi().await?;
+            }
+            EnvAction::Mcp => {
+                self.mcp().await?;
+            }
         }
         Ok(())
     }
@@ -419,6 +545,21 @@ impl EnvArgs {
         args.execute()
     }
 
+    pub fn trace(&self) -> eyre::Result<()> {
+        let args = self.trace_args.as_ref().ok_or_eyre("no trace args")?;
+        args.execute()
+    }
+
+    pub async fn openapi(&self) -> eyre::Result<()> {
+        let args = self.openapi_args.as_ref().ok_or_eyre("no openapi args")?;
+        args.execute().await
+    }
+
+    pub async fn mcp(&self) -> eyre::Result<()> {
+        let args = self.mcp_args.as_ref().ok_or_eyre("no mcp args")?;
+        args.execute(&self.allow_dirs).await
+    }
+
     /// Create EnvArgs for a list operation.
     pub(crate) fn for_list(api_endpoint: String, registry_endpoint: String) -> Self {
         Self {
@@ -434,6 +575,9 @@ impl EnvArgs {
             pull_args: None,
             delete_args: None,
             cp_args: None,
+            trace_args: None,
+            openapi_args: None,
+            mcp_args: None,
             should_open_editor: false,
             api_endpoint,
             registry_endpoint,
@@ -460,6 +604,9 @@ impl EnvArgs {
             pull_args: None,
             delete_args: None,
             cp_args: None,
+            trace_args: None,
+            openapi_args: None,
+            mcp_args: None,
             should_open_editor: false,
             api_endpoint,
             registry_endpoint,
@@ -467,36 +614,6 @@ impl EnvArgs {
         }
     }
 
-    /// Create EnvArgs for a call operation.
-    pub(crate) fn for_call(
-        env_name: &str,
-        component: &str,
-        function: &str,
-        args: Vec<String>,
-        allow_dirs: Vec<std::path::PathBuf>,
-        api_endpoint: String,
-        registry_endpoint: String,
-    ) -> Self {
-        Self {
-            action: EnvAction::Call,
-            env_resource_or_id: Some(ResourceOrIdArg::from_str(env_name).unwrap()),
-            component_arg: Some(ResourceOrIdArg::from_str(component).unwrap()),
-            component_ref: None,
-            function: Some(function.to_string()),
-            function_args: args,
-            run_args: None,
-            set_var_args: None,
-            push_args: None,
-            pull_args: None,
-            delete_args: None,
-            cp_args: None,
-            should_open_editor: false,
-            api_endpoint,
-            registry_endpoint,
-            allow_dirs,
-        }
-    }
-
     /// Create EnvArgs for an init operation.
     pub(crate) fn for_init(
         env_name: &str,
@@ -516,6 +633,9 @@ impl EnvArgs {
             pull_args: None,
             delete_args: None,
             cp_args: None,
+            trace_args: None,
+            openapi_args: None,
+            mcp_args: None,
             should_open_editor: false,
             api_endpoint,
             registry_endpoint,
@@ -543,6 +663,9 @@ impl EnvArgs {
             pull_args: None,
             delete_args: None,
             cp_args: None,
+            trace_args: None,
+            openapi_args: None,
+            mcp_args: None,
             should_open_editor: false,
             api_endpoint,
             registry_endpoint,
@@ -570,6 +693,9 @@ impl EnvArgs {
             pull_args: None,
             delete_args: None,
             cp_args: None,
+            trace_args: None,
+            openapi_args: None,
+            mcp_args: None,
             should_open_editor: false,
             api_endpoint,
             registry_endpoint,
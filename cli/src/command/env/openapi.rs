@@ -0,0 +1,382 @@
+use crate::command::resource_or_id::ResourceOrIdArg;
+use crate::local_store::LocalStore;
+use crate::runtime::resolve_components;
+use asterai_runtime::component::binary::ComponentBinary;
+use asterai_runtime::component::function_interface::ComponentFunctionInterface;
+use asterai_runtime::component::json_schema::type_def_json_schema;
+use asterai_runtime::environment::Environment;
+use asterai_runtime::resource::ResourceId;
+use asterai_runtime::runtime::parsing::JSON_MAPPING_VERSION;
+use eyre::{Context, OptionExt, bail, eyre};
+use serde_json::{Map, Value, json};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub struct OpenApiArgs {
+    /// Environment reference (name or namespace:name[@version]).
+    env_ref: ResourceOrIdArg,
+    /// Base URL of the call API, e.g. "http://127.0.0.1:8080".
+    server_url: Option<String>,
+    /// Write the document to this file instead of stdout.
+    output: Option<PathBuf>,
+}
+
+impl OpenApiArgs {
+    pub fn parse(args: impl Iterator<Item = String>) -> eyre::Result<Self> {
+        let mut env_ref: Option<ResourceOrIdArg> = None;
+        let mut server_url: Option<String> = None;
+        let mut output: Option<PathBuf> = None;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--server" => {
+                    server_url = Some(args.next().ok_or_eyre("--server requires a value")?);
+                }
+                "--output" | "-o" => {
+                    let path = args.next().ok_or_eyre("--output requires a value")?;
+                    output = Some(PathBuf::from(path));
+                }
+                "--help" | "-h" | "help" => {
+                    print_help();
+                    std::process::exit(0);
+                }
+                other => {
+                    if other.starts_with('-') {
+                        bail!("unknown flag: {}", other);
+                    }
+                    if env_ref.is_some() {
+                        bail!("unexpected argument: {}", other);
+                    }
+                    env_ref = Some(ResourceOrIdArg::from_str(other).unwrap());
+                }
+            }
+        }
+        let env_ref = env_ref.ok_or_eyre(
+            "missing environment reference\n\n\
+             Usage: asterai env openapi <name>\n\
+             Example: asterai env openapi my-env",
+        )?;
+        Ok(Self {
+            env_ref,
+            server_url,
+            output,
+        })
+    }
+
+    pub async fn execute(&self) -> eyre::Result<()> {
+        let resource_id = ResourceId::from_str(&self.env_ref.with_local_namespace_fallback())
+            .map_err(|e| eyre!(e))?;
+        let environment = LocalStore::fetch_environment(&resource_id)
+            .map_err(|_| eyre!("environment '{}' not found locally", resource_id))?;
+        let components = resolve_components(&environment, vec![]).await?;
+        let document =
+            build_openapi_document(&environment, &components, self.server_url.as_deref(), false);
+        let json = serde_json::to_string_pretty(&document)?;
+        let Some(output) = &self.output else {
+            println!("{json}");
+            return Ok(());
+        };
+        std::fs::write(output, json)
+            .wrap_err_with(|| format!("failed to write {}", output.display()))?;
+        println!("wrote OpenAPI document to {}", output.display());
+        Ok(())
+    }
+}
+
+/// Builds the OpenAPI 3.1 document of an environment's call API,
+/// with one operation per exported component function.
+pub(crate) fn build_openapi_document(
+    environment: &Environment,
+    components: &[ComponentBinary],
+    server_url: Option<&str>,
+    is_auth_required: bool,
+) -> Value {
+    let env_ns = environment.namespace();
+    let env_name = environment.name();
+    let call_path = format!("/v1/environment/{env_ns}/{env_name}/call");
+    let mut paths = Map::new();
+    paths.insert(
+        call_path.clone(),
+        json!({ "post": generic_call_operation() }),
+    );
+    let session_path = format!("/v1/environment/{env_ns}/{env_name}/session/{{session}}");
+    paths.insert(
+        session_path.clone(),
+        json!({ "delete": end_session_operation() }),
+    );
+    paths.insert(
+        format!("{session_path}/handle/{{handle}}"),
+        json!({ "delete": release_handle_operation() }),
+    );
+    let mut components = components.to_vec();
+    components.sort_by_key(|c| c.component().id().to_string());
+    for binary in &components {
+        let resolve = binary.wit().resolve();
+        let mut functions = binary.get_functions();
+        functions.retain(|f| f.check_is_json_callable(resolve).is_ok());
+        functions.sort_by_key(|f| f.name.to_string());
+        for function in &functions {
+            let component = binary.component();
+            let path = format!(
+                "{call_path}/{}/{}/{}",
+                component.namespace(),
+                component.name(),
+                function.name
+            );
+            paths.insert(
+                path,
+                json!({ "post": function_operation(binary, function) }),
+            );
+        }
+    }
+    let mut document = json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": format!("{env_ns}:{env_name}"),
+            "version": environment.version(),
+            "description": format!(
+                "Call API of the asterai environment {env_ns}:{env_name}. \
+                 Each operation calls a component function with JSON-encoded arguments."
+            ),
+            "x-json-mapping-version": JSON_MAPPING_VERSION,
+        },
+        "paths": paths,
+    });
+    if let Some(server_url) = server_url {
+        document["servers"] = json!([{ "url": server_url }]);
+    }
+    if is_auth_required {
+        document["components"] = json!({
+            "securitySchemes": {
+                "bearerAuth": { "type": "http", "scheme": "bearer" },
+            },
+        });
+        document["security"] = json!([{ "bearerAuth": [] }]);
+    }
+    document
+}
+
+fn function_operation(binary: &ComponentBinary, function: &ComponentFunctionInterface) -> Value {
+    let resolve = binary.wit().resolve();
+    let component_id = binary.component().id();
+    let args: Vec<Value> = function
+        .inputs
+        .iter()
+        .map(|(name, type_def)| {
+            let mut schema = type_def_json_schema(resolve, type_def);
+            if let Value::Object(map) = &mut schema {
+                map.insert("title".to_owned(), json!(name));
+            }
+            schema
+        })
+        .collect();
+    let output = match &function.output_type {
+        Some(type_def) => type_def_json_schema(resolve, type_def),
+        None => json!({ "type": "null" }),
+    };
+    let mut operation = json!({
+        "operationId": operation_id(&component_id.to_string(), &function.name.to_string()),
+        "summary": format!("{component_id} {}", function.name),
+        "tags": [component_id.to_string()],
+        "parameters": call_parameters(),
+        "requestBody": {
+            "required": true,
+            "content": {
+                "application/json": {
+                    "schema": {
+                        "type": "object",
+                        "properties": {
+                            "args": {
+                                "type": "array",
+                                "prefixItems": args,
+                                "minItems": args.len(),
+                                "maxItems": args.len(),
+                            },
+                        },
+                        "required": ["args"],
+                    },
+                },
+            },
+        },
+        "responses": call_responses(output),
+    });
+    if let Some(docs) = &function.docs {
+        operation["description"] = json!(docs);
+    }
+    operation
+}
+
+fn generic_call_operation() -> Value {
+    json!({
+        "operationId": "call",
+        "summary": "Call any component function",
+        "description": "Calls a function given by name. \
+                        Prefer the per-function operations, which have typed arguments.",
+        "parameters": call_parameters(),
+        "requestBody": {
+            "required": true,
+            "content": {
+                "application/json": {
+                    "schema": {
+                        "type": "object",
+                        "properties": {
+                            "component": {
+                                "type": "string",
+                                "description": "Component, e.g. \"asterai:fs\".",
+                            },
+                            "function": {
+                                "type": "string",
+                                "description": "Function, e.g. \"fs/read\".",
+                            },
+                            "args": { "type": "array" },
+                        },
+                        "required": ["component", "function"],
+                    },
+                },
+            },
+        },
+        "responses": call_responses(json!({})),
+    })
+}
+
+fn end_session_operation() -> Value {
+    json!({
+        "operationId": "endSession",
+        "summary": "End a session",
+        "description": "Releases the handles of a session, dropping its resources.",
+        "parameters": [session_path_parameter()],
+        "responses": release_responses("Session not found."),
+    })
+}
+
+fn release_handle_operation() -> Value {
+    json!({
+        "operationId": "releaseHandle",
+        "summary": "Release a handle",
+        "description": "Releases a handle of a session, dropping its resource.",
+        "parameters": [
+            session_path_parameter(),
+            {
+                "name": "handle",
+                "in": "path",
+                "required": true,
+                "schema": { "type": "integer", "minimum": 0 },
+            },
+        ],
+        "responses": release_responses("Session or handle not found."),
+    })
+}
+
+fn session_path_parameter() -> Value {
+    json!({
+        "name": "session",
+        "in": "path",
+        "required": true,
+        "schema": { "type": "string" },
+    })
+}
+
+fn release_responses(not_found: &str) -> Value {
+    let text = json!({ "text/plain": { "schema": { "type": "string" } } });
+    json!({
+        "204": { "description": "Released." },
+        "401": { "description": "Missing or invalid bearer token.", "content": text },
+        "404": { "description": not_found, "content": text },
+    })
+}
+
+fn call_parameters() -> Value {
+    json!([
+        {
+            "name": "int64",
+            "in": "query",
+            "description": "Encoding of u64 and s64 values in the output. \
+                            Strings are exact in clients that parse numbers as doubles.",
+            "schema": { "type": "string", "enum": ["number", "string"], "default": "number" },
+        },
+        {
+            "name": "asterai-session",
+            "in": "header",
+            "description": "Session whose handles the call's arguments refer to, \
+                            and that handles in its output are added to. \
+                            The value `new` starts a session. Without a session, \
+                            handles in the output are released when the call ends.",
+            "schema": { "type": "string" },
+        },
+    ])
+}
+
+fn call_responses(output: Value) -> Value {
+    let text = json!({ "text/plain": { "schema": { "type": "string" } } });
+    json!({
+        "200": {
+            "description": "The function's return value.",
+            "headers": {
+                "asterai-json-mapping": {
+                    "description": "Version of the JSON mapping of values.",
+                    "schema": { "type": "integer" },
+                },
+                "asterai-session": {
+                    "description": "Session holding the handles of the call. \
+                                    It lives until it is ended or idle for 30 minutes.",
+                    "schema": { "type": "string" },
+                },
+            },
+            "content": {
+                "application/json": {
+                    "schema": {
+                        "type": "object",
+                        "properties": { "output": output },
+                        "required": ["output"],
+                    },
+                },
+            },
+        },
+        "401": { "description": "Missing or invalid bearer token.", "content": text },
+        "404": {
+            "description": "Component, function or session not found.",
+            "content": text,
+        },
+        "500": { "description": "Invalid arguments or failed call.", "content": text },
+    })
+}
+
+/// Derives an operation ID such as `asterai_fs__fs_read`.
+fn operation_id(component_id: &str, function: &str) -> String {
+    let sanitize = |s: &str| -> String {
+        s.chars()
+            .map(|c| match c.is_ascii_alphanumeric() {
+                true => c,
+                false => '_',
+            })
+            .collect()
+    };
+    format!("{}__{}", sanitize(component_id), sanitize(function))
+}
+
+fn print_help() {
+    println!(
+        r#"Print the OpenAPI document of an environment's call API.
+
+The document has one operation per exported component function, at
+/v1/environment/<ns>/<name>/call/<component-ns>/<component-name>/<function>,
+with JSON Schemas for the arguments and return value. `env run` serves
+the same document at /openapi.json.
+
+Usage: asterai env openapi <name> [options]
+
+Arguments:
+  <[namespace:]name[@version]>  Environment reference
+
+Options:
+  --server <url>       Base URL of the call API, e.g. http://127.0.0.1:8080
+  -o, --output <path>  Write the document to a file instead of stdout
+  -h, --help           Show this help message
+
+Examples:
+  asterai env openapi my-env
+  asterai env openapi my-env --server http://127.0.0.1:8080 -o openapi.json
+"#
+    );
+}
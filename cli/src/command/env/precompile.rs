@@ -0,0 +1,16 @@
+use crate::command::component::precompile::precompile_components;
+use crate::command::env::EnvArgs;
+use crate::local_store::LocalStore;
+use crate::runtime::resolve_components;
+
+impl EnvArgs {
+    /// Precompiles all components the environment runs with,
+    /// including auto-resolved dependencies.
+    pub async fn precompile(&self) -> eyre::Result<()> {
+        let resource_id = self.resource_id()?;
+        let env = LocalStore::fetch_environment(&resource_id)
+            .map_err(|_| eyre::eyre!("environment '{}' not found locally", resource_id))?;
+        let components = resolve_components(&env, vec![]).await?;
+        precompile_components(&components).await
+    }
+}
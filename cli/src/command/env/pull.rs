@@ -4,6 +4,7 @@ use crate::local_store::LocalStore;
 use crate::registry::{GetEnvironmentResponse, RegistryClient};
 use asterai_runtime::component::Component;
 use asterai_runtime::environment::{Environment, EnvironmentMetadata};
+use asterai_runtime::resource::ResourceId;
 use asterai_runtime::resource::metadata::ResourceKind;
 use eyre::{Context, OptionExt, bail};
 use reqwest::StatusCode;
@@ -120,7 +121,7 @@ impl PullArgs {
             component_list.push(component);
         }
         // Create local environment using new structure.
-        let environment = Environment {
+        let mut environment = Environment {
             metadata: EnvironmentMetadata {
                 namespace: env_data.namespace.clone(),
                 name: env_data.name.clone(),
@@ -128,7 +129,15 @@ impl PullArgs {
             },
             components: components_map,
             vars: env_data.vars,
+            wiring: env_data.wiring,
+            interceptors: env_data.interceptors,
+            mcp_servers: env_data.mcp_servers,
+            mocks: HashMap::new(),
         };
+        let local = ResourceId::new_from_parts(namespace.to_string(), name.to_string())
+            .ok()
+            .and_then(|id| LocalStore::fetch_environment(&id).ok());
+        environment.keep_local_settings(local.as_ref());
         LocalStore::write_environment(&environment)?;
         // Write additional metadata (pulled_from).
         let env_dir = LocalStore::environment_dir(&environment);
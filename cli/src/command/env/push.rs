@@ -1,6 +1,7 @@
 use crate::auth::Auth;
 use crate::local_store::LocalStore;
 use asterai_runtime::environment::Environment;
+use asterai_runtime::environment::mcp_servers::McpServerConfig;
 use asterai_runtime::resource::ResourceId;
 use eyre::{Context, OptionExt, bail};
 use reqwest::StatusCode;
@@ -20,6 +21,12 @@ pub struct PushArgs {
 struct PutEnvironmentRequest {
     components: Vec<String>,
     vars: HashMap<String, String>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    wiring: HashMap<String, HashMap<String, String>>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    interceptors: HashMap<String, Vec<String>>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    mcp_servers: HashMap<String, McpServerConfig>,
 }
 
 /// Response from pushing an environment.
@@ -93,6 +100,16 @@ impl PushArgs {
         let name = target_id.name();
 
         println!("pushing environment {}:{}...", namespace, name);
+        if !environment.mocks.is_empty() {
+            eprintln!("warning: mocks are only used locally and are not pushed");
+        }
+        let mcp_servers = environment.shared_mcp_servers();
+        if mcp_servers.len() != environment.mcp_servers.len() {
+            eprintln!(
+                "warning: MCP servers launched by a command are only used locally \
+                 and are not pushed"
+            );
+        }
 
         // Convert components to API format (namespace:name@version).
         let components: Vec<String> = environment.component_refs();
@@ -101,6 +118,9 @@ impl PushArgs {
         let request = PutEnvironmentRequest {
             components,
             vars: environment.vars.clone(),
+            wiring: environment.wiring.clone(),
+            interceptors: environment.interceptors.clone(),
+            mcp_servers,
         };
 
         let base_url = api_endpoint;
@@ -1,13 +1,21 @@
 use crate::auth::Auth;
-use crate::command::env::call_api::{AppState, RUNTIME_SECRET_ENV, handle_call};
+use crate::command::env::call_api::{
+    AppState, HandleSessions, RUNTIME_SECRET_ENV, check_bearer_token, handle_call,
+    handle_end_session, handle_function_call, handle_openapi, handle_release_handle,
+    release_tables,
+};
+use crate::command::env::openapi::build_openapi_document;
 use crate::command::resource_or_id::ResourceOrIdArg;
 use crate::local_store::LocalStore;
 use crate::registry::{GetEnvironmentResponse, RegistryClient};
-use crate::runtime::build_runtime;
+use crate::runtime::{build_runtime_from_components, check_config, resolve_components};
+use crate::trace::{self, TraceOptions};
 use asterai_runtime::component::Component;
 use asterai_runtime::environment::{Environment, EnvironmentMetadata};
+use asterai_runtime::resource::ResourceId;
 use asterai_runtime::resource::metadata::ResourceKind;
 use asterai_runtime::runtime::http::{self, HttpRouteTable};
+use asterai_runtime::runtime::metrics;
 use axum::extract::State;
 use axum::response::IntoResponse;
 use eyre::{Context, OptionExt, bail};
@@ -32,6 +40,11 @@ pub(super) struct RunArgs {
     host: String,
     cors_origins: Option<String>,
     allow_dirs: Vec<std::path::PathBuf>,
+    /// Write call traces to the environment's local trace file.
+    is_trace: bool,
+    otlp_endpoint: Option<String>,
+    /// Serve Prometheus metrics at `/metrics`.
+    is_metrics: bool,
 }
 
 impl RunArgs {
@@ -44,6 +57,9 @@ impl RunArgs {
         let mut port: u16 = 8080;
         let mut host = "127.0.0.1".to_string();
         let mut cors_origins: Option<String> = None;
+        let mut is_trace = false;
+        let mut otlp_endpoint: Option<String> = None;
+        let mut is_metrics = false;
         let mut args = args.peekable();
         while let Some(arg) = args.next() {
             match arg.as_str() {
@@ -69,6 +85,18 @@ impl RunArgs {
                             .ok_or_else(|| eyre::eyre!("--cors-origins requires a value"))?,
                     );
                 }
+                "--trace" => {
+                    is_trace = true;
+                }
+                "--otlp-endpoint" => {
+                    otlp_endpoint = Some(
+                        args.next()
+                            .ok_or_else(|| eyre::eyre!("--otlp-endpoint requires a value"))?,
+                    );
+                }
+                "--metrics" => {
+                    is_metrics = true;
+                }
                 "--help" | "-h" | "help" => {
                     print_help();
                     std::process::exit(0);
@@ -96,6 +124,9 @@ impl RunArgs {
             host,
             cors_origins,
             allow_dirs,
+            is_trace,
+            otlp_endpoint,
+            is_metrics,
         })
     }
 
@@ -139,29 +170,81 @@ impl RunArgs {
                 .await?
             }
         };
+        let mut trace_options = TraceOptions::from_env(&environment);
+        if self.is_trace {
+            trace_options.file = Some(trace::local_trace_path(
+                environment.namespace(),
+                environment.name(),
+            ));
+        }
+        if let Some(endpoint) = &self.otlp_endpoint {
+            trace_options.otlp_endpoint = Some(endpoint.clone());
+        }
+        trace_options.init(&environment)?;
+        if self.is_metrics {
+            metrics::enable();
+        }
+        // Fail before starting anything if the environment is not configured.
+        let components = resolve_components(&environment, vec![]).await?;
+        check_config(&environment, &components)?;
         // Run the environment.
-        let runtime = build_runtime(environment, &self.allow_dirs).await?;
+        let runtime =
+            build_runtime_from_components(environment.clone(), &self.allow_dirs, components)
+                .await?;
         let route_table = runtime.http_route_table();
-        let runtime = Arc::new(Mutex::new(runtime));
+        let ws_manager = runtime.ws_manager();
+        let cron_manager = runtime.cron_manager();
         // Always start the HTTP server (call API + component routes).
         let addr: SocketAddr = format!("{}:{}", self.host, self.port).parse()?;
         let runtime_secret = std::env::var(RUNTIME_SECRET_ENV).ok();
         if runtime_secret.is_some() {
             println!("call API authentication enabled ({RUNTIME_SECRET_ENV} is set)");
         }
+        let openapi = build_openapi_document(
+            &environment,
+            &runtime.component_interfaces(),
+            Some(&format!("http://{addr}")),
+            runtime_secret.is_some(),
+        );
+        let runtime = Arc::new(Mutex::new(runtime));
         let state = AppState {
             route_table: route_table.clone(),
             runtime: runtime.clone(),
             runtime_secret,
+            ws_manager,
+            cron_manager,
+            openapi: Arc::new(openapi),
+            sessions: HandleSessions::default(),
         };
-        let mut app = axum::Router::new()
+        let eviction_state = state.clone();
+        state.sessions.spawn_eviction(move |tables| {
+            let state = eviction_state.clone();
+            async move { release_tables(&state, tables).await }
+        });
+        let mut router = axum::Router::new()
             .route("/health", axum::routing::get(|| async { "ok" }))
+            .route("/openapi.json", axum::routing::get(handle_openapi))
             .route(
                 "/v1/environment/{env_ns}/{env_name}/call",
                 axum::routing::post(handle_call),
             )
-            .fallback(handle_request)
-            .with_state(state);
+            .route(
+                "/v1/environment/{env_ns}/{env_name}/call/{comp_ns}/{comp_name}/{*function}",
+                axum::routing::post(handle_function_call),
+            )
+            .route(
+                "/v1/environment/{env_ns}/{env_name}/session/{session}",
+                axum::routing::delete(handle_end_session),
+            )
+            .route(
+                "/v1/environment/{env_ns}/{env_name}/session/{session}/handle/{handle}",
+                axum::routing::delete(handle_release_handle),
+            );
+        if self.is_metrics {
+            router = router.route("/metrics", axum::routing::get(handle_metrics));
+            println!("serving metrics at http://{addr}/metrics");
+        }
+        let mut app = router.fallback(handle_request).with_state(state);
         if let Some(cors) = build_cors_layer(self.cors_origins.as_deref()) {
             app = app.layer(cors);
         }
@@ -186,6 +269,7 @@ impl RunArgs {
                 ws_mgr.close_all().await;
             }
         }
+        trace::flush().await;
         Ok(())
     }
 
@@ -283,7 +367,7 @@ impl RunArgs {
             component_list.push(component);
         }
         // Create local environment.
-        let environment = Environment {
+        let mut environment = Environment {
             metadata: EnvironmentMetadata {
                 namespace: env_data.namespace.clone(),
                 name: env_data.name.clone(),
@@ -291,7 +375,15 @@ impl RunArgs {
             },
             components: components_map,
             vars: env_data.vars,
+            wiring: env_data.wiring,
+            interceptors: env_data.interceptors,
+            mcp_servers: env_data.mcp_servers,
+            mocks: HashMap::new(),
         };
+        let local = ResourceId::new_from_parts(namespace.to_string(), name.to_string())
+            .ok()
+            .and_then(|id| LocalStore::fetch_environment(&id).ok());
+        environment.keep_local_settings(local.as_ref());
         LocalStore::write_environment(&environment)?;
         // Write additional metadata (pulled_from).
         let env_dir = LocalStore::environment_dir(&environment);
@@ -371,6 +463,26 @@ async fn handle_request(
     }
 }
 
+async fn handle_metrics(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+) -> impl IntoResponse {
+    if let Some(secret) = &state.runtime_secret
+        && !check_bearer_token(&headers, secret)
+    {
+        return (HyperStatusCode::UNAUTHORIZED, "unauthorized").into_response();
+    }
+    let body = metrics::render(state.ws_manager.as_deref(), state.cron_manager.as_deref()).await;
+    (
+        [(
+            axum::http::header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        body,
+    )
+        .into_response()
+}
+
 fn print_routes(route_table: &HttpRouteTable, addr: &SocketAddr) {
     let env_ns = route_table.env_namespace();
     let env_name = route_table.env_name();
@@ -422,10 +534,21 @@ Options:
                               directory. Can be specified multiple times.
                               Tilde (~) is expanded.
   --cors-origins <origins>    Comma-separated CORS origins, or "*" for all
+  --trace                     Write call traces to the local trace file
+                              (view with `asterai env trace`)
+  --otlp-endpoint <url>       Export call traces to an OTLP/HTTP collector
+  --metrics                   Serve Prometheus metrics at /metrics
   -h, --help                  Show this help message
 
+Agent approval policies (ASTERBOT_APPROVALS) are not applied: they only
+apply in the agents TUI and `asterai agents chat`.
+
 Environment variables:
-  ASTERAI_RUNTIME_SECRET      Require this secret as Bearer token for call API
+  ASTERAI_RUNTIME_SECRET      Require this secret as Bearer token for call API,
+                              /metrics and /openapi.json
+  ASTERAI_TRACE               Set to 1 to write call traces to the local trace
+                              file, or to a path to write them there
+  OTEL_EXPORTER_OTLP_ENDPOINT Same as --otlp-endpoint
 
 Examples:
   asterai env run my-env                    # Run latest, default namespace
@@ -434,6 +557,8 @@ Examples:
   asterai env run my-env --no-pull          # Run cached version only
   asterai env run my-env -p 3000            # Run with HTTP server on port 3000
   asterai env run my-env --allow-dir ~/.asterbot  # With filesystem access
+  asterai env run my-env --trace            # Record call traces
+  asterai env run my-env --metrics          # Serve metrics for Prometheus
 "#
     );
 }
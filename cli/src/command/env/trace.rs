@@ -0,0 +1,224 @@
+use crate::command::resource_or_id::ResourceOrIdArg;
+use crate::trace::local_trace_path;
+use asterai_runtime::runtime::call_trace::Span;
+use chrono::DateTime;
+use eyre::{Context, OptionExt, bail};
+use std::collections::HashMap;
+use std::fs;
+use std::str::FromStr;
+
+const DEFAULT_TRACE_COUNT: usize = 10;
+
+#[derive(Debug)]
+pub struct TraceArgs {
+    /// Environment reference (name or namespace:name).
+    env_ref: ResourceOrIdArg,
+    /// Number of most recent traces to show.
+    last: usize,
+    /// Only show the trace with an ID starting with this.
+    trace_id: Option<String>,
+    /// Only show traces with a failed call.
+    is_errors_only: bool,
+    /// Delete the local trace file instead of showing it.
+    should_clear: bool,
+}
+
+impl TraceArgs {
+    pub fn parse(args: impl Iterator<Item = String>) -> eyre::Result<Self> {
+        let mut env_ref: Option<ResourceOrIdArg> = None;
+        let mut last = DEFAULT_TRACE_COUNT;
+        let mut trace_id: Option<String> = None;
+        let mut is_errors_only = false;
+        let mut should_clear = false;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--last" | "-n" => {
+                    let value = args.next().ok_or_eyre("--last requires a value")?;
+                    last = value
+                        .parse()
+                        .map_err(|_| eyre::eyre!("invalid trace count: {}", value))?;
+                }
+                "--id" => {
+                    trace_id = Some(args.next().ok_or_eyre("--id requires a value")?);
+                }
+                "--errors" => {
+                    is_errors_only = true;
+                }
+                "--clear" => {
+                    should_clear = true;
+                }
+                "--help" | "-h" | "help" => {
+                    print_help();
+                    std::process::exit(0);
+                }
+                other => {
+                    if other.starts_with('-') {
+                        bail!("unknown flag: {}", other);
+                    }
+                    if env_ref.is_some() {
+                        bail!("unexpected argument: {}", other);
+                    }
+                    env_ref = Some(ResourceOrIdArg::from_str(other).unwrap());
+                }
+            }
+        }
+        let env_ref = env_ref.ok_or_eyre(
+            "missing environment reference\n\n\
+             Usage: asterai env trace <name>\n\
+             Example: asterai env trace my-env",
+        )?;
+        Ok(Self {
+            env_ref,
+            last,
+            trace_id,
+            is_errors_only,
+            should_clear,
+        })
+    }
+
+    pub fn execute(&self) -> eyre::Result<()> {
+        let namespace = self.env_ref.resolved_namespace();
+        let name = self.env_ref.name();
+        let path = local_trace_path(&namespace, name);
+        if self.should_clear {
+            if path.exists() {
+                fs::remove_file(&path)
+                    .wrap_err_with(|| format!("failed to delete {}", path.display()))?;
+            }
+            println!("cleared traces of environment {namespace}:{name}");
+            return Ok(());
+        }
+        if !path.exists() {
+            println!(
+                "no traces recorded for environment {namespace}:{name}\n\
+                 (run it with --trace or ASTERAI_TRACE=1 to record traces)"
+            );
+            return Ok(());
+        }
+        let content = fs::read_to_string(&path)
+            .wrap_err_with(|| format!("failed to read {}", path.display()))?;
+        let spans: Vec<Span> = content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect();
+        let mut traces = group_by_trace(spans);
+        if let Some(trace_id) = &self.trace_id {
+            traces.retain(|(id, _)| id.starts_with(trace_id.as_str()));
+        }
+        if self.is_errors_only {
+            traces.retain(|(_, spans)| spans.iter().any(|s| !s.is_ok()));
+        }
+        if traces.is_empty() {
+            println!("no matching traces");
+            return Ok(());
+        }
+        let skip = traces.len().saturating_sub(self.last);
+        for (trace_id, spans) in traces.into_iter().skip(skip) {
+            print_trace(&trace_id, &spans);
+        }
+        Ok(())
+    }
+}
+
+/// Groups spans by trace, ordered by the start of each trace.
+fn group_by_trace(spans: Vec<Span>) -> Vec<(String, Vec<Span>)> {
+    let mut by_trace: HashMap<String, Vec<Span>> = HashMap::new();
+    for span in spans {
+        by_trace
+            .entry(span.trace_id.clone())
+            .or_default()
+            .push(span);
+    }
+    let mut traces: Vec<(String, Vec<Span>)> = by_trace.into_iter().collect();
+    for (_, spans) in &mut traces {
+        spans.sort_by_key(|s| s.start_time_unix_nano);
+    }
+    traces.sort_by_key(|(_, spans)| spans[0].start_time_unix_nano);
+    traces
+}
+
+fn print_trace(trace_id: &str, spans: &[Span]) {
+    let start = spans[0].start_time_unix_nano;
+    let end = spans
+        .iter()
+        .map(|s| s.start_time_unix_nano + s.duration_nanos)
+        .max()
+        .unwrap_or(start);
+    let started_at = DateTime::from_timestamp_nanos(start as i64)
+        .format("%Y-%m-%d %H:%M:%S%.3f UTC")
+        .to_string();
+    println!(
+        "trace {trace_id} at {started_at} ({} span(s), {})",
+        spans.len(),
+        format_duration(end - start)
+    );
+    let span_ids: Vec<&str> = spans.iter().map(|s| s.span_id.as_str()).collect();
+    // Spans whose parent is not recorded, e.g. when the trace file
+    // was cleared mid-trace, are shown as roots.
+    let roots = spans.iter().filter(|s| {
+        s.parent_span_id
+            .as_deref()
+            .is_none_or(|parent| !span_ids.contains(&parent))
+    });
+    for root in roots {
+        print_span(root, spans, 1);
+    }
+    println!();
+}
+
+fn print_span(span: &Span, spans: &[Span], depth: usize) {
+    let outcome = match &span.error {
+        None => "ok".to_owned(),
+        Some(e) => format!("error: {}", e.lines().next().unwrap_or_default()),
+    };
+    println!(
+        "{}{} {} {} {} {}",
+        "  ".repeat(depth),
+        span.kind,
+        span.component,
+        span.function,
+        format_duration(span.duration_nanos),
+        outcome
+    );
+    let children = spans
+        .iter()
+        .filter(|s| s.parent_span_id.as_deref() == Some(span.span_id.as_str()));
+    for child in children {
+        print_span(child, spans, depth + 1);
+    }
+}
+
+fn format_duration(nanos: u64) -> String {
+    format!("{:.2}ms", nanos as f64 / 1_000_000.0)
+}
+
+fn print_help() {
+    println!(
+        r#"Show call traces recorded for an environment.
+
+Traces are recorded when running or calling an environment with
+--trace (env run) or ASTERAI_TRACE=1. Each trace shows the chain of
+calls between components, with durations and outcomes.
+
+Usage: asterai env trace <name> [options]
+
+Arguments:
+  <[namespace:]name>   Environment reference
+
+Options:
+  -n, --last <n>       Number of most recent traces to show (default: 10)
+  --id <trace-id>      Only show the trace with this ID (or ID prefix)
+  --errors             Only show traces with a failed call
+  --clear              Delete recorded traces
+  -h, --help           Show this help message
+
+Examples:
+  asterai env trace my-env
+  asterai env trace my-env --last 3
+  asterai env trace my-env --errors
+  asterai env trace my-env --clear
+"#
+    );
+}
@@ -1,15 +1,21 @@
+use crate::command::agents::AgentsArgs;
 use crate::command::auth::AuthArgs;
 use crate::command::component::ComponentArgs;
 use crate::command::env::EnvArgs;
 use crate::command::help::Help;
+use crate::command::registry::RegistryArgs;
+use crate::command::store::StoreArgs;
 use crate::command::version::Version;
 
+mod agents;
 pub(crate) mod auth;
 mod common_flags;
 pub(crate) mod component;
 pub(crate) mod env;
 mod help;
+mod registry;
 pub(crate) mod resource_or_id;
+mod store;
 mod version;
 
 #[allow(clippy::large_enum_variant)]
@@ -17,7 +23,9 @@ pub enum Command {
     Auth(AuthArgs),
     Env(EnvArgs),
     Component(ComponentArgs),
-    Agents,
+    Registry(RegistryArgs),
+    Store(StoreArgs),
+    Agents(AgentsArgs),
     Help,
     Version,
 }
@@ -31,7 +39,9 @@ impl Command {
             "auth" => AuthArgs::parse(args).map(Self::Auth),
             "env" => EnvArgs::parse(args).map(Self::Env),
             "component" => ComponentArgs::parse(args).map(Self::Component),
-            "agents" => Ok(Self::Agents),
+            "registry" => RegistryArgs::parse(args).map(Self::Registry),
+            "store" => StoreArgs::parse(args).map(Self::Store),
+            "agents" => AgentsArgs::parse(args).map(Self::Agents),
             "-v" | "-V" | "--version" => Ok(Self::Version),
             _ => Ok(Self::Help),
         }
@@ -42,7 +52,9 @@ impl Command {
             Command::Auth(args) => args.execute().await,
             Command::Env(args) => args.execute().await,
             Command::Component(args) => args.execute().await,
-            Command::Agents => crate::tui::run().await,
+            Command::Registry(args) => args.execute().await,
+            Command::Store(args) => args.execute(),
+            Command::Agents(args) => args.execute().await,
             Command::Version => Version::execute(),
             Command::Help => Help::execute(),
         }
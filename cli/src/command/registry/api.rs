@@ -0,0 +1,525 @@
+use crate::command::component::push::parse_package_name;
+use crate::command::registry::serve::{RegistryState, error_response, unauthorized};
+use crate::command::registry::storage::{
+    ARTIFACT_TYPE_COMPONENT, ARTIFACT_TYPE_WIT, Descriptor, EnvironmentRecord, MEDIA_TYPE_EMPTY,
+    MEDIA_TYPE_MANIFEST, MEDIA_TYPE_WASM, Manifest, RegistryStore, latest_semver,
+};
+use asterai_runtime::component::Component;
+use asterai_runtime::environment::ChangeReason;
+use axum::extract::{Multipart, Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use eyre::{OptionExt, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use wit_parser::PackageName;
+
+#[derive(Serialize)]
+struct TokenResponse {
+    token: String,
+    expires_in: u64,
+    issued_at: i64,
+}
+
+#[derive(Serialize)]
+struct UserResponse {
+    slug: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ArtifactSummary {
+    namespace: String,
+    name: String,
+    latest_version: String,
+}
+
+#[derive(Serialize)]
+struct ListComponentsResponse {
+    components: Vec<ArtifactSummary>,
+}
+
+#[derive(Serialize)]
+struct ListEnvironmentsResponse {
+    environments: Vec<ArtifactSummary>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PushComponentResponse {
+    namespace: String,
+    name: String,
+    version: String,
+}
+
+/// Matches the structured error the CLI expects on a version conflict.
+#[derive(Serialize)]
+struct VersionConflictError {
+    error: &'static str,
+    message: String,
+    version: String,
+    can_auto_bump: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PutEnvironmentRequest {
+    components: Vec<String>,
+    /// The rest of the manifest, stored as is.
+    #[serde(flatten)]
+    manifest: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PutEnvironmentResponse {
+    namespace: String,
+    name: String,
+    version: String,
+    previous_version: Option<String>,
+    change_type: Option<&'static str>,
+    change_reason: String,
+}
+
+/// Files and flags from a component push request.
+#[derive(Default)]
+struct ComponentUpload {
+    package: Option<Vec<u8>>,
+    component: Option<Vec<u8>>,
+    force: bool,
+}
+
+pub async fn get_token(State(state): State<RegistryState>, headers: HeaderMap) -> Response {
+    if !state.check_is_api_key_valid(&headers) {
+        return unauthorized();
+    }
+    let response = TokenResponse {
+        token: state.token.clone(),
+        expires_in: 3600,
+        issued_at: unix_timestamp(),
+    };
+    axum::Json(response).into_response()
+}
+
+pub async fn get_user(State(state): State<RegistryState>, headers: HeaderMap) -> Response {
+    if !state.check_is_api_key_valid(&headers) {
+        return unauthorized();
+    }
+    let response = UserResponse {
+        slug: state.namespace.clone(),
+    };
+    axum::Json(response).into_response()
+}
+
+pub async fn put_component(
+    State(state): State<RegistryState>,
+    headers: HeaderMap,
+    multipart: Multipart,
+) -> Response {
+    if !state.check_is_api_key_valid(&headers) {
+        return unauthorized();
+    }
+    let upload = match read_component_upload(multipart).await {
+        Ok(upload) => upload,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, e),
+    };
+    let package_name = match upload
+        .package
+        .as_deref()
+        .ok_or_eyre("missing package.wasm")
+        .and_then(parse_package_name)
+    {
+        Ok(package_name) => package_name,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, e),
+    };
+    if let Some(response) = reject_foreign_namespace(&state, &package_name.namespace) {
+        return response;
+    }
+    let _guard = state.write_lock.lock().await;
+    match store_component(&state.store, package_name, upload) {
+        Ok(Ok(response)) => axum::Json(response).into_response(),
+        Ok(Err(conflict)) => (StatusCode::CONFLICT, axum::Json(conflict)).into_response(),
+        Err(e) => error_response(StatusCode::BAD_REQUEST, e),
+    }
+}
+
+pub async fn list_components(State(state): State<RegistryState>, headers: HeaderMap) -> Response {
+    if !state.check_is_api_key_valid(&headers) {
+        return unauthorized();
+    }
+    let mut components = Vec::new();
+    for (namespace, name) in state.store.list_repositories() {
+        let tags = state.store.list_tags(&namespace, &name).unwrap_or_default();
+        let Some(latest_version) = latest_semver(tags.into_iter()) else {
+            continue;
+        };
+        components.push(ArtifactSummary {
+            namespace,
+            name,
+            latest_version,
+        });
+    }
+    axum::Json(ListComponentsResponse { components }).into_response()
+}
+
+pub async fn list_environments(State(state): State<RegistryState>, headers: HeaderMap) -> Response {
+    if !state.check_is_api_key_valid(&headers) {
+        return unauthorized();
+    }
+    let mut environments = Vec::new();
+    for (namespace, name) in state.store.list_environments() {
+        let Ok(Some(latest_version)) = state.store.latest_environment_version(&namespace, &name)
+        else {
+            continue;
+        };
+        environments.push(ArtifactSummary {
+            namespace,
+            name,
+            latest_version,
+        });
+    }
+    axum::Json(ListEnvironmentsResponse { environments }).into_response()
+}
+
+pub async fn put_environment(
+    State(state): State<RegistryState>,
+    Path((namespace, name)): Path<(String, String)>,
+    headers: HeaderMap,
+    axum::Json(body): axum::Json<PutEnvironmentRequest>,
+) -> Response {
+    if !state.check_is_api_key_valid(&headers) {
+        return unauthorized();
+    }
+    if let Some(response) = reject_foreign_namespace(&state, &namespace) {
+        return response;
+    }
+    let _guard = state.write_lock.lock().await;
+    match store_environment(&state.store, namespace, name, body) {
+        Ok(response) => axum::Json(response).into_response(),
+        Err(e) => error_response(StatusCode::BAD_REQUEST, e),
+    }
+}
+
+pub async fn get_environment(
+    State(state): State<RegistryState>,
+    Path((namespace, name)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> Response {
+    fetch_environment(&state, &headers, &namespace, &name, None)
+}
+
+pub async fn get_environment_version(
+    State(state): State<RegistryState>,
+    Path((namespace, name, version)): Path<(String, String, String)>,
+    headers: HeaderMap,
+) -> Response {
+    fetch_environment(&state, &headers, &namespace, &name, Some(&version))
+}
+
+pub async fn delete_environment(
+    State(state): State<RegistryState>,
+    Path((namespace, name)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> Response {
+    if !state.check_is_api_key_valid(&headers) {
+        return unauthorized();
+    }
+    if let Some(response) = reject_foreign_namespace(&state, &namespace) {
+        return response;
+    }
+    let _guard = state.write_lock.lock().await;
+    match state.store.delete_environment(&namespace, &name) {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => environment_not_found(&namespace, &name),
+        Err(e) => error_response(StatusCode::BAD_REQUEST, e),
+    }
+}
+
+fn fetch_environment(
+    state: &RegistryState,
+    headers: &HeaderMap,
+    namespace: &str,
+    name: &str,
+    version: Option<&str>,
+) -> Response {
+    if !state.check_is_api_key_valid(headers) {
+        return unauthorized();
+    }
+    match state.store.read_environment(namespace, name, version) {
+        Ok(Some(record)) => axum::Json(record).into_response(),
+        Ok(None) => environment_not_found(namespace, name),
+        Err(e) => error_response(StatusCode::BAD_REQUEST, e),
+    }
+}
+
+async fn read_component_upload(mut multipart: Multipart) -> eyre::Result<ComponentUpload> {
+    let mut upload = ComponentUpload::default();
+    while let Some(field) = multipart.next_field().await? {
+        let field_name = field.name().unwrap_or_default().to_string();
+        let bytes = field.bytes().await?;
+        match field_name.as_str() {
+            "package.wasm" => upload.package = Some(bytes.to_vec()),
+            "component.wasm" => upload.component = Some(bytes.to_vec()),
+            "force" => upload.force = bytes.as_ref() == b"true",
+            // Visibility is not enforced by the local registry.
+            "public" => {}
+            other => bail!("unexpected field: {other}"),
+        }
+    }
+    Ok(upload)
+}
+
+/// Stores an uploaded component as an OCI manifest tagged with its version,
+/// plus a WIT referrer manifest pointing at it.
+/// Returns the conflict error if the version exists and cannot be replaced.
+fn store_component(
+    store: &RegistryStore,
+    package_name: PackageName,
+    upload: ComponentUpload,
+) -> eyre::Result<Result<PushComponentResponse, VersionConflictError>> {
+    let package = upload.package.ok_or_eyre("missing package.wasm")?;
+    let namespace = package_name.namespace;
+    let name = package_name.name;
+    let version = match &package_name.version {
+        Some(v) => v.to_string(),
+        None => "latest".to_string(),
+    };
+    let is_mutable = package_name
+        .version
+        .as_ref()
+        .is_none_or(|v| !v.pre.is_empty());
+    let does_exist = store
+        .resolve_reference(&namespace, &name, &version)?
+        .is_some();
+    if does_exist && !upload.force && !is_mutable {
+        return Ok(Err(VersionConflictError {
+            error: "version_exists",
+            message: format!(
+                "version {version} of {namespace}:{name} already exists \
+                 (use --force to overwrite)"
+            ),
+            version,
+            can_auto_bump: true,
+        }));
+    }
+    let config = store.write_blob(b"{}", MEDIA_TYPE_EMPTY)?;
+    let layers = match &upload.component {
+        Some(bytes) => vec![store.write_blob(bytes, MEDIA_TYPE_WASM)?],
+        None => vec![],
+    };
+    let manifest = Manifest {
+        schema_version: 2,
+        media_type: MEDIA_TYPE_MANIFEST.to_string(),
+        artifact_type: ARTIFACT_TYPE_COMPONENT.to_string(),
+        config: config.clone(),
+        layers,
+        subject: None,
+    };
+    let manifest_descriptor = store.write_manifest(&manifest)?;
+    let wit_manifest = Manifest {
+        schema_version: 2,
+        media_type: MEDIA_TYPE_MANIFEST.to_string(),
+        artifact_type: ARTIFACT_TYPE_WIT.to_string(),
+        config,
+        layers: vec![store.write_blob(&package, MEDIA_TYPE_WASM)?],
+        subject: Some(Descriptor {
+            artifact_type: None,
+            ..manifest_descriptor.clone()
+        }),
+    };
+    let wit_descriptor = store.write_manifest(&wit_manifest)?;
+    store.add_referrer(
+        &namespace,
+        &name,
+        &manifest_descriptor.digest,
+        wit_descriptor,
+    )?;
+    store.tag_manifest(&namespace, &name, &version, &manifest_descriptor.digest)?;
+    println!("stored component {namespace}:{name}@{version}");
+    Ok(Ok(PushComponentResponse {
+        namespace,
+        name,
+        version,
+    }))
+}
+
+/// Stores a new environment version, bumping the semver version
+/// according to what changed since the latest stored version.
+fn store_environment(
+    store: &RegistryStore,
+    namespace: String,
+    name: String,
+    mut request: PutEnvironmentRequest,
+) -> eyre::Result<PutEnvironmentResponse> {
+    // These are set by the registry, not by the pushed manifest.
+    for key in ["namespace", "name", "version"] {
+        request.manifest.remove(key);
+    }
+    for component_ref in &request.components {
+        check_component_exists(store, component_ref)?;
+    }
+    let previous = store.read_environment(&namespace, &name, None)?;
+    let change_reason = match &previous {
+        Some(prev) => get_change_reason(prev, &request),
+        None => ChangeReason::Initial,
+    };
+    let change_type = get_change_type(&change_reason);
+    let version = match (&previous, change_type) {
+        (Some(prev), Some(change_type)) => bump_version(&prev.version, change_type)?,
+        (Some(prev), None) => prev.version.clone(),
+        (None, _) => "0.1.0".to_string(),
+    };
+    let record = EnvironmentRecord {
+        namespace,
+        name,
+        version,
+        components: request.components,
+        manifest: request.manifest,
+    };
+    store.write_environment(&record)?;
+    println!(
+        "stored environment {}:{}@{} ({})",
+        record.namespace, record.name, record.version, change_reason
+    );
+    Ok(PutEnvironmentResponse {
+        namespace: record.namespace,
+        name: record.name,
+        version: record.version,
+        previous_version: previous.map(|p| p.version),
+        change_type,
+        change_reason: change_reason.to_string(),
+    })
+}
+
+fn check_component_exists(store: &RegistryStore, component_ref: &str) -> eyre::Result<()> {
+    let component = Component::from_str(component_ref)?;
+    let version = component.version().to_string();
+    let reference = store.resolve_reference(component.namespace(), component.name(), &version)?;
+    if reference.is_none() {
+        bail!(
+            "component '{}:{}' not found in registry",
+            component.namespace(),
+            component.name()
+        );
+    }
+    Ok(())
+}
+
+fn get_change_reason(
+    previous: &EnvironmentRecord,
+    request: &PutEnvironmentRequest,
+) -> ChangeReason {
+    let previous_components = component_versions(&previous.components);
+    let components = component_versions(&request.components);
+    if previous_components
+        .keys()
+        .any(|id| !components.contains_key(id))
+    {
+        return ChangeReason::ComponentRemoved;
+    }
+    if components
+        .keys()
+        .any(|id| !previous_components.contains_key(id))
+    {
+        return ChangeReason::ComponentAdded;
+    }
+    if components != previous_components {
+        return ChangeReason::ComponentUpgraded;
+    }
+    let changed_keys: Vec<&String> = request
+        .manifest
+        .keys()
+        .chain(previous.manifest.keys())
+        .filter(|key| {
+            let (new, old) = (request.manifest.get(*key), previous.manifest.get(*key));
+            new != old && !(is_empty_value(new) && is_empty_value(old))
+        })
+        .collect();
+    let is_changed = |key: &str| changed_keys.iter().any(|k| k.as_str() == key);
+    if changed_keys.is_empty() {
+        return ChangeReason::NoChange;
+    }
+    if is_changed("vars") {
+        return ChangeReason::VarsChanged;
+    }
+    if is_changed("wiring") {
+        return ChangeReason::WiringChanged;
+    }
+    if is_changed("interceptors") {
+        return ChangeReason::InterceptorsChanged;
+    }
+    if is_changed("mcpServers") {
+        return ChangeReason::McpServersChanged;
+    }
+    ChangeReason::ManifestChanged
+}
+
+/// Returns whether a manifest field is absent or empty,
+/// which clients treat the same.
+fn is_empty_value(value: Option<&serde_json::Value>) -> bool {
+    match value {
+        None | Some(serde_json::Value::Null) => true,
+        Some(serde_json::Value::Object(map)) => map.is_empty(),
+        Some(serde_json::Value::Array(items)) => items.is_empty(),
+        Some(_) => false,
+    }
+}
+
+fn get_change_type(reason: &ChangeReason) -> Option<&'static str> {
+    match reason {
+        ChangeReason::ComponentRemoved => Some("major"),
+        ChangeReason::ComponentAdded | ChangeReason::ComponentUpgraded => Some("minor"),
+        ChangeReason::VarsChanged
+        | ChangeReason::WiringChanged
+        | ChangeReason::InterceptorsChanged
+        | ChangeReason::McpServersChanged
+        | ChangeReason::ManifestChanged => Some("patch"),
+        ChangeReason::Initial | ChangeReason::NoChange => None,
+    }
+}
+
+fn bump_version(version: &str, change_type: &str) -> eyre::Result<String> {
+    let v = semver::Version::parse(version)?;
+    let bumped = match change_type {
+        "major" => semver::Version::new(v.major + 1, 0, 0),
+        "minor" => semver::Version::new(v.major, v.minor + 1, 0),
+        _ => semver::Version::new(v.major, v.minor, v.patch + 1),
+    };
+    Ok(bumped.to_string())
+}
+
+/// Maps `namespace:name` to version for a list of component refs.
+fn component_versions(refs: &[String]) -> HashMap<&str, &str> {
+    refs.iter()
+        .map(|r| r.split_once('@').unwrap_or((r.as_str(), "")))
+        .collect()
+}
+
+fn unix_timestamp() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// Returns a 403 response if `namespace` is not the one served,
+/// as clients may only write to the served namespace.
+fn reject_foreign_namespace(state: &RegistryState, namespace: &str) -> Option<Response> {
+    if namespace == state.namespace {
+        return None;
+    }
+    let message = format!(
+        "namespace '{namespace}' is not served by this registry (only '{}')",
+        state.namespace
+    );
+    Some((StatusCode::FORBIDDEN, message).into_response())
+}
+
+fn environment_not_found(namespace: &str, name: &str) -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        format!("environment '{namespace}:{name}' not found"),
+    )
+        .into_response()
+}
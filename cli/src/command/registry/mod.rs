@@ -0,0 +1,59 @@
+use crate::command::registry::serve::ServeArgs;
+use eyre::{bail, eyre};
+use std::str::FromStr;
+use strum_macros::EnumString;
+
+mod api;
+mod oci;
+mod serve;
+mod storage;
+
+#[derive(Debug)]
+pub struct RegistryArgs {
+    action: RegistryAction,
+    serve_args: Option<ServeArgs>,
+}
+
+#[derive(Debug, Copy, Clone, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum RegistryAction {
+    Serve,
+}
+
+impl RegistryArgs {
+    pub fn parse(mut args: impl Iterator<Item = String>) -> eyre::Result<Self> {
+        let Some(action_string) = args.next() else {
+            bail!("missing registry command action");
+        };
+        let action = RegistryAction::from_str(&action_string)
+            .map_err(|_| eyre!("unknown registry action"))?;
+        let none_args = Self {
+            action,
+            serve_args: None,
+        };
+        let command_args = match action {
+            RegistryAction::Serve => Self {
+                serve_args: Some(ServeArgs::parse(args)?),
+                ..none_args
+            },
+        };
+        Ok(command_args)
+    }
+
+    pub async fn execute(&self) -> eyre::Result<()> {
+        match self.action {
+            RegistryAction::Serve => {
+                self.serve().await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn serve(&self) -> eyre::Result<()> {
+        let args = self
+            .serve_args
+            .as_ref()
+            .ok_or_else(|| eyre!("no serve args"))?;
+        args.execute().await
+    }
+}
@@ -0,0 +1,144 @@
+use crate::command::registry::serve::{RegistryState, error_response, unauthorized};
+use crate::command::registry::storage::{Index, MEDIA_TYPE_INDEX, MEDIA_TYPE_MANIFEST, TagList};
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct ReferrersQuery {
+    #[serde(rename = "artifactType")]
+    artifact_type: Option<String>,
+}
+
+/// OCI distribution API version check.
+pub async fn get_base(State(state): State<RegistryState>, headers: HeaderMap) -> Response {
+    if !state.check_is_token_valid(&headers) {
+        return unauthorized();
+    }
+    StatusCode::OK.into_response()
+}
+
+pub async fn get_manifest(
+    State(state): State<RegistryState>,
+    Path((namespace, name, reference)): Path<(String, String, String)>,
+    headers: HeaderMap,
+) -> Response {
+    if !state.check_is_token_valid(&headers) {
+        return unauthorized();
+    }
+    let store = &state.store;
+    let digest = match store.resolve_reference(&namespace, &name, &reference) {
+        Ok(Some(digest)) => digest,
+        Ok(None) => return manifest_unknown(&reference),
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, e),
+    };
+    let bytes = match store.read_blob(&digest) {
+        Ok(Some(bytes)) => bytes,
+        Ok(None) => return manifest_unknown(&reference),
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, e),
+    };
+    // Manifests and referrer indexes share the blob store,
+    // so report the media type declared by the document itself.
+    let media_type = serde_json::from_slice::<serde_json::Value>(&bytes)
+        .ok()
+        .and_then(|v| v["mediaType"].as_str().map(|s| s.to_string()))
+        .unwrap_or_else(|| MEDIA_TYPE_MANIFEST.to_string());
+    (
+        [
+            (header::CONTENT_TYPE, media_type),
+            (
+                header::HeaderName::from_static("docker-content-digest"),
+                digest,
+            ),
+        ],
+        bytes,
+    )
+        .into_response()
+}
+
+pub async fn get_blob(
+    State(state): State<RegistryState>,
+    Path((_namespace, _name, digest)): Path<(String, String, String)>,
+    headers: HeaderMap,
+) -> Response {
+    if !state.check_is_token_valid(&headers) {
+        return unauthorized();
+    }
+    match state.store.read_blob(&digest) {
+        Ok(Some(bytes)) => (
+            [
+                (header::CONTENT_TYPE, "application/octet-stream".to_string()),
+                (
+                    header::HeaderName::from_static("docker-content-digest"),
+                    digest,
+                ),
+            ],
+            bytes,
+        )
+            .into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, format!("blob {digest} not found")).into_response(),
+        Err(e) => error_response(StatusCode::BAD_REQUEST, e),
+    }
+}
+
+pub async fn list_tags(
+    State(state): State<RegistryState>,
+    Path((namespace, name)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> Response {
+    if !state.check_is_token_valid(&headers) {
+        return unauthorized();
+    }
+    match state.store.list_tags(&namespace, &name) {
+        Ok(tags) if tags.is_empty() => (
+            StatusCode::NOT_FOUND,
+            format!("repository {namespace}/{name} not found"),
+        )
+            .into_response(),
+        Ok(tags) => axum::Json(TagList {
+            name: format!("{namespace}/{name}"),
+            tags,
+        })
+        .into_response(),
+        Err(e) => error_response(StatusCode::BAD_REQUEST, e),
+    }
+}
+
+pub async fn get_referrers(
+    State(state): State<RegistryState>,
+    Path((namespace, name, digest)): Path<(String, String, String)>,
+    Query(query): Query<ReferrersQuery>,
+    headers: HeaderMap,
+) -> Response {
+    if !state.check_is_token_valid(&headers) {
+        return unauthorized();
+    }
+    let index = match state.store.read_referrers(&namespace, &name, &digest) {
+        Ok(index) => index,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, e),
+    };
+    // Clients send media types unencoded, so a `+` arrives decoded as a space.
+    let artifact_type = query.artifact_type.map(|t| t.replace(' ', "+"));
+    let manifests = index
+        .manifests
+        .into_iter()
+        .filter(|m| match &artifact_type {
+            Some(t) => m.artifact_type.as_ref() == Some(t),
+            None => true,
+        })
+        .collect();
+    (
+        [(header::CONTENT_TYPE, MEDIA_TYPE_INDEX)],
+        axum::Json(Index::new(manifests)),
+    )
+        .into_response()
+}
+
+fn manifest_unknown(reference: &str) -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        format!("manifest {reference} not found"),
+    )
+        .into_response()
+}
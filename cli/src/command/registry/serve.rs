@@ -0,0 +1,423 @@
+use crate::auth::Auth;
+use crate::command::registry::storage::RegistryStore;
+use crate::command::registry::{api, oci};
+use crate::config::BASE_DIR;
+use crate::runtime::expand_tilde;
+use asterai_runtime::checksum::Checksum;
+use axum::extract::DefaultBodyLimit;
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use eyre::bail;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Maximum accepted request body size (component uploads).
+const MAX_BODY_BYTES: usize = 512 * 1024 * 1024;
+/// Token issued when the registry runs without an API key.
+const ANONYMOUS_TOKEN: &str = "anonymous";
+
+#[derive(Debug)]
+pub(super) struct ServeArgs {
+    dir: PathBuf,
+    host: String,
+    port: u16,
+    api_key: Option<String>,
+    namespace: String,
+}
+
+#[derive(Clone)]
+pub struct RegistryState {
+    pub store: Arc<RegistryStore>,
+    /// Namespace returned to clients by `/v1/user`,
+    /// and the only one they may push to.
+    pub namespace: String,
+    /// If set, API routes require this key and OCI routes require `token`.
+    pub api_key: Option<String>,
+    pub token: String,
+    /// Serialises writes that read-modify-write the store.
+    pub write_lock: Arc<Mutex<()>>,
+}
+
+impl ServeArgs {
+    pub fn parse(args: impl Iterator<Item = String>) -> eyre::Result<Self> {
+        let home = std::env::var("HOME")
+            .or_else(|_| std::env::var("USERPROFILE"))
+            .ok();
+        let mut dir = BASE_DIR.join("registry");
+        let mut host = "127.0.0.1".to_string();
+        let mut port: u16 = 3003;
+        let mut api_key: Option<String> = None;
+        let mut namespace: Option<String> = None;
+        let mut args = args.peekable();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--dir" | "-d" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| eyre::eyre!("--dir requires a value"))?;
+                    dir = expand_tilde(&value, home.as_deref());
+                }
+                "--host" => {
+                    host = args
+                        .next()
+                        .ok_or_else(|| eyre::eyre!("--host requires a value"))?;
+                }
+                "--port" | "-p" => {
+                    let val = args
+                        .next()
+                        .ok_or_else(|| eyre::eyre!("--port requires a value"))?;
+                    port = val
+                        .parse()
+                        .map_err(|_| eyre::eyre!("invalid port: {}", val))?;
+                }
+                "--api-key" => {
+                    api_key = Some(
+                        args.next()
+                            .ok_or_else(|| eyre::eyre!("--api-key requires a value"))?,
+                    );
+                }
+                "--namespace" | "-n" => {
+                    namespace = Some(
+                        args.next()
+                            .ok_or_else(|| eyre::eyre!("--namespace requires a value"))?,
+                    );
+                }
+                "--help" | "-h" | "help" => {
+                    print_help();
+                    std::process::exit(0);
+                }
+                other => {
+                    if other.starts_with('-') {
+                        bail!("unknown flag: {}", other);
+                    }
+                    bail!("unexpected argument: {}", other);
+                }
+            }
+        }
+        Ok(Self {
+            dir,
+            host,
+            port,
+            api_key,
+            namespace: namespace.unwrap_or_else(Auth::read_user_or_fallback_namespace),
+        })
+    }
+
+    pub async fn execute(&self) -> eyre::Result<()> {
+        let store = RegistryStore::open(self.dir.clone())?;
+        let token = match &self.api_key {
+            Some(key) => hex::encode(Checksum::hash_str(key.trim()).bytes()),
+            None => ANONYMOUS_TOKEN.to_string(),
+        };
+        let state = RegistryState {
+            store: Arc::new(store),
+            namespace: self.namespace.clone(),
+            api_key: self.api_key.as_ref().map(|k| k.trim().to_string()),
+            token,
+            write_lock: Arc::new(Mutex::new(())),
+        };
+        let app = build_router(state);
+        let addr: SocketAddr = format!("{}:{}", self.host, self.port).parse()?;
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        println!("serving registry from {}", self.dir.display());
+        println!("listening on http://{addr}");
+        if self.api_key.is_none() {
+            println!("authentication disabled (no --api-key set)");
+        }
+        println!("use with: asterai <command> --endpoint http://{addr} --registry http://{addr}");
+        axum::serve(listener, app)
+            .with_graceful_shutdown(async {
+                let _ = tokio::signal::ctrl_c().await;
+            })
+            .await?;
+        Ok(())
+    }
+}
+
+impl RegistryState {
+    /// Checks the API key on `/v1` routes.
+    /// Accepts both the raw key and `Bearer <key>`.
+    pub fn check_is_api_key_valid(&self, headers: &HeaderMap) -> bool {
+        let Some(expected) = &self.api_key else {
+            return true;
+        };
+        get_authorization(headers).is_some_and(|value| value == expected)
+    }
+
+    /// Checks the registry token on `/v2` routes.
+    pub fn check_is_token_valid(&self, headers: &HeaderMap) -> bool {
+        if self.api_key.is_none() {
+            return true;
+        }
+        get_authorization(headers).is_some_and(|value| value == self.token)
+    }
+}
+
+fn build_router(state: RegistryState) -> axum::Router {
+    axum::Router::new()
+        .route("/health", get(|| async { "ok" }))
+        .route("/v1/registry/token", get(api::get_token))
+        .route("/v1/user", get(api::get_user))
+        .route("/v1/component", axum::routing::put(api::put_component))
+        .route("/v1/components", get(api::list_components))
+        .route("/v1/environments", get(api::list_environments))
+        .route(
+            "/v1/environment/{namespace}/{name}",
+            get(api::get_environment)
+                .put(api::put_environment)
+                .delete(api::delete_environment),
+        )
+        .route(
+            "/v1/environment/{namespace}/{name}/{version}",
+            get(api::get_environment_version),
+        )
+        .route("/v2/", get(oci::get_base))
+        .route(
+            "/v2/{namespace}/{name}/manifests/{reference}",
+            get(oci::get_manifest),
+        )
+        .route("/v2/{namespace}/{name}/blobs/{digest}", get(oci::get_blob))
+        .route("/v2/{namespace}/{name}/tags/list", get(oci::list_tags))
+        .route(
+            "/v2/{namespace}/{name}/referrers/{digest}",
+            get(oci::get_referrers),
+        )
+        .layer(DefaultBodyLimit::max(MAX_BODY_BYTES))
+        .with_state(state)
+}
+
+/// Returns the Authorization header value without any `Bearer ` prefix.
+fn get_authorization(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?.trim();
+    Some(value.strip_prefix("Bearer ").unwrap_or(value))
+}
+
+pub fn error_response(status: StatusCode, error: eyre::Report) -> Response {
+    (status, format!("{error:#}")).into_response()
+}
+
+pub fn unauthorized() -> Response {
+    (StatusCode::UNAUTHORIZED, "unauthorized").into_response()
+}
+
+fn print_help() {
+    println!(
+        r#"Serve a self-hosted registry backed by a local directory.
+
+Implements the subset of the asterai API and OCI distribution endpoints
+used by the CLI, so push, pull, ls and rm work against it unchanged.
+
+Usage: asterai registry serve [options]
+
+Options:
+  -d, --dir <path>          Storage directory (default: ~/.asterai/registry)
+      --host <host>         Host to bind to (default: 127.0.0.1)
+  -p, --port <port>         Port to listen on (default: 3003)
+      --api-key <key>       Require this API key (default: no authentication)
+  -n, --namespace <name>    Namespace reported to `asterai auth login`,
+                            and the only one clients may push to
+                            (default: your current namespace)
+  -h, --help                Show this help message
+
+Examples:
+  asterai registry serve
+  asterai registry serve --dir /srv/asterai --host 0.0.0.0 --api-key secret
+  asterai auth login secret -e http://localhost:3003
+  asterai env pull my-env -e http://localhost:3003 -r http://localhost:3003
+"#
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::{GetEnvironmentResponse, RegistryClient};
+    use serde_json::{Value, json};
+
+    /// Serves a registry backed by `dir` on a free port,
+    /// returning its base URL.
+    async fn serve(dir: &std::path::Path) -> String {
+        let state = RegistryState {
+            store: Arc::new(RegistryStore::open(dir.to_path_buf()).unwrap()),
+            namespace: "test".to_string(),
+            api_key: None,
+            token: ANONYMOUS_TOKEN.to_string(),
+            write_lock: Arc::new(Mutex::new(())),
+        };
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, build_router(state)).await.unwrap();
+        });
+        format!("http://{addr}")
+    }
+
+    fn encode_package(wit: &str) -> Vec<u8> {
+        let mut resolve = wit_parser::Resolve::default();
+        let pkg_id = resolve.push_str("greeter.wit", wit).unwrap();
+        wit_component::encode(&resolve, pkg_id).unwrap()
+    }
+
+    async fn push_environment(client: &reqwest::Client, url: &str, manifest: Value) -> Value {
+        let response = client
+            .put(format!("{url}/v1/environment/test/env"))
+            .json(&manifest)
+            .send()
+            .await
+            .unwrap();
+        assert!(response.status().is_success(), "{}", response.status());
+        response.json().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_push_pull_list_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = serve(dir.path()).await;
+        let client = reqwest::Client::new();
+        // Push a component.
+        let package =
+            encode_package("package test:greeter@0.1.0;\ninterface api { greet: func(); }\n");
+        let form = reqwest::multipart::Form::new()
+            .part("package.wasm", reqwest::multipart::Part::bytes(package))
+            .part(
+                "component.wasm",
+                reqwest::multipart::Part::bytes(b"component".to_vec()),
+            );
+        let response = client
+            .put(format!("{url}/v1/component"))
+            .multipart(form)
+            .send()
+            .await
+            .unwrap();
+        assert!(response.status().is_success(), "{}", response.status());
+        // Pull it back.
+        let registry = RegistryClient::new(&client, &url, &url);
+        let token = registry
+            .get_token(Some("unused"), "test/greeter")
+            .await
+            .unwrap();
+        let (manifest, digest) = registry
+            .fetch_manifest("test/greeter", "0.1.0", &token)
+            .await
+            .unwrap();
+        let blob = registry
+            .download_blob("test/greeter", &manifest.layers[0].digest, &token)
+            .await
+            .unwrap();
+        assert_eq!(blob, b"component");
+        let wit = registry
+            .fetch_wit_referrer("test/greeter", &digest, &token)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            crate::command::component::push::parse_package_name(&wit)
+                .unwrap()
+                .to_string(),
+            "test:greeter@0.1.0"
+        );
+        // Push an environment using it, then a new version of it.
+        let manifest = json!({
+            "components": ["test:greeter@0.1.0"],
+            "vars": { "GREETING": "hi" },
+            "futureField": { "kept": true },
+        });
+        let pushed = push_environment(&client, &url, manifest.clone()).await;
+        assert_eq!(pushed["version"], "0.1.0");
+        let mut manifest = manifest;
+        manifest["vars"]["GREETING"] = json!("hello");
+        let pushed = push_environment(&client, &url, manifest).await;
+        assert_eq!(pushed["version"], "0.1.1");
+        assert_eq!(pushed["changeReason"], "varsChanged");
+        // Pull the environment, as `env pull` does.
+        let response = client
+            .get(format!("{url}/v1/environment/test/env"))
+            .send()
+            .await
+            .unwrap();
+        let pulled: Value = response.json().await.unwrap();
+        assert_eq!(pulled["futureField"], json!({ "kept": true }));
+        let pulled: GetEnvironmentResponse = serde_json::from_value(pulled).unwrap();
+        assert_eq!(pulled.version, "0.1.1");
+        assert_eq!(pulled.components, ["test:greeter@0.1.0"]);
+        assert_eq!(pulled.vars["GREETING"], "hello");
+        let response = client
+            .get(format!("{url}/v1/environment/test/env/0.1.0"))
+            .send()
+            .await
+            .unwrap();
+        let pulled: GetEnvironmentResponse = response.json().await.unwrap();
+        assert_eq!(pulled.vars["GREETING"], "hi");
+        // List both.
+        let list = |path: &'static str| {
+            let client = client.clone();
+            let url = url.clone();
+            async move {
+                let response = client.get(format!("{url}{path}")).send().await.unwrap();
+                response.json::<Value>().await.unwrap()
+            }
+        };
+        assert_eq!(
+            list("/v1/components").await["components"],
+            json!([{ "namespace": "test", "name": "greeter", "latestVersion": "0.1.0" }])
+        );
+        assert_eq!(
+            list("/v1/environments").await["environments"],
+            json!([{ "namespace": "test", "name": "env", "latestVersion": "0.1.1" }])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_push_environment_with_missing_component() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = serve(dir.path()).await;
+        let response = reqwest::Client::new()
+            .put(format!("{url}/v1/environment/test/env"))
+            .json(&json!({ "components": ["test:missing@0.1.0"], "vars": {} }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(response.text().await.unwrap().contains("not found"));
+    }
+
+    #[tokio::test]
+    async fn test_push_to_other_namespace_is_forbidden() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = serve(dir.path()).await;
+        let client = reqwest::Client::new();
+        let package =
+            encode_package("package other:greeter@0.1.0;\ninterface api { greet: func(); }\n");
+        let form = reqwest::multipart::Form::new()
+            .part("package.wasm", reqwest::multipart::Part::bytes(package));
+        let response = client
+            .put(format!("{url}/v1/component"))
+            .multipart(form)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = client
+            .put(format!("{url}/v1/environment/other/env"))
+            .json(&json!({ "components": [] }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = client
+            .delete(format!("{url}/v1/environment/other/env"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let list = client
+            .get(format!("{url}/v1/components"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(list.json::<Value>().await.unwrap()["components"], json!([]));
+    }
+}
@@ -0,0 +1,352 @@
+use asterai_runtime::checksum::Checksum;
+use eyre::{Context, bail};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const MEDIA_TYPE_MANIFEST: &str = "application/vnd.oci.image.manifest.v1+json";
+pub const MEDIA_TYPE_INDEX: &str = "application/vnd.oci.image.index.v1+json";
+pub const MEDIA_TYPE_EMPTY: &str = "application/vnd.oci.empty.v1+json";
+pub const MEDIA_TYPE_WASM: &str = "application/wasm";
+pub const ARTIFACT_TYPE_COMPONENT: &str = "application/vnd.wasm.component.v1+wasm";
+pub const ARTIFACT_TYPE_WIT: &str = "application/vnd.wasm.wit.v1+wasm";
+
+/// OCI content descriptor, as written by the local registry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Descriptor {
+    pub media_type: String,
+    pub digest: String,
+    pub size: u64,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub artifact_type: Option<String>,
+}
+
+/// OCI image manifest, as written by the local registry.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    pub schema_version: u32,
+    pub media_type: String,
+    pub artifact_type: String,
+    pub config: Descriptor,
+    pub layers: Vec<Descriptor>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub subject: Option<Descriptor>,
+}
+
+/// OCI image index, used for referrers responses.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Index {
+    pub schema_version: u32,
+    pub media_type: String,
+    pub manifests: Vec<Descriptor>,
+}
+
+/// OCI tag list response.
+#[derive(Debug, Serialize)]
+pub struct TagList {
+    pub name: String,
+    pub tags: Vec<String>,
+}
+
+/// A stored environment version.
+///
+/// Only the components are interpreted by the registry. The rest of
+/// the manifest (vars, wiring, ...) is stored and served as pushed,
+/// so that new manifest fields need no change to the registry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentRecord {
+    pub namespace: String,
+    pub name: String,
+    pub version: String,
+    pub components: Vec<String>,
+    #[serde(flatten)]
+    pub manifest: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Directory-backed storage for the local registry.
+///
+/// Layout:
+/// ```text
+/// <root>/blobs/sha256/<hex>
+/// <root>/repositories/<namespace>/<name>/tags/<tag>
+/// <root>/repositories/<namespace>/<name>/referrers/<hex>.json
+/// <root>/environments/<namespace>/<name>/<version>.json
+/// ```
+pub struct RegistryStore {
+    root: PathBuf,
+}
+
+impl Index {
+    pub fn new(manifests: Vec<Descriptor>) -> Self {
+        Self {
+            schema_version: 2,
+            media_type: MEDIA_TYPE_INDEX.to_string(),
+            manifests,
+        }
+    }
+}
+
+impl RegistryStore {
+    pub fn open(root: PathBuf) -> eyre::Result<Self> {
+        fs::create_dir_all(root.join("blobs").join("sha256"))
+            .wrap_err_with(|| format!("failed to create registry directory {:?}", root))?;
+        fs::create_dir_all(root.join("repositories"))?;
+        fs::create_dir_all(root.join("environments"))?;
+        Ok(Self { root })
+    }
+
+    /// Writes a blob and returns its descriptor.
+    pub fn write_blob(&self, bytes: &[u8], media_type: &str) -> eyre::Result<Descriptor> {
+        let hex = hex::encode(Checksum::from_bytes(bytes).bytes());
+        let path = self.root.join("blobs").join("sha256").join(&hex);
+        if !path.exists() {
+            fs::write(&path, bytes).wrap_err_with(|| format!("failed to write blob {hex}"))?;
+        }
+        Ok(Descriptor {
+            media_type: media_type.to_string(),
+            digest: format!("sha256:{hex}"),
+            size: bytes.len() as u64,
+            artifact_type: None,
+        })
+    }
+
+    pub fn read_blob(&self, digest: &str) -> eyre::Result<Option<Vec<u8>>> {
+        let hex = parse_digest_hex(digest)?;
+        let path = self.root.join("blobs").join("sha256").join(hex);
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(fs::read(path)?))
+    }
+
+    /// Writes a manifest blob and returns its descriptor.
+    pub fn write_manifest(&self, manifest: &Manifest) -> eyre::Result<Descriptor> {
+        let bytes = serde_json::to_vec(manifest)?;
+        let mut descriptor = self.write_blob(&bytes, MEDIA_TYPE_MANIFEST)?;
+        descriptor.artifact_type = Some(manifest.artifact_type.clone());
+        Ok(descriptor)
+    }
+
+    /// Resolves a tag or digest to a manifest digest.
+    pub fn resolve_reference(
+        &self,
+        namespace: &str,
+        name: &str,
+        reference: &str,
+    ) -> eyre::Result<Option<String>> {
+        if reference.starts_with("sha256:") {
+            parse_digest_hex(reference)?;
+            return Ok(Some(reference.to_string()));
+        }
+        check_path_segment(reference)?;
+        let path = self.tags_dir(namespace, name)?.join(reference);
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(fs::read_to_string(path)?.trim().to_string()))
+    }
+
+    pub fn tag_manifest(
+        &self,
+        namespace: &str,
+        name: &str,
+        tag: &str,
+        digest: &str,
+    ) -> eyre::Result<()> {
+        check_path_segment(tag)?;
+        let dir = self.tags_dir(namespace, name)?;
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join(tag), digest)?;
+        Ok(())
+    }
+
+    pub fn list_tags(&self, namespace: &str, name: &str) -> eyre::Result<Vec<String>> {
+        let mut tags = list_file_names(&self.tags_dir(namespace, name)?);
+        tags.sort();
+        Ok(tags)
+    }
+
+    /// Records `descriptor` as a referrer of the manifest `subject_digest`.
+    pub fn add_referrer(
+        &self,
+        namespace: &str,
+        name: &str,
+        subject_digest: &str,
+        descriptor: Descriptor,
+    ) -> eyre::Result<()> {
+        let mut index = self.read_referrers(namespace, name, subject_digest)?;
+        index.manifests.retain(|m| m.digest != descriptor.digest);
+        index.manifests.push(descriptor);
+        let path = self.referrers_path(namespace, name, subject_digest)?;
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::write(path, serde_json::to_vec_pretty(&index)?)?;
+        Ok(())
+    }
+
+    pub fn read_referrers(
+        &self,
+        namespace: &str,
+        name: &str,
+        subject_digest: &str,
+    ) -> eyre::Result<Index> {
+        let path = self.referrers_path(namespace, name, subject_digest)?;
+        if !path.exists() {
+            return Ok(Index::new(vec![]));
+        }
+        let content = fs::read(path)?;
+        Ok(serde_json::from_slice(&content)?)
+    }
+
+    /// Lists all repositories as (namespace, name) pairs.
+    pub fn list_repositories(&self) -> Vec<(String, String)> {
+        list_nested_dirs(&self.root.join("repositories"))
+    }
+
+    pub fn write_environment(&self, record: &EnvironmentRecord) -> eyre::Result<()> {
+        check_path_segment(&record.version)?;
+        let dir = self.environment_dir(&record.namespace, &record.name)?;
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("{}.json", record.version));
+        fs::write(path, serde_json::to_vec_pretty(record)?)?;
+        Ok(())
+    }
+
+    /// Reads an environment at the given version, or the latest one.
+    pub fn read_environment(
+        &self,
+        namespace: &str,
+        name: &str,
+        version: Option<&str>,
+    ) -> eyre::Result<Option<EnvironmentRecord>> {
+        let version = match version {
+            Some(v) => v.to_string(),
+            None => match self.latest_environment_version(namespace, name)? {
+                Some(v) => v,
+                None => return Ok(None),
+            },
+        };
+        check_path_segment(&version)?;
+        let path = self
+            .environment_dir(namespace, name)?
+            .join(format!("{version}.json"));
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read(path)?;
+        Ok(Some(serde_json::from_slice(&content)?))
+    }
+
+    pub fn latest_environment_version(
+        &self,
+        namespace: &str,
+        name: &str,
+    ) -> eyre::Result<Option<String>> {
+        let versions = list_file_names(&self.environment_dir(namespace, name)?)
+            .into_iter()
+            .filter_map(|f| f.strip_suffix(".json").map(|s| s.to_string()));
+        Ok(latest_semver(versions))
+    }
+
+    /// Lists all environments as (namespace, name) pairs.
+    pub fn list_environments(&self) -> Vec<(String, String)> {
+        list_nested_dirs(&self.root.join("environments"))
+    }
+
+    /// Deletes all versions of an environment.
+    /// Returns false if the environment does not exist.
+    pub fn delete_environment(&self, namespace: &str, name: &str) -> eyre::Result<bool> {
+        let dir = self.environment_dir(namespace, name)?;
+        if !dir.exists() {
+            return Ok(false);
+        }
+        fs::remove_dir_all(dir)?;
+        Ok(true)
+    }
+
+    fn tags_dir(&self, namespace: &str, name: &str) -> eyre::Result<PathBuf> {
+        Ok(self.repository_dir(namespace, name)?.join("tags"))
+    }
+
+    fn referrers_path(
+        &self,
+        namespace: &str,
+        name: &str,
+        subject_digest: &str,
+    ) -> eyre::Result<PathBuf> {
+        let hex = parse_digest_hex(subject_digest)?;
+        Ok(self
+            .repository_dir(namespace, name)?
+            .join("referrers")
+            .join(format!("{hex}.json")))
+    }
+
+    fn repository_dir(&self, namespace: &str, name: &str) -> eyre::Result<PathBuf> {
+        check_path_segment(namespace)?;
+        check_path_segment(name)?;
+        Ok(self.root.join("repositories").join(namespace).join(name))
+    }
+
+    fn environment_dir(&self, namespace: &str, name: &str) -> eyre::Result<PathBuf> {
+        check_path_segment(namespace)?;
+        check_path_segment(name)?;
+        Ok(self.root.join("environments").join(namespace).join(name))
+    }
+}
+
+/// Returns the highest semver version, ignoring unparseable entries.
+pub fn latest_semver(versions: impl Iterator<Item = String>) -> Option<String> {
+    versions
+        .filter_map(|v| semver::Version::parse(&v).ok())
+        .max()
+        .map(|v| v.to_string())
+}
+
+/// Validates an `sha256:<hex>` digest and returns the hex part.
+fn parse_digest_hex(digest: &str) -> eyre::Result<&str> {
+    let Some(hex) = digest.strip_prefix("sha256:") else {
+        bail!("unsupported digest: {digest}");
+    };
+    let is_valid = hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit());
+    if !is_valid {
+        bail!("invalid digest: {digest}");
+    }
+    Ok(hex)
+}
+
+/// Rejects values that are unsafe to use as a single path segment.
+fn check_path_segment(value: &str) -> eyre::Result<()> {
+    let is_valid = !value.is_empty()
+        && !value.starts_with('.')
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '+'));
+    if !is_valid {
+        bail!("invalid name: {value}");
+    }
+    Ok(())
+}
+
+fn list_file_names(dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    entries
+        .flatten()
+        .filter_map(|e| e.file_name().to_str().map(|s| s.to_string()))
+        .collect()
+}
+
+fn list_nested_dirs(dir: &Path) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+    for namespace in list_file_names(dir) {
+        for name in list_file_names(&dir.join(&namespace)) {
+            pairs.push((namespace.clone(), name));
+        }
+    }
+    pairs.sort();
+    pairs
+}
@@ -0,0 +1,91 @@
+use crate::command::store::{dir_size, format_size};
+use crate::config::{ARTIFACTS_DIR, WASMTIME_CACHE_DIR};
+use crate::local_store::LocalStore;
+use eyre::bail;
+use std::collections::BTreeMap;
+
+/// Size of one stored version of a resource.
+struct VersionUsage {
+    version: String,
+    kind: String,
+    size: u64,
+}
+
+pub(super) fn parse(mut args: impl Iterator<Item = String>) -> eyre::Result<()> {
+    let Some(arg) = args.next() else {
+        return Ok(());
+    };
+    match arg.as_str() {
+        "--help" | "-h" | "help" => {
+            print_help();
+            std::process::exit(0);
+        }
+        other => bail!("unexpected argument: {}", other),
+    }
+}
+
+/// Prints disk usage per namespace, resource and version.
+pub(super) fn execute() -> eyre::Result<()> {
+    // namespace -> name -> versions.
+    let mut usage: BTreeMap<String, BTreeMap<String, Vec<VersionUsage>>> = BTreeMap::new();
+    for path in LocalStore::list_all_paths() {
+        let Ok(resource) = LocalStore::resource_from_path(&path) else {
+            continue;
+        };
+        let kind = match LocalStore::parse_metadata(&path) {
+            Ok(metadata) => metadata.kind.to_string(),
+            Err(_) => "unknown".to_string(),
+        };
+        usage
+            .entry(resource.namespace().to_string())
+            .or_default()
+            .entry(resource.name().to_string())
+            .or_default()
+            .push(VersionUsage {
+                version: resource.version().to_string(),
+                kind,
+                size: dir_size(&path),
+            });
+    }
+    println!("artifacts ({}):", ARTIFACTS_DIR.display());
+    if usage.is_empty() {
+        println!("  (none)");
+    }
+    let mut total = 0;
+    for (namespace, resources) in &mut usage {
+        let namespace_size: u64 = resources.values().flatten().map(|v| v.size).sum();
+        total += namespace_size;
+        println!("  {:<48} {:>10}", namespace, format_size(namespace_size));
+        for (name, versions) in resources {
+            let resource_size: u64 = versions.iter().map(|v| v.size).sum();
+            let resource_id = format!("{namespace}:{name}");
+            println!("    {:<46} {:>10}", resource_id, format_size(resource_size));
+            versions.sort_by_cached_key(|v| semver::Version::parse(&v.version).ok());
+            for v in versions.iter() {
+                let label = format!("@{}  [{}]", v.version, v.kind);
+                println!("      {:<44} {:>10}", label, format_size(v.size));
+            }
+        }
+    }
+    println!("  {:<48} {:>10}", "total", format_size(total));
+    if let Some(cache_dir) = &*WASMTIME_CACHE_DIR
+        && cache_dir.exists()
+    {
+        println!();
+        println!("wasmtime compilation cache ({}):", cache_dir.display());
+        println!("  {:<48} {:>10}", "total", format_size(dir_size(cache_dir)));
+    }
+    Ok(())
+}
+
+fn print_help() {
+    println!(
+        r#"Show disk usage of the local artifact store.
+
+Prints the size of each namespace, resource and version under the artifacts
+directory, followed by the size of the wasmtime compilation cache.
+
+Usage: asterai store du
+"#
+    );
+}
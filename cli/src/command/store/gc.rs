@@ -0,0 +1,182 @@
+use crate::command::store::{dir_size, format_size};
+use crate::local_store::LocalStore;
+use asterai_runtime::component::binary::ComponentBinary;
+use asterai_runtime::environment::deps;
+use asterai_runtime::resource::Resource;
+use eyre::{OptionExt, bail};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub(super) struct GcArgs {
+    /// Only print what would be removed.
+    is_dry_run: bool,
+    /// Number of most recent versions to keep per component,
+    /// even if unreferenced.
+    keep_latest: usize,
+}
+
+impl GcArgs {
+    pub fn parse(args: impl Iterator<Item = String>) -> eyre::Result<Self> {
+        let mut is_dry_run = false;
+        let mut keep_latest = 0;
+        let mut args = args.peekable();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--dry-run" | "-n" => {
+                    is_dry_run = true;
+                }
+                "--keep-latest" | "-k" => {
+                    let value = args.next().ok_or_eyre("--keep-latest requires a value")?;
+                    keep_latest = value
+                        .parse()
+                        .map_err(|_| eyre::eyre!("invalid --keep-latest value: {}", value))?;
+                }
+                "--help" | "-h" | "help" => {
+                    print_help();
+                    std::process::exit(0);
+                }
+                other => {
+                    if other.starts_with('-') {
+                        bail!("unknown flag: {}", other);
+                    }
+                    bail!("unexpected argument: {}", other);
+                }
+            }
+        }
+        Ok(Self {
+            is_dry_run,
+            keep_latest,
+        })
+    }
+
+    pub fn execute(&self) -> eyre::Result<()> {
+        let components = LocalStore::list_component_paths();
+        let mut keep = referenced_components(&components);
+        keep.extend(latest_versions(&components, self.keep_latest));
+        let mut removed_count = 0;
+        let mut freed = 0;
+        for (resource, path) in &components {
+            if keep.contains(resource) {
+                continue;
+            }
+            let size = dir_size(path);
+            match self.is_dry_run {
+                true => println!("would remove {} ({})", resource, format_size(size)),
+                false => {
+                    fs::remove_dir_all(path)?;
+                    println!("removed {} ({})", resource, format_size(size));
+                }
+            }
+            removed_count += 1;
+            freed += size;
+        }
+        let verb = match self.is_dry_run {
+            true => "would free",
+            false => "freed",
+        };
+        println!(
+            "{} component version(s), {} {}",
+            removed_count,
+            verb,
+            format_size(freed)
+        );
+        Ok(())
+    }
+}
+
+/// Returns the component versions referenced by local environments,
+/// along with the dependencies that would be auto-resolved at run time
+/// (the latest local version of each unsatisfied import).
+fn referenced_components(components: &[(Resource, PathBuf)]) -> HashSet<Resource> {
+    let by_ref: HashMap<String, &(Resource, PathBuf)> =
+        components.iter().map(|c| (c.0.to_string(), c)).collect();
+    let mut keep = HashSet::new();
+    for env in LocalStore::list_environments() {
+        let refs = env.component_refs();
+        let env_components: Vec<&(Resource, PathBuf)> =
+            refs.iter().filter_map(|r| by_ref.get(r).copied()).collect();
+        keep.extend(env_components.iter().map(|c| c.0.clone()));
+        keep.extend(resolve_local_dependencies(&env_components, components));
+    }
+    keep
+}
+
+/// Mirrors run-time dependency auto-resolution against the local store,
+/// returning the latest local version of each transitively missing package.
+fn resolve_local_dependencies(
+    roots: &[&(Resource, PathBuf)],
+    components: &[(Resource, PathBuf)],
+) -> Vec<Resource> {
+    let mut binaries: Vec<ComponentBinary> = roots
+        .iter()
+        .filter_map(|(_, path)| LocalStore::parse_component(path).ok())
+        .collect();
+    let mut resolved = Vec::new();
+    let mut seen = HashSet::new();
+    loop {
+        let missing = deps::unsatisfied_import_packages(&binaries, &HashSet::new());
+        let mut has_progress = false;
+        for id in missing {
+            if !seen.insert(id.clone()) {
+                continue;
+            }
+            let latest = components
+                .iter()
+                .filter(|(r, _)| r.id() == id)
+                .max_by(|a, b| a.0.version().cmp(b.0.version()));
+            let Some((resource, path)) = latest else {
+                continue;
+            };
+            let Ok(binary) = LocalStore::parse_component(path) else {
+                continue;
+            };
+            binaries.push(binary);
+            resolved.push(resource.clone());
+            has_progress = true;
+        }
+        if !has_progress {
+            return resolved;
+        }
+    }
+}
+
+/// Returns the `n` most recent versions of every component.
+fn latest_versions(components: &[(Resource, PathBuf)], n: usize) -> Vec<Resource> {
+    let mut by_id: HashMap<String, Vec<&Resource>> = HashMap::new();
+    for (resource, _) in components {
+        by_id
+            .entry(resource.id().to_string())
+            .or_default()
+            .push(resource);
+    }
+    by_id
+        .into_values()
+        .flat_map(|mut versions| {
+            versions.sort_by(|a, b| b.version().cmp(a.version()));
+            versions.into_iter().take(n).cloned()
+        })
+        .collect()
+}
+
+fn print_help() {
+    println!(
+        r#"Remove component versions not referenced by any local environment.
+
+Components referenced by an environment's env.toml are kept, along with the
+dependencies that would be auto-resolved when running it.
+
+Usage: asterai store gc [options]
+
+Options:
+  -n, --dry-run           Only show what would be removed
+  -k, --keep-latest <N>   Also keep the N most recent versions of each component
+  -h, --help              Show this help message
+
+Examples:
+  asterai store gc --dry-run
+  asterai store gc --keep-latest 1
+"#
+    );
+}
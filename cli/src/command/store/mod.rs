@@ -0,0 +1,94 @@
+use crate::command::store::gc::GcArgs;
+use eyre::{bail, eyre};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use strum_macros::EnumString;
+
+mod du;
+mod gc;
+
+#[derive(Debug)]
+pub struct StoreArgs {
+    action: StoreAction,
+    gc_args: Option<GcArgs>,
+}
+
+#[derive(Debug, Copy, Clone, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum StoreAction {
+    Du,
+    Gc,
+}
+
+impl StoreArgs {
+    pub fn parse(mut args: impl Iterator<Item = String>) -> eyre::Result<Self> {
+        let Some(action_string) = args.next() else {
+            bail!("missing store command action");
+        };
+        let action =
+            StoreAction::from_str(&action_string).map_err(|_| eyre!("unknown store action"))?;
+        let none_args = Self {
+            action,
+            gc_args: None,
+        };
+        let command_args = match action {
+            StoreAction::Du => {
+                du::parse(args)?;
+                none_args
+            }
+            StoreAction::Gc => Self {
+                gc_args: Some(GcArgs::parse(args)?),
+                ..none_args
+            },
+        };
+        Ok(command_args)
+    }
+
+    pub fn execute(&self) -> eyre::Result<()> {
+        match self.action {
+            StoreAction::Du => {
+                du::execute()?;
+            }
+            StoreAction::Gc => {
+                self.gc()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn gc(&self) -> eyre::Result<()> {
+        let args = self.gc_args.as_ref().ok_or_else(|| eyre!("no gc args"))?;
+        args.execute()
+    }
+}
+
+/// Returns the total size in bytes of all files under `path`.
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(t) if t.is_dir() => dir_size(&entry.path()),
+            Ok(_) => entry.metadata().map(|m| m.len()).unwrap_or(0),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// Formats a byte count for display (e.g. "1.5 MiB").
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
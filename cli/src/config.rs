@@ -22,3 +22,23 @@ pub static BIN_DIR: Lazy<PathBuf> = Lazy::new(|| BASE_DIR.join("bin"));
 
 /// Directory for storing artifacts (environments, components).
 pub static ARTIFACTS_DIR: Lazy<PathBuf> = Lazy::new(|| BIN_DIR.join("artifacts"));
+
+/// Directory for local call traces, one JSONL file per environment.
+pub static TRACES_DIR: Lazy<PathBuf> = Lazy::new(|| BASE_DIR.join("traces"));
+
+/// Default wasmtime compilation cache directory (`Cache::from_file(None)`).
+pub static WASMTIME_CACHE_DIR: Lazy<Option<PathBuf>> = Lazy::new(|| {
+    let cache_dir = dirs::cache_dir()?;
+    if cfg!(target_os = "macos") {
+        return Some(cache_dir.join("BytecodeAlliance.wasmtime"));
+    }
+    if cfg!(windows) {
+        return Some(
+            cache_dir
+                .join("BytecodeAlliance")
+                .join("wasmtime")
+                .join("cache"),
+        );
+    }
+    Some(cache_dir.join("wasmtime"))
+});
@@ -0,0 +1,121 @@
+use super::run_command;
+use crate::command::component::pkg::run_pkg;
+use crate::language::Language;
+use async_trait::async_trait;
+use eyre::{Context, bail};
+use include_dir::{Dir, include_dir};
+use std::path::{Path, PathBuf};
+
+static TEMPLATE: Dir = include_dir!("$CARGO_MANIFEST_DIR/init/c");
+
+/// The directory wasi-sdk is installed in when `WASI_SDK_PATH` is not set.
+const DEFAULT_WASI_SDK_PATH: &str = "/opt/wasi-sdk";
+
+/// C language support, built with wit-bindgen and wasi-sdk.
+pub struct C;
+
+#[async_trait]
+impl Language for C {
+    fn name(&self) -> &'static str {
+        "c"
+    }
+
+    fn template(&self) -> &'static Dir<'static> {
+        &TEMPLATE
+    }
+
+    fn is_dir_a_component(&self, dir: &Path) -> bool {
+        dir.join("component.wit").exists() && !find_sources(dir).is_empty()
+    }
+
+    fn get_wit_file_path(&self, dir: &Path) -> PathBuf {
+        dir.join("component.wit")
+    }
+
+    fn get_package_wasm_path(&self, dir: &Path) -> PathBuf {
+        dir.join("wit").join("package.wasm")
+    }
+
+    fn get_package_wit_path(&self, dir: &Path) -> PathBuf {
+        dir.join("wit").join("package.wit")
+    }
+
+    fn get_component_wasm_path(&self, dir: &Path) -> eyre::Result<PathBuf> {
+        Ok(dir.join("build").join("component.wasm"))
+    }
+
+    async fn build_component(&self, dir: &Path, api_endpoint: &str) -> eyre::Result<PathBuf> {
+        // Generate package.wasm and package.wit from the WIT file.
+        let wit_file = self.get_wit_file_path(dir);
+        let pkg_wasm = self.get_package_wasm_path(dir);
+        let pkg_wit = self.get_package_wit_path(dir);
+        run_pkg(&wit_file, &pkg_wasm, Some(&pkg_wit), api_endpoint).await?;
+        // Generate the bindings: component.h, component.c and
+        // component_component_type.o, which holds the component's type.
+        let bindings_dir = dir.join("build").join("bindings");
+        let bindings_dir_str = bindings_dir.to_string_lossy();
+        run_command(
+            dir,
+            "wit-bindgen",
+            &[
+                "c",
+                "--world",
+                "component",
+                "--out-dir",
+                &bindings_dir_str,
+                "wit/package.wit",
+            ],
+        )
+        .wrap_err(
+            "wit-bindgen is required to build C components \
+             (cargo install wit-bindgen-cli)",
+        )?;
+        // Build, linking the bindings into a component with the wasip2 target.
+        let sdk_path =
+            std::env::var("WASI_SDK_PATH").unwrap_or_else(|_| DEFAULT_WASI_SDK_PATH.to_string());
+        let clang = Path::new(&sdk_path).join("bin").join("clang");
+        let wasm_path = self.get_component_wasm_path(dir)?;
+        let sources: Vec<String> = find_sources(dir)
+            .iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect();
+        let bindings_c = bindings_dir.join("component.c");
+        let bindings_type = bindings_dir.join("component_component_type.o");
+        let include = format!("-I{bindings_dir_str}");
+        let mut args = vec![
+            "--target=wasm32-wasip2".to_string(),
+            "-mexec-model=reactor".to_string(),
+            "-Os".to_string(),
+            include,
+            "-o".to_string(),
+            wasm_path.to_string_lossy().to_string(),
+        ];
+        args.extend(sources);
+        args.push(bindings_c.to_string_lossy().to_string());
+        args.push(bindings_type.to_string_lossy().to_string());
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        run_command(dir, &clang.to_string_lossy(), &args).wrap_err_with(|| {
+            format!(
+                "wasi-sdk 22 or later is required to build C components \
+                 (set WASI_SDK_PATH, or install it at {DEFAULT_WASI_SDK_PATH})"
+            )
+        })?;
+        if !wasm_path.exists() {
+            bail!("built WASM file not found at {:?}", wasm_path);
+        }
+        Ok(wasm_path)
+    }
+}
+
+/// Returns the C source files of the component in `dir`, sorted.
+fn find_sources(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut sources: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "c"))
+        .collect();
+    sources.sort();
+    sources
+}
@@ -0,0 +1,101 @@
+use super::run_command;
+use crate::command::component::pkg::run_pkg;
+use crate::language::Language;
+use async_trait::async_trait;
+use eyre::{Context, bail};
+use include_dir::{Dir, include_dir};
+use std::path::{Path, PathBuf};
+
+static TEMPLATE: Dir = include_dir!("$CARGO_MANIFEST_DIR/init/go");
+
+/// Go language support, built with TinyGo and wit-bindgen-go.
+pub struct Go;
+
+#[async_trait]
+impl Language for Go {
+    fn name(&self) -> &'static str {
+        "go"
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        &["golang", "tinygo"]
+    }
+
+    fn template(&self) -> &'static Dir<'static> {
+        &TEMPLATE
+    }
+
+    fn is_dir_a_component(&self, dir: &Path) -> bool {
+        dir.join("go.mod").exists() && dir.join("component.wit").exists()
+    }
+
+    fn get_wit_file_path(&self, dir: &Path) -> PathBuf {
+        dir.join("component.wit")
+    }
+
+    fn get_package_wasm_path(&self, dir: &Path) -> PathBuf {
+        dir.join("wit").join("package.wasm")
+    }
+
+    fn get_package_wit_path(&self, dir: &Path) -> PathBuf {
+        dir.join("wit").join("package.wit")
+    }
+
+    fn get_component_wasm_path(&self, dir: &Path) -> eyre::Result<PathBuf> {
+        Ok(dir.join("build").join("component.wasm"))
+    }
+
+    async fn build_component(&self, dir: &Path, api_endpoint: &str) -> eyre::Result<PathBuf> {
+        // Generate package.wasm and package.wit from the WIT file.
+        let wit_file = self.get_wit_file_path(dir);
+        let pkg_wasm = self.get_package_wasm_path(dir);
+        let pkg_wit = self.get_package_wit_path(dir);
+        run_pkg(&wit_file, &pkg_wasm, Some(&pkg_wit), api_endpoint).await?;
+        // Generate the bindings into the internal package.
+        run_command(
+            dir,
+            "wit-bindgen-go",
+            &[
+                "generate",
+                "--world",
+                "component",
+                "--out",
+                "internal",
+                "wit/package.wasm",
+            ],
+        )
+        .wrap_err(
+            "wit-bindgen-go is required to build Go components \
+             (go install go.bytecodealliance.org/cmd/wit-bindgen-go@latest)",
+        )?;
+        // Add the dependencies of the bindings, such as go.bytecodealliance.org/cm.
+        run_command(dir, "go", &["mod", "tidy"])?;
+        // Build.
+        let wasm_path = self.get_component_wasm_path(dir)?;
+        if let Some(parent) = wasm_path.parent() {
+            std::fs::create_dir_all(parent)
+                .wrap_err_with(|| format!("failed to create directory {:?}", parent))?;
+        }
+        let wasm_path_str = wasm_path.to_string_lossy();
+        run_command(
+            dir,
+            "tinygo",
+            &[
+                "build",
+                "-target=wasip2",
+                "--wit-package",
+                "wit/package.wasm",
+                "--wit-world",
+                "component",
+                "-o",
+                &wasm_path_str,
+                ".",
+            ],
+        )
+        .wrap_err("TinyGo 0.34 or later is required to build Go components")?;
+        if !wasm_path.exists() {
+            bail!("built WASM file not found at {:?}", wasm_path);
+        }
+        Ok(wasm_path)
+    }
+}
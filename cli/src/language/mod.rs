@@ -4,9 +4,15 @@ use include_dir::Dir;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
+mod c;
+mod go;
+mod python;
 mod rust;
 mod typescript;
 
+pub use c::C;
+pub use go::Go;
+pub use python::Python;
 pub use rust::Rust;
 pub use typescript::TypeScript;
 
@@ -51,7 +57,13 @@ pub trait Language {
 
 /// Returns all supported languages.
 pub fn all() -> Vec<Box<dyn Language>> {
-    vec![Box::new(Rust), Box::new(TypeScript)]
+    vec![
+        Box::new(Rust),
+        Box::new(TypeScript),
+        Box::new(Go),
+        Box::new(Python),
+        Box::new(C),
+    ]
 }
 
 /// Detects the language of a component in the given directory.
@@ -209,6 +209,25 @@ impl LocalStore {
         Ok(())
     }
 
+    /// List all local component versions with their paths,
+    /// without parsing the component binaries.
+    pub fn list_component_paths() -> Vec<(Resource, PathBuf)> {
+        let mut components = Vec::new();
+        for resource_path in Self::list_all_paths() {
+            let Ok(metadata) = Self::parse_metadata(&resource_path) else {
+                continue;
+            };
+            if metadata.kind != ResourceKind::Component {
+                continue;
+            }
+            let Ok(resource) = Self::resource_from_path(&resource_path) else {
+                continue;
+            };
+            components.push((resource, resource_path));
+        }
+        components
+    }
+
     /// List all local components.
     pub fn list_components() -> Vec<ComponentBinary> {
         let mut components = Vec::new();
@@ -239,6 +258,7 @@ impl LocalStore {
         let component_bytes = fs::read(&component_path)?;
         let component = Component::from_str(&resource.to_string())?;
         let mut binary = ComponentBinary::from_component_bytes(component, component_bytes)?;
+        binary.set_precompiled_dir(path.to_path_buf());
         let package_path = path.join("package.wasm");
         if package_path.exists() {
             let package_bytes = fs::read(&package_path)?;
@@ -253,13 +273,14 @@ impl LocalStore {
         Self::parse_component(&path)
     }
 
+    /// Fetch a locally stored version of a component.
+    pub fn fetch_component_version(component: &Component) -> eyre::Result<ComponentBinary> {
+        Self::parse_component(&Self::component_dir(component))
+    }
+
     /// Check if a component exists locally.
     pub fn component_exists(component: &Component) -> bool {
-        let component_dir = ARTIFACTS_DIR.join(component.namespace()).join(format!(
-            "{}@{}",
-            component.name(),
-            component.version()
-        ));
+        let component_dir = Self::component_dir(component);
         if !component_dir.exists() {
             return false;
         }
@@ -271,4 +292,12 @@ impl LocalStore {
         }
         component_dir.join("component.wasm").exists()
     }
+
+    fn component_dir(component: &Component) -> PathBuf {
+        ARTIFACTS_DIR.join(component.namespace()).join(format!(
+            "{}@{}",
+            component.name(),
+            component.version()
+        ))
+    }
 }
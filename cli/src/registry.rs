@@ -1,6 +1,7 @@
 use crate::auth::Auth;
 use crate::config::ARTIFACTS_DIR;
 use asterai_runtime::component::Component;
+use asterai_runtime::environment::mcp_servers::McpServerConfig;
 use asterai_runtime::resource::metadata::ResourceKind;
 use eyre::{Context, bail};
 use serde::Deserialize;
@@ -73,6 +74,12 @@ pub struct GetEnvironmentResponse {
     pub version: String,
     pub components: Vec<String>,
     pub vars: HashMap<String, String>,
+    #[serde(default)]
+    pub wiring: HashMap<String, HashMap<String, String>>,
+    #[serde(default)]
+    pub interceptors: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    pub mcp_servers: HashMap<String, McpServerConfig>,
 }
 
 /// Client for interacting with the OCI registry.
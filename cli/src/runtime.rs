@@ -2,13 +2,15 @@ use crate::auth::Auth;
 use crate::config::{API_URL, REGISTRY_URL};
 use crate::local_store::LocalStore;
 use crate::registry::RegistryClient;
+use crate::trace::TraceOptions;
 use crate::version_resolver;
 use asterai_runtime::component::binary::ComponentBinary;
 use asterai_runtime::component::{Component, ComponentId};
 use asterai_runtime::environment::Environment;
 use asterai_runtime::environment::deps;
 use asterai_runtime::runtime::ComponentRuntime;
-use eyre::Context;
+use eyre::{Context, bail};
+use std::collections::HashSet;
 use std::path::PathBuf;
 use std::str::FromStr;
 use tokio::sync::mpsc;
@@ -29,6 +31,57 @@ pub async fn build_runtime_with(
     allow_dirs: &[PathBuf],
     extra_components: Vec<ComponentBinary>,
 ) -> eyre::Result<ComponentRuntime> {
+    let components = resolve_components(&environment, extra_components).await?;
+    build_runtime_from_components(environment, allow_dirs, components).await
+}
+
+/// Build a ComponentRuntime from an Environment and its resolved components,
+/// see [`resolve_components`].
+pub async fn build_runtime_from_components(
+    environment: Environment,
+    allow_dirs: &[PathBuf],
+    components: Vec<ComponentBinary>,
+) -> eyre::Result<ComponentRuntime> {
+    let wiring = environment.interface_wiring()?;
+    let interceptors = environment.interceptors()?;
+    let mcp_servers = environment.mcp_servers()?;
+    let mocks = environment.mocks()?;
+    TraceOptions::from_env(&environment).init(&environment)?;
+    let vars = environment.vars_with_config_defaults(&components);
+    if !allow_dirs.is_empty() {
+        println!("allowed directories:");
+        for dir in allow_dirs {
+            println!("  {}", dir.display());
+        }
+    }
+    // TODO: update this according to new API.
+    let app_id = Uuid::new_v4();
+    let (component_output_tx, mut component_output_rx) = mpsc::channel(32);
+    // Just drain the messages for now. TODO: add to this fn's arg?
+    tokio::spawn(async move { while component_output_rx.recv().await.is_some() {} });
+    ComponentRuntime::new(
+        components,
+        app_id,
+        component_output_tx,
+        &vars,
+        allow_dirs,
+        wiring,
+        interceptors,
+        mcp_servers,
+        mocks,
+        &environment.metadata.namespace,
+        &environment.metadata.name,
+    )
+    .await
+}
+
+/// Resolves the components an Environment runs with, including
+/// auto-resolved dependencies, pulling any that are not stored locally.
+/// Extra pre-loaded components take priority over local store and registry.
+pub async fn resolve_components(
+    environment: &Environment,
+    extra_components: Vec<ComponentBinary>,
+) -> eyre::Result<Vec<ComponentBinary>> {
     let mut local_components = LocalStore::list_components();
     local_components.extend(extra_components);
     let mut components = Vec::with_capacity(environment.components.len());
@@ -47,54 +100,57 @@ pub async fn build_runtime_with(
         let component = pull_component(&component_id, version).await?;
         components.push(component);
     }
-    // Auto-resolve missing dependencies.
-    resolve_dependencies(&mut components, &mut local_components).await?;
-    // Warn about imported interfaces exported by multiple components.
-    // Components are sorted alphabetically for instantiation, so the
-    // first provider in the sorted list is the one the linker will use.
-    for (interface, providers) in deps::conflicting_exports(&components) {
+    // Auto-resolve missing dependencies, other than mocked interfaces.
+    let mocked = environment.mocked_interfaces();
+    resolve_dependencies(&mut components, &mut local_components, &mocked).await?;
+    // Warn about imported interfaces exported by multiple components
+    // that are not explicitly wired. Components are sorted alphabetically
+    // for instantiation, so the first provider in the sorted list is the
+    // one the linker will use.
+    let wiring = environment.interface_wiring()?;
+    for (interface, providers) in deps::conflicting_exports(&components, &wiring) {
         let default = &providers[0];
         eprintln!(
             "warning: interface {} is exported by multiple components: {}. \
-             {} was picked as the default implementor.",
+             {} was picked as the default implementor. \
+             Add a [wiring] entry to the environment to pick one per consumer.",
             interface,
             providers.join(", "),
             default,
         );
     }
-    if !allow_dirs.is_empty() {
-        println!("allowed directories:");
-        for dir in allow_dirs {
-            println!("  {}", dir.display());
-        }
+    Ok(components)
+}
+
+/// Fails with the config vars declared by `components`
+/// that are missing or invalid in the environment.
+pub fn check_config(environment: &Environment, components: &[ComponentBinary]) -> eyre::Result<()> {
+    let issues = environment.config_issues(components);
+    if issues.is_empty() {
+        return Ok(());
     }
-    // TODO: update this according to new API.
-    let app_id = Uuid::new_v4();
-    let (component_output_tx, mut component_output_rx) = mpsc::channel(32);
-    // Just drain the messages for now. TODO: add to this fn's arg?
-    tokio::spawn(async move { while component_output_rx.recv().await.is_some() {} });
-    ComponentRuntime::new(
-        components,
-        app_id,
-        component_output_tx,
-        &environment.vars,
-        allow_dirs,
-        &environment.metadata.namespace,
-        &environment.metadata.name,
+    let issues: Vec<String> = issues.iter().map(|issue| format!("  {issue}")).collect();
+    bail!(
+        "environment {} is not fully configured:\n{}\n\n\
+         Set the variables with: asterai env set-var {} --var NAME=VALUE",
+        environment.display_ref(),
+        issues.join("\n"),
+        environment.name()
     )
-    .await
 }
 
 /// Iteratively resolves unsatisfied component imports by pulling missing
 /// dependencies from the registry. Runs until all imports are satisfied
-/// or a dependency cannot be found.
+/// or a dependency cannot be found. Imports of `mocked` interfaces
+/// are satisfied by their mocks.
 async fn resolve_dependencies(
     components: &mut Vec<ComponentBinary>,
     local_components: &mut Vec<ComponentBinary>,
+    mocked: &HashSet<String>,
 ) -> eyre::Result<()> {
     const MAX_ITERATIONS: usize = 100;
     for _ in 0..MAX_ITERATIONS {
-        let missing = deps::unsatisfied_import_packages(components);
+        let missing = deps::unsatisfied_import_packages(components, mocked);
         if missing.is_empty() {
             return Ok(());
         }
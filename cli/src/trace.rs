@@ -0,0 +1,229 @@
+//! Call trace exporters, configured per run.
+use crate::config::TRACES_DIR;
+use asterai_runtime::environment::Environment;
+use asterai_runtime::runtime::call_trace::{self, JsonlExporter, Span, SpanExporter, SpanKind};
+use serde_json::{Value, json};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+
+/// Enables writing spans to the local trace file of the environment,
+/// or to the given file path.
+pub const TRACE_ENV: &str = "ASTERAI_TRACE";
+/// Standard OpenTelemetry variable for the OTLP/HTTP collector endpoint.
+pub const OTLP_ENDPOINT_ENV: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+
+const OTLP_BATCH_SIZE: usize = 256;
+const OTLP_FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+
+static OTLP_SENDER: OnceLock<mpsc::UnboundedSender<OtlpMessage>> = OnceLock::new();
+
+/// Where spans are exported to.
+#[derive(Debug, Default)]
+pub struct TraceOptions {
+    pub file: Option<PathBuf>,
+    pub otlp_endpoint: Option<String>,
+}
+
+impl TraceOptions {
+    /// Reads trace options from `ASTERAI_TRACE` and
+    /// `OTEL_EXPORTER_OTLP_ENDPOINT`.
+    pub fn from_env(environment: &Environment) -> Self {
+        let file = std::env::var(TRACE_ENV)
+            .ok()
+            .and_then(|value| match value.as_str() {
+                "" | "0" | "false" => None,
+                "1" | "true" => Some(local_trace_path(
+                    environment.namespace(),
+                    environment.name(),
+                )),
+                path => Some(PathBuf::from(path)),
+            });
+        let otlp_endpoint = std::env::var(OTLP_ENDPOINT_ENV)
+            .ok()
+            .filter(|endpoint| !endpoint.is_empty());
+        Self {
+            file,
+            otlp_endpoint,
+        }
+    }
+
+    /// Sets the span exporter for this process.
+    /// Does nothing if no exporter is configured or one was already set.
+    pub fn init(self, environment: &Environment) -> eyre::Result<()> {
+        if call_trace::is_enabled() {
+            return Ok(());
+        }
+        let mut exporters: Vec<Box<dyn SpanExporter>> = Vec::new();
+        if let Some(file) = &self.file {
+            exporters.push(Box::new(JsonlExporter::new(file)?));
+        }
+        if let Some(endpoint) = &self.otlp_endpoint {
+            exporters.push(Box::new(OtlpExporter::spawn(endpoint, environment)));
+        }
+        if exporters.is_empty() {
+            return Ok(());
+        }
+        if !call_trace::set_exporter(Box::new(MultiExporter(exporters))) {
+            return Ok(());
+        }
+        if let Some(file) = &self.file {
+            println!("writing call traces to {}", file.display());
+        }
+        if let Some(endpoint) = &self.otlp_endpoint {
+            println!("exporting call traces to {endpoint}");
+        }
+        Ok(())
+    }
+}
+
+/// Returns the local trace file of an environment.
+pub fn local_trace_path(namespace: &str, name: &str) -> PathBuf {
+    TRACES_DIR.join(namespace).join(format!("{name}.jsonl"))
+}
+
+/// Waits for spans queued for OTLP export to be sent.
+pub async fn flush() {
+    let Some(sender) = OTLP_SENDER.get() else {
+        return;
+    };
+    let (tx, rx) = oneshot::channel();
+    if sender.send(OtlpMessage::Flush(tx)).is_ok() {
+        let _ = rx.await;
+    }
+}
+
+struct MultiExporter(Vec<Box<dyn SpanExporter>>);
+
+impl SpanExporter for MultiExporter {
+    fn export(&self, span: &Span) {
+        for exporter in &self.0 {
+            exporter.export(span);
+        }
+    }
+}
+
+enum OtlpMessage {
+    Span(Span),
+    Flush(oneshot::Sender<()>),
+}
+
+/// Sends spans in batches to an OTLP/HTTP collector, JSON-encoded.
+struct OtlpExporter {
+    sender: mpsc::UnboundedSender<OtlpMessage>,
+}
+
+impl OtlpExporter {
+    fn spawn(endpoint: &str, environment: &Environment) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let url = format!("{}/v1/traces", endpoint.trim_end_matches('/'));
+        let resource = json!({
+            "attributes": [
+                attribute("service.name", "asterai"),
+                attribute("asterai.environment", &environment.resource_id()),
+            ]
+        });
+        tokio::spawn(run_otlp_exporter(url, resource, receiver));
+        let _ = OTLP_SENDER.set(sender.clone());
+        Self { sender }
+    }
+}
+
+impl SpanExporter for OtlpExporter {
+    fn export(&self, span: &Span) {
+        let _ = self.sender.send(OtlpMessage::Span(span.clone()));
+    }
+}
+
+async fn run_otlp_exporter(
+    url: String,
+    resource: Value,
+    mut receiver: mpsc::UnboundedReceiver<OtlpMessage>,
+) {
+    let client = reqwest::Client::new();
+    let mut batch = Vec::new();
+    loop {
+        let message = tokio::time::timeout(OTLP_FLUSH_INTERVAL, receiver.recv()).await;
+        let flushed = match message {
+            Ok(Some(OtlpMessage::Span(span))) => {
+                batch.push(span);
+                if batch.len() < OTLP_BATCH_SIZE {
+                    continue;
+                }
+                None
+            }
+            Ok(Some(OtlpMessage::Flush(tx))) => Some(tx),
+            // Timed out, so send what has been collected so far.
+            Err(_) => None,
+            Ok(None) => break,
+        };
+        if !batch.is_empty() {
+            let spans = std::mem::take(&mut batch);
+            send_otlp_batch(&client, &url, &resource, &spans).await;
+        }
+        if let Some(tx) = flushed {
+            let _ = tx.send(());
+        }
+    }
+}
+
+async fn send_otlp_batch(client: &reqwest::Client, url: &str, resource: &Value, spans: &[Span]) {
+    let body = json!({
+        "resourceSpans": [{
+            "resource": resource,
+            "scopeSpans": [{
+                "scope": { "name": "asterai", "version": env!("CARGO_PKG_VERSION") },
+                "spans": spans.iter().map(otlp_span).collect::<Vec<_>>(),
+            }]
+        }]
+    });
+    let result = client.post(url).json(&body).send().await;
+    match result {
+        Ok(response) if !response.status().is_success() => {
+            eprintln!(
+                "warning: OTLP collector rejected {} span(s): {}",
+                spans.len(),
+                response.status()
+            );
+        }
+        Ok(_) => {}
+        Err(e) => eprintln!("warning: failed to export {} span(s): {e}", spans.len()),
+    }
+}
+
+fn otlp_span(span: &Span) -> Value {
+    // Span kinds as defined by OTLP: 1 is internal, 2 is server.
+    let kind = match span.kind {
+        SpanKind::Http => 2,
+        _ => 1,
+    };
+    // Status codes as defined by OTLP: 1 is ok, 2 is error.
+    let status = match &span.error {
+        None => json!({ "code": 1 }),
+        Some(message) => json!({ "code": 2, "message": message }),
+    };
+    let end_time = span.start_time_unix_nano + span.duration_nanos;
+    let mut otlp_span = json!({
+        "traceId": span.trace_id,
+        "spanId": span.span_id,
+        "name": format!("{} {}", span.component, span.function),
+        "kind": kind,
+        "startTimeUnixNano": span.start_time_unix_nano.to_string(),
+        "endTimeUnixNano": end_time.to_string(),
+        "attributes": [
+            attribute("asterai.span.kind", &span.kind.to_string()),
+            attribute("asterai.component", &span.component),
+            attribute("asterai.function", &span.function),
+        ],
+        "status": status,
+    });
+    if let Some(parent_span_id) = &span.parent_span_id {
+        otlp_span["parentSpanId"] = json!(parent_span_id);
+    }
+    otlp_span
+}
+
+fn attribute(key: &str, value: &str) -> Value {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
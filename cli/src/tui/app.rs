@@ -1,12 +1,18 @@
 use crate::artifact::ArtifactSyncTag;
 use crate::command::env::list::EnvListEntry;
+use crate::tui::transcript::Transcript;
+use asterai_runtime::component::config::ConfigVar;
 use asterai_runtime::runtime::ComponentRuntime;
+use asterai_runtime::runtime::approval::{ApprovalPolicies, ApprovalRequest};
+use asterai_runtime::runtime::output::OutputEvent;
 use ratatui::prelude::*;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{Instant, SystemTime, UNIX_EPOCH};
-use tokio::sync::{Mutex, oneshot};
+use tokio::sync::{Mutex, mpsc, oneshot};
+use tokio::task::AbortHandle;
 
 pub const SPINNER_FRAMES: &[char] = &['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
 
@@ -89,6 +95,34 @@ pub const PROVIDERS: &[Provider] = &[
     ),
 ];
 
+/// The provider of an OpenAI-compatible endpoint, such as a local
+/// llama.cpp or Ollama server. Its models are listed by the endpoint.
+pub const CUSTOM_PROVIDER: &str = "Custom OpenAI-compatible endpoint";
+/// The prefix of the model IDs of the custom provider, e.g. `custom/llama3`.
+pub const CUSTOM_MODEL_PREFIX: &str = "custom/";
+/// The env var of the base URL of the custom provider, read by `asterai:llm`.
+pub const CUSTOM_URL_VAR: &str = "CUSTOM_LLM_URL";
+/// The env var of the optional API key of the custom provider.
+pub const CUSTOM_KEY_VAR: &str = "CUSTOM_LLM_KEY";
+/// The base URL of the custom provider when none is entered (Ollama's).
+pub const DEFAULT_CUSTOM_URL: &str = "http://localhost:11434/v1";
+
+/// The provider with the ID `id`, the prefix of its model IDs, e.g. `anthropic`.
+pub fn provider_by_id(id: &str) -> Option<&'static Provider> {
+    PROVIDERS.iter().find(|(_, _, models)| {
+        models
+            .iter()
+            .any(|(model_id, _)| model_id.split('/').next() == Some(id))
+    })
+}
+
+/// The provider serving the model `model_id`, if it is a listed model.
+pub fn provider_of_model(model_id: &str) -> Option<&'static Provider> {
+    PROVIDERS
+        .iter()
+        .find(|(_, _, models)| models.iter().any(|(id, _)| *id == model_id))
+}
+
 #[derive(Debug, Clone)]
 pub struct DynamicItem {
     pub value: String,
@@ -106,38 +140,11 @@ pub const CORE_COMPONENTS: &[&str] = &[
 
 pub const DEFAULT_TOOLS: &[&str] = &["asterbot:soul", "asterbot:memory", "asterbot:skills"];
 
-/// Maps tool short names to the environment variables they require.
-pub struct ComponentEnvVar {
-    pub name: &'static str,
-    pub required: bool,
-}
-
-// TODO: fetch this from a proper data source.
-// The env/config feature has been implemented to demonstrate better UX,
-// but there is no infrastructure or ata storage for required/optional
-// env vars per componnet at the moment.
-pub const TOOL_ENV_VARS: &[(&str, &[ComponentEnvVar])] = &[(
-    "asterai:telegram",
-    &[
-        ComponentEnvVar {
-            name: "TELEGRAM_TOKEN",
-            required: true,
-        },
-        ComponentEnvVar {
-            name: "TELEGRAM_WEBHOOK_URL",
-            required: false,
-        },
-    ],
-)];
-
-/// Get env var definitions for a tool by its full component identifier.
-pub fn tool_env_vars(tool: &str) -> &'static [ComponentEnvVar] {
-    TOOL_ENV_VARS
-        .iter()
-        .find(|(name, _)| *name == tool)
-        .map(|(_, vars)| *vars)
-        .unwrap_or(&[])
-}
+/// The env var of the approval policies of the agent's tool calls,
+/// e.g. `asterai:telegram=ask,asterbot:fs/fs/write=deny`.
+/// They apply to agents run from the agents TUI and `asterai agents chat`,
+/// where calls needing approval are denied, but not to `asterai env run`.
+pub const APPROVALS_VAR: &str = "ASTERBOT_APPROVALS";
 
 pub const SLASH_COMMANDS: &[SlashCommand] = &[
     SlashCommand {
@@ -173,7 +180,44 @@ pub const SLASH_COMMANDS: &[SlashCommand] = &[
     },
     SlashCommand {
         name: "model",
-        description: "View or switch LLM model",
+        description: "View or switch LLM model and provider",
+        subs: &[],
+    },
+    SlashCommand {
+        name: "history",
+        description: "Browse, search and resume sessions",
+        subs: &[
+            SubCommand {
+                name: "list",
+                description: "Show past sessions",
+                needs_arg: false,
+            },
+            SubCommand {
+                name: "search",
+                description: "Search all sessions",
+                needs_arg: true,
+            },
+        ],
+    },
+    SlashCommand {
+        name: "export",
+        description: "Write the conversation to a file",
+        subs: &[
+            SubCommand {
+                name: "md",
+                description: "Export as Markdown",
+                needs_arg: false,
+            },
+            SubCommand {
+                name: "json",
+                description: "Export as JSON",
+                needs_arg: false,
+            },
+        ],
+    },
+    SlashCommand {
+        name: "agents",
+        description: "Open another agent in a new tab",
         subs: &[],
     },
     SlashCommand {
@@ -228,6 +272,22 @@ pub const SLASH_COMMANDS: &[SlashCommand] = &[
             },
         ],
     },
+    SlashCommand {
+        name: "approvals",
+        description: "Approve, ask for or deny tool calls",
+        subs: &[
+            SubCommand {
+                name: "list",
+                description: "Show approval policies",
+                needs_arg: false,
+            },
+            SubCommand {
+                name: "set",
+                description: "Set TARGET auto|ask|deny",
+                needs_arg: true,
+            },
+        ],
+    },
     SlashCommand {
         name: "banner",
         description: "Configure banner content",
@@ -282,7 +342,7 @@ pub struct SubCommand {
 pub enum Screen {
     Auth(AuthState),
     Picker(PickerState),
-    Setup(SetupState),
+    Setup(Box<SetupState>),
     Chat(Box<ChatState>),
 }
 
@@ -299,6 +359,8 @@ pub enum SetupStep {
     Name,
     Username,
     Provider,
+    /// The base URL of the custom provider.
+    BaseUrl,
     ApiKey,
     Model,
     Directories,
@@ -311,7 +373,8 @@ pub enum SetupStep {
     PushPrompt,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum MessageRole {
     User,
     Assistant,
@@ -331,6 +394,33 @@ pub struct AgentConfig {
     pub allowed_dirs: Vec<String>,
     /// Banner mode: "auto", "quote", or "off".
     pub banner_mode: String,
+    /// Config vars declared by the agent's components, by component ID.
+    pub tool_config: HashMap<String, Vec<ConfigVar>>,
+    /// Whether tool calls run, need approval, or are denied.
+    pub approvals: ApprovalPolicies,
+}
+
+impl AgentConfig {
+    /// The config vars declared by the tool `tool`, e.g. `asterai:telegram`.
+    pub fn tool_config(&self, tool: &str) -> &[ConfigVar] {
+        self.tool_config.get(tool).map(Vec::as_slice).unwrap_or(&[])
+    }
+}
+
+/// What an agent is provisioned from, by the setup wizard
+/// or by `asterai agents create`.
+#[derive(Debug, Clone)]
+pub struct AgentSpec {
+    pub bot_name: String,
+    pub user_name: String,
+    /// The display name of the provider, or "custom".
+    pub provider: String,
+    pub model: String,
+    /// The env vars configuring the provider, e.g. its API key.
+    pub provider_vars: Vec<(String, String)>,
+    pub tools: Vec<String>,
+    /// Directories the agent may access, besides its state dir.
+    pub allowed_dirs: Vec<String>,
 }
 
 #[derive(Clone)]
@@ -352,6 +442,10 @@ pub struct App {
     pub should_quit: bool,
     pub agent: Option<AgentConfig>,
     pub pending_response: Option<oneshot::Receiver<eyre::Result<Option<String>>>>,
+    /// Reply chunks and tool calls of the running turn.
+    pub pending_output: Option<mpsc::Receiver<OutputEvent>>,
+    /// The task of the running turn, aborted to cancel it.
+    pub response_task: Option<AbortHandle>,
     pub pending_banner: Option<oneshot::Receiver<Option<String>>>,
     pub pending_components: Option<oneshot::Receiver<eyre::Result<Vec<DynamicItem>>>>,
     /// Latest CLI version from crates.io (None = not yet checked).
@@ -362,15 +456,51 @@ pub struct App {
     pub saved_picker: Option<Vec<AgentEntry>>,
     pub runtime: Option<Arc<Mutex<ComponentRuntime>>>,
     pub pending_runtime: Option<oneshot::Receiver<eyre::Result<ComponentRuntime>>>,
+    /// The agents open in background tabs, in tab order.
+    /// The active agent's tab comes before `tabs[active_tab]`.
+    pub tabs: Vec<AgentTab>,
+    pub active_tab: usize,
+    /// Given to agent runtimes to send tool calls needing approval on,
+    /// tagged with the env name of their agent.
+    pub approval_tx: mpsc::Sender<(String, ApprovalRequest)>,
+    pub approval_rx: mpsc::Receiver<(String, ApprovalRequest)>,
+    /// Tool calls waiting for approval, of which the first is shown.
+    pub approvals: VecDeque<(String, ApprovalRequest)>,
+}
+
+/// An agent open in a background tab, with its chat and running turn.
+pub struct AgentTab {
+    pub agent: AgentConfig,
+    pub chat: Box<ChatState>,
+    pub runtime: Option<Arc<Mutex<ComponentRuntime>>>,
+    pub pending_runtime: Option<oneshot::Receiver<eyre::Result<ComponentRuntime>>>,
+    pub pending_response: Option<oneshot::Receiver<eyre::Result<Option<String>>>>,
+    pub pending_output: Option<mpsc::Receiver<OutputEvent>>,
+    pub response_task: Option<AbortHandle>,
+}
+
+impl AgentTab {
+    pub fn is_busy(&self) -> bool {
+        self.pending_response.is_some() || self.pending_runtime.is_some()
+    }
+
+    /// Whether `@name` mentions this agent, by env or bot name.
+    pub fn is_mentioned_as(&self, name: &str) -> bool {
+        self.agent.env_name.eq_ignore_ascii_case(name)
+            || self.agent.bot_name.eq_ignore_ascii_case(name)
+    }
 }
 
 impl Default for App {
     fn default() -> Self {
+        let (approval_tx, approval_rx) = mpsc::channel(16);
         Self {
             screen: Screen::Auth(AuthState::Checking),
             should_quit: false,
             agent: None,
             pending_response: None,
+            pending_output: None,
+            response_task: None,
             pending_banner: None,
             pending_components: None,
             latest_cli_version: None,
@@ -380,6 +510,11 @@ impl Default for App {
             saved_picker: None,
             runtime: None,
             pending_runtime: None,
+            tabs: Vec::new(),
+            active_tab: 0,
+            approval_tx,
+            approval_rx,
+            approvals: VecDeque::new(),
         }
     }
 }
@@ -390,11 +525,74 @@ impl App {
             state.messages.push(ChatMessage {
                 role,
                 content,
+                author: None,
                 styled_lines: None,
             });
         }
     }
 
+    /// Moves the active agent to a background tab at `active_tab`.
+    /// Does nothing if no agent is open in the chat screen.
+    pub fn park_active_tab(&mut self) {
+        if !matches!(self.screen, Screen::Chat(_)) {
+            return;
+        }
+        let Some(agent) = self.agent.take() else {
+            return;
+        };
+        let screen = std::mem::replace(&mut self.screen, Screen::Picker(PickerState::loading(0)));
+        let Screen::Chat(mut chat) = screen else {
+            return;
+        };
+        // Background fetches of the chat screen would land in the wrong tab.
+        chat.banner_loading = false;
+        chat.dynamic_loading = false;
+        chat.dynamic_command = None;
+        self.pending_banner = None;
+        self.pending_components = None;
+        self.pending_env_check = None;
+        let tab = AgentTab {
+            agent,
+            chat,
+            runtime: self.runtime.take(),
+            pending_runtime: self.pending_runtime.take(),
+            pending_response: self.pending_response.take(),
+            pending_output: self.pending_output.take(),
+            response_task: self.response_task.take(),
+        };
+        let idx = self.active_tab.min(self.tabs.len());
+        self.tabs.insert(idx, tab);
+        self.active_tab = idx;
+    }
+
+    /// Makes the background tab `idx` the active one.
+    /// The active agent, if any, must have been parked or closed.
+    pub fn activate_tab(&mut self, idx: usize) {
+        if idx >= self.tabs.len() {
+            return;
+        }
+        let tab = self.tabs.remove(idx);
+        self.agent = Some(tab.agent);
+        self.runtime = tab.runtime;
+        self.pending_runtime = tab.pending_runtime;
+        self.pending_response = tab.pending_response;
+        self.pending_output = tab.pending_output;
+        self.response_task = tab.response_task;
+        self.screen = Screen::Chat(tab.chat);
+        self.active_tab = idx;
+    }
+
+    /// Switches to the tab `offset` places after the active one, wrapping around.
+    pub fn switch_tab(&mut self, offset: isize) {
+        if self.tabs.is_empty() || self.agent.is_none() {
+            return;
+        }
+        let count = self.tabs.len() as isize + 1;
+        let target = (self.active_tab as isize + offset).rem_euclid(count) as usize;
+        self.park_active_tab();
+        self.activate_tab(target);
+    }
+
     pub fn show_info_overlay(&mut self, lines: Vec<Line<'static>>) {
         if let Screen::Chat(state) = &mut self.screen {
             state.info_overlay = Some(lines);
@@ -429,7 +627,11 @@ pub struct SetupState {
     pub env_name: String,
     pub user_name: String,
     pub provider_idx: usize,
+    /// The base URL of the custom provider, if chosen.
+    pub base_url: String,
     pub api_key: String,
+    /// The models served by the custom provider.
+    pub custom_models: Vec<String>,
     pub model: String,
     pub model_idx: usize,
     pub allowed_dirs: Vec<String>,
@@ -445,7 +647,9 @@ impl Default for SetupState {
             env_name: String::new(),
             user_name: String::new(),
             provider_idx: 0,
+            base_url: String::new(),
             api_key: String::new(),
+            custom_models: Vec::new(),
             model: String::new(),
             model_idx: 0,
             allowed_dirs: Vec::new(),
@@ -455,19 +659,38 @@ impl Default for SetupState {
     }
 }
 
+impl SetupState {
+    /// Whether the custom provider is chosen, listed after `PROVIDERS`.
+    pub fn is_custom_provider(&self) -> bool {
+        self.provider_idx == PROVIDERS.len()
+    }
+}
+
 pub struct ChatMessage {
     pub role: MessageRole,
     pub content: String,
+    /// The agent of a reply from an agent other than that of the chat,
+    /// i.e. one mentioned with `@name`.
+    pub author: Option<String>,
     /// Optional pre-styled lines (overrides plain `content` rendering).
     pub styled_lines: Option<Vec<Line<'static>>>,
 }
 
 pub struct ChatState {
     pub messages: Vec<ChatMessage>,
+    /// Transcript of the session, started with its first message.
+    pub transcript: Option<Transcript>,
     pub input: String,
     pub input_history: Vec<String>,
     pub history_idx: Option<usize>,
     pub waiting: bool,
+    /// The reply of the running turn streamed so far.
+    pub streamed_reply: String,
+    /// The tool call in progress in the running turn, e.g. "asterai:fs/read".
+    pub active_tool_call: Option<String>,
+    /// The agent answering the running turn, if it was handed off
+    /// to another agent with `@name`.
+    pub handoff: Option<String>,
     pub spinner_tick: usize,
     pub slash_matches: Vec<usize>,
     pub slash_selected: usize,
@@ -492,7 +715,7 @@ pub struct ChatState {
     pub info_overlay: Option<Vec<Line<'static>>>,
     pub info_overlay_scroll: u16,
     /// Env var prompt (for tools requiring env vars on first use).
-    pub env_prompt_vars: Vec<String>,
+    pub env_prompt_vars: Vec<ConfigVar>,
     pub env_prompt_idx: usize,
     pub env_prompt_input: String,
     /// Cached env var presence for banner display: var_name -> is_set.
@@ -503,16 +726,75 @@ impl ChatState {
     pub fn has_env_prompt(&self) -> bool {
         self.env_prompt_idx < self.env_prompt_vars.len()
     }
+
+    /// Pushes a message of the conversation, recording it
+    /// in the transcript of the session of `agent`.
+    pub fn push_recorded(
+        &mut self,
+        agent: Option<&AgentConfig>,
+        role: MessageRole,
+        content: String,
+    ) {
+        self.record(
+            agent,
+            ChatMessage {
+                role,
+                content,
+                author: None,
+                styled_lines: None,
+            },
+        );
+    }
+
+    /// Pushes the reply of `author` to a message handed off with `@name`,
+    /// recording it in the transcript of the session of `agent`.
+    pub fn push_handoff_reply(
+        &mut self,
+        agent: Option<&AgentConfig>,
+        author: String,
+        content: String,
+    ) {
+        self.record(
+            agent,
+            ChatMessage {
+                role: MessageRole::Assistant,
+                content,
+                author: Some(author),
+                styled_lines: None,
+            },
+        );
+    }
+
+    fn record(&mut self, agent: Option<&AgentConfig>, message: ChatMessage) {
+        let result = agent.map(|agent| {
+            self.transcript
+                .get_or_insert_with(|| Transcript::start(&resolve_state_dir(&agent.env_name)))
+                .append(message.role, message.author.as_deref(), &message.content)
+        });
+        self.messages.push(message);
+        if let Some(Err(e)) = result {
+            self.messages.push(ChatMessage {
+                role: MessageRole::System,
+                content: format!("Failed to save transcript: {e:#}"),
+                author: None,
+                styled_lines: None,
+            });
+        }
+    }
 }
 
 impl Default for ChatState {
     fn default() -> Self {
         Self {
             messages: Vec::new(),
+            transcript: None,
             input: String::new(),
             input_history: Vec::new(),
             history_idx: None,
             waiting: false,
+            streamed_reply: String::new(),
+            active_tool_call: None,
+            handoff: None,
             spinner_tick: 0,
             slash_matches: Vec::new(),
             slash_selected: 0,
@@ -1,4 +1,5 @@
-use app::{App, AuthState, Screen};
+use app::{AgentConfig, AgentTab, App, AuthState, ChatMessage, ChatState, MessageRole, Screen};
+use asterai_runtime::runtime::output::OutputEvent;
 use crossterm::event::{self, Event, KeyCode, KeyModifiers};
 use crossterm::execute;
 use crossterm::terminal::{
@@ -7,9 +8,12 @@ use crossterm::terminal::{
 use ratatui::prelude::*;
 use std::io::{BufWriter, Write};
 use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::AbortHandle;
 
 pub mod app;
 pub mod ops;
+pub mod transcript;
 pub mod views;
 
 pub type Tty = BufWriter<std::fs::File>;
@@ -92,7 +96,8 @@ async fn run_app(
             || app.pending_version_check.is_some()
             || app.pending_sync.is_some()
             || app.pending_env_check.is_some()
-            || app.pending_runtime.is_some();
+            || app.pending_runtime.is_some()
+            || app.tabs.iter().any(AgentTab::is_busy);
         // Always poll with timeout so the cursor blink can advance.
         let is_chat = matches!(&app.screen, Screen::Chat(_));
         let needs_poll = has_pending || is_chat;
@@ -103,8 +108,19 @@ async fn run_app(
             },
             false => Some(event::read()?),
         };
-        if app.pending_response.is_some() {
-            check_pending_response(app);
+        if let Screen::Chat(state) = &mut app.screen {
+            drain_output_events(&mut app.pending_output, state);
+            check_pending_response(
+                &mut app.pending_response,
+                &mut app.pending_output,
+                &mut app.response_task,
+                state,
+                app.agent.as_ref(),
+            );
+        }
+        poll_background_tabs(app);
+        while let Ok(approval) = app.approval_rx.try_recv() {
+            app.approvals.push_back(approval);
         }
         // Tick spinners on timeout (no user event).
         if ev.is_none() {
@@ -154,10 +170,24 @@ async fn run_app(
         }
         if let Some(rx) = &mut app.pending_runtime {
             match rx.try_recv() {
+                // A runtime rebuilt after a cancelled turn keeps the chat.
+                Ok(Ok(rt)) if matches!(app.screen, Screen::Chat(_)) => {
+                    app.pending_runtime = None;
+                    app.runtime = Some(std::sync::Arc::new(tokio::sync::Mutex::new(rt)));
+                }
+                Ok(Err(e)) if matches!(app.screen, Screen::Chat(_)) => {
+                    app.pending_runtime = None;
+                    app.push_message(
+                        app::MessageRole::System,
+                        format!("Failed to reload the agent: {e:#}"),
+                    );
+                }
                 Ok(Ok(rt)) => {
                     app.pending_runtime = None;
                     app.runtime = Some(std::sync::Arc::new(tokio::sync::Mutex::new(rt)));
                     app.screen = Screen::Chat(Box::default());
+                    // A newly opened agent's tab comes last.
+                    app.active_tab = app.tabs.len();
                     views::chat::start_banner_fetch(app);
                     views::chat::start_env_check(app);
                 }
@@ -211,51 +241,102 @@ async fn run_app(
     }
 }
 
-fn check_pending_response(app: &mut App) {
-    let Some(rx) = &mut app.pending_response else {
+/// Applies the reply chunks and tool calls received for the running turn.
+fn drain_output_events(rx: &mut Option<mpsc::Receiver<OutputEvent>>, state: &mut ChatState) {
+    let Some(rx) = rx else {
         return;
     };
-    match rx.try_recv() {
-        Ok(result) => {
-            app.pending_response = None;
-            if let Screen::Chat(state) = &mut app.screen {
-                state.waiting = false;
-                match result {
-                    Ok(Some(text)) => {
-                        state.messages.push(app::ChatMessage {
-                            role: app::MessageRole::Assistant,
-                            content: text,
-                            styled_lines: None,
-                        });
-                    }
-                    Ok(None) => {
-                        state.messages.push(app::ChatMessage {
-                            role: app::MessageRole::System,
-                            content: "(No response received)".to_string(),
-                            styled_lines: None,
-                        });
-                    }
-                    Err(e) => {
-                        state.messages.push(app::ChatMessage {
-                            role: app::MessageRole::System,
-                            content: format!("Error: {e:#}"),
-                            styled_lines: None,
-                        });
-                    }
-                }
-            }
+    while let Ok(event) = rx.try_recv() {
+        match event {
+            OutputEvent::Chunk(chunk) => state.streamed_reply.push_str(&chunk),
+            OutputEvent::CallStarted {
+                component,
+                function,
+            } => state.active_tool_call = Some(format!("{component}/{function}")),
+            OutputEvent::CallFinished { .. } => state.active_tool_call = None,
         }
-        Err(tokio::sync::oneshot::error::TryRecvError::Empty) => {}
-        Err(tokio::sync::oneshot::error::TryRecvError::Closed) => {
-            app.pending_response = None;
-            if let Screen::Chat(state) = &mut app.screen {
-                state.waiting = false;
-                state.messages.push(app::ChatMessage {
-                    role: app::MessageRole::System,
-                    content: "Request was cancelled.".to_string(),
-                    styled_lines: None,
-                });
-            }
+    }
+}
+
+/// Applies the result of the running turn of `agent` once it is received.
+fn check_pending_response(
+    response: &mut Option<oneshot::Receiver<eyre::Result<Option<String>>>>,
+    output: &mut Option<mpsc::Receiver<OutputEvent>>,
+    task: &mut Option<AbortHandle>,
+    state: &mut ChatState,
+    agent: Option<&AgentConfig>,
+) {
+    let Some(rx) = response else {
+        return;
+    };
+    let result = match rx.try_recv() {
+        Ok(result) => Some(result),
+        Err(oneshot::error::TryRecvError::Empty) => return,
+        Err(oneshot::error::TryRecvError::Closed) => None,
+    };
+    *response = None;
+    *output = None;
+    *task = None;
+    state.waiting = false;
+    state.active_tool_call = None;
+    let handoff = state.handoff.take();
+    let streamed = std::mem::take(&mut state.streamed_reply);
+    let push_reply = |state: &mut ChatState, text: String| match handoff {
+        Some(author) => state.push_handoff_reply(agent, author, text),
+        None => state.push_recorded(agent, MessageRole::Assistant, text),
+    };
+    let system = |content: String| ChatMessage {
+        role: MessageRole::System,
+        content,
+        author: None,
+        styled_lines: None,
+    };
+    match result {
+        // The returned reply is complete, unlike the streamed one.
+        Some(Ok(Some(text))) => push_reply(state, text),
+        Some(Ok(None)) if !streamed.is_empty() => push_reply(state, streamed),
+        Some(Ok(None)) => {
+            state
+                .messages
+                .push(system("(No response received)".to_string()));
+        }
+        Some(Err(e)) => state.messages.push(system(format!("Error: {e:#}"))),
+        None => {
+            state
+                .messages
+                .push(system("Request was cancelled.".to_string()));
+        }
+    }
+}
+
+/// Applies the results of the turns and runtime rebuilds of background tabs.
+fn poll_background_tabs(app: &mut App) {
+    for tab in &mut app.tabs {
+        drain_output_events(&mut tab.pending_output, &mut tab.chat);
+        check_pending_response(
+            &mut tab.pending_response,
+            &mut tab.pending_output,
+            &mut tab.response_task,
+            &mut tab.chat,
+            Some(&tab.agent),
+        );
+        let Some(rx) = &mut tab.pending_runtime else {
+            continue;
+        };
+        let result = match rx.try_recv() {
+            Ok(result) => result,
+            Err(oneshot::error::TryRecvError::Empty) => continue,
+            Err(oneshot::error::TryRecvError::Closed) => Err(eyre::eyre!("build cancelled")),
+        };
+        tab.pending_runtime = None;
+        match result {
+            Ok(rt) => tab.runtime = Some(std::sync::Arc::new(tokio::sync::Mutex::new(rt))),
+            Err(e) => tab.chat.messages.push(ChatMessage {
+                role: MessageRole::System,
+                content: format!("Failed to reload the agent: {e:#}"),
+                author: None,
+                styled_lines: None,
+            }),
         }
     }
 }
@@ -282,6 +363,7 @@ fn check_pending_components(app: &mut App) {
                 state.messages.push(app::ChatMessage {
                     role: app::MessageRole::System,
                     content: format!("Failed to load components: {e:#}"),
+                    author: None,
                     styled_lines: None,
                 });
             }
@@ -11,15 +11,25 @@ use crate::command::resource_or_id::ResourceOrIdArg;
 use crate::config::{API_URL, REGISTRY_URL};
 use crate::local_store::LocalStore;
 use crate::runtime::build_runtime;
-use crate::tui::app::{AgentConfig, resolve_state_dir};
-use asterai_runtime::component::ComponentId;
+use crate::trace;
+use crate::tui::app::{
+    APPROVALS_VAR, AgentConfig, AgentSpec, CORE_COMPONENTS, CUSTOM_URL_VAR, default_user_name,
+    resolve_state_dir, sanitize_bot_name,
+};
+use asterai_runtime::component::binary::ComponentBinary;
+use asterai_runtime::component::config::ConfigVar;
 use asterai_runtime::component::function_name::ComponentFunctionName;
+use asterai_runtime::component::{Component, ComponentId};
+use asterai_runtime::runtime::approval::{ApprovalPolicies, ApprovalRequest};
+use asterai_runtime::runtime::handles::HandleTable;
+use asterai_runtime::runtime::output::OutputEvent;
 use asterai_runtime::runtime::parsing::ValExt;
 use asterai_runtime::runtime::{ComponentRuntime, Val};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, mpsc};
 
 /// Check if logged in. Returns username slug or None.
 pub async fn check_auth() -> Option<String> {
@@ -73,23 +83,180 @@ pub fn inspect_environment_sync(env_name: &str) -> Option<InspectData> {
 
 /// Call the converse function on an agent environment.
 pub async fn call_converse(message: &str, agent: &AgentConfig) -> eyre::Result<Option<String>> {
-    let (api, registry) = endpoints();
-    let state_dir = resolve_state_dir(&agent.env_name);
-    let mut allow_dirs: Vec<PathBuf> = vec![state_dir];
-    for dir in &agent.allowed_dirs {
-        allow_dirs.push(PathBuf::from(dir));
-    }
-    let escaped = message.replace('\\', "\\\\").replace('"', "\\\"");
-    let args = EnvArgs::for_call(
-        &agent.env_name,
-        "asterbot:agent",
-        "agent/converse",
-        vec![format!("\"{escaped}\"")],
-        allow_dirs,
-        api,
-        registry,
+    // Nothing can approve calls here, so those needing approval are denied.
+    let runtime = build_agent_runtime(agent, None).await?;
+    let result = converse(Arc::new(Mutex::new(runtime)), message, None).await;
+    trace::flush().await;
+    result
+}
+
+/// Provision the agent of `spec`: create its environment, add the core
+/// components and tools, and set its vars. `on_step` is called with
+/// the current and total number of steps and a message before each step.
+pub async fn provision_agent(
+    spec: &AgentSpec,
+    mut on_step: impl FnMut(usize, usize, &str) -> eyre::Result<()>,
+) -> eyre::Result<AgentConfig> {
+    let env_name = sanitize_bot_name(&spec.bot_name);
+    let state_dir = resolve_state_dir(&env_name);
+    let _ = std::fs::create_dir_all(&state_dir);
+    let mut allowed_dirs = vec![state_dir.to_string_lossy().to_string()];
+    allowed_dirs.extend(spec.allowed_dirs.iter().cloned());
+    let all_components: Vec<&str> = CORE_COMPONENTS
+        .iter()
+        .copied()
+        .chain(spec.tools.iter().map(String::as_str))
+        .collect();
+    let wasi_state_dir = state_dir.to_string_lossy().replace('\\', "/");
+    let tool_names = spec.tools.join(",");
+    let dirs_value = allowed_dirs.join(",");
+    let mut vars = vec![
+        ("ASTERBOT_MODEL", spec.model.as_str()),
+        ("ASTERBOT_TOOLS", &tool_names),
+        ("ASTERBOT_HOST_DIR", &wasi_state_dir),
+        ("ASTERBOT_BOT_NAME", spec.bot_name.as_str()),
+        ("ASTERBOT_USER_NAME", spec.user_name.as_str()),
+        ("ASTERBOT_ALLOWED_DIRS", dirs_value.as_str()),
+    ];
+    vars.extend(
+        spec.provider_vars
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str())),
     );
-    args.call_returning().await
+    // Components + init + vars.
+    let total = all_components.len() + 1 + vars.len();
+    let mut current = 0;
+    on_step(current, total, "Creating environment...")?;
+    match env_init(&env_name) {
+        Ok(_) => {}
+        Err(e) => {
+            let msg = format!("{e:#}");
+            if !msg.contains("already exists") {
+                return Err(e);
+            }
+            let _ = pull_env(&env_name).await;
+        }
+    }
+    current += 1;
+    for comp in &all_components {
+        on_step(current, total, &format!("Adding {comp}..."))?;
+        match add_component(&env_name, comp).await {
+            Ok(_) => {}
+            Err(e) => {
+                let msg = format!("{e:#}");
+                if !msg.contains("already") {
+                    return Err(e);
+                }
+            }
+        }
+        current += 1;
+    }
+    for (key, value) in &vars {
+        on_step(current, total, &format!("Setting {key}..."))?;
+        let _ = set_var(&env_name, key, value);
+        current += 1;
+    }
+    let components: Vec<String> = all_components.iter().map(|c| c.to_string()).collect();
+    Ok(AgentConfig {
+        env_name,
+        namespace: Auth::read_user_or_fallback_namespace(),
+        bot_name: spec.bot_name.clone(),
+        user_name: spec.user_name.clone(),
+        model: Some(spec.model.clone()),
+        provider: spec.provider.clone(),
+        tools: spec.tools.clone(),
+        allowed_dirs,
+        banner_mode: "auto".to_string(),
+        tool_config: components_config(&components),
+        approvals: ApprovalPolicies::default(),
+    })
+}
+
+/// Load the agent of the environment `env_name` from its inspect data.
+pub fn load_agent(
+    env_name: &str,
+    namespace: &str,
+    data: &InspectData,
+) -> eyre::Result<AgentConfig> {
+    let has_agent = data.components.iter().any(|c| {
+        let base = c.split('@').next().unwrap_or(c);
+        base == "asterbot:agent"
+    });
+    if !has_agent {
+        eyre::bail!("Not an agent (missing asterbot:agent component).");
+    }
+    let provider = if data.vars.contains(&CUSTOM_URL_VAR.to_string()) {
+        "custom"
+    } else if data.vars.contains(&"ANTHROPIC_KEY".to_string()) {
+        "anthropic"
+    } else if data.vars.contains(&"OPENAI_KEY".to_string()) {
+        "openai"
+    } else if data.vars.contains(&"GOOGLE_KEY".to_string()) {
+        "google"
+    } else {
+        "unknown"
+    };
+    let tools: Vec<String> = data
+        .components
+        .iter()
+        .filter(|c| {
+            let base = c.split('@').next().unwrap_or(c);
+            !CORE_COMPONENTS.contains(&base)
+        })
+        .map(|c| c.split('@').next().unwrap_or(c).to_string())
+        .collect();
+    let allowed_dirs = data
+        .var_values
+        .get("ASTERBOT_ALLOWED_DIRS")
+        .map(|v| {
+            v.split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+    // Ensure state dir exists.
+    let state_dir = resolve_state_dir(env_name);
+    let _ = std::fs::create_dir_all(&state_dir);
+    // Set ASTERBOT_HOST_DIR if not already set.
+    if !data.var_values.contains_key("ASTERBOT_HOST_DIR") {
+        let wasi_dir = state_dir.to_string_lossy().replace('\\', "/");
+        let _ = set_var(env_name, "ASTERBOT_HOST_DIR", &wasi_dir);
+    }
+    let user_name = data
+        .var_values
+        .get("ASTERBOT_USER_NAME")
+        .cloned()
+        .unwrap_or_else(default_user_name);
+    // Invalid policies fail the load rather than being dropped,
+    // as dropping them would let calls meant to be denied proceed.
+    let approvals = match data.var_values.get(APPROVALS_VAR) {
+        Some(value) => ApprovalPolicies::from_str(value)
+            .map_err(|e| eyre::eyre!("invalid {APPROVALS_VAR}: {e}"))?,
+        None => ApprovalPolicies::default(),
+    };
+    let banner_mode = data
+        .var_values
+        .get("ASTERBOT_BANNER")
+        .cloned()
+        .unwrap_or_else(|| "auto".to_string());
+    Ok(AgentConfig {
+        env_name: env_name.to_string(),
+        namespace: namespace.to_string(),
+        bot_name: data
+            .var_values
+            .get("ASTERBOT_BOT_NAME")
+            .cloned()
+            .unwrap_or(env_name.to_string()),
+        user_name,
+        model: data.var_values.get("ASTERBOT_MODEL").cloned(),
+        provider: provider.to_string(),
+        tools,
+        allowed_dirs,
+        banner_mode,
+        tool_config: components_config(&data.components),
+        approvals,
+    })
 }
 
 /// Init an environment.
@@ -99,13 +266,29 @@ pub fn env_init(env_name: &str) -> eyre::Result<()> {
     args.init()
 }
 
-/// Add a component to an environment.
-pub async fn add_component(env_name: &str, component: &str) -> eyre::Result<()> {
+/// Add a component to an environment, returning the added component.
+pub async fn add_component(env_name: &str, component: &str) -> eyre::Result<ComponentBinary> {
     let (api, registry) = endpoints();
     let args = EnvArgs::for_add_component(env_name, component, api, registry)?;
     args.add_component().await
 }
 
+/// Reads the config vars declared by locally stored components,
+/// by component ID, from their references, e.g. `asterai:telegram@1.0.0`.
+/// Components that are not stored locally are skipped.
+pub fn components_config(component_refs: &[String]) -> HashMap<String, Vec<ConfigVar>> {
+    component_refs
+        .iter()
+        .filter_map(|component_ref| {
+            let component = Component::from_str(component_ref).ok()?;
+            let binary = LocalStore::fetch_component_version(&component).ok()?;
+            let config = binary.config().to_vec();
+            Some((component.id().to_string(), config))
+        })
+        .filter(|(_, config)| !config.is_empty())
+        .collect()
+}
+
 /// Remove a component from an environment.
 pub async fn remove_component(env_name: &str, component: &str) -> eyre::Result<()> {
     let (api, registry) = endpoints();
@@ -150,6 +333,47 @@ pub async fn list_remote_components() -> eyre::Result<Vec<(String, String, Strin
         .collect())
 }
 
+/// Normalize the base URL of an OpenAI-compatible endpoint to end with
+/// `/v1`, e.g. `http://localhost:8080/` becomes `http://localhost:8080/v1`.
+pub fn normalize_custom_url(url: &str) -> String {
+    let url = url.trim().trim_end_matches('/');
+    match url.ends_with("/v1") {
+        true => url.to_string(),
+        false => format!("{url}/v1"),
+    }
+}
+
+/// Fetch the IDs of the models served by the OpenAI-compatible endpoint
+/// at `base_url`, from its `/models` route, sorted.
+pub async fn fetch_custom_models(
+    base_url: &str,
+    api_key: Option<&str>,
+) -> eyre::Result<Vec<String>> {
+    #[derive(serde::Deserialize)]
+    struct ModelList {
+        data: Vec<ModelEntry>,
+    }
+    #[derive(serde::Deserialize)]
+    struct ModelEntry {
+        id: String,
+    }
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(5))
+        .build()?;
+    let mut request = client.get(format!("{base_url}/models"));
+    if let Some(api_key) = api_key.filter(|k| !k.is_empty()) {
+        request = request.bearer_auth(api_key);
+    }
+    let resp = request.send().await?.error_for_status()?;
+    let list: ModelList = resp.json().await?;
+    let mut models: Vec<String> = list.data.into_iter().map(|m| m.id).collect();
+    if models.is_empty() {
+        eyre::bail!("no models are served at {base_url}");
+    }
+    models.sort();
+    Ok(models)
+}
+
 /// Fetch the latest CLI version from crates.io.
 pub async fn fetch_latest_cli_version() -> Option<String> {
     #[derive(serde::Deserialize)]
@@ -202,7 +426,10 @@ pub fn delete_local_env(namespace: &str, name: &str) -> eyre::Result<usize> {
 }
 
 /// Build a ComponentRuntime for an agent, ready to reuse across calls.
-pub async fn build_agent_runtime(agent: &AgentConfig) -> eyre::Result<ComponentRuntime> {
+pub async fn build_agent_runtime(
+    agent: &AgentConfig,
+    approver_tx: Option<mpsc::Sender<(String, ApprovalRequest)>>,
+) -> eyre::Result<ComponentRuntime> {
     let state_dir = resolve_state_dir(&agent.env_name);
     let mut allow_dirs: Vec<PathBuf> = vec![state_dir];
     for dir in &agent.allowed_dirs {
@@ -213,13 +440,50 @@ pub async fn build_agent_runtime(agent: &AgentConfig) -> eyre::Result<ComponentR
         .with_local_namespace_fallback();
     let resource_id = asterai_runtime::resource::ResourceId::from_str(&resource_id_str)?;
     let environment = LocalStore::fetch_environment(&resource_id)?;
-    build_runtime(environment, &allow_dirs).await
+    let runtime = build_runtime(environment, &allow_dirs).await?;
+    runtime
+        .call_approvals()
+        .set_policies(agent.approvals.clone());
+    let Some(approver_tx) = approver_tx else {
+        return Ok(runtime);
+    };
+    // Forward the runtime's requests tagged with the agent they are for,
+    // until the runtime is dropped.
+    let (request_tx, mut request_rx) = mpsc::channel(1);
+    let env_name = agent.env_name.clone();
+    tokio::spawn(async move {
+        while let Some(request) = request_rx.recv().await {
+            if approver_tx.send((env_name.clone(), request)).await.is_err() {
+                return;
+            }
+        }
+    });
+    runtime.call_approvals().set_approver(Some(request_tx));
+    Ok(runtime)
 }
 
 /// Call the converse function using a cached runtime.
 pub async fn call_with_runtime(
     runtime: Arc<Mutex<ComponentRuntime>>,
     message: &str,
+) -> eyre::Result<Option<String>> {
+    converse(runtime, message, None).await
+}
+
+/// Call the converse function using a cached runtime, streaming the
+/// reply chunks and tool calls of the turn to `output_tx` as it runs.
+pub async fn call_with_runtime_streaming(
+    runtime: Arc<Mutex<ComponentRuntime>>,
+    message: &str,
+    output_tx: mpsc::Sender<OutputEvent>,
+) -> eyre::Result<Option<String>> {
+    converse(runtime, message, Some(output_tx)).await
+}
+
+async fn converse(
+    runtime: Arc<Mutex<ComponentRuntime>>,
+    message: &str,
+    output_tx: Option<mpsc::Sender<OutputEvent>>,
 ) -> eyre::Result<Option<String>> {
     let comp_id = ComponentId::from_str("asterbot:agent")?;
     let function_name = ComponentFunctionName::new(Some("agent".to_owned()), "converse".to_owned());
@@ -228,12 +492,21 @@ pub async fn call_with_runtime(
         .find_function(&comp_id, &function_name, None)?
         .ok_or_else(|| eyre::eyre!("converse function not found"))?;
     let input = Val::String(message.into());
-    let output_opt = rt.call_function(function, &[input]).await?;
+    let output_opt = match output_tx {
+        Some(output_tx) => {
+            rt.call_function_streaming(function, &[input], output_tx)
+                .await?
+        }
+        None => rt.call_function(function, &[input]).await?,
+    };
     if let Some(output) = output_opt
         && let Some(function_output) = output.function_output_opt
     {
-        let json = function_output.value.val.try_into_json_value();
-        return Ok(json.map(|j| match j {
+        let json = function_output
+            .value
+            .val
+            .into_json_value(&HandleTable::default());
+        return Ok(Some(match json {
             serde_json::Value::String(s) => s,
             other => other.to_string(),
         }));
@@ -0,0 +1,272 @@
+//! Chat transcripts of the agents TUI.
+//!
+//! Each session is a JSON Lines file of its messages under the agent's
+//! state dir, at `transcripts/<id>.jsonl`. IDs are the UTC start times
+//! of sessions, so they sort chronologically.
+//!
+//! The agent keeps its own memory of the conversation in
+//! `conversation.json`. When a session is cleared or another one is
+//! resumed, that file is saved as `transcripts/<id>.conversation.json`,
+//! and restored when the session is resumed, so that the agent
+//! remembers the resumed conversation.
+use crate::tui::app::MessageRole;
+use chrono::{DateTime, Local, Utc};
+use eyre::Context;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const TRANSCRIPTS_DIR: &str = "transcripts";
+const CONVERSATION_FILE: &str = "conversation.json";
+/// Characters of context on each side of a search match.
+const SNIPPET_CONTEXT: usize = 30;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TranscriptMessage {
+    pub role: MessageRole,
+    /// The agent of a reply from another agent mentioned with `@name`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    pub content: String,
+    pub at: DateTime<Utc>,
+}
+
+/// The transcript of a chat session.
+pub struct Transcript {
+    state_dir: PathBuf,
+    id: String,
+}
+
+pub struct SessionSummary {
+    pub id: String,
+    pub started_at: DateTime<Utc>,
+    pub message_count: usize,
+    /// The first message of the user.
+    pub preview: String,
+}
+
+pub struct SearchHit {
+    pub session: SessionSummary,
+    pub snippet: String,
+}
+
+impl Transcript {
+    /// Starts a new session of the agent with the state dir `state_dir`.
+    /// Its file is created with the first message.
+    pub fn start(state_dir: &Path) -> Self {
+        let id = Utc::now().format("%Y%m%dT%H%M%S%3fZ").to_string();
+        Self::open(state_dir, &id)
+    }
+
+    pub fn open(state_dir: &Path, id: &str) -> Self {
+        Self {
+            state_dir: state_dir.to_path_buf(),
+            id: id.to_owned(),
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn append(
+        &self,
+        role: MessageRole,
+        author: Option<&str>,
+        content: &str,
+    ) -> eyre::Result<()> {
+        let dir = self.state_dir.join(TRANSCRIPTS_DIR);
+        fs::create_dir_all(&dir)?;
+        let message = TranscriptMessage {
+            role,
+            author: author.map(str::to_owned),
+            content: content.to_owned(),
+            at: Utc::now(),
+        };
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path())?;
+        writeln!(file, "{}", serde_json::to_string(&message)?)?;
+        Ok(())
+    }
+
+    /// Loads the messages of the session, skipping lines that
+    /// cannot be parsed, e.g. a line cut off by a crash.
+    pub fn load(&self) -> eyre::Result<Vec<TranscriptMessage>> {
+        let content = match fs::read_to_string(self.path()) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e).wrap_err("failed to read transcript"),
+        };
+        let messages = content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect();
+        Ok(messages)
+    }
+
+    /// Saves the agent's memory of the conversation as that of this session.
+    pub fn save_agent_conversation(&self) -> eyre::Result<()> {
+        let live = self.state_dir.join(CONVERSATION_FILE);
+        if !live.exists() {
+            return Ok(());
+        }
+        fs::create_dir_all(self.state_dir.join(TRANSCRIPTS_DIR))?;
+        fs::copy(&live, self.conversation_path())?;
+        Ok(())
+    }
+
+    /// Restores the agent's memory of the conversation of this session,
+    /// or clears it if none was saved. Fails without touching the
+    /// agent's memory if the session has no messages.
+    pub fn restore_agent_conversation(&self) -> eyre::Result<()> {
+        if self.load()?.is_empty() {
+            eyre::bail!("session {} has no messages", self.id);
+        }
+        let live = self.state_dir.join(CONVERSATION_FILE);
+        match self.conversation_path().exists() {
+            true => fs::copy(self.conversation_path(), live).map(|_| ())?,
+            false => fs::write(live, "[]")?,
+        }
+        Ok(())
+    }
+
+    fn path(&self) -> PathBuf {
+        self.state_dir
+            .join(TRANSCRIPTS_DIR)
+            .join(format!("{}.jsonl", self.id))
+    }
+
+    fn conversation_path(&self) -> PathBuf {
+        self.state_dir
+            .join(TRANSCRIPTS_DIR)
+            .join(format!("{}.conversation.json", self.id))
+    }
+}
+
+/// Lists the sessions of the agent, newest first.
+pub fn list_sessions(state_dir: &Path) -> Vec<SessionSummary> {
+    load_sessions(state_dir)
+        .into_iter()
+        .filter_map(|(id, messages)| summarize(id, &messages))
+        .collect()
+}
+
+/// Finds the sessions with a message containing `query`,
+/// ignoring case, newest first.
+pub fn search(state_dir: &Path, query: &str) -> Vec<SearchHit> {
+    if query.is_empty() {
+        return vec![];
+    }
+    let query = query.to_lowercase();
+    load_sessions(state_dir)
+        .into_iter()
+        .filter_map(|(id, messages)| {
+            let snippet = messages
+                .iter()
+                .find_map(|m| snippet_of(&m.content, &query))?;
+            let session = summarize(id, &messages)?;
+            Some(SearchHit { session, snippet })
+        })
+        .collect()
+}
+
+/// Renders a session as Markdown.
+pub fn to_markdown(
+    messages: &[TranscriptMessage],
+    bot_name: &str,
+    user_name: &str,
+    session_id: &str,
+) -> String {
+    let mut md = format!("# Conversation with {bot_name}\n\n_Session {session_id}_\n");
+    for message in messages {
+        let name = match message.role {
+            MessageRole::User => user_name,
+            MessageRole::Assistant => message.author.as_deref().unwrap_or(bot_name),
+            MessageRole::System => "system",
+        };
+        let at = message.at.with_timezone(&Local).format("%Y-%m-%d %H:%M");
+        md.push_str(&format!("\n**{name}** ({at}):\n\n{}\n", message.content));
+    }
+    md
+}
+
+/// Renders a session as JSON.
+pub fn to_json(
+    messages: &[TranscriptMessage],
+    env_name: &str,
+    session_id: &str,
+) -> eyre::Result<String> {
+    let json = serde_json::json!({
+        "agent": env_name,
+        "session": session_id,
+        "messages": messages,
+    });
+    Ok(serde_json::to_string_pretty(&json)?)
+}
+
+/// Loads all sessions, newest first.
+fn load_sessions(state_dir: &Path) -> Vec<(String, Vec<TranscriptMessage>)> {
+    let Ok(entries) = fs::read_dir(state_dir.join(TRANSCRIPTS_DIR)) else {
+        return vec![];
+    };
+    let mut ids: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().into_owned();
+            name.strip_suffix(".jsonl").map(str::to_owned)
+        })
+        .collect();
+    ids.sort_by(|a, b| b.cmp(a));
+    ids.into_iter()
+        .map(|id| {
+            let messages = Transcript::open(state_dir, &id).load().unwrap_or_default();
+            (id, messages)
+        })
+        .collect()
+}
+
+fn summarize(id: String, messages: &[TranscriptMessage]) -> Option<SessionSummary> {
+    let started_at = messages.first()?.at;
+    let preview = messages
+        .iter()
+        .find(|m| m.role == MessageRole::User)
+        .map(|m| m.content.lines().next().unwrap_or_default().to_owned())
+        .unwrap_or_default();
+    Some(SessionSummary {
+        id,
+        started_at,
+        message_count: messages.len(),
+        preview,
+    })
+}
+
+/// Returns the text around the first match of the lowercase `query`
+/// in `content`, on one line.
+fn snippet_of(content: &str, query: &str) -> Option<String> {
+    let chars: Vec<char> = content.chars().collect();
+    let lower: Vec<char> = content.to_lowercase().chars().collect();
+    // Lowercasing may change the number of chars, in which case
+    // positions in `lower` do not map to `chars`.
+    let is_aligned = lower.len() == chars.len();
+    let query: Vec<char> = query.chars().collect();
+    let start = lower.windows(query.len()).position(|w| w == query)?;
+    if !is_aligned {
+        return Some(content.lines().next().unwrap_or_default().to_owned());
+    }
+    let from = start.saturating_sub(SNIPPET_CONTEXT);
+    let to = (start + query.len() + SNIPPET_CONTEXT).min(chars.len());
+    let text: String = chars[from..to].iter().collect();
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let prefix = match from > 0 {
+        true => "…",
+        false => "",
+    };
+    let suffix = match to < chars.len() {
+        true => "…",
+        false => "",
+    };
+    Some(format!("{prefix}{text}{suffix}"))
+}
@@ -1,12 +1,21 @@
+use crate::command::env::inspect::{format_attributes, format_status};
 use crate::tui::app::{
-    App, CORE_COMPONENTS, ChatMessage, ChatState, DynamicItem, MessageRole, PROVIDERS, PickerState,
-    SLASH_COMMANDS, SPINNER_FRAMES, Screen, resolve_state_dir, tool_env_vars,
+    APPROVALS_VAR, App, CORE_COMPONENTS, CUSTOM_KEY_VAR, CUSTOM_MODEL_PREFIX, CUSTOM_URL_VAR,
+    ChatMessage, ChatState, DynamicItem, MessageRole, PROVIDERS, PickerState, SLASH_COMMANDS,
+    SPINNER_FRAMES, Screen, provider_of_model, resolve_state_dir,
 };
 use crate::tui::ops;
+use crate::tui::transcript::{self, Transcript};
+use asterai_runtime::component::config::ConfigVar;
+use asterai_runtime::runtime::ComponentRuntime;
+use asterai_runtime::runtime::approval::{ApprovalPolicy, ApprovalRequest};
 use crossterm::event::{Event, KeyCode};
 use ratatui::prelude::*;
 use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+use std::str::FromStr;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
 
 const ROBOT: &[&str] = &[
     "     o     ",
@@ -32,7 +41,7 @@ pub fn render(f: &mut Frame, state: &ChatState, app: &App) {
         true => state
             .env_prompt_vars
             .get(state.env_prompt_idx)
-            .map(|v| v.len() + 1) // "VAR="
+            .map(|v| v.name.len() + 1) // "VAR="
             .unwrap_or(0),
         false => 2, // "> "
     };
@@ -42,6 +51,19 @@ pub fn render(f: &mut Frame, state: &ChatState, app: &App) {
         false => 1,
     };
     let input_h = (visual_lines + 2).max(3); // +2 for top/bottom borders
+    // The tab bar is only shown with several agents open.
+    let area = match app.tabs.is_empty() {
+        true => area,
+        false => {
+            render_tab_bar(f, state, app, Rect::new(area.x, area.y, area.width, 1));
+            Rect::new(
+                area.x,
+                area.y + 1,
+                area.width,
+                area.height.saturating_sub(1),
+            )
+        }
+    };
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -121,6 +143,10 @@ pub fn render(f: &mut Frame, state: &ChatState, app: &App) {
     if state.info_overlay.is_some() {
         render_info_overlay(f, state, chunks[1]);
     }
+    // A tool call waiting for approval renders over everything else.
+    if let Some((env_name, request)) = app.approvals.front() {
+        render_approval(f, env_name, request, app.approvals.len() - 1, chunks[1]);
+    }
 }
 
 pub async fn handle_event(app: &mut App, event: Event) -> eyre::Result<()> {
@@ -141,11 +167,22 @@ pub async fn handle_event(app: &mut App, event: Event) -> eyre::Result<()> {
     if key_event.kind != crossterm::event::KeyEventKind::Press {
         return Ok(());
     }
-    // Ignore Ctrl+key combos (e.g. Ctrl+V) to avoid stray characters.
+    // A tool call waiting for approval takes all keys until it is answered.
+    if !app.approvals.is_empty() {
+        answer_approval(app, key_event.code);
+        return Ok(());
+    }
+    // Ignore Ctrl+key combos (e.g. Ctrl+V) to avoid stray characters,
+    // except those switching tabs.
     if key_event
         .modifiers
         .contains(crossterm::event::KeyModifiers::CONTROL)
     {
+        match key_event.code {
+            KeyCode::Char('n') => app.switch_tab(1),
+            KeyCode::Char('p') => app.switch_tab(-1),
+            _ => {}
+        }
         return Ok(());
     }
     let code = key_event.code;
@@ -159,6 +196,9 @@ pub async fn handle_event(app: &mut App, event: Event) -> eyre::Result<()> {
         return Ok(());
     };
     if state.waiting {
+        if code == KeyCode::Esc {
+            cancel_turn(app);
+        }
         return Ok(());
     }
     // --- Info overlay handling (scroll or dismiss) ---
@@ -197,7 +237,15 @@ pub async fn handle_event(app: &mut App, event: Event) -> eyre::Result<()> {
         // --- Env var prompt handling ---
         KeyCode::Enter if has_env_prompt => {
             let value = state.env_prompt_input.trim().to_string();
-            let var_name = state.env_prompt_vars[state.env_prompt_idx].clone();
+            let var = state.env_prompt_vars[state.env_prompt_idx].clone();
+            let var_name = var.name.clone();
+            // Keep the prompt open until the value is valid or skipped.
+            if !value.is_empty()
+                && let Err(e) = var.validate(&value)
+            {
+                set_toast_color(app, &format!("{var_name} {e}"), Color::Red);
+                return Ok(());
+            }
             if !value.is_empty() {
                 let mut ok = false;
                 if let Some(agent) = &app.agent {
@@ -264,9 +312,8 @@ pub async fn handle_event(app: &mut App, event: Event) -> eyre::Result<()> {
             }
         }
         KeyCode::Down if has_dynamic && !state.dynamic_loading => {
-            if state.dynamic_selected + 1 < state.dynamic_matches.len() {
-                state.dynamic_selected += 1;
-            }
+            let last = state.dynamic_matches.len().saturating_sub(1);
+            state.dynamic_selected = (state.dynamic_selected + 1).min(last);
         }
         KeyCode::Enter
             if has_dynamic && !state.dynamic_loading && !state.dynamic_matches.is_empty() =>
@@ -313,9 +360,8 @@ pub async fn handle_event(app: &mut App, event: Event) -> eyre::Result<()> {
             }
         }
         KeyCode::Down if has_sub_menu => {
-            if state.sub_selected + 1 < state.sub_matches.len() {
-                state.sub_selected += 1;
-            }
+            let last = state.sub_matches.len().saturating_sub(1);
+            state.sub_selected = (state.sub_selected + 1).min(last);
         }
         KeyCode::Enter | KeyCode::Tab if has_sub_menu => {
             let cmd_idx = state.active_command.unwrap();
@@ -360,9 +406,8 @@ pub async fn handle_event(app: &mut App, event: Event) -> eyre::Result<()> {
             }
         }
         KeyCode::Down if has_slash_menu => {
-            if state.slash_selected + 1 < state.slash_matches.len() {
-                state.slash_selected += 1;
-            }
+            let last = state.slash_matches.len().saturating_sub(1);
+            state.slash_selected = (state.slash_selected + 1).min(last);
         }
         KeyCode::Enter | KeyCode::Tab
             if has_slash_menu && state.slash_selected < state.slash_matches.len() =>
@@ -392,16 +437,14 @@ pub async fn handle_event(app: &mut App, event: Event) -> eyre::Result<()> {
             state.slash_selected = 0;
             state.input.clear();
         }
-        KeyCode::Up if !has_slash_menu => {
-            if !state.input_history.is_empty() {
-                let idx = match state.history_idx {
-                    Some(i) if i > 0 => i - 1,
-                    Some(i) => i,
-                    None => state.input_history.len() - 1,
-                };
-                state.history_idx = Some(idx);
-                state.input = state.input_history[idx].clone();
-            }
+        KeyCode::Up if !has_slash_menu && !state.input_history.is_empty() => {
+            let idx = match state.history_idx {
+                Some(i) if i > 0 => i - 1,
+                Some(i) => i,
+                None => state.input_history.len() - 1,
+            };
+            state.history_idx = Some(idx);
+            state.input = state.input_history[idx].clone();
         }
         KeyCode::Down if !has_slash_menu => {
             if let Some(idx) = state.history_idx {
@@ -448,24 +491,8 @@ pub async fn handle_event(app: &mut App, event: Event) -> eyre::Result<()> {
                 state.slash_matches.clear();
                 state.slash_selected = 0;
             } else {
-                // Empty input — return to agent picker.
-                app.agent = None;
-                app.runtime = None;
-                app.pending_response = None;
-                app.pending_banner = None;
-                app.pending_components = None;
-                // Restore saved picker state instantly (no loading screen).
-                if let Some(agents) = app.saved_picker.take() {
-                    app.screen = Screen::Picker(PickerState {
-                        agents,
-                        selected: 0,
-                        loading: false,
-                        error: None,
-                        spinner_tick: 0,
-                    });
-                } else {
-                    app.screen = Screen::Picker(PickerState::loading(0));
-                }
+                // Empty input — close the agent's tab.
+                close_active_tab(app);
             }
         }
         _ => {}
@@ -473,6 +500,43 @@ pub async fn handle_event(app: &mut App, event: Event) -> eyre::Result<()> {
     Ok(())
 }
 
+/// Renders the open agents in tab order, marking the active one
+/// and those with a turn running.
+fn render_tab_bar(f: &mut Frame, state: &ChatState, app: &App, area: Rect) {
+    let frame = SPINNER_FRAMES[state.spinner_tick % SPINNER_FRAMES.len()];
+    let tab_span = |name: &str, is_active: bool, is_busy: bool| {
+        let label = match is_busy {
+            true => format!(" {frame} {name} "),
+            false => format!(" {name} "),
+        };
+        let style = match is_active {
+            true => Style::default().fg(Color::Black).bg(Color::Cyan).bold(),
+            false => Style::default().fg(Color::DarkGray),
+        };
+        Span::styled(label, style)
+    };
+    let active_idx = app.active_tab.min(app.tabs.len());
+    let mut spans = Vec::new();
+    for (i, tab) in app.tabs.iter().enumerate() {
+        if i == active_idx
+            && let Some(agent) = &app.agent
+        {
+            spans.push(tab_span(&agent.bot_name, true, state.waiting));
+        }
+        spans.push(tab_span(&tab.agent.bot_name, false, tab.is_busy()));
+    }
+    if active_idx == app.tabs.len()
+        && let Some(agent) = &app.agent
+    {
+        spans.push(tab_span(&agent.bot_name, true, state.waiting));
+    }
+    spans.push(Span::styled(
+        "  ctrl+n/p switch · @name hand off · /agents open",
+        Style::default().fg(Color::DarkGray),
+    ));
+    f.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
 fn render_banner(f: &mut Frame, name: &str, chat: &ChatState, app: &App, area: Rect) {
     let agent = app.agent.as_ref();
     let banner_text = &chat.banner_text;
@@ -571,14 +635,17 @@ fn render_banner(f: &mut Frame, name: &str, chat: &ChatState, app: &App, area: R
                 .next_back()
                 .unwrap_or(full_name)
                 .to_string();
-            let env_vars = tool_env_vars(full_name);
-            let required_vars: Vec<_> = env_vars.iter().filter(|v| v.required).collect();
+            let config = agent.map(|a| a.tool_config(full_name)).unwrap_or_default();
+            let required_vars: Vec<_> = config
+                .iter()
+                .filter(|v| v.is_required && v.default.is_none())
+                .collect();
             let style = if required_vars.is_empty() || !env_loaded {
                 tool_plain
             } else {
                 let all_set = required_vars
                     .iter()
-                    .all(|v| env_status.get(v.name).copied().unwrap_or(false));
+                    .all(|v| env_status.get(&v.name).copied().unwrap_or(false));
                 match all_set {
                     true => tool_green,
                     false => tool_orange,
@@ -685,13 +752,26 @@ fn render_messages(f: &mut Frame, state: &ChatState, env_name: &str, area: Rect)
     }
     let assistant_prefix = format!("{env_name}: ");
     let mut lines: Vec<Line> = Vec::new();
-    for msg in &state.messages {
-        let (prefix, style) = match msg.role {
-            MessageRole::User => ("You: ", Style::default().fg(Color::Cyan)),
-            MessageRole::Assistant => {
+    let streamed = match state.streamed_reply.is_empty() {
+        true => None,
+        false => Some(ChatMessage {
+            role: MessageRole::Assistant,
+            content: state.streamed_reply.clone(),
+            author: state.handoff.clone(),
+            styled_lines: None,
+        }),
+    };
+    for msg in state.messages.iter().chain(streamed.as_ref()) {
+        let author_prefix = msg.author.as_ref().map(|author| format!("{author}: "));
+        let (prefix, style) = match (msg.role, &author_prefix) {
+            (MessageRole::User, _) => ("You: ", Style::default().fg(Color::Cyan)),
+            (MessageRole::Assistant, Some(author_prefix)) => {
+                (author_prefix.as_str(), Style::default().fg(Color::Magenta))
+            }
+            (MessageRole::Assistant, None) => {
                 (assistant_prefix.as_str(), Style::default().fg(Color::White))
             }
-            MessageRole::System => ("", Style::default().fg(Color::Yellow)),
+            (MessageRole::System, _) => ("", Style::default().fg(Color::Yellow)),
         };
         lines.push(Line::from(""));
         if let Some(styled) = &msg.styled_lines {
@@ -704,18 +784,27 @@ fn render_messages(f: &mut Frame, state: &ChatState, env_name: &str, area: Rect)
                     false => "",
                 };
                 lines.push(Line::from(vec![
-                    Span::styled(line_prefix, style.bold()),
+                    Span::styled(line_prefix.to_string(), style.bold()),
                     Span::styled(text_line, style),
                 ]));
             }
         }
     }
-    if state.waiting {
+    let status = match (&state.active_tool_call, &state.handoff) {
+        (Some(call), _) => Some(format!("tool call in progress: {call}")),
+        (None, Some(agent)) if streamed.is_none() => Some(format!("{agent} is thinking...")),
+        (None, None) if streamed.is_none() => Some("Thinking...".to_string()),
+        (None, _) => None,
+    };
+    if state.waiting
+        && let Some(status) = status
+    {
         let frame = SPINNER_FRAMES[state.spinner_tick % SPINNER_FRAMES.len()];
         lines.push(Line::from(""));
         lines.push(Line::from(vec![
             Span::styled(format!("{frame} "), Style::default().fg(Color::Cyan)),
-            Span::styled("Thinking...", Style::default().fg(Color::DarkGray)),
+            Span::styled(status, Style::default().fg(Color::DarkGray)),
+            Span::styled("  (Esc to cancel)", Style::default().fg(Color::DarkGray)),
         ]));
     }
     let total_lines = lines.len() as u16;
@@ -737,7 +826,7 @@ fn render_input(f: &mut Frame, state: &ChatState, area: Rect) {
         return;
     }
     if state.has_env_prompt() {
-        let var_name = &state.env_prompt_vars[state.env_prompt_idx];
+        let var = &state.env_prompt_vars[state.env_prompt_idx];
         let remaining = state.env_prompt_vars.len() - state.env_prompt_idx;
         let ghost = Style::default().fg(Color::Rgb(60, 60, 70));
         let hint_text = match remaining > 1 {
@@ -745,18 +834,20 @@ fn render_input(f: &mut Frame, state: &ChatState, area: Rect) {
             false => "  (Esc to skip)".to_string(),
         };
         let placeholder = match state.env_prompt_input.is_empty() {
-            true => "paste your API key here",
+            true => var.description.as_deref().unwrap_or("enter a value"),
             false => "",
         };
+        // Secret values are masked as they are typed.
+        let input = match var.is_secret {
+            true => "*".repeat(state.env_prompt_input.chars().count()),
+            false => state.env_prompt_input.clone(),
+        };
         let mut spans: Vec<Span<'static>> = vec![
             Span::styled(
-                format!("{var_name}="),
+                format!("{}=", var.name),
                 Style::default().fg(Color::Yellow).bold(),
             ),
-            Span::styled(
-                state.env_prompt_input.clone(),
-                Style::default().fg(Color::White),
-            ),
+            Span::styled(input, Style::default().fg(Color::White)),
             Span::styled(cursor_ch.to_string(), Style::default().fg(Color::White)),
         ];
         if !placeholder.is_empty() {
@@ -994,6 +1085,100 @@ fn render_info_overlay(f: &mut Frame, state: &ChatState, area: Rect) {
     }
 }
 
+/// Renders the dialog of a tool call waiting for approval,
+/// with `queued` more calls waiting after it.
+fn render_approval(
+    f: &mut Frame,
+    env_name: &str,
+    request: &ApprovalRequest,
+    queued: usize,
+    area: Rect,
+) {
+    let label = Style::default().fg(Color::Yellow);
+    let mut lines: Vec<Line<'static>> = vec![
+        Line::from(vec![
+            Span::styled("Agent: ", label),
+            Span::raw(env_name.to_owned()),
+        ]),
+        Line::from(vec![
+            Span::styled("Call:  ", label),
+            Span::styled(
+                format!("{}/{}", request.component, request.function),
+                Style::default().fg(Color::White).bold(),
+            ),
+        ]),
+        Line::from(Span::styled("Args:", label)),
+    ];
+    let args = serde_json::from_str::<serde_json::Value>(&request.args_json)
+        .and_then(|v| serde_json::to_string_pretty(&v))
+        .unwrap_or_else(|_| request.args_json.clone());
+    for line in args.lines() {
+        lines.push(Line::from(Span::styled(
+            format!("  {line}"),
+            Style::default().fg(Color::Gray),
+        )));
+    }
+    // +2 for top/bottom borders, capped to the available area.
+    let h = (lines.len() as u16 + 2).min(area.height);
+    let dialog = Rect::new(
+        area.x + 1,
+        area.y + area.height.saturating_sub(h),
+        area.width.saturating_sub(2),
+        h,
+    );
+    f.render_widget(Clear, dialog);
+    let title = match queued {
+        0 => " Approve tool call? ".to_owned(),
+        n => format!(" Approve tool call? ({n} more waiting) "),
+    };
+    let block = Block::default()
+        .title(title)
+        .title_alignment(Alignment::Left)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow));
+    let inner = block.inner(dialog);
+    f.render_widget(block, dialog);
+    f.render_widget(Paragraph::new(lines), inner);
+    let hint = " y/Enter approve · n/Esc deny ";
+    let hint_w = hint.chars().count() as u16;
+    if hint_w + 2 < dialog.width {
+        let hint_area = Rect::new(
+            dialog.x + dialog.width.saturating_sub(hint_w + 1),
+            dialog.y + dialog.height.saturating_sub(1),
+            hint_w,
+            1,
+        );
+        f.render_widget(
+            Paragraph::new(Span::styled(hint, Style::default().fg(Color::Yellow))),
+            hint_area,
+        );
+    }
+}
+
+/// Approves or denies the first tool call waiting for approval,
+/// ignoring keys other than y, n, Enter and Esc.
+fn answer_approval(app: &mut App, code: KeyCode) {
+    let is_approved = match code {
+        KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => true,
+        KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => false,
+        _ => return,
+    };
+    let Some((_, request)) = app.approvals.pop_front() else {
+        return;
+    };
+    let call = format!("{}/{}", request.component, request.function);
+    match is_approved {
+        true => {
+            request.approve();
+            set_toast_color(app, &format!("Approved {call}."), Color::Green);
+        }
+        false => {
+            request.deny();
+            set_toast_color(app, &format!("Denied {call}."), Color::Red);
+        }
+    }
+}
+
 /// Kick off an async banner content fetch if banner_mode is "auto".
 pub fn start_banner_fetch(app: &mut App) {
     let Some(agent) = &app.agent else { return };
@@ -1029,7 +1214,11 @@ pub fn start_banner_fetch(app: &mut App) {
 pub fn start_env_check(app: &mut App) {
     let Some(agent) = &app.agent else { return };
     let env_name = agent.env_name.clone();
-    let tools = agent.tools.clone();
+    let config: Vec<ConfigVar> = agent
+        .tools
+        .iter()
+        .flat_map(|tool| agent.tool_config(tool).to_vec())
+        .collect();
     let (tx, rx) = tokio::sync::oneshot::channel();
     app.pending_env_check = Some(rx);
     tokio::spawn(async move {
@@ -1040,55 +1229,215 @@ pub fn start_env_check(app: &mut App) {
             .map(|d| d.var_values)
             .unwrap_or_default();
         let mut status = std::collections::HashMap::new();
-        for tool in &tools {
-            for v in tool_env_vars(tool) {
-                status.insert(v.name.to_string(), var_values.contains_key(v.name));
-            }
+        for v in config {
+            let is_ok = v.check(&var_values).is_none();
+            status.insert(v.name, is_ok);
         }
         let _ = tx.send(status);
     });
 }
 
 fn send_message(app: &mut App, input: &str) {
+    if !matches!(app.screen, Screen::Chat(_)) {
+        return;
+    }
+    // A message starting with `@name` is handed off to that agent.
+    let handoff = match input.strip_prefix('@') {
+        Some(rest) => {
+            let (name, message) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            match resolve_handoff(app, name, message.trim()) {
+                Ok(handoff) => Some(handoff),
+                Err(e) => {
+                    push_system(app, &e);
+                    return;
+                }
+            }
+        }
+        None => None,
+    };
     let Screen::Chat(state) = &mut app.screen else {
         return;
     };
-    state.messages.push(ChatMessage {
-        role: MessageRole::User,
-        content: input.to_string(),
-        styled_lines: None,
-    });
+    state.push_recorded(app.agent.as_ref(), MessageRole::User, input.to_string());
     state.waiting = true;
-    let runtime = app.runtime.clone();
-    let message = input.to_string();
+    let (runtime, message) = match handoff {
+        Some((env_name, runtime, message)) => {
+            state.handoff = Some(env_name);
+            (Some(runtime), message)
+        }
+        None => (app.runtime.clone(), input.to_string()),
+    };
+    let Some(runtime) = runtime else {
+        state.waiting = false;
+        push_system(app, "Error: no active runtime");
+        return;
+    };
     let (tx, rx) = tokio::sync::oneshot::channel();
+    let (output_tx, output_rx) = tokio::sync::mpsc::channel(64);
     app.pending_response = Some(rx);
+    app.pending_output = Some(output_rx);
+    let task = tokio::task::spawn(async move {
+        ops::call_with_runtime_streaming(runtime, &message, output_tx).await
+    });
+    app.response_task = Some(task.abort_handle());
     tokio::spawn(async move {
-        let result = match runtime {
-            Some(rt) => {
-                match tokio::task::spawn(async move { ops::call_with_runtime(rt, &message).await })
-                    .await
-                {
-                    Ok(r) => r,
-                    Err(e) => {
-                        let panic_msg = if let Ok(s) = e.try_into_panic() {
-                            s.downcast_ref::<String>()
-                                .cloned()
-                                .or_else(|| s.downcast_ref::<&str>().map(|s| s.to_string()))
-                                .unwrap_or_else(|| "internal error".to_string())
-                        } else {
-                            "request was cancelled".to_string()
-                        };
-                        Err(eyre::eyre!("{panic_msg}"))
-                    }
-                }
+        let result = match task.await {
+            Ok(r) => r,
+            Err(e) => {
+                let panic_msg = if let Ok(s) = e.try_into_panic() {
+                    s.downcast_ref::<String>()
+                        .cloned()
+                        .or_else(|| s.downcast_ref::<&str>().map(|s| s.to_string()))
+                        .unwrap_or_else(|| "internal error".to_string())
+                } else {
+                    "request was cancelled".to_string()
+                };
+                Err(eyre::eyre!("{panic_msg}"))
             }
-            None => Err(eyre::eyre!("no active runtime")),
         };
         let _ = tx.send(result);
     });
 }
 
+/// Finds the agent open in a background tab mentioned as `@name`,
+/// returning its env name and runtime with the message to hand off.
+fn resolve_handoff(
+    app: &App,
+    name: &str,
+    message: &str,
+) -> Result<(String, Arc<Mutex<ComponentRuntime>>, String), String> {
+    if message.is_empty() {
+        return Err(format!("Usage: @{name} <message>"));
+    }
+    let Some(tab) = app.tabs.iter().find(|tab| tab.is_mentioned_as(name)) else {
+        let open: Vec<String> = app
+            .tabs
+            .iter()
+            .map(|tab| format!("@{}", tab.agent.env_name))
+            .collect();
+        return match open.is_empty() {
+            true => Err(format!(
+                "No agent @{name} is open. Open it in a new tab with /agents."
+            )),
+            false => Err(format!(
+                "No agent @{name} is open. Open agents: {}",
+                open.join(", ")
+            )),
+        };
+    };
+    if tab.is_busy() {
+        return Err(format!(
+            "@{} is busy, try again shortly.",
+            tab.agent.env_name
+        ));
+    }
+    let Some(runtime) = tab.runtime.clone() else {
+        return Err(format!("@{} is not loaded.", tab.agent.env_name));
+    };
+    Ok((tab.agent.env_name.clone(), runtime, message.to_string()))
+}
+
+/// Cancels the running turn, keeping the reply streamed so far.
+/// The runtime is rebuilt, as the store of a call
+/// cancelled midway cannot be called into again.
+fn cancel_turn(app: &mut App) {
+    if let Some(task) = app.response_task.take() {
+        task.abort();
+    }
+    app.pending_response = None;
+    app.pending_output = None;
+    let Screen::Chat(state) = &mut app.screen else {
+        return;
+    };
+    state.waiting = false;
+    state.active_tool_call = None;
+    let handoff = state.handoff.take();
+    let partial = std::mem::take(&mut state.streamed_reply);
+    match (partial.is_empty(), handoff.clone()) {
+        (true, _) => {}
+        (false, Some(author)) => state.push_handoff_reply(app.agent.as_ref(), author, partial),
+        (false, None) => state.push_recorded(app.agent.as_ref(), MessageRole::Assistant, partial),
+    }
+    push_system(app, "Turn cancelled.");
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    // A handed off turn ran on the runtime of the mentioned agent.
+    let agent = match handoff {
+        Some(env_name) => {
+            let Some(tab) = app.tabs.iter_mut().find(|t| t.agent.env_name == env_name) else {
+                return;
+            };
+            tab.runtime = None;
+            tab.pending_runtime = Some(rx);
+            tab.agent.clone()
+        }
+        None => {
+            let Some(agent) = app.agent.clone() else {
+                return;
+            };
+            app.runtime = None;
+            app.pending_runtime = Some(rx);
+            agent
+        }
+    };
+    // Dropping the turn's calls waiting for approval denies them,
+    // so that their threads finish.
+    app.approvals
+        .retain(|(env_name, _)| *env_name != agent.env_name);
+    let approval_tx = app.approval_tx.clone();
+    tokio::spawn(async move {
+        let result = ops::build_agent_runtime(&agent, Some(approval_tx)).await;
+        let _ = tx.send(result);
+    });
+}
+
+/// Closes the active agent's tab, switching to the next one,
+/// or returning to the agent picker if it was the last one.
+fn close_active_tab(app: &mut App) {
+    if let Some(task) = app.response_task.take() {
+        task.abort();
+    }
+    app.agent = None;
+    app.runtime = None;
+    app.pending_runtime = None;
+    app.pending_response = None;
+    app.pending_output = None;
+    app.pending_banner = None;
+    app.pending_components = None;
+    if !app.tabs.is_empty() {
+        app.activate_tab(app.active_tab.min(app.tabs.len() - 1));
+        return;
+    }
+    show_picker(app);
+}
+
+/// Shows the agent picker, restoring its saved state
+/// instantly (no loading screen) if there is one.
+fn show_picker(app: &mut App) {
+    let Some(agents) = app.saved_picker.take() else {
+        app.screen = Screen::Picker(PickerState::loading(0));
+        return;
+    };
+    app.screen = Screen::Picker(PickerState {
+        agents,
+        selected: 0,
+        loading: false,
+        error: None,
+        spinner_tick: 0,
+    });
+}
+
+/// Opens the agent picker to open another agent in a new tab,
+/// keeping the active one in a background tab.
+fn cmd_agents(app: &mut App) -> eyre::Result<()> {
+    if let Screen::Chat(state) = &mut app.screen {
+        state.input.clear();
+        clear_menu_state(state);
+    }
+    app.park_active_tab();
+    show_picker(app);
+    Ok(())
+}
+
 async fn dispatch_slash(app: &mut App, input: &str) -> eyre::Result<()> {
     let parts: Vec<&str> = input[1..].split_whitespace().collect();
     if parts.is_empty() {
@@ -1100,7 +1449,10 @@ async fn dispatch_slash(app: &mut App, input: &str) -> eyre::Result<()> {
         "help" | "h" | "?" => cmd_help(app),
         "tools" | "t" => cmd_tools(app, args).await,
         "clear" | "c" => cmd_clear(app),
-        "model" | "m" => cmd_model(app, args),
+        "history" | "sessions" => cmd_history(app, args),
+        "export" => cmd_export(app, args),
+        "agents" | "open" => cmd_agents(app),
+        "model" | "m" => cmd_model(app, args).await,
         "name" | "rename" => cmd_name(app, args),
         "me" | "whoami" => cmd_me(app, args),
         "dir" | "dirs" => cmd_dir(app, args),
@@ -1109,6 +1461,7 @@ async fn dispatch_slash(app: &mut App, input: &str) -> eyre::Result<()> {
         "push" => cmd_push(app).await,
         "pull" | "sync" => cmd_pull(app).await,
         "config" | "vars" => cmd_config(app, args).await,
+        "approvals" => cmd_approvals(app, args),
         "quit" | "exit" | "q" => {
             app.should_quit = true;
             Ok(())
@@ -1243,6 +1596,7 @@ fn push_system(app: &mut App, msg: &str) {
         state.messages.push(ChatMessage {
             role: MessageRole::System,
             content: msg.to_string(),
+            author: None,
             styled_lines: None,
         });
     }
@@ -1315,6 +1669,11 @@ async fn cmd_tools(app: &mut App, args: &[&str]) -> eyre::Result<()> {
             )));
         } else {
             let env_name = app.agent.as_ref().map(|a| a.env_name.clone());
+            let tool_config = app
+                .agent
+                .as_ref()
+                .map(|a| a.tool_config.clone())
+                .unwrap_or_default();
             let var_values = if let Some(ref name) = env_name {
                 ops::inspect_environment(name)
                     .await
@@ -1331,19 +1690,13 @@ async fn cmd_tools(app: &mut App, args: &[&str]) -> eyre::Result<()> {
                     format!("  {tool}"),
                     Style::default().fg(Color::White),
                 )));
-                let needed = tool_env_vars(tool);
-                let missing: Vec<_> = needed
-                    .iter()
-                    .filter(|v| v.required && !var_values.contains_key(v.name))
-                    .collect();
-                if !missing.is_empty() {
-                    let vars = missing
-                        .iter()
-                        .map(|v| v.name)
-                        .collect::<Vec<_>>()
-                        .join(", ");
+                let config = tool_config.get(tool).map(Vec::as_slice).unwrap_or(&[]);
+                for v in config {
+                    let Some(problem) = v.check(&var_values) else {
+                        continue;
+                    };
                     warnings.push(Line::from(Span::styled(
-                        format!("  {tool}: {vars}"),
+                        format!("  {tool}: {} {problem}", v.name),
                         Style::default().fg(Color::Rgb(255, 165, 0)),
                     )));
                 }
@@ -1388,26 +1741,28 @@ async fn cmd_tools(app: &mut App, args: &[&str]) -> eyre::Result<()> {
             }
         };
         match ops::add_component(&env_name, &component).await {
-            Ok(_) => {
+            Ok(binary) => {
+                let config = binary.config().to_vec();
                 if let Some(agent) = &mut app.agent {
                     let base = component.split('@').next().unwrap_or(&component);
                     if !agent.tools.contains(&base.to_string()) {
                         agent.tools.push(base.to_string());
                     }
+                    if !config.is_empty() {
+                        agent.tool_config.insert(base.to_string(), config.clone());
+                    }
                 }
                 save_tools(app);
-                let env_vars = tool_env_vars(&component);
-                if env_vars.is_empty() {
+                if config.is_empty() {
                     set_toast(app, &format!("+ {component}"));
                     start_env_check(app);
                 } else {
                     // Check which vars are already set.
                     let data = ops::inspect_environment(&env_name).await.ok().flatten();
                     let var_values = data.map(|d| d.var_values).unwrap_or_default();
-                    let missing: Vec<String> = env_vars
-                        .iter()
-                        .filter(|v| !var_values.contains_key(v.name))
-                        .map(|v| v.name.to_string())
+                    let missing: Vec<ConfigVar> = config
+                        .into_iter()
+                        .filter(|v| v.check(&var_values).is_some())
                         .collect();
                     if missing.is_empty() {
                         set_toast(app, &format!("+ {component}"));
@@ -1432,15 +1787,16 @@ async fn cmd_tools(app: &mut App, args: &[&str]) -> eyre::Result<()> {
         match ops::remove_component(&env_name, component).await {
             Ok(_) => {
                 let base = component.split('@').next().unwrap_or(component);
+                let mut config = Vec::new();
                 if let Some(agent) = &mut app.agent {
                     agent.tools.retain(|t| t != base);
+                    config = agent.tool_config.remove(base).unwrap_or_default();
                 }
                 save_tools(app);
-                // Clean up env vars associated with the removed tool.
-                let env_vars = tool_env_vars(component);
-                let mut removed_vars: Vec<&str> = Vec::new();
-                for v in env_vars {
-                    if ops::set_var(&env_name, v.name, "").is_ok() {
+                // Clean up the config vars declared by the removed tool.
+                let mut removed_vars: Vec<String> = Vec::new();
+                for v in config {
+                    if ops::set_var(&env_name, &v.name, "").is_ok() {
                         removed_vars.push(v.name);
                     }
                 }
@@ -1466,6 +1822,12 @@ async fn cmd_tools(app: &mut App, args: &[&str]) -> eyre::Result<()> {
 fn cmd_clear(app: &mut App) -> eyre::Result<()> {
     if let Screen::Chat(state) = &mut app.screen {
         state.messages.clear();
+        // The next message starts a new session.
+        if let Some(transcript) = state.transcript.take()
+            && let Err(e) = transcript.save_agent_conversation()
+        {
+            push_system(app, &format!("Failed to save the agent's memory: {e:#}"));
+        }
     }
     if let Some(agent) = &app.agent {
         let state_dir = resolve_state_dir(&agent.env_name);
@@ -1476,37 +1838,268 @@ fn cmd_clear(app: &mut App) -> eyre::Result<()> {
     Ok(())
 }
 
-fn cmd_model(app: &mut App, args: &[&str]) -> eyre::Result<()> {
-    if args.is_empty() {
-        // Open the model picker.
-        let current_model = app.agent.as_ref().and_then(|b| b.model.clone());
-        let items = build_model_items(current_model.as_deref());
-        // Pre-select the current model.
-        let selected = current_model
-            .as_deref()
-            .and_then(|m| items.iter().position(|item| item.value == m))
-            .unwrap_or(0);
-        if let Screen::Chat(state) = &mut app.screen {
-            state.dynamic_command = Some("model".to_string());
-            state.dynamic_items = items;
-            state.dynamic_matches = (0..state.dynamic_items.len()).collect();
-            state.dynamic_selected = selected;
-            state.dynamic_loading = false;
-            state.input.clear();
+fn cmd_history(app: &mut App, args: &[&str]) -> eyre::Result<()> {
+    let Some(agent) = &app.agent else {
+        return Ok(());
+    };
+    let state_dir = resolve_state_dir(&agent.env_name);
+    let items: Vec<DynamicItem> = match args {
+        [] | ["list"] => transcript::list_sessions(&state_dir)
+            .into_iter()
+            .map(|session| DynamicItem {
+                label: format!("{}  {}", format_session_time(&session), session.preview),
+                description: format!("{} messages", session.message_count),
+                value: session.id,
+                disabled: false,
+            })
+            .collect(),
+        ["search", query @ ..] if !query.is_empty() => {
+            let query = query.join(" ");
+            transcript::search(&state_dir, &query)
+                .into_iter()
+                .map(|hit| DynamicItem {
+                    label: format!("{}  {}", format_session_time(&hit.session), hit.snippet),
+                    description: hit.session.preview,
+                    value: hit.session.id,
+                    disabled: false,
+                })
+                .collect()
+        }
+        ["resume", id] => {
+            resume_session(app, id);
+            return Ok(());
+        }
+        _ => {
+            push_system(app, "Usage: /history [list | search <text>]");
+            return Ok(());
+        }
+    };
+    if items.is_empty() {
+        set_toast(app, "No sessions found.");
+        return Ok(());
+    }
+    if let Screen::Chat(state) = &mut app.screen {
+        state.dynamic_command = Some("history resume".to_string());
+        state.dynamic_items = items;
+        state.dynamic_matches = (0..state.dynamic_items.len()).collect();
+        state.dynamic_selected = 0;
+        state.dynamic_loading = false;
+        state.input.clear();
+    }
+    Ok(())
+}
+
+fn format_session_time(session: &transcript::SessionSummary) -> String {
+    session
+        .started_at
+        .with_timezone(&chrono::Local)
+        .format("%Y-%m-%d %H:%M")
+        .to_string()
+}
+
+/// Shows the messages of a past session and continues it,
+/// restoring the agent's memory of it.
+fn resume_session(app: &mut App, id: &str) {
+    let Some(agent) = &app.agent else {
+        return;
+    };
+    let state_dir = resolve_state_dir(&agent.env_name);
+    // Only IDs of listed sessions are accepted, as the ID
+    // is used in paths under the state dir.
+    let is_known = transcript::list_sessions(&state_dir)
+        .iter()
+        .any(|session| session.id == id);
+    if !is_known {
+        push_system(
+            app,
+            &format!("Failed to resume session: session {id} not found"),
+        );
+        return;
+    }
+    let Screen::Chat(state) = &mut app.screen else {
+        return;
+    };
+    let transcript = Transcript::open(&state_dir, id);
+    let result = (|| -> eyre::Result<_> {
+        let messages = transcript.load()?;
+        if let Some(current) = &state.transcript {
+            current.save_agent_conversation()?;
+        }
+        transcript.restore_agent_conversation()?;
+        Ok(messages)
+    })();
+    let messages = match result {
+        Ok(messages) => messages,
+        Err(e) => {
+            push_system(app, &format!("Failed to resume session: {e:#}"));
+            return;
+        }
+    };
+    state.messages = messages
+        .into_iter()
+        .map(|m| ChatMessage {
+            role: m.role,
+            content: m.content,
+            author: m.author,
+            styled_lines: None,
+        })
+        .collect();
+    state.transcript = Some(transcript);
+    set_toast(app, "Session resumed.");
+}
+
+fn cmd_export(app: &mut App, args: &[&str]) -> eyre::Result<()> {
+    let Some(agent) = &app.agent else {
+        return Ok(());
+    };
+    let Screen::Chat(state) = &app.screen else {
+        return Ok(());
+    };
+    let Some(transcript) = &state.transcript else {
+        set_toast(app, "Nothing to export yet.");
+        return Ok(());
+    };
+    let (format, path) = match args {
+        [format] => (*format, None),
+        [format, path] => (*format, Some(path.to_string())),
+        _ => {
+            push_system(app, "Usage: /export md|json [path]");
+            return Ok(());
         }
+    };
+    let id = transcript.id().to_string();
+    let messages = transcript.load();
+    let content = messages.and_then(|messages| match format {
+        "md" => Ok(transcript::to_markdown(
+            &messages,
+            &agent.bot_name,
+            &agent.user_name,
+            &id,
+        )),
+        "json" => transcript::to_json(&messages, &agent.env_name, &id),
+        other => Err(eyre::eyre!(
+            "unknown export format '{other}', expected md or json"
+        )),
+    });
+    let path =
+        path.unwrap_or_else(|| format!("{}-{id}.{format}", agent.env_name.replace(':', "-")));
+    match content.and_then(|content| Ok(std::fs::write(&path, content)?)) {
+        Ok(()) => set_toast_color(app, &format!("Exported to {path}"), Color::Green),
+        Err(e) => push_system(app, &format!("Failed to export: {e:#}")),
+    }
+    Ok(())
+}
+
+async fn cmd_model(app: &mut App, args: &[&str]) -> eyre::Result<()> {
+    let Some(agent) = &app.agent else {
+        push_system(app, "No active agent.");
+        return Ok(());
+    };
+    let env_name = agent.env_name.clone();
+    let current_model = agent.model.clone();
+    let var_values = ops::inspect_environment(&env_name)
+        .await
+        .ok()
+        .flatten()
+        .map(|d| d.var_values)
+        .unwrap_or_default();
+    if args.is_empty() {
+        // Open the model picker, with the models of the custom endpoint if set.
+        let custom = match var_values.get(CUSTOM_URL_VAR) {
+            Some(url) => {
+                let key = var_values.get(CUSTOM_KEY_VAR).map(String::as_str);
+                Some((url.clone(), ops::fetch_custom_models(url, key).await))
+            }
+            None => None,
+        };
+        open_model_picker(app, build_model_items(current_model.as_deref(), custom));
+        return Ok(());
+    }
+    if args[0] == "custom" {
+        let Some(url) = args.get(1) else {
+            push_system(app, "Usage: /model custom <base-url> [api-key]");
+            return Ok(());
+        };
+        let url = ops::normalize_custom_url(url);
+        let key = args.get(2).copied().unwrap_or("");
+        let models = match ops::fetch_custom_models(&url, Some(key)).await {
+            Ok(models) => models,
+            Err(e) => {
+                push_system(app, &format!("Could not list the models at {url}: {e:#}"));
+                return Ok(());
+            }
+        };
+        ops::set_var(&env_name, CUSTOM_URL_VAR, &url)?;
+        // An empty key clears the key of a previous endpoint.
+        ops::set_var(&env_name, CUSTOM_KEY_VAR, key)?;
+        let mut items =
+            build_model_items(current_model.as_deref(), Some((url.clone(), Ok(models))));
+        // Only list the models of the new endpoint.
+        items.retain(|item| item.value.starts_with(CUSTOM_MODEL_PREFIX));
+        open_model_picker(app, items);
+        set_toast(app, &format!("Endpoint set to {url}. Pick a model."));
         return Ok(());
     }
     let new_model = args[0].to_string();
-    if let Some(agent) = &app.agent {
-        let _ = ops::set_var(&agent.env_name, "ASTERBOT_MODEL", &new_model);
+    let is_custom_model = new_model.starts_with(CUSTOM_MODEL_PREFIX);
+    if is_custom_model && !var_values.contains_key(CUSTOM_URL_VAR) {
+        push_system(
+            app,
+            "Set the endpoint first with /model custom <base-url> [api-key]",
+        );
+        return Ok(());
     }
+    let provider = provider_of_model(&new_model);
+    let _ = ops::set_var(&env_name, "ASTERBOT_MODEL", &new_model);
     if let Some(agent) = &mut app.agent {
         agent.model = Some(new_model.clone());
+        match (provider, is_custom_model) {
+            (Some((name, _, _)), _) => agent.provider = name.to_string(),
+            (None, true) => agent.provider = "custom".to_string(),
+            (None, false) => {}
+        }
+    }
+    // Switching to a provider without a key prompts for it.
+    let missing_key = provider.filter(|(_, key_var, _)| !var_values.contains_key(*key_var));
+    let Some((provider_name, key_var, _)) = missing_key else {
+        set_toast(app, &format!("Model set to {new_model}"));
+        return Ok(());
+    };
+    set_toast(
+        app,
+        &format!("Model set to {new_model} (enter your API key below)"),
+    );
+    if let Screen::Chat(state) = &mut app.screen {
+        state.env_prompt_vars = vec![ConfigVar {
+            name: key_var.to_string(),
+            description: Some(format!("paste your {provider_name} API key here")),
+            is_required: true,
+            is_secret: true,
+            default: None,
+            pattern: None,
+        }];
+        state.env_prompt_idx = 0;
+        state.env_prompt_input.clear();
     }
-    set_toast(app, &format!("Model set to {new_model}"));
     Ok(())
 }
 
+/// Open the dynamic picker for `/model` with `items`, pre-selecting the current model.
+fn open_model_picker(app: &mut App, items: Vec<DynamicItem>) {
+    let selected = items
+        .iter()
+        .position(|item| item.label.ends_with(" *"))
+        .unwrap_or(0);
+    if let Screen::Chat(state) = &mut app.screen {
+        state.dynamic_command = Some("model".to_string());
+        state.dynamic_items = items;
+        state.dynamic_matches = (0..state.dynamic_items.len()).collect();
+        state.dynamic_selected = selected;
+        state.dynamic_loading = false;
+        state.input.clear();
+    }
+}
+
 fn cmd_name(app: &mut App, args: &[&str]) -> eyre::Result<()> {
     if args.is_empty() {
         let name = app
@@ -1748,7 +2341,7 @@ async fn cmd_status(app: &mut App) -> eyre::Result<()> {
         lines.push(Line::from(Span::styled("  Tools:", label)));
         for tool in &agent.tools {
             let short = tool.split(':').next_back().unwrap_or(tool).to_string();
-            let env_vars = tool_env_vars(tool);
+            let env_vars = agent.tool_config(tool);
             if env_vars.is_empty() {
                 lines.push(Line::from(Span::styled(
                     format!("    {short}"),
@@ -1762,12 +2355,16 @@ async fn cmd_status(app: &mut App) -> eyre::Result<()> {
                     if i > 0 {
                         spans.push(Span::styled(", ", tool_name_style));
                     }
-                    let suffix = match v.required {
+                    let suffix = match v.is_required {
                         true => "",
                         false => " (optional)",
                     };
-                    let name = v.name;
-                    match var_values.contains_key(name) {
+                    let name = &v.name;
+                    let is_set = match var_values.contains_key(name) {
+                        true => v.check(&var_values).is_none(),
+                        false => v.default.is_some(),
+                    };
+                    match is_set {
                         true => spans.push(Span::styled(format!("{name}{suffix} \u{2713}"), ok)),
                         false => spans.push(Span::styled(format!("{name}{suffix} \u{2717}"), warn)),
                     }
@@ -1821,8 +2418,14 @@ async fn cmd_config(app: &mut App, args: &[&str]) -> eyre::Result<()> {
             return Ok(());
         };
         let env_name = agent.env_name.clone();
+        let mut tool_config: Vec<(String, Vec<ConfigVar>)> = agent
+            .tool_config
+            .iter()
+            .map(|(tool, config)| (tool.clone(), config.clone()))
+            .collect();
+        tool_config.sort_by(|a, b| a.0.cmp(&b.0));
         let data = ops::inspect_environment(&env_name).await?;
-        let vars = data.map(|d| d.vars).unwrap_or_default();
+        let (vars, var_values) = data.map(|d| (d.vars, d.var_values)).unwrap_or_default();
         let mut lines: Vec<Line<'static>> = Vec::new();
         lines.push(Line::from(Span::styled(
             format!("Environment variables ({env_name}):"),
@@ -1841,6 +2444,37 @@ async fn cmd_config(app: &mut App, args: &[&str]) -> eyre::Result<()> {
                 )));
             }
         }
+        // The config declared by tools, with the status of each var.
+        // Values are not shown, since some of them are secrets.
+        for (tool, config) in &tool_config {
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                format!("{tool} config:"),
+                Style::default().fg(Color::Yellow).bold(),
+            )));
+            for v in config {
+                let status_color = match v.check(&var_values) {
+                    Some(_) => Color::Rgb(255, 165, 0),
+                    None => Color::Green,
+                };
+                lines.push(Line::from(vec![
+                    Span::styled(
+                        format!("  {}{}: ", v.name, format_attributes(v)),
+                        Style::default().fg(Color::White),
+                    ),
+                    Span::styled(
+                        format_status(v, &var_values),
+                        Style::default().fg(status_color),
+                    ),
+                ]));
+                if let Some(description) = &v.description {
+                    lines.push(Line::from(Span::styled(
+                        format!("    {description}"),
+                        Style::default().fg(Color::DarkGray),
+                    )));
+                }
+            }
+        }
         lines.push(Line::from(""));
         lines.push(Line::from(Span::styled(
             "/config set KEY=VALUE  Set a variable",
@@ -1862,6 +2496,15 @@ async fn cmd_config(app: &mut App, args: &[&str]) -> eyre::Result<()> {
             return Ok(());
         };
         let env_name = agent.env_name.clone();
+        let declared = agent.tool_config.values().flatten().find(|v| v.name == key);
+        // An empty value unsets the var, so it is not validated.
+        if let Some(var) = declared
+            && !value.is_empty()
+            && let Err(e) = var.validate(value)
+        {
+            push_system(app, &format!("{key} {e}"));
+            return Ok(());
+        }
         match ops::set_var(&env_name, key, value) {
             Ok(_) => {
                 set_toast_color(app, &format!("{key} set."), Color::Green);
@@ -1875,6 +2518,87 @@ async fn cmd_config(app: &mut App, args: &[&str]) -> eyre::Result<()> {
     Ok(())
 }
 
+fn cmd_approvals(app: &mut App, args: &[&str]) -> eyre::Result<()> {
+    let Some(agent) = &mut app.agent else {
+        push_system(app, "No active agent.");
+        return Ok(());
+    };
+    if args.is_empty() || args[0] == "list" {
+        let mut lines: Vec<Line<'static>> = Vec::new();
+        lines.push(Line::from(Span::styled(
+            "Tool call approvals:",
+            Style::default().fg(Color::Yellow).bold(),
+        )));
+        if agent.approvals.is_empty() {
+            lines.push(Line::from(Span::styled(
+                "  (none, all calls run)",
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+        for (target, policy) in agent.approvals.iter() {
+            let color = match policy {
+                ApprovalPolicy::Auto => Color::Green,
+                ApprovalPolicy::Ask => Color::Rgb(255, 165, 0),
+                ApprovalPolicy::Deny => Color::Red,
+            };
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {target}: "), Style::default().fg(Color::White)),
+                Span::styled(policy.to_string(), Style::default().fg(color)),
+            ]));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "/approvals set <target> auto|ask|deny  Set a policy",
+            Style::default().fg(Color::DarkGray),
+        )));
+        lines.push(Line::from(Span::styled(
+            "A target is a tool, e.g. asterai:telegram, or one of its",
+            Style::default().fg(Color::DarkGray),
+        )));
+        lines.push(Line::from(Span::styled(
+            "functions, e.g. asterai:telegram/api/send-message.",
+            Style::default().fg(Color::DarkGray),
+        )));
+        app.show_info_overlay(lines);
+        return Ok(());
+    }
+    let usage = "Usage: /approvals set <target> auto|ask|deny";
+    if args[0] != "set" || args.len() != 3 {
+        push_system(app, usage);
+        return Ok(());
+    }
+    let target = args[1];
+    let Ok(policy) = ApprovalPolicy::from_str(args[2]) else {
+        push_system(app, usage);
+        return Ok(());
+    };
+    if !target.contains(':') {
+        push_system(
+            app,
+            &format!("{target} is not a tool, e.g. asterai:telegram, or one of its functions."),
+        );
+        return Ok(());
+    }
+    agent.approvals.set(target, policy);
+    let approvals = agent.approvals.clone();
+    if let Err(e) = ops::set_var(&agent.env_name, APPROVALS_VAR, &approvals.to_string()) {
+        push_system(app, &format!("Failed: {e:#}"));
+        return Ok(());
+    }
+    // Apply the policies to the running agent too, once its turn ends.
+    if let Some(runtime) = app.runtime.clone() {
+        tokio::spawn(async move {
+            runtime
+                .lock()
+                .await
+                .call_approvals()
+                .set_policies(approvals);
+        });
+    }
+    set_toast_color(app, &format!("{target}: {policy}"), Color::Green);
+    Ok(())
+}
+
 fn save_tools(app: &App) {
     let Some(agent) = &app.agent else { return };
     let value = agent.tools.join(",");
@@ -1948,8 +2672,12 @@ fn build_remove_items(tools: &[String]) -> Vec<DynamicItem> {
     items
 }
 
-/// Build the DynamicItem list for /model: all models from PROVIDERS grouped by provider.
-fn build_model_items(current_model: Option<&str>) -> Vec<DynamicItem> {
+/// Build the DynamicItem list for /model: all models from PROVIDERS grouped by provider,
+/// then those of the custom endpoint, given as its URL and the result of listing its models.
+fn build_model_items(
+    current_model: Option<&str>,
+    custom: Option<(String, eyre::Result<Vec<String>>)>,
+) -> Vec<DynamicItem> {
     let mut items = vec![DynamicItem {
         value: String::new(),
         label: "asterai managed LLM (coming soon)".to_string(),
@@ -1971,6 +2699,34 @@ fn build_model_items(current_model: Option<&str>) -> Vec<DynamicItem> {
             });
         }
     }
+    let Some((url, models)) = custom else {
+        return items;
+    };
+    let models = match models {
+        Ok(models) => models,
+        Err(e) => {
+            items.push(DynamicItem {
+                value: String::new(),
+                label: format!("custom endpoint unreachable: {e:#}"),
+                description: url,
+                disabled: true,
+            });
+            return items;
+        }
+    };
+    for model in models {
+        let value = format!("{CUSTOM_MODEL_PREFIX}{model}");
+        let label = match current_model == Some(value.as_str()) {
+            true => format!("{model} *"),
+            false => model,
+        };
+        items.push(DynamicItem {
+            value,
+            label,
+            description: url.clone(),
+            disabled: false,
+        });
+    }
     items
 }
 
@@ -1,8 +1,7 @@
 use crate::artifact::ArtifactSyncTag;
 use crate::tui::Tty;
 use crate::tui::app::{
-    AgentConfig, AgentEntry, App, CLI_VERSION, CORE_COMPONENTS, PickerState, SPINNER_FRAMES,
-    Screen, SetupState, default_user_name, resolve_state_dir,
+    AgentEntry, App, CLI_VERSION, PickerState, SPINNER_FRAMES, Screen, resolve_state_dir,
 };
 use crate::tui::ops;
 use crossterm::event::{Event, KeyCode};
@@ -214,15 +213,19 @@ pub fn render(f: &mut Frame, state: &PickerState, app: &App) {
         Some(err) => Line::from(Span::styled(err.as_str(), Style::default().fg(Color::Red))),
         None => {
             let sel = state.selected;
+            let esc_hint = match app.tabs.is_empty() {
+                true => "esc quit",
+                false => "esc back to chat",
+            };
             let hint = if sel == create_idx {
-                "↑↓ navigate · enter create · esc quit".to_string()
+                format!("↑↓ navigate · enter create · {esc_hint}")
             } else {
                 let agent = &state.agents[sel];
                 let sync_hint = match agent.sync_tag {
                     ArtifactSyncTag::Remote | ArtifactSyncTag::Behind => " · p pull",
                     _ => "",
                 };
-                format!("↑↓ navigate · enter chat{sync_hint} · d delete · r refresh · esc quit")
+                format!("↑↓ navigate · enter chat{sync_hint} · d delete · r refresh · {esc_hint}")
             };
             Line::from(Span::styled(hint, Style::default().fg(Color::DarkGray)))
         }
@@ -252,26 +255,27 @@ pub async fn handle_event(
     state.error = None;
     let total = state.agents.len() + 1;
     match code {
-        KeyCode::Up | KeyCode::Char('k') => {
-            if state.selected > 0 {
-                state.selected -= 1;
-            }
+        KeyCode::Up | KeyCode::Char('k') if state.selected > 0 => {
+            state.selected -= 1;
         }
-        KeyCode::Down | KeyCode::Char('j') => {
-            if state.selected + 1 < total {
-                state.selected += 1;
-            }
+        KeyCode::Down | KeyCode::Char('j') if state.selected + 1 < total => {
+            state.selected += 1;
         }
         KeyCode::Enter => {
             let selected = state.selected;
             let create_idx = state.agents.len();
             if selected == create_idx {
-                app.screen = Screen::Setup(SetupState::default());
+                app.screen = Screen::Setup(Box::default());
             } else {
                 let agent = state.agents[selected].clone();
                 resolve_and_enter_chat(app, agent, terminal).await?;
             }
         }
+        // With agents open in tabs, Esc returns to them.
+        KeyCode::Esc if !app.tabs.is_empty() => {
+            app.saved_picker = Some(state.agents.clone());
+            app.activate_tab(app.active_tab.min(app.tabs.len() - 1));
+        }
         KeyCode::Esc => {
             app.should_quit = true;
         }
@@ -315,6 +319,11 @@ pub async fn handle_event(
             let selected = state.selected;
             if selected < state.agents.len() {
                 let agent = &state.agents[selected];
+                let is_open = app.tabs.iter().any(|t| t.agent.env_name == agent.name);
+                if is_open {
+                    state.error = Some(format!("{} is open in a tab.", agent.name));
+                    return Ok(());
+                }
                 // Only allow deleting local envs (not remote-only).
                 if agent.sync_tag != ArtifactSyncTag::Remote {
                     let name = agent.name.clone();
@@ -338,7 +347,7 @@ pub async fn handle_event(
                 let idx = num - 1;
                 let create_idx = state.agents.len();
                 if idx == create_idx {
-                    app.screen = Screen::Setup(SetupState::default());
+                    app.screen = Screen::Setup(Box::default());
                 } else if idx < state.agents.len() {
                     let Screen::Picker(state) = &app.screen else {
                         return Ok(());
@@ -418,6 +427,14 @@ async fn resolve_and_enter_chat(
     agent: AgentEntry,
     terminal: &mut Terminal<CrosstermBackend<Tty>>,
 ) -> eyre::Result<()> {
+    // An agent open in a tab is switched to rather than opened again.
+    if let Some(idx) = app.tabs.iter().position(|t| t.agent.env_name == agent.name) {
+        if let Screen::Picker(state) = &app.screen {
+            app.saved_picker = Some(state.agents.clone());
+        }
+        app.activate_tab(idx);
+        return Ok(());
+    }
     if agent.sync_tag == ArtifactSyncTag::Remote {
         // Show pulling status and redraw before the network call.
         if let Screen::Picker(state) = &mut app.screen {
@@ -440,77 +457,12 @@ async fn resolve_and_enter_chat(
             return Ok(());
         }
     };
-    let has_agent = data.components.iter().any(|c| {
-        let base = c.split('@').next().unwrap_or(c);
-        base == "asterbot:agent"
-    });
-    if !has_agent {
-        set_picker_error(
-            app,
-            "Not an agent (missing asterbot:agent component).".to_string(),
-        );
-        return Ok(());
-    }
-    let provider = if data.vars.contains(&"ANTHROPIC_KEY".to_string()) {
-        "anthropic"
-    } else if data.vars.contains(&"OPENAI_KEY".to_string()) {
-        "openai"
-    } else if data.vars.contains(&"GOOGLE_KEY".to_string()) {
-        "google"
-    } else {
-        "unknown"
-    };
-    let tools: Vec<String> = data
-        .components
-        .iter()
-        .filter(|c| {
-            let base = c.split('@').next().unwrap_or(c);
-            !CORE_COMPONENTS.contains(&base)
-        })
-        .map(|c| c.split('@').next().unwrap_or(c).to_string())
-        .collect();
-    let allowed_dirs = data
-        .var_values
-        .get("ASTERBOT_ALLOWED_DIRS")
-        .map(|v| {
-            v.split(',')
-                .map(|s| s.trim().to_string())
-                .filter(|s| !s.is_empty())
-                .collect()
-        })
-        .unwrap_or_default();
-    // Ensure state dir exists.
-    let state_dir = resolve_state_dir(&agent.name);
-    let _ = std::fs::create_dir_all(&state_dir);
-    // Set ASTERBOT_HOST_DIR if not already set.
-    if !data.var_values.contains_key("ASTERBOT_HOST_DIR") {
-        let wasi_dir = state_dir.to_string_lossy().replace('\\', "/");
-        let _ = ops::set_var(&agent.name, "ASTERBOT_HOST_DIR", &wasi_dir);
-    }
-    let user_name = data
-        .var_values
-        .get("ASTERBOT_USER_NAME")
-        .cloned()
-        .unwrap_or_else(default_user_name);
-    let banner_mode = data
-        .var_values
-        .get("ASTERBOT_BANNER")
-        .cloned()
-        .unwrap_or_else(|| "auto".to_string());
-    let config = AgentConfig {
-        env_name: agent.name.clone(),
-        namespace: agent.namespace.clone(),
-        bot_name: data
-            .var_values
-            .get("ASTERBOT_BOT_NAME")
-            .cloned()
-            .unwrap_or(agent.name.clone()),
-        user_name,
-        model: data.var_values.get("ASTERBOT_MODEL").cloned(),
-        provider: provider.to_string(),
-        tools,
-        allowed_dirs,
-        banner_mode,
+    let config = match ops::load_agent(&agent.name, &agent.namespace, &data) {
+        Ok(config) => config,
+        Err(e) => {
+            set_picker_error(app, e.to_string());
+            return Ok(());
+        }
     };
     // Save picker state for instant restore on Esc from chat.
     if let Screen::Picker(state) = &mut app.screen {
@@ -521,8 +473,9 @@ async fn resolve_and_enter_chat(
     // Spawn runtime build in background so the spinner can animate.
     let (tx, rx) = tokio::sync::oneshot::channel();
     app.pending_runtime = Some(rx);
+    let approval_tx = app.approval_tx.clone();
     tokio::spawn(async move {
-        let result = ops::build_agent_runtime(&config).await;
+        let result = ops::build_agent_runtime(&config, Some(approval_tx)).await;
         let _ = tx.send(result);
     });
     Ok(())
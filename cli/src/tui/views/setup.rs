@@ -1,7 +1,8 @@
 use crate::tui::Tty;
 use crate::tui::app::{
-    AgentConfig, App, CORE_COMPONENTS, DEFAULT_TOOLS, PROVIDERS, Screen, SetupState, SetupStep,
-    default_user_name, resolve_state_dir, sanitize_bot_name,
+    AgentSpec, App, CUSTOM_KEY_VAR, CUSTOM_MODEL_PREFIX, CUSTOM_PROVIDER, CUSTOM_URL_VAR,
+    DEFAULT_CUSTOM_URL, DEFAULT_TOOLS, PROVIDERS, Screen, SetupState, SetupStep, default_user_name,
+    sanitize_bot_name,
 };
 use crate::tui::ops;
 use crossterm::event::{Event, KeyCode};
@@ -26,6 +27,7 @@ pub fn render(f: &mut Frame, state: &SetupState) {
         SetupStep::Name => render_name_step(f, state, content_area),
         SetupStep::Username => render_username_step(f, state, content_area),
         SetupStep::Provider => render_provider_step(f, state, content_area),
+        SetupStep::BaseUrl => render_base_url_step(f, state, content_area),
         SetupStep::ApiKey => render_api_key_step(f, state, content_area),
         SetupStep::Model => render_model_step(f, state, content_area),
         SetupStep::Directories => {}
@@ -76,6 +78,16 @@ pub async fn handle_event(
                 state.step = SetupStep::Username;
                 state.input = state.user_name.clone();
             }
+            SetupStep::BaseUrl => {
+                state.step = SetupStep::Provider;
+                state.input.clear();
+                state.error = None;
+            }
+            SetupStep::ApiKey if state.is_custom_provider() => {
+                state.step = SetupStep::BaseUrl;
+                state.input = state.base_url.clone();
+                state.error = None;
+            }
             SetupStep::ApiKey => {
                 state.step = SetupStep::Provider;
                 state.input.clear();
@@ -98,7 +110,8 @@ pub async fn handle_event(
         SetupStep::Name => handle_name(state, code),
         SetupStep::Username => handle_username(state, code),
         SetupStep::Provider => handle_provider(state, code),
-        SetupStep::ApiKey => handle_api_key(state, code),
+        SetupStep::BaseUrl => handle_base_url(state, code),
+        SetupStep::ApiKey => handle_api_key(state, code).await,
         SetupStep::Model => handle_model(state, code),
         SetupStep::Directories => {}
         SetupStep::Provisioning { .. } => {}
@@ -169,6 +182,7 @@ fn render_provider_step(f: &mut Frame, state: &SetupState, area: Rect) {
     let all_items: Vec<(&str, bool)> = PROVIDERS
         .iter()
         .map(|(name, _, _)| (*name, false))
+        .chain(std::iter::once((CUSTOM_PROVIDER, false)))
         .chain(std::iter::once(("asterai managed LLM (coming soon)", true)))
         .collect();
     for (i, (name, disabled)) in all_items.iter().enumerate() {
@@ -204,16 +218,16 @@ fn render_provider_step(f: &mut Frame, state: &SetupState, area: Rect) {
     f.render_widget(Paragraph::new(lines), area);
 }
 
-fn render_api_key_step(f: &mut Frame, state: &SetupState, area: Rect) {
-    let provider_name = PROVIDERS
-        .get(state.provider_idx)
-        .map(|p| p.0)
-        .unwrap_or("LLM");
+fn render_base_url_step(f: &mut Frame, state: &SetupState, area: Rect) {
     let mut lines = vec![
         Line::from(Span::styled(
-            format!("Enter your {provider_name} API key"),
+            "Enter the base URL of the endpoint",
             Style::default().bold(),
         )),
+        Line::from(Span::styled(
+            "Any OpenAI-compatible server, e.g. llama.cpp (http://localhost:8080/v1) or Ollama.",
+            Style::default().fg(Color::DarkGray),
+        )),
         Line::from(""),
     ];
     if let Some(err) = &state.error {
@@ -223,6 +237,39 @@ fn render_api_key_step(f: &mut Frame, state: &SetupState, area: Rect) {
         )));
         lines.push(Line::from(""));
     }
+    lines.push(Line::from(vec![
+        Span::raw(format!("Base URL (default: {DEFAULT_CUSTOM_URL}): ")),
+        Span::styled(&state.input, Style::default().fg(Color::Cyan)),
+        Span::styled("_", Style::default().fg(Color::DarkGray)),
+    ]));
+    f.render_widget(Paragraph::new(lines), area);
+}
+
+fn render_api_key_step(f: &mut Frame, state: &SetupState, area: Rect) {
+    let title = match PROVIDERS.get(state.provider_idx) {
+        Some((provider_name, _, _)) => format!("Enter your {provider_name} API key"),
+        None => "Enter the API key of the endpoint (optional)".to_string(),
+    };
+    let mut lines = vec![
+        Line::from(Span::styled(title, Style::default().bold())),
+        Line::from(""),
+    ];
+    if state.is_custom_provider() {
+        lines.insert(
+            1,
+            Line::from(Span::styled(
+                "Leave it empty if the server needs no key.",
+                Style::default().fg(Color::DarkGray),
+            )),
+        );
+    }
+    if let Some(err) = &state.error {
+        lines.push(Line::from(Span::styled(
+            err.as_str(),
+            Style::default().fg(Color::Red),
+        )));
+        lines.push(Line::from(""));
+    }
     let masked: String = "*".repeat(state.input.len());
     lines.push(Line::from(vec![
         Span::raw("API key: "),
@@ -233,10 +280,7 @@ fn render_api_key_step(f: &mut Frame, state: &SetupState, area: Rect) {
 }
 
 fn render_model_step(f: &mut Frame, state: &SetupState, area: Rect) {
-    let models = PROVIDERS
-        .get(state.provider_idx)
-        .map(|p| p.2)
-        .unwrap_or(&[]);
+    let models = model_choices(state);
     let mut lines = vec![
         Line::from(Span::styled("Select model", Style::default().bold())),
         Line::from(""),
@@ -251,7 +295,7 @@ fn render_model_step(f: &mut Frame, state: &SetupState, area: Rect) {
             Span::raw(pointer),
             Span::styled(format!("{}. ", i + 1), Style::default().fg(Color::DarkGray)),
             Span::styled(
-                *label,
+                label.clone(),
                 match is_selected {
                     true => Style::default().fg(Color::Cyan).bold(),
                     false => Style::default(),
@@ -344,38 +388,83 @@ fn handle_username(state: &mut SetupState, code: KeyCode) {
     }
 }
 
+/// The models to choose from as (model_id, label) pairs.
+fn model_choices(state: &SetupState) -> Vec<(String, String)> {
+    if state.is_custom_provider() {
+        return state
+            .custom_models
+            .iter()
+            .map(|id| (format!("{CUSTOM_MODEL_PREFIX}{id}"), id.clone()))
+            .collect();
+    }
+    PROVIDERS
+        .get(state.provider_idx)
+        .map(|p| p.2)
+        .unwrap_or(&[])
+        .iter()
+        .map(|(id, label)| (id.to_string(), label.to_string()))
+        .collect()
+}
+
+/// Moves on from the provider step, to the base URL step for the custom provider.
+fn select_provider(state: &mut SetupState) {
+    state.input.clear();
+    state.step = match state.is_custom_provider() {
+        true => SetupStep::BaseUrl,
+        false => SetupStep::ApiKey,
+    };
+}
+
 fn handle_provider(state: &mut SetupState, code: KeyCode) {
-    let total = PROVIDERS.len() + 1; // +1 for "coming soon" entry
+    // The custom provider follows `PROVIDERS`, then the "coming soon" entry.
+    let selectable = PROVIDERS.len() + 1;
+    let total = selectable + 1;
     match code {
-        KeyCode::Up | KeyCode::Char('k') => {
-            if state.provider_idx > 0 {
-                state.provider_idx -= 1;
-            }
+        KeyCode::Up | KeyCode::Char('k') if state.provider_idx > 0 => {
+            state.provider_idx -= 1;
         }
-        KeyCode::Down | KeyCode::Char('j') => {
-            if state.provider_idx + 1 < total {
-                state.provider_idx += 1;
-            }
-        }
-        KeyCode::Enter => {
-            if state.provider_idx < PROVIDERS.len() {
-                state.step = SetupStep::ApiKey;
-                state.input.clear();
-            }
+        KeyCode::Down | KeyCode::Char('j') if state.provider_idx + 1 < total => {
+            state.provider_idx += 1;
         }
+        KeyCode::Enter if state.provider_idx < selectable => select_provider(state),
         KeyCode::Char(c) if c.is_ascii_digit() => {
             let num = c.to_digit(10).unwrap() as usize;
-            if num >= 1 && num <= PROVIDERS.len() {
+            if num >= 1 && num <= selectable {
                 state.provider_idx = num - 1;
-                state.step = SetupStep::ApiKey;
-                state.input.clear();
+                select_provider(state);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn handle_base_url(state: &mut SetupState, code: KeyCode) {
+    match code {
+        KeyCode::Char(c) => {
+            state.input.push(c);
+            state.error = None;
+        }
+        KeyCode::Backspace => {
+            state.input.pop();
+        }
+        KeyCode::Enter => {
+            let url = match state.input.trim().is_empty() {
+                true => DEFAULT_CUSTOM_URL.to_string(),
+                false => ops::normalize_custom_url(&state.input),
+            };
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                state.error = Some("The URL must start with http:// or https://.".to_string());
+                return;
             }
+            state.base_url = url;
+            state.input = state.api_key.clone();
+            state.step = SetupStep::ApiKey;
         }
         _ => {}
     }
 }
 
-fn handle_api_key(state: &mut SetupState, code: KeyCode) {
+async fn handle_api_key(state: &mut SetupState, code: KeyCode) {
     match code {
         KeyCode::Char(c) => {
             state.input.push(c);
@@ -386,10 +475,22 @@ fn handle_api_key(state: &mut SetupState, code: KeyCode) {
         }
         KeyCode::Enter => {
             let key = state.input.trim().to_string();
-            if key.is_empty() {
+            if key.is_empty() && !state.is_custom_provider() {
                 state.error = Some("API key is required.".to_string());
                 return;
             }
+            if state.is_custom_provider() {
+                match ops::fetch_custom_models(&state.base_url, Some(&key)).await {
+                    Ok(models) => state.custom_models = models,
+                    Err(e) => {
+                        state.error = Some(format!(
+                            "Could not list the models at {}: {e:#}",
+                            state.base_url
+                        ));
+                        return;
+                    }
+                }
+            }
             state.api_key = key;
             state.input.clear();
             state.model_idx = 0;
@@ -400,25 +501,18 @@ fn handle_api_key(state: &mut SetupState, code: KeyCode) {
 }
 
 fn handle_model(state: &mut SetupState, code: KeyCode) {
-    let models = PROVIDERS
-        .get(state.provider_idx)
-        .map(|p| p.2)
-        .unwrap_or(&[]);
+    let models = model_choices(state);
     let total = models.len();
     match code {
-        KeyCode::Up | KeyCode::Char('k') => {
-            if state.model_idx > 0 {
-                state.model_idx -= 1;
-            }
+        KeyCode::Up | KeyCode::Char('k') if state.model_idx > 0 => {
+            state.model_idx -= 1;
         }
-        KeyCode::Down | KeyCode::Char('j') => {
-            if state.model_idx + 1 < total {
-                state.model_idx += 1;
-            }
+        KeyCode::Down | KeyCode::Char('j') if state.model_idx + 1 < total => {
+            state.model_idx += 1;
         }
         KeyCode::Enter => {
             if let Some((model_id, _)) = models.get(state.model_idx) {
-                state.model = model_id.to_string();
+                state.model = model_id.clone();
             }
             state.input.clear();
             state.step = SetupStep::Provisioning {
@@ -432,7 +526,7 @@ fn handle_model(state: &mut SetupState, code: KeyCode) {
             if num >= 1 && num <= total {
                 state.model_idx = num - 1;
                 if let Some((model_id, _)) = models.get(state.model_idx) {
-                    state.model = model_id.to_string();
+                    state.model = model_id.clone();
                 }
                 state.input.clear();
                 state.step = SetupStep::Provisioning {
@@ -478,83 +572,35 @@ async fn run_provisioning(
     let Screen::Setup(state) = &app.screen else {
         return Ok(());
     };
-    let env_name = state.env_name.clone();
-    let bot_name = state.bot_name.clone();
-    let user_name = state.user_name.clone();
-    let provider_idx = state.provider_idx;
-    let api_key = state.api_key.clone();
-    let model = state.model.clone();
-    let state_dir = resolve_state_dir(&state.env_name);
-    let _ = std::fs::create_dir_all(&state_dir);
-    let allowed_dirs = vec![state_dir.to_string_lossy().to_string()];
-    let all_components: Vec<&str> = CORE_COMPONENTS
-        .iter()
-        .chain(DEFAULT_TOOLS.iter())
-        .copied()
-        .collect();
-    // Components + init + 7 vars.
-    let total = all_components.len() + 8;
-    let mut current = 0;
-    update_provisioning(app, current, total, "Creating environment...");
-    terminal.draw(|f| super::render(f, app))?;
-    match ops::env_init(&env_name) {
-        Ok(_) => {}
-        Err(e) => {
-            let msg = format!("{e:#}");
-            if !msg.contains("already exists") {
-                return Err(e);
+    let provider_vars = match PROVIDERS.get(state.provider_idx) {
+        Some((_, env_var, _)) => vec![(env_var.to_string(), state.api_key.clone())],
+        None => {
+            let mut vars = vec![(CUSTOM_URL_VAR.to_string(), state.base_url.clone())];
+            if !state.api_key.is_empty() {
+                vars.push((CUSTOM_KEY_VAR.to_string(), state.api_key.clone()));
             }
-            let _ = ops::pull_env(&env_name).await;
+            vars
         }
-    }
-    current += 1;
-    for comp in &all_components {
-        update_provisioning(app, current, total, &format!("Adding {comp}..."));
-        terminal.draw(|f| super::render(f, app))?;
-        match ops::add_component(&env_name, comp).await {
-            Ok(_) => {}
-            Err(e) => {
-                let msg = format!("{e:#}");
-                if !msg.contains("already") {
-                    return Err(e);
-                }
-            }
-        }
-        current += 1;
-    }
-    let provider = PROVIDERS.get(provider_idx);
-    let env_var = provider.map(|p| p.1).unwrap_or("API_KEY");
-    let wasi_state_dir = state_dir.to_string_lossy().replace('\\', "/");
-    let tool_names: String = DEFAULT_TOOLS.join(",");
-    let dirs_value = allowed_dirs.join(",");
-    let vars = vec![
-        ("ASTERBOT_MODEL", model.as_str()),
-        (env_var, api_key.as_str()),
-        ("ASTERBOT_TOOLS", &tool_names),
-        ("ASTERBOT_HOST_DIR", &wasi_state_dir),
-        ("ASTERBOT_BOT_NAME", bot_name.as_str()),
-        ("ASTERBOT_USER_NAME", user_name.as_str()),
-        ("ASTERBOT_ALLOWED_DIRS", dirs_value.as_str()),
-    ];
-    for (key, value) in &vars {
-        update_provisioning(app, current, total, &format!("Setting {key}..."));
-        terminal.draw(|f| super::render(f, app))?;
-        let _ = ops::set_var(&env_name, key, value);
-        current += 1;
-    }
-    // Build agent config.
-    let namespace = crate::auth::Auth::read_user_or_fallback_namespace();
-    let agent = AgentConfig {
-        env_name: env_name.clone(),
-        namespace,
-        bot_name,
-        user_name,
-        model: Some(model),
-        provider: provider.map(|p| p.0).unwrap_or("custom").to_string(),
+    };
+    let spec = AgentSpec {
+        bot_name: state.bot_name.clone(),
+        user_name: state.user_name.clone(),
+        provider: PROVIDERS
+            .get(state.provider_idx)
+            .map(|p| p.0)
+            .unwrap_or("custom")
+            .to_string(),
+        model: state.model.clone(),
+        provider_vars,
         tools: DEFAULT_TOOLS.iter().map(|s| s.to_string()).collect(),
-        allowed_dirs,
-        banner_mode: "auto".to_string(),
+        allowed_dirs: Vec::new(),
     };
+    let agent = ops::provision_agent(&spec, |current, total, message| {
+        update_provisioning(app, current, total, message);
+        terminal.draw(|f| super::render(f, app))?;
+        Ok(())
+    })
+    .await?;
     app.agent = Some(agent);
     let Screen::Setup(state) = &mut app.screen else {
         return Ok(());
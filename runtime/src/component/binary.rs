@@ -1,13 +1,18 @@
 use crate::component::Component;
+use crate::component::config::ConfigVar;
 use crate::component::function_interface::ComponentFunctionInterface;
 use crate::component::function_name::ComponentFunctionName;
+use crate::component::precompiled;
 use crate::component::wit::{
     ComponentFunction, ComponentInterface, ComponentWit, ExportedInterface, ImportedInterface,
 };
+use crate::runtime::wasm_instance::{ENGINE, SYNC_ENGINE};
 use derive_getters::Getters;
-use eyre::eyre;
+use eyre::{bail, eyre};
+use log::{trace, warn};
 use serde::{Deserialize, Serialize, Serializer};
 use std::fmt::Debug;
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -34,6 +39,9 @@ pub struct ComponentBinary {
     /// The renaming phenomenon happens due to the way WASM tooling works.
     wit: ComponentWit,
     wasmtime_component: Arc<Mutex<WasmtimeComponentBinary>>,
+    /// Directory holding ahead-of-time compiled artifacts for this
+    /// component, if it is stored locally. See [`precompiled`].
+    precompiled_dir: Option<PathBuf>,
 }
 
 enum WasmtimeComponentBinary {
@@ -90,16 +98,24 @@ impl ComponentBinary {
             component,
             wit: ComponentWit::new(resolve, world_id),
             wasmtime_component: Arc::new(Mutex::new(WasmtimeComponentBinary::Raw(component_bytes))),
+            precompiled_dir: None,
         })
     }
 
+    /// Sets the directory used to load and store precompiled artifacts,
+    /// typically the directory holding the component's `component.wasm`.
+    pub fn set_precompiled_dir(&mut self, dir: PathBuf) {
+        self.precompiled_dir = Some(dir);
+    }
+
     /// Fetches a compiled component for this component.
     ///
     /// If the component is cached, this will return the cached instance
     /// and return instantly.
     ///
-    /// If the instance is not cached, this will compile the component,
-    /// store it in this instance's cache and return the component.
+    /// If the instance is not cached, this will compile the component
+    /// on a blocking thread, store it in this instance's cache
+    /// and return the component.
     pub async fn fetch_compiled_component(
         &self,
         engine: &Engine,
@@ -112,20 +128,86 @@ impl ComponentBinary {
         let WasmtimeComponentBinary::Raw(bytes) = raw else {
             unreachable!()
         };
-        let component = WasmtimeComponent::from_binary(engine, &bytes).map_err(|e| eyre!(e))?;
+        let this = self.clone();
+        let engine = engine.clone();
+        let (bytes, result) = tokio::task::spawn_blocking(move || {
+            let result = this.compile(&engine, &bytes);
+            (bytes, result)
+        })
+        .await?;
+        let component = match result {
+            Ok(component) => component,
+            Err(e) => {
+                *component_cache = WasmtimeComponentBinary::Raw(bytes);
+                return Err(e);
+            }
+        };
         *component_cache = WasmtimeComponentBinary::Compiled(bytes, component.clone());
         Ok(component)
     }
 
     /// Compiles this component for the given engine synchronously.
-    /// Does not use or update the internal cache.
+    /// Does not use or update the internal cache, but does use
+    /// precompiled artifacts if a precompiled directory is set.
     pub fn compile_for_engine_sync(&self, engine: &Engine) -> eyre::Result<WasmtimeComponent> {
         let component_cache = self.wasmtime_component.blocking_lock();
         let bytes = match &*component_cache {
             WasmtimeComponentBinary::Raw(bytes) => bytes,
             WasmtimeComponentBinary::Compiled(bytes, _) => bytes,
         };
-        WasmtimeComponent::from_binary(engine, bytes).map_err(|e| eyre!(e))
+        self.compile(engine, bytes)
+    }
+
+    /// Compiles and stores precompiled artifacts for all engines
+    /// used by the runtime.
+    ///
+    /// Returns `false` if all artifacts were already up to date.
+    pub async fn precompile(&self) -> eyre::Result<bool> {
+        let Some(dir) = self.precompiled_dir.clone() else {
+            bail!(
+                "component {} has no local directory for precompiled artifacts",
+                self.component
+            );
+        };
+        let bytes = match &*self.wasmtime_component.lock().await {
+            WasmtimeComponentBinary::Raw(bytes) => bytes.clone(),
+            WasmtimeComponentBinary::Compiled(bytes, _) => bytes.clone(),
+        };
+        tokio::task::spawn_blocking(move || {
+            let mut is_compiled = false;
+            for engine in [&*ENGINE, &*SYNC_ENGINE] {
+                if precompiled::load(&dir, engine, &bytes).is_some() {
+                    continue;
+                }
+                let component =
+                    WasmtimeComponent::from_binary(engine, &bytes).map_err(|e| eyre!(e))?;
+                precompiled::store(&dir, engine, &bytes, &component)?;
+                is_compiled = true;
+            }
+            Ok(is_compiled)
+        })
+        .await?
+    }
+
+    /// Loads the component from the precompiled directory if possible,
+    /// otherwise compiles it and stores the result there.
+    fn compile(&self, engine: &Engine, bytes: &[u8]) -> eyre::Result<WasmtimeComponent> {
+        let Some(dir) = &self.precompiled_dir else {
+            return WasmtimeComponent::from_binary(engine, bytes).map_err(|e| eyre!(e));
+        };
+        if let Some(component) = precompiled::load(dir, engine, bytes) {
+            trace!("loaded precompiled component {}", self.component);
+            return Ok(component);
+        }
+        let component = WasmtimeComponent::from_binary(engine, bytes).map_err(|e| eyre!(e))?;
+        // Failing to store the artifact only costs a recompile next time.
+        if let Err(e) = precompiled::store(dir, engine, bytes, &component) {
+            warn!(
+                "failed to store precompiled component {}: {e:#}",
+                self.component
+            );
+        }
+        Ok(component)
     }
 
     pub fn component(&self) -> &Component {
@@ -168,40 +250,21 @@ impl ComponentBinary {
         &self.wit
     }
 
+    /// The config vars declared by the component,
+    /// see [`crate::component::config`].
+    pub fn config(&self) -> &[ConfigVar] {
+        self.wit.config()
+    }
+
     pub fn get_functions(&self) -> Vec<ComponentFunctionInterface> {
-        let resolve = self.wit.resolve();
         let Some(world) = self.wit.world() else {
             return Vec::new();
         };
-        let component_package_name = self.component.package_name();
         world
             .exports
             .iter()
             .flat_map(|(_, item)| match item {
-                WorldItem::Interface { id, .. } => {
-                    let interface = resolve.interfaces.get(*id).unwrap();
-                    let package_name = match interface.package {
-                        None => component_package_name,
-                        Some(package_id) => {
-                            let package_opt = resolve.packages.get(package_id);
-                            package_opt
-                                .map(|p| &p.name)
-                                .unwrap_or(component_package_name)
-                        }
-                    };
-                    let interface_name = interface.name.clone().unwrap_or_default();
-                    interface
-                        .functions
-                        .iter()
-                        .map(|(_, function)| {
-                            self.map_wit_function_component_function(
-                                function,
-                                Some(interface_name.clone()),
-                                package_name.clone(),
-                            )
-                        })
-                        .collect()
-                }
+                WorldItem::Interface { id, .. } => self.map_interface_functions(*id),
                 WorldItem::Function(function) => {
                     let package_name = self.component.package_name.clone();
                     vec![self.map_wit_function_component_function(function, None, package_name)]
@@ -211,10 +274,56 @@ impl ComponentBinary {
             .collect()
     }
 
+    /// Functions of the interfaces this component imports, typed
+    /// by its own WIT. Their `component` is this component.
+    pub fn get_imported_functions(&self) -> Vec<ComponentFunctionInterface> {
+        let Some(world) = self.wit.world() else {
+            return Vec::new();
+        };
+        world
+            .imports
+            .values()
+            .flat_map(|item| match item {
+                WorldItem::Interface { id, .. } => self.map_interface_functions(*id),
+                _ => Vec::new(),
+            })
+            .collect()
+    }
+
     pub fn get_imports_count(&self) -> usize {
         self.wit.world().map(|w| w.imports.len()).unwrap_or(0)
     }
 
+    fn map_interface_functions(
+        &self,
+        interface_id: wit_parser::InterfaceId,
+    ) -> Vec<ComponentFunctionInterface> {
+        let resolve = self.wit.resolve();
+        let component_package_name = self.component.package_name();
+        let interface = resolve.interfaces.get(interface_id).unwrap();
+        let package_name = match interface.package {
+            None => component_package_name,
+            Some(package_id) => {
+                let package_opt = resolve.packages.get(package_id);
+                package_opt
+                    .map(|p| &p.name)
+                    .unwrap_or(component_package_name)
+            }
+        };
+        let interface_name = interface.name.clone().unwrap_or_default();
+        interface
+            .functions
+            .iter()
+            .map(|(_, function)| {
+                self.map_wit_function_component_function(
+                    function,
+                    Some(interface_name.clone()),
+                    package_name.clone(),
+                )
+            })
+            .collect()
+    }
+
     fn map_wit_function_component_function(
         &self,
         function: &Function,
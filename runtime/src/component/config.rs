@@ -0,0 +1,289 @@
+//! Configuration declared by components.
+//!
+//! Components declare the environment variables they are configured with
+//! in the doc comment of their WIT package, with one `@config` line per
+//! variable followed by the lines of its description:
+//!
+//! ```wit
+//! /// Sends messages with a Telegram bot.
+//! ///
+//! /// @config TELEGRAM_TOKEN required secret pattern="[0-9]+:.+"
+//! /// The token of the bot, from BotFather.
+//! /// @config TELEGRAM_WEBHOOK_URL default="https://example.com/hook"
+//! /// The public URL to receive updates at.
+//! package asterai:telegram@0.1.0;
+//! ```
+//!
+//! The attributes of a variable are:
+//! - `required`: the environment cannot run without it.
+//! - `secret`: its value is hidden when entered or displayed.
+//! - `default="value"`: the value used when it is not set.
+//! - `pattern="regex"`: a regular expression that values must match in full.
+//!
+//! A description ends at a blank line or at the next `@config` line.
+//! Malformed `@config` lines are ignored with a warning when a
+//! component is loaded, so that one typo does not make it unusable.
+use eyre::{bail, eyre};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+const ANNOTATION: &str = "@config";
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ConfigVar {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(rename = "required")]
+    pub is_required: bool,
+    #[serde(rename = "secret")]
+    pub is_secret: bool,
+    pub default: Option<String>,
+    pub pattern: Option<String>,
+}
+
+/// A config var that is missing or invalid in an environment.
+#[derive(Clone, Debug)]
+pub struct ConfigIssue {
+    /// The ID of the component declaring the var, e.g. `asterai:telegram`.
+    pub component: String,
+    pub var: ConfigVar,
+    pub problem: String,
+}
+
+impl ConfigVar {
+    /// Checks `value` against the pattern of the var, if any.
+    pub fn validate(&self, value: &str) -> eyre::Result<()> {
+        let Some(pattern) = &self.pattern else {
+            return Ok(());
+        };
+        match full_match_regex(pattern)?.is_match(value) {
+            true => Ok(()),
+            false => bail!("does not match the pattern {pattern}"),
+        }
+    }
+
+    /// Returns the problem with the var in an environment
+    /// with the vars `vars`, if any.
+    pub fn check(&self, vars: &HashMap<String, String>) -> Option<String> {
+        let Some(value) = vars.get(&self.name) else {
+            let is_missing = self.is_required && self.default.is_none();
+            return match is_missing {
+                true => Some("is required but not set".to_owned()),
+                false => None,
+            };
+        };
+        self.validate(value).err().map(|e| e.to_string())
+    }
+}
+
+impl Display for ConfigIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} {}", self.component, self.var.name, self.problem)?;
+        if let Some(description) = &self.var.description {
+            write!(f, " ({description})")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses the config vars declared in the docs of a WIT package.
+/// Malformed `@config` lines are skipped along with their description,
+/// and returned as errors next to the vars that could be parsed.
+pub fn parse_config_docs(docs: &str) -> (Vec<ConfigVar>, Vec<eyre::Report>) {
+    let mut vars: Vec<ConfigVar> = Vec::new();
+    let mut errors = Vec::new();
+    let mut is_in_description = false;
+    for line in docs.lines().map(str::trim) {
+        let (first_word, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        if first_word == ANNOTATION {
+            let result = parse_annotation(rest).and_then(|var| {
+                match vars.iter().any(|v| v.name == var.name) {
+                    true => bail!("config var {} is declared more than once", var.name),
+                    false => Ok(var),
+                }
+            });
+            is_in_description = result.is_ok();
+            match result {
+                Ok(var) => vars.push(var),
+                Err(e) => errors.push(eyre!("invalid `{line}`: {e}")),
+            }
+            continue;
+        }
+        if line.is_empty() {
+            is_in_description = false;
+            continue;
+        }
+        let Some(var) = vars.last_mut().filter(|_| is_in_description) else {
+            continue;
+        };
+        let description = var.description.get_or_insert_default();
+        if !description.is_empty() {
+            description.push(' ');
+        }
+        description.push_str(line);
+    }
+    (vars, errors)
+}
+
+/// Parses the name and attributes following `@config`.
+fn parse_annotation(annotation: &str) -> eyre::Result<ConfigVar> {
+    let mut words = split_words(annotation)?.into_iter();
+    let name = words.next().ok_or_else(|| eyre!("missing variable name"))?;
+    let is_valid_name = name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !is_valid_name {
+        bail!("invalid variable name {name}");
+    }
+    let mut var = ConfigVar {
+        name,
+        description: None,
+        is_required: false,
+        is_secret: false,
+        default: None,
+        pattern: None,
+    };
+    for word in words {
+        match word.split_once('=') {
+            None if word == "required" => var.is_required = true,
+            None if word == "secret" => var.is_secret = true,
+            Some(("default", value)) => var.default = Some(value.to_owned()),
+            Some(("pattern", value)) => {
+                full_match_regex(value)?;
+                var.pattern = Some(value.to_owned());
+            }
+            _ => bail!("unknown attribute {word}"),
+        }
+    }
+    if let Some(default) = &var.default {
+        var.validate(default)
+            .map_err(|e| eyre!("default value {default} {e}"))?;
+    }
+    Ok(var)
+}
+
+/// Splits on whitespace outside of double quotes, removing the quotes.
+/// Within quotes, `\"` and `\\` escape a quote and a backslash.
+fn split_words(s: &str) -> eyre::Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut is_quoted = false;
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                is_quoted = !is_quoted;
+                word.get_or_insert_default();
+            }
+            '\\' if is_quoted && matches!(chars.peek(), Some('"' | '\\')) => {
+                word.get_or_insert_default().extend(chars.next());
+            }
+            c if c.is_whitespace() && !is_quoted => words.extend(word.take()),
+            c => word.get_or_insert_default().push(c),
+        }
+    }
+    if is_quoted {
+        bail!("unterminated quote");
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+fn full_match_regex(pattern: &str) -> eyre::Result<Regex> {
+    Regex::new(&format!("^(?:{pattern})$")).map_err(|e| eyre!("invalid pattern {pattern}: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCS: &str = r#"Sends messages with a Telegram bot.
+
+@config TELEGRAM_TOKEN required secret pattern="[0-9]+:\S+"
+The token of the bot,
+from BotFather.
+@config TELEGRAM_WEBHOOK_URL default="https://example.com/hook"
+
+Not part of a description."#;
+
+    #[test]
+    fn test_parse_config_docs() {
+        let (vars, errors) = parse_config_docs(DOCS);
+        assert!(errors.is_empty());
+        assert_eq!(
+            vars,
+            vec![
+                ConfigVar {
+                    name: "TELEGRAM_TOKEN".to_owned(),
+                    description: Some("The token of the bot, from BotFather.".to_owned()),
+                    is_required: true,
+                    is_secret: true,
+                    default: None,
+                    pattern: Some(r"[0-9]+:\S+".to_owned()),
+                },
+                ConfigVar {
+                    name: "TELEGRAM_WEBHOOK_URL".to_owned(),
+                    description: None,
+                    is_required: false,
+                    is_secret: false,
+                    default: Some("https://example.com/hook".to_owned()),
+                    pattern: None,
+                },
+            ]
+        );
+        assert!(parse_config_docs("No config.").0.is_empty());
+    }
+
+    #[test]
+    fn test_parse_config_docs_errors() {
+        let error = |docs: &str| {
+            let (_, errors) = parse_config_docs(docs);
+            assert_eq!(errors.len(), 1);
+            errors[0].to_string()
+        };
+        assert!(error("@config").contains("missing variable name"));
+        assert!(error("@config A-B").contains("invalid variable name"));
+        assert!(error("@config A optional").contains("unknown attribute optional"));
+        assert!(error(r#"@config A pattern="[""#).contains("invalid pattern"));
+        assert!(error(r#"@config A default="x"#).contains("unterminated quote"));
+        assert!(error(r#"@config A pattern="[0-9]+" default=x"#).contains("default value x"));
+        assert!(error("@config A\n@config A").contains("more than once"));
+    }
+
+    #[test]
+    fn test_parse_config_docs_skips_invalid_entries() {
+        let docs = "@config A optional\nDescription of A.\n@config B\nDescription of B.";
+        let (vars, errors) = parse_config_docs(docs);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(vars.len(), 1);
+        assert_eq!(vars[0].name, "B");
+        assert_eq!(vars[0].description.as_deref(), Some("Description of B."));
+    }
+
+    #[test]
+    fn test_check_config_var() {
+        let (vars, _) = parse_config_docs(DOCS);
+        let env = |entries: &[(&str, &str)]| -> HashMap<String, String> {
+            entries
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+        assert_eq!(
+            vars[0].check(&env(&[])).as_deref(),
+            Some("is required but not set")
+        );
+        assert_eq!(
+            vars[0].check(&env(&[("TELEGRAM_TOKEN", "abc")])).as_deref(),
+            Some(r"does not match the pattern [0-9]+:\S+")
+        );
+        // The pattern must match the whole value.
+        assert!(
+            vars[0]
+                .check(&env(&[("TELEGRAM_TOKEN", "1:a b")]))
+                .is_some()
+        );
+        assert_eq!(vars[0].check(&env(&[("TELEGRAM_TOKEN", "1:ab")])), None);
+        assert_eq!(vars[1].check(&env(&[])), None);
+    }
+}
@@ -1,11 +1,12 @@
 use crate::component::Component;
 use crate::component::function_name::ComponentFunctionName;
+use crate::runtime::parsing::has_future_or_stream;
 use derive_getters::Getters;
-use eyre::{OptionExt, WrapErr, eyre};
+use eyre::{OptionExt, WrapErr, bail, eyre};
 use log::trace;
 use wasmtime::AsContextMut;
 use wasmtime::component::{ComponentNamedList, Func, Instance, Lift, Lower, TypedFunc, Val};
-use wit_parser::{PackageName, TypeDef};
+use wit_parser::{PackageName, Resolve, TypeDef};
 
 #[derive(Getters, Debug, Clone)]
 pub struct ComponentFunctionInterface {
@@ -40,6 +41,26 @@ impl ComponentFunctionInterface {
         }
     }
 
+    /// Checks that the function can be called with JSON args and output.
+    /// Functions taking or returning futures or streams, even nested,
+    /// cannot be, as the host can neither read nor write their data.
+    pub fn check_is_json_callable(&self, resolve: &Resolve) -> eyre::Result<()> {
+        let has_future_or_stream = self
+            .inputs
+            .iter()
+            .map(|(_, type_def)| type_def)
+            .chain(&self.output_type)
+            .any(|type_def| has_future_or_stream(type_def, resolve));
+        if has_future_or_stream {
+            bail!(
+                "function '{}' takes or returns a future or stream, \
+                 which cannot be called with JSON",
+                self.name
+            );
+        }
+        Ok(())
+    }
+
     pub fn get_func(
         &self,
         mut store: impl AsContextMut,
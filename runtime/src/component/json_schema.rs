@@ -0,0 +1,316 @@
+//! JSON Schemas for WIT types.
+//!
+//! Schemas describe the JSON mapping of WIT values documented in
+//! [`crate::runtime::parsing`], as accepted by
+//! [`json_value_to_val`](crate::runtime::parsing::json_value_to_val)
+//! and produced by [`ValExt`](crate::runtime::parsing::ValExt),
+//! including the optional string encoding of 64-bit integers.
+//! Alternative inputs, such as arrays for `list<u8>` and decimal
+//! strings for other integers, are left out of schemas.
+//!
+//! WIT types cannot be recursive, so schemas are always inlined.
+use crate::runtime::parsing::is_u8;
+use serde_json::{Map, Value, json};
+use wit_parser::{Handle, Resolve, Type, TypeDef, TypeDefKind};
+
+/// Returns the JSON Schema of a WIT type.
+pub fn type_json_schema(resolve: &Resolve, ty: Type) -> Value {
+    match ty {
+        Type::Bool => json!({ "type": "boolean" }),
+        Type::U8 => integer_schema(u8::MIN as i64, u8::MAX as u64),
+        Type::U16 => integer_schema(u16::MIN as i64, u16::MAX as u64),
+        Type::U32 => integer_schema(u32::MIN as i64, u32::MAX as u64),
+        Type::U64 => int64_schema(u64::MIN as i64, u64::MAX),
+        Type::S8 => integer_schema(i8::MIN as i64, i8::MAX as u64),
+        Type::S16 => integer_schema(i16::MIN as i64, i16::MAX as u64),
+        Type::S32 => integer_schema(i32::MIN as i64, i32::MAX as u64),
+        Type::S64 => int64_schema(i64::MIN, i64::MAX as u64),
+        Type::F32 | Type::F64 => json!({
+            "anyOf": [
+                { "type": "number" },
+                { "enum": ["NaN", "Infinity", "-Infinity"] },
+            ],
+        }),
+        Type::Char => json!({ "type": "string", "minLength": 1, "maxLength": 1 }),
+        Type::String => json!({ "type": "string" }),
+        Type::Id(id) => type_def_json_schema(resolve, &resolve.types[id]),
+        Type::ErrorContext => handle_schema("error-context"),
+    }
+}
+
+/// Returns the JSON Schema of a WIT type definition,
+/// titled with the type's name if it has one.
+pub fn type_def_json_schema(resolve: &Resolve, type_def: &TypeDef) -> Value {
+    let mut schema = type_def_kind_json_schema(resolve, &type_def.kind);
+    if let (Some(name), Value::Object(map)) = (&type_def.name, &mut schema) {
+        map.insert("title".to_owned(), json!(name));
+    }
+    if let (Some(docs), Value::Object(map)) = (&type_def.docs.contents, &mut schema) {
+        map.insert("description".to_owned(), json!(docs));
+    }
+    schema
+}
+
+/// Returns the JSON Schema of named parameters as an object keyed by
+/// parameter name, the shape LLM tool definitions expect.
+/// Params are given with their schema and whether they are optional,
+/// see [`is_optional_param`]; all others are required.
+pub fn params_json_schema<'a>(params: impl IntoIterator<Item = (&'a str, Value, bool)>) -> Value {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    for (name, schema, is_optional) in params {
+        properties.insert(name.to_owned(), schema);
+        if !is_optional {
+            required.push(name);
+        }
+    }
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    })
+}
+
+/// Returns whether a param of this type may be left out of named args,
+/// which is the case for options, passed as `none` when missing.
+pub fn is_optional_param(type_def: &TypeDef) -> bool {
+    matches!(type_def.kind, TypeDefKind::Option(_))
+}
+
+fn type_def_kind_json_schema(resolve: &Resolve, kind: &TypeDefKind) -> Value {
+    match kind {
+        TypeDefKind::Type(ty) => type_json_schema(resolve, *ty),
+        TypeDefKind::Record(record) => {
+            let mut properties = Map::new();
+            for field in &record.fields {
+                let mut schema = type_json_schema(resolve, field.ty);
+                if let (Some(docs), Value::Object(map)) = (&field.docs.contents, &mut schema) {
+                    map.insert("description".to_owned(), json!(docs));
+                }
+                properties.insert(field.name.clone(), schema);
+            }
+            let required: Vec<&str> = record.fields.iter().map(|f| f.name.as_str()).collect();
+            json!({
+                "type": "object",
+                "properties": properties,
+                "required": required,
+            })
+        }
+        TypeDefKind::List(ty) if is_u8(ty, resolve) => json!({
+            "anyOf": [
+                { "type": "string", "contentEncoding": "base64" },
+                { "type": "array", "maxItems": 0 },
+            ],
+        }),
+        TypeDefKind::List(ty) => json!({
+            "type": "array",
+            "items": type_json_schema(resolve, *ty),
+        }),
+        TypeDefKind::Tuple(tuple) => {
+            let items: Vec<Value> = tuple
+                .types
+                .iter()
+                .map(|ty| type_json_schema(resolve, *ty))
+                .collect();
+            json!({
+                "type": "array",
+                "prefixItems": items,
+                "minItems": tuple.types.len(),
+                "maxItems": tuple.types.len(),
+            })
+        }
+        TypeDefKind::Enum(e) => {
+            let cases: Vec<&str> = e.cases.iter().map(|c| c.name.as_str()).collect();
+            json!({ "type": "string", "enum": cases })
+        }
+        TypeDefKind::Option(ty) => json!({
+            "anyOf": [type_json_schema(resolve, *ty), { "type": "null" }],
+        }),
+        TypeDefKind::Flags(flags) => {
+            let names: Vec<&str> = flags.flags.iter().map(|f| f.name.as_str()).collect();
+            json!({
+                "type": "array",
+                "items": { "type": "string", "enum": names },
+                "uniqueItems": true,
+            })
+        }
+        TypeDefKind::Result(result) => {
+            let ok = match result.ok {
+                Some(ty) => type_json_schema(resolve, ty),
+                None => json!({ "type": "null" }),
+            };
+            let err = match result.err {
+                Some(ty) => type_json_schema(resolve, ty),
+                None => json!({}),
+            };
+            json!({
+                "anyOf": [
+                    ok,
+                    {
+                        "type": "object",
+                        "properties": { "error": err },
+                        "required": ["error"],
+                    },
+                ],
+            })
+        }
+        TypeDefKind::Variant(variant) => {
+            let cases: Vec<Value> = variant
+                .cases
+                .iter()
+                .map(|case| {
+                    let mut properties = Map::new();
+                    properties.insert("tag".to_owned(), json!({ "const": case.name }));
+                    let mut required = vec!["tag"];
+                    if let Some(ty) = case.ty {
+                        properties.insert("value".to_owned(), type_json_schema(resolve, ty));
+                        required.push("value");
+                    }
+                    let mut schema = json!({
+                        "type": "object",
+                        "properties": properties,
+                        "required": required,
+                    });
+                    if let Some(docs) = &case.docs.contents {
+                        schema["description"] = json!(docs);
+                    }
+                    schema
+                })
+                .collect();
+            json!({ "oneOf": cases })
+        }
+        TypeDefKind::Handle(Handle::Own(id) | Handle::Borrow(id)) => {
+            let name = resolve.types[*id].name.as_deref().unwrap_or("resource");
+            handle_schema(name)
+        }
+        TypeDefKind::Resource => handle_schema("resource"),
+        TypeDefKind::Future(_) => handle_schema("future"),
+        TypeDefKind::Stream(_) => handle_schema("stream"),
+        _ => unsupported_schema("unknown"),
+    }
+}
+
+/// The schema of a handle to a value held by the host.
+fn handle_schema(type_name: &str) -> Value {
+    json!({
+        "type": "object",
+        "properties": { "handle": { "type": "integer", "minimum": 1 } },
+        "required": ["handle"],
+        "description": format!("handle of a {type_name} held by the host"),
+    })
+}
+
+fn integer_schema(minimum: i64, maximum: u64) -> Value {
+    json!({ "type": "integer", "minimum": minimum, "maximum": maximum })
+}
+
+/// The schema of 64-bit integers, which may be output as decimal strings.
+fn int64_schema(minimum: i64, maximum: u64) -> Value {
+    json!({
+        "anyOf": [
+            integer_schema(minimum, maximum),
+            { "type": "string", "pattern": "^-?[0-9]+$" },
+        ],
+    })
+}
+
+/// A schema no value matches, for types without a JSON encoding.
+fn unsupported_schema(type_name: &str) -> Value {
+    json!({
+        "not": {},
+        "description": format!("{type_name} values cannot be encoded as JSON"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema_of(wit: &str, type_name: &str) -> Value {
+        let mut resolve = Resolve::default();
+        resolve.push_str("test.wit", wit).unwrap();
+        let (_, type_def) = resolve
+            .types
+            .iter()
+            .find(|(_, t)| t.name.as_deref() == Some(type_name))
+            .unwrap();
+        type_def_json_schema(&resolve, type_def)
+    }
+
+    const WIT: &str = r#"
+        package test:schema;
+        interface types {
+            record point { x: s32, label: option<string> }
+            variant shape { circle(f64), empty }
+            enum color { red, green }
+            flags perms { read, write }
+            type outcome = result<list<u8>, string>;
+            type pair = tuple<char, bool>;
+            resource conn;
+            type conn-ref = borrow<conn>;
+            type byte = u8;
+            type bytes = list<byte>;
+        }
+    "#;
+
+    #[test]
+    fn test_record_and_option() {
+        let schema = schema_of(WIT, "point");
+        assert_eq!(schema["title"], "point");
+        assert_eq!(schema["required"], json!(["x", "label"]));
+        assert_eq!(schema["properties"]["x"]["minimum"], json!(i32::MIN));
+        assert_eq!(
+            schema["properties"]["label"],
+            json!({ "anyOf": [{ "type": "string" }, { "type": "null" }] })
+        );
+    }
+
+    #[test]
+    fn test_variant_uses_tag_and_value() {
+        let schema = schema_of(WIT, "shape");
+        let cases = schema["oneOf"].as_array().unwrap();
+        assert_eq!(cases[0]["properties"]["tag"], json!({ "const": "circle" }));
+        assert_eq!(cases[0]["required"], json!(["tag", "value"]));
+        assert_eq!(cases[1]["required"], json!(["tag"]));
+    }
+
+    #[test]
+    fn test_enum_flags_result_and_tuple() {
+        assert_eq!(schema_of(WIT, "color")["enum"], json!(["red", "green"]));
+        assert_eq!(
+            schema_of(WIT, "perms")["items"]["enum"],
+            json!(["read", "write"])
+        );
+        let outcome = schema_of(WIT, "outcome");
+        assert_eq!(outcome["anyOf"][0]["anyOf"][0]["contentEncoding"], "base64");
+        assert_eq!(outcome["anyOf"][1]["required"], json!(["error"]));
+        assert_eq!(
+            schema_of(WIT, "bytes")["anyOf"][0]["contentEncoding"],
+            "base64"
+        );
+        let pair = schema_of(WIT, "pair");
+        assert_eq!(pair["prefixItems"][0]["maxLength"], json!(1));
+        assert_eq!(pair["maxItems"], json!(2));
+    }
+
+    #[test]
+    fn test_resource_handle() {
+        let schema = schema_of(WIT, "conn-ref");
+        assert_eq!(schema["required"], json!(["handle"]));
+        assert_eq!(schema["description"], "handle of a conn held by the host");
+    }
+
+    #[test]
+    fn test_optional_params_are_not_required() {
+        let schema = params_json_schema([
+            ("name", json!({ "type": "string" }), false),
+            (
+                "label",
+                json!({ "anyOf": [{ "type": "string" }, { "type": "null" }] }),
+                true,
+            ),
+        ]);
+        assert_eq!(schema["required"], json!(["name"]));
+        assert_eq!(schema["properties"]["label"]["anyOf"][1]["type"], "null");
+    }
+}
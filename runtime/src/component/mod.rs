@@ -10,10 +10,13 @@ use std::str::FromStr;
 pub use wit_parser::PackageName;
 
 pub mod binary;
+pub mod config;
 pub mod function_interface;
 pub mod function_name;
+pub mod json_schema;
 pub mod log;
 pub mod pkg;
+pub mod precompiled;
 pub mod set;
 pub mod wit;
 
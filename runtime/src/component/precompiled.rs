@@ -0,0 +1,102 @@
+//! Ahead-of-time compiled component cache.
+//!
+//! Serialized `.cwasm` artifacts are stored in the component's directory,
+//! next to its `component.wasm`. The file name holds both the checksum
+//! of the component bytes and a fingerprint of the engine configuration,
+//! so an artifact is only reused by an engine that could have produced it.
+use crate::checksum::Checksum;
+use eyre::eyre;
+use log::trace;
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use wasmtime::Engine;
+use wasmtime::component::Component as WasmtimeComponent;
+
+const FILE_PREFIX: &str = "component-";
+const FILE_EXTENSION: &str = "cwasm";
+
+/// Returns the precompiled artifact path for the component bytes
+/// and engine.
+pub fn artifact_path(dir: &Path, engine: &Engine, component_bytes: &[u8]) -> PathBuf {
+    let checksum = checksum_prefix(component_bytes);
+    let fingerprint = engine_fingerprint(engine);
+    dir.join(format!(
+        "{FILE_PREFIX}{checksum}-{fingerprint}.{FILE_EXTENSION}"
+    ))
+}
+
+/// Loads a precompiled component from `dir`, if a valid one exists.
+pub fn load(dir: &Path, engine: &Engine, component_bytes: &[u8]) -> Option<WasmtimeComponent> {
+    let path = artifact_path(dir, engine, component_bytes);
+    if !path.exists() {
+        return None;
+    }
+    // SAFETY: the artifact was written by `store` from a component
+    // compiled by an engine with the same configuration fingerprint.
+    // Wasmtime also validates the artifact header against the engine
+    // before using it.
+    match unsafe { WasmtimeComponent::deserialize_file(engine, &path) } {
+        Ok(component) => Some(component),
+        Err(e) => {
+            trace!(
+                "ignoring invalid precompiled artifact {}: {e}",
+                path.display()
+            );
+            None
+        }
+    }
+}
+
+/// Serializes a compiled component into `dir`, removing artifacts
+/// left over from previous contents of the component.
+pub fn store(
+    dir: &Path,
+    engine: &Engine,
+    component_bytes: &[u8],
+    component: &WasmtimeComponent,
+) -> eyre::Result<()> {
+    let path = artifact_path(dir, engine, component_bytes);
+    let serialized = component.serialize().map_err(|e| eyre!(e))?;
+    // Write to a uniquely named temporary file first so that concurrent
+    // readers never observe a partially written artifact, and concurrent
+    // writers never write to the same file.
+    let mut tmp_file = tempfile::NamedTempFile::new_in(dir)?;
+    tmp_file.write_all(&serialized)?;
+    tmp_file.persist(&path).map_err(|e| eyre!(e.error))?;
+    remove_stale(dir, &checksum_prefix(component_bytes));
+    Ok(())
+}
+
+/// Removes artifacts compiled from a different `component.wasm`.
+fn remove_stale(dir: &Path, checksum: &str) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let current_prefix = format!("{FILE_PREFIX}{checksum}-");
+    for entry in entries.flatten() {
+        let file_name = entry.file_name();
+        let Some(file_name) = file_name.to_str() else {
+            continue;
+        };
+        let is_artifact = file_name.starts_with(FILE_PREFIX)
+            && file_name.ends_with(&format!(".{FILE_EXTENSION}"));
+        if is_artifact && !file_name.starts_with(&current_prefix) {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+fn checksum_prefix(component_bytes: &[u8]) -> String {
+    let checksum = hex::encode(Checksum::from_bytes(component_bytes).bytes());
+    checksum[..16].to_string()
+}
+
+/// Hashes the engine's precompile compatibility hash, which covers
+/// the wasmtime version, target and the relevant `Config` settings.
+fn engine_fingerprint(engine: &Engine) -> String {
+    let mut hasher = DefaultHasher::new();
+    engine.precompile_compatibility_hash().hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
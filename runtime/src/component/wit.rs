@@ -1,7 +1,11 @@
+use crate::component::config::{ConfigVar, parse_config_docs};
+use crate::component::json_schema::{is_optional_param, params_json_schema, type_json_schema};
 use eyre::eyre;
 use std::collections::HashMap;
 use wit_parser::decoding::DecodedWasm;
-use wit_parser::{PackageId, Resolve, Type, TypeDefKind, World, WorldId, WorldItem};
+use wit_parser::{
+    Handle, PackageId, Resolve, Type, TypeDefKind, TypeId, World, WorldId, WorldItem,
+};
 
 /// Lightweight read-only wrapper around parsed WIT data.
 #[derive(Clone)]
@@ -9,6 +13,8 @@ pub struct ComponentWit {
     resolve: Resolve,
     world_id: Option<WorldId>,
     pkg_id: Option<PackageId>,
+    /// Config vars declared in the package docs, see [`crate::component::config`].
+    config: Vec<ConfigVar>,
 }
 
 impl ComponentWit {
@@ -18,6 +24,7 @@ impl ComponentWit {
             resolve,
             world_id: Some(world_id),
             pkg_id: None,
+            config: Vec::new(),
         }
     }
 
@@ -26,17 +33,16 @@ impl ComponentWit {
     pub fn from_bytes(bytes: &[u8]) -> eyre::Result<Self> {
         let decoded = wit_parser::decoding::decode(bytes).map_err(|e| eyre!(e))?;
         match decoded {
-            DecodedWasm::Component(resolve, world_id) => Ok(Self {
-                resolve,
-                world_id: Some(world_id),
-                pkg_id: None,
-            }),
+            DecodedWasm::Component(resolve, world_id) => Ok(Self::new(resolve, world_id)),
             DecodedWasm::WitPackage(resolve, pkg_id) => {
-                let world_id = resolve.packages[pkg_id].worlds.values().next().copied();
+                let package = &resolve.packages[pkg_id];
+                let world_id = package.worlds.values().next().copied();
+                let config = package_config(package);
                 Ok(Self {
                     resolve,
                     world_id,
                     pkg_id: Some(pkg_id),
+                    config,
                 })
             }
         }
@@ -54,8 +60,13 @@ impl ComponentWit {
         self.world()?.docs.contents.clone()
     }
 
+    pub fn config(&self) -> &[ConfigVar] {
+        &self.config
+    }
+
     /// Overlays doc comments from a WIT package onto this component's
-    /// Resolve. This is needed because the compiled component binary does
+    /// Resolve, and reads the config vars declared in the package docs.
+    /// This is needed because the compiled component binary does
     /// not include the `package-docs` section, so docs must be sourced
     /// from the original `package.wasm`.
     pub fn apply_package_docs(&mut self, package_bytes: &[u8]) -> eyre::Result<()> {
@@ -65,6 +76,7 @@ impl ComponentWit {
             _ => return Ok(()),
         };
         let pkg = &pkg_resolve.packages[pkg_id];
+        self.config = package_config(pkg);
         // Build docs lookup: iface_name → (iface_docs, {func_name → func_docs}).
         let mut docs_map: HashMap<&str, (Option<&str>, HashMap<&str, &str>)> = HashMap::new();
         for (_name, iface_id) in &pkg.interfaces {
@@ -150,6 +162,22 @@ impl ComponentWit {
     }
 }
 
+/// Parses the config vars declared in the docs of `package`,
+/// warning about and skipping the malformed ones.
+fn package_config(package: &wit_parser::Package) -> Vec<ConfigVar> {
+    let Some(docs) = &package.docs.contents else {
+        return Vec::new();
+    };
+    let (vars, errors) = parse_config_docs(docs);
+    for e in errors {
+        eprintln!(
+            "warning: ignoring config in docs of package {}: {e}",
+            package.name
+        );
+    }
+    vars
+}
+
 /// Uniform interface for querying WIT imports and exports.
 pub trait ComponentInterface {
     fn imported_interfaces(&self) -> Vec<ImportedInterface>;
@@ -177,6 +205,19 @@ pub struct ComponentFunction {
     pub return_type_name: Option<String>,
     /// Fully expanded WIT schema for return type. None if no return.
     pub return_type_schema: Option<String>,
+    /// JSON Schema for return type. None if no return.
+    pub return_json_schema: Option<serde_json::Value>,
+}
+
+impl ComponentFunction {
+    /// JSON Schema of the function's parameters, see [`params_json_schema`].
+    pub fn params_json_schema(&self) -> serde_json::Value {
+        params_json_schema(
+            self.params
+                .iter()
+                .map(|p| (p.name.as_str(), p.json_schema.clone(), p.is_optional)),
+        )
+    }
 }
 
 pub struct FunctionParam {
@@ -185,6 +226,10 @@ pub struct FunctionParam {
     pub type_name: String,
     /// Fully expanded WIT schema, e.g. "record { name: string, age: u32 }".
     pub type_schema: String,
+    /// JSON Schema of the type's JSON encoding.
+    pub json_schema: serde_json::Value,
+    /// Whether the param may be left out of named args.
+    pub is_optional: bool,
 }
 
 impl ComponentInterface for ComponentWit {
@@ -258,16 +303,23 @@ fn build_exported_function(resolve: &Resolve, func: &wit_parser::Function) -> Co
             name: name.clone(),
             type_name: type_display(resolve, *ty),
             type_schema: type_schema_display(resolve, *ty),
+            json_schema: type_json_schema(resolve, *ty),
+            is_optional: match ty {
+                Type::Id(id) => is_optional_param(&resolve.types[*id]),
+                _ => false,
+            },
         })
         .collect();
     let return_type_name = func.result.map(|ty| type_display(resolve, ty));
     let return_type_schema = func.result.map(|ty| type_schema_display(resolve, ty));
+    let return_json_schema = func.result.map(|ty| type_json_schema(resolve, ty));
     ComponentFunction {
         name: func.name.clone(),
         docs: func.docs.contents.clone(),
         params,
         return_type_name,
         return_type_schema,
+        return_json_schema,
     }
 }
 
@@ -292,6 +344,16 @@ fn format_interface_name(resolve: &Resolve, id: wit_parser::InterfaceId) -> Stri
     )
 }
 
+/// Extracts the interface name from a fully qualified one,
+/// e.g. `outgoing-handler` from `wasi:http/outgoing-handler@0.2.0`.
+pub fn short_interface_name(fq_name: &str) -> String {
+    let name = fq_name.rsplit_once('/').map(|(_, n)| n).unwrap_or(fq_name);
+    name.split_once('@')
+        .map(|(n, _)| n)
+        .unwrap_or(name)
+        .to_owned()
+}
+
 /// Converts a WIT type to a human-readable display string.
 pub fn type_display(resolve: &Resolve, ty: Type) -> String {
     match ty {
@@ -351,7 +413,7 @@ fn type_kind_display(resolve: &Resolve, kind: &TypeDefKind) -> String {
         TypeDefKind::Enum(_) => "enum".to_owned(),
         TypeDefKind::Flags(_) => "flags".to_owned(),
         TypeDefKind::Type(ty) => type_display(resolve, *ty),
-        _ => "unknown".to_owned(),
+        _ => handle_kind_display(resolve, kind, type_display),
     }
 }
 
@@ -434,6 +496,33 @@ fn type_kind_schema_display(resolve: &Resolve, kind: &TypeDefKind) -> String {
             )
         }
         TypeDefKind::Type(ty) => type_schema_display(resolve, *ty),
+        _ => handle_kind_display(resolve, kind, type_schema_display),
+    }
+}
+
+/// Displays the kinds of types whose values are held by the host as handles:
+/// resources, futures and streams.
+fn handle_kind_display(
+    resolve: &Resolve,
+    kind: &TypeDefKind,
+    display: fn(&Resolve, Type) -> String,
+) -> String {
+    let resource_name = |id: &TypeId| {
+        resolve.types[*id]
+            .name
+            .clone()
+            .unwrap_or_else(|| "resource".to_owned())
+    };
+    let with_payload = |name: &str, ty: &Option<Type>| match ty {
+        Some(ty) => format!("{name}<{}>", display(resolve, *ty)),
+        None => name.to_owned(),
+    };
+    match kind {
+        TypeDefKind::Resource => "resource".to_owned(),
+        TypeDefKind::Handle(Handle::Own(id)) => resource_name(id),
+        TypeDefKind::Handle(Handle::Borrow(id)) => format!("borrow<{}>", resource_name(id)),
+        TypeDefKind::Future(ty) => with_payload("future", ty),
+        TypeDefKind::Stream(ty) => with_payload("stream", ty),
         _ => "unknown".to_owned(),
     }
 }
@@ -1,5 +1,6 @@
 use crate::component::binary::ComponentBinary;
 use crate::component::wit::ComponentInterface;
+use crate::environment::wiring::{InterfaceWiring, unversioned_interface_name};
 use crate::resource::ResourceId;
 use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
@@ -7,7 +8,11 @@ use std::str::FromStr;
 /// Returns package IDs (e.g. "asterai:fs") whose interfaces are imported by
 /// at least one component but not exported by any component in the set.
 /// These represent dependencies that must be auto-resolved.
-pub fn unsatisfied_import_packages(components: &[impl ComponentInterface]) -> Vec<ResourceId> {
+/// Imports of `mocked` interfaces (unversioned names) are satisfied by mocks.
+pub fn unsatisfied_import_packages(
+    components: &[impl ComponentInterface],
+    mocked: &HashSet<String>,
+) -> Vec<ResourceId> {
     let mut provided: HashSet<ResourceId> = HashSet::new();
     for comp in components {
         for export in comp.exported_interfaces() {
@@ -21,6 +26,9 @@ pub fn unsatisfied_import_packages(components: &[impl ComponentInterface]) -> Ve
     let mut seen: HashSet<ResourceId> = HashSet::new();
     for comp in components {
         for import in comp.imported_interfaces() {
+            if mocked.contains(unversioned_interface_name(&import.name)) {
+                continue;
+            }
             let Some(id) = extract_package_id(&import.name) else {
                 continue;
             };
@@ -41,12 +49,20 @@ pub fn unsatisfied_import_packages(components: &[impl ComponentInterface]) -> Ve
 
 /// Returns interfaces that are both imported by some component AND exported
 /// by more than one component. Only these are problematic — the linker must
-/// pick one provider. Duplicate exports that nothing imports are harmless.
-pub fn conflicting_exports(components: &[ComponentBinary]) -> Vec<(String, Vec<String>)> {
-    // Collect all imported interface names (excluding host-provided).
+/// pick one provider. Duplicate exports that nothing imports are harmless,
+/// and so are imports explicitly wired to a provider.
+pub fn conflicting_exports(
+    components: &[ComponentBinary],
+    wiring: &InterfaceWiring,
+) -> Vec<(String, Vec<String>)> {
+    // Collect all imported interface names (excluding host-provided and wired).
     let mut imported: HashSet<String> = HashSet::new();
     for comp in components {
+        let comp_id = comp.component().id();
         for import in comp.imported_interfaces() {
+            if wiring.provider(&comp_id, &import.name).is_some() {
+                continue;
+            }
             if let Some(id) = extract_package_id(&import.name)
                 && !is_host_provided_id(&id)
             {
@@ -0,0 +1,128 @@
+//! Interceptor components wrapping calls to interfaces.
+use crate::component::ComponentId;
+use crate::component::binary::ComponentBinary;
+use crate::component::wit::ComponentInterface;
+use crate::environment::wiring::unversioned_interface_name;
+use eyre::{bail, eyre};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// The interface interceptor components must export.
+pub const INTERCEPTOR_INTERFACE: &str = "asterai:host/interceptor";
+
+/// Interceptor chains for intercepted interfaces.
+///
+/// Calls between components to an intercepted interface pass through
+/// each interceptor's `before-call` hook in order, and then through
+/// the `after-call` hooks in reverse order.
+#[derive(Debug, Clone, Default)]
+pub struct Interceptors {
+    /// Unversioned interface name -> interceptors, in call order.
+    chains: HashMap<String, Vec<ComponentId>>,
+}
+
+impl Interceptors {
+    /// Parses the `interceptors` table of an environment manifest.
+    pub fn parse(interceptors: &HashMap<String, Vec<String>>) -> eyre::Result<Self> {
+        let mut chains = HashMap::new();
+        for (interface, components) in interceptors {
+            if !interface.contains('/') {
+                bail!(
+                    "invalid interface '{interface}' in interceptors: \
+                     expected namespace:package/interface"
+                );
+            }
+            let chain = components
+                .iter()
+                .map(|c| {
+                    ComponentId::from_str(c)
+                        .map_err(|e| eyre!("invalid component '{c}' in interceptors: {e}"))
+                })
+                .collect::<eyre::Result<Vec<_>>>()?;
+            chains.insert(unversioned_interface_name(interface).to_owned(), chain);
+        }
+        Ok(Self { chains })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chains.values().all(|chain| chain.is_empty())
+    }
+
+    /// Returns the interceptors for `interface`, in call order.
+    /// Any version in `interface` is ignored.
+    pub fn for_interface(&self, interface: &str) -> &[ComponentId] {
+        self.chains
+            .get(unversioned_interface_name(interface))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Checks that each interceptor is in the set and exports
+    /// the interceptor interface, and that each intercepted
+    /// interface is exported by some component.
+    pub fn validate(&self, components: &[ComponentBinary]) -> eyre::Result<()> {
+        let exports = |binary: &ComponentBinary, interface: &str| {
+            binary
+                .exported_interfaces()
+                .iter()
+                .any(|e| unversioned_interface_name(&e.name) == interface)
+        };
+        for (interface, chain) in &self.chains {
+            if !chain.is_empty() && !components.iter().any(|c| exports(c, interface)) {
+                bail!("intercepted interface {interface} is not exported by any component");
+            }
+            for interceptor in chain {
+                let Some(binary) = components
+                    .iter()
+                    .find(|c| c.component().id() == *interceptor)
+                else {
+                    bail!("interceptor {interceptor} (for {interface}) is not in the environment");
+                };
+                if !exports(binary, INTERCEPTOR_INTERFACE) {
+                    bail!("interceptor {interceptor} does not export {INTERCEPTOR_INTERFACE}");
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn interceptors(entries: &[(&str, &[&str])]) -> HashMap<String, Vec<String>> {
+        entries
+            .iter()
+            .map(|(interface, components)| {
+                let components = components.iter().map(|c| c.to_string()).collect();
+                (interface.to_string(), components)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_for_interface_keeps_order_and_ignores_versions() {
+        let interceptors = Interceptors::parse(&interceptors(&[(
+            "asterai:fs/fs@1.0.0",
+            &["acme:audit", "acme:cache"],
+        )]))
+        .unwrap();
+        let chain: Vec<String> = interceptors
+            .for_interface("asterai:fs/fs@1.1.0")
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        assert_eq!(chain, vec!["acme:audit", "acme:cache"]);
+        assert!(interceptors.for_interface("asterai:llm/llm").is_empty());
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_entries() {
+        let invalid_interface = interceptors(&[("asterai:fs", &["acme:audit"])]);
+        assert!(Interceptors::parse(&invalid_interface).is_err());
+        let invalid_component = interceptors(&[("asterai:fs/fs", &["audit"])]);
+        assert!(Interceptors::parse(&invalid_component).is_err());
+        assert!(Interceptors::parse(&HashMap::new()).unwrap().is_empty());
+    }
+}
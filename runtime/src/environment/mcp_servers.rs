@@ -0,0 +1,136 @@
+//! External MCP servers included in environments as virtual components.
+use crate::component::ComponentId;
+use crate::component::binary::ComponentBinary;
+use eyre::{bail, eyre};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// How to reach an MCP server, as configured in an environment manifest.
+///
+/// Exactly one of `command` and `url` is set. Values of `env` and
+/// `headers` may refer to vars of the environment as `${NAME}`, so that
+/// secrets can be kept in the environment's vars.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct McpServerConfig {
+    /// Program and arguments launching a server that speaks MCP
+    /// over stdio, e.g. `["npx", "-y", "@modelcontextprotocol/server-github"]`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub command: Vec<String>,
+    /// URL of a server that speaks MCP over streamable HTTP.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Environment variables of the server process.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+    /// Headers of requests to the server URL, e.g. `Authorization`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+}
+
+impl McpServerConfig {
+    /// Whether the server is launched by a local command,
+    /// rather than reached by URL.
+    pub fn is_local(&self) -> bool {
+        !self.command.is_empty()
+    }
+}
+
+/// MCP servers of an environment, keyed by the virtual component
+/// they are served as.
+#[derive(Debug, Clone, Default)]
+pub struct McpServers {
+    servers: Vec<(ComponentId, McpServerConfig)>,
+}
+
+impl McpServers {
+    /// Parses the `mcp-servers` table of an environment manifest.
+    pub fn parse(servers: &HashMap<String, McpServerConfig>) -> eyre::Result<Self> {
+        let mut parsed = Vec::new();
+        for (component, config) in servers {
+            let id = ComponentId::from_str(component)
+                .map_err(|e| eyre!("invalid component '{component}' in mcp-servers: {e}"))?;
+            match (config.command.is_empty(), &config.url) {
+                (true, None) => bail!("MCP server {id} needs a command or a url"),
+                (false, Some(_)) => bail!("MCP server {id} cannot have both a command and a url"),
+                _ => {}
+            }
+            parsed.push((id, config.clone()));
+        }
+        parsed.sort_by_key(|(id, _)| id.to_string());
+        Ok(Self { servers: parsed })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.servers.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(ComponentId, McpServerConfig)> {
+        self.servers.iter()
+    }
+
+    /// Checks that no MCP server has the name of a component.
+    pub fn validate(&self, components: &[ComponentBinary]) -> eyre::Result<()> {
+        for (id, _) in &self.servers {
+            if components.iter().any(|c| c.component().id() == *id) {
+                bail!("MCP server {id} has the same name as a component");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Replaces each `${NAME}` in `value` with the environment var `NAME`,
+/// or nothing if the environment has none. Variables of the process
+/// are not used, so that a manifest cannot send them to its servers.
+pub fn expand_vars(value: &str, vars: &HashMap<String, String>) -> String {
+    let mut expanded = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start + 2..].find('}') else {
+            break;
+        };
+        let name = &rest[start + 2..start + 2 + len];
+        expanded.push_str(&rest[..start]);
+        expanded.push_str(vars.get(name).map(String::as_str).unwrap_or_default());
+        rest = &rest[start + 3 + len..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_requires_one_transport() {
+        let config = |command: &[&str], url: Option<&str>| McpServerConfig {
+            command: command.iter().map(|s| s.to_string()).collect(),
+            url: url.map(str::to_owned),
+            ..Default::default()
+        };
+        let parse = |config: McpServerConfig| {
+            McpServers::parse(&HashMap::from([("tools:github".to_owned(), config)]))
+        };
+        assert!(parse(config(&["mcp-server"], None)).is_ok());
+        assert!(parse(config(&[], Some("http://localhost/mcp"))).is_ok());
+        assert!(parse(config(&[], None)).is_err());
+        assert!(parse(config(&["mcp-server"], Some("http://localhost/mcp"))).is_err());
+        let invalid_name = HashMap::from([("github".to_owned(), config(&["mcp-server"], None))]);
+        assert!(McpServers::parse(&invalid_name).is_err());
+    }
+
+    #[test]
+    fn test_expand_vars() {
+        let vars = HashMap::from([("TOKEN".to_owned(), "abc".to_owned())]);
+        assert_eq!(expand_vars("Bearer ${TOKEN}", &vars), "Bearer abc");
+        assert_eq!(expand_vars("${TOKEN}-${TOKEN}", &vars), "abc-abc");
+        assert_eq!(
+            expand_vars("${ASTERAI_TEST_UNSET_VAR}x", &vars),
+            "x".to_owned()
+        );
+        assert_eq!(expand_vars("${PATH}", &vars), "");
+        assert_eq!(expand_vars("${unterminated", &vars), "${unterminated");
+    }
+}
@@ -0,0 +1,257 @@
+//! Mock providers of imported interfaces.
+//!
+//! A mock file holds the responses of an interface's functions, keyed
+//! by function name, in JSON or TOML (by its `.toml` extension):
+//!
+//! ```toml
+//! [[prompt]]
+//! args = ["hello*", "*"]
+//! output = "hi there"
+//!
+//! [[prompt]]
+//! output = "I don't know"
+//! ```
+//!
+//! A call returns the output of the first response whose `args` pattern
+//! matches its args, either positionally as an array or by parameter name
+//! as an object. Within a pattern, `"*"` matches any value, other strings
+//! with `*` match strings by glob, objects match objects having at least
+//! their fields, and other values must be equal. Responses without
+//! `args` match any call.
+use crate::environment::wiring::unversioned_interface_name;
+use eyre::{Context, bail, eyre};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Mock providers of an environment, by unversioned interface name.
+#[derive(Debug, Clone, Default)]
+pub struct Mocks {
+    interfaces: HashMap<String, Arc<MockResponses>>,
+}
+
+/// The responses of a mocked interface's functions.
+#[derive(Debug, Default, Deserialize)]
+#[serde(transparent)]
+pub struct MockResponses {
+    functions: HashMap<String, Vec<MockResponse>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct MockResponse {
+    /// Pattern of the args of calls this response matches.
+    #[serde(default)]
+    args: Option<Value>,
+    /// JSON encoding of the returned value, if the function returns one.
+    #[serde(default)]
+    output: Option<Value>,
+}
+
+impl Mocks {
+    /// Loads the mock files of the `mocks` table of an environment manifest.
+    pub fn load(mocks: &HashMap<String, String>) -> eyre::Result<Self> {
+        let mut interfaces = HashMap::new();
+        for (interface, path) in mocks {
+            if !interface.contains('/') {
+                bail!(
+                    "invalid interface '{interface}' in mocks: \
+                     expected namespace:package/interface"
+                );
+            }
+            // Relative paths would depend on the directory the
+            // environment happens to run from.
+            let path = Path::new(path);
+            if !path.is_absolute() {
+                bail!(
+                    "invalid mock file '{}' of {interface}: expected an absolute path",
+                    path.display()
+                );
+            }
+            let responses = MockResponses::load(path)
+                .wrap_err_with(|| format!("failed to load mock of {interface}"))?;
+            interfaces.insert(
+                unversioned_interface_name(interface).to_owned(),
+                Arc::new(responses),
+            );
+        }
+        Ok(Self { interfaces })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.interfaces.is_empty()
+    }
+
+    /// Returns the responses of `interface`, if it is mocked.
+    /// Any version in `interface` is ignored.
+    pub fn for_interface(&self, interface: &str) -> Option<&Arc<MockResponses>> {
+        self.interfaces.get(unversioned_interface_name(interface))
+    }
+}
+
+impl MockResponses {
+    /// Reads a mock file, as TOML if its extension is `.toml`
+    /// and as JSON otherwise.
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("failed to read {}", path.display()))?;
+        let is_toml = path.extension().is_some_and(|e| e == "toml");
+        match is_toml {
+            true => toml::from_str(&content).map_err(|e| eyre!("invalid mock file: {e}")),
+            false => serde_json::from_str(&content).map_err(|e| eyre!("invalid mock file: {e}")),
+        }
+    }
+
+    /// Returns the output of the first response of `function` matching
+    /// `args`, with `param_names` naming the args for object patterns.
+    pub fn respond(
+        &self,
+        function: &str,
+        param_names: &[&str],
+        args: &[Value],
+    ) -> eyre::Result<Option<&Value>> {
+        let Some(responses) = self.functions.get(function) else {
+            bail!("mock has no responses for function {function}");
+        };
+        let named_args: serde_json::Map<String, Value> = param_names
+            .iter()
+            .map(|name| name.to_string())
+            .zip(args.iter().cloned())
+            .collect();
+        let named_args = Value::Object(named_args);
+        let args = Value::Array(args.to_vec());
+        let response = responses.iter().find(|r| match &r.args {
+            None => true,
+            Some(pattern @ Value::Object(_)) => matches(pattern, &named_args),
+            Some(pattern) => matches(pattern, &args),
+        });
+        let Some(response) = response else {
+            bail!("no mock response of function {function} matches args {args}");
+        };
+        Ok(response.output.as_ref())
+    }
+}
+
+/// Returns whether `value` matches `pattern`, see the module docs.
+fn matches(pattern: &Value, value: &Value) -> bool {
+    match (pattern, value) {
+        (Value::String(p), _) if p == "*" => true,
+        (Value::String(p), Value::String(s)) if p.contains('*') => glob_matches(p, s),
+        (Value::Object(p), Value::Object(v)) => p
+            .iter()
+            .all(|(key, p)| v.get(key).is_some_and(|v| matches(p, v))),
+        (Value::Array(p), Value::Array(v)) => {
+            p.len() == v.len() && p.iter().zip(v).all(|(p, v)| matches(p, v))
+        }
+        _ => pattern == value,
+    }
+}
+
+/// Returns whether `s` matches `pattern`, where each `*` matches
+/// any sequence of characters.
+fn glob_matches(pattern: &str, s: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = s.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    let last = parts.pop().unwrap_or_default();
+    for part in parts {
+        let Some(index) = rest.find(part) else {
+            return false;
+        };
+        rest = &rest[index + part.len()..];
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn responses(json: Value) -> MockResponses {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("hello*", "hello world"));
+        assert!(glob_matches("*world", "hello world"));
+        assert!(glob_matches("h*o*d", "hello world"));
+        assert!(glob_matches("a*a", "aa"));
+        assert!(!glob_matches("a*a", "a"));
+        assert!(!glob_matches("hello*", "say hello"));
+        assert!(!glob_matches("*x*", "hello"));
+    }
+
+    #[test]
+    fn test_respond_with_first_matching_response() {
+        let mock = responses(json!({
+            "prompt": [
+                { "args": ["hello*", "*"], "output": "hi" },
+                { "args": { "model": "big" }, "output": "big answer" },
+                { "output": "default" },
+            ],
+        }));
+        let params = ["prompt", "model"];
+        let respond = |args: Value| {
+            let Value::Array(args) = args else {
+                unreachable!()
+            };
+            mock.respond("prompt", &params, &args).unwrap().cloned()
+        };
+        assert_eq!(respond(json!(["hello there", "small"])), Some(json!("hi")));
+        assert_eq!(respond(json!(["what?", "big"])), Some(json!("big answer")));
+        assert_eq!(respond(json!(["what?", "small"])), Some(json!("default")));
+    }
+
+    #[test]
+    fn test_respond_errors() {
+        let mock = responses(json!({
+            "add": [{ "args": [1, { "x": 2 }], "output": 3 }],
+        }));
+        assert_eq!(
+            mock.respond("add", &["a", "b"], &[json!(1), json!({ "x": 2, "y": 0 })])
+                .unwrap(),
+            Some(&json!(3))
+        );
+        let error = mock
+            .respond("add", &["a", "b"], &[json!(2), json!({ "x": 2 })])
+            .unwrap_err();
+        assert!(error.to_string().contains("no mock response"));
+        let error = mock.respond("sub", &[], &[]).unwrap_err();
+        assert!(error.to_string().contains("no responses for function sub"));
+    }
+
+    #[test]
+    fn test_load_toml_mock() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("llm.toml");
+        std::fs::write(
+            &path,
+            "[[prompt]]\nargs = [\"*\"]\noutput = { ok = \"hi\" }\n",
+        )
+        .unwrap();
+        let mocks = Mocks::load(&HashMap::from([(
+            "asterai:llm/llm@1.0.0".to_owned(),
+            path.to_string_lossy().into_owned(),
+        )]))
+        .unwrap();
+        let mock = mocks.for_interface("asterai:llm/llm@2.0.0").unwrap();
+        assert_eq!(
+            mock.respond("prompt", &["prompt"], &[json!("hello")])
+                .unwrap(),
+            Some(&json!({ "ok": "hi" }))
+        );
+        let path = path.to_string_lossy().into_owned();
+        let invalid = HashMap::from([("asterai:llm".to_owned(), path)]);
+        assert!(Mocks::load(&invalid).is_err());
+        let relative = HashMap::from([("asterai:llm/llm".to_owned(), "llm.toml".to_owned())]);
+        let error = Mocks::load(&relative).unwrap_err();
+        assert!(error.to_string().contains("expected an absolute path"));
+    }
+}
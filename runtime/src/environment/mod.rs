@@ -1,10 +1,20 @@
 use crate::component::Component;
+use crate::component::binary::ComponentBinary;
+use crate::component::config::ConfigIssue;
 use crate::component::wit::ComponentInterface;
+use crate::environment::interceptors::Interceptors;
+use crate::environment::mcp_servers::{McpServerConfig, McpServers};
+use crate::environment::mocks::Mocks;
+use crate::environment::wiring::{InterfaceWiring, unversioned_interface_name};
 use crate::resource::ResourceId;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 pub mod deps;
+pub mod interceptors;
+pub mod mcp_servers;
+pub mod mocks;
+pub mod wiring;
 
 /// Environment manifest - the deployable unit in Asterai.
 ///
@@ -19,6 +29,38 @@ pub struct Environment {
     pub components: HashMap<String, String>,
     /// Environment variables/secrets.
     pub vars: HashMap<String, String>,
+    /// Explicit providers for imported interfaces, per consumer.
+    /// Key is the consumer "namespace:name", value maps an interface
+    /// (e.g. "asterai:llm/llm") to the provider "namespace:name".
+    /// Interfaces not wired here are linked to the first provider
+    /// in alphabetical order.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub wiring: HashMap<String, HashMap<String, String>>,
+    /// Interceptor components wrapping calls between components.
+    /// Key is an interface (e.g. "asterai:fs/fs"), value lists the
+    /// "namespace:name" of components exporting `asterai:host/interceptor`
+    /// that calls to the interface pass through, in order.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub interceptors: HashMap<String, Vec<String>>,
+    /// External MCP servers included as virtual components.
+    /// Key is the "namespace:name" components call the server's
+    /// tools through, as functions named after the tools.
+    /// Servers launched by a command are local: they are not pushed
+    /// to the registry, and are ignored in pulled environments, so that
+    /// pulling an environment never runs a command it provides.
+    #[serde(
+        default,
+        rename = "mcp-servers",
+        skip_serializing_if = "HashMap::is_empty"
+    )]
+    pub mcp_servers: HashMap<String, McpServerConfig>,
+    /// Mock providers of imported interfaces, for local development.
+    /// Key is an interface (e.g. "asterai:llm/llm"), value is the absolute
+    /// path of a JSON or TOML file with the responses of its functions,
+    /// see [`mocks`]. Mocks are not pushed to the registry, and pulling
+    /// an environment keeps the local ones.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub mocks: HashMap<String, String>,
 }
 
 /// Metadata for an environment manifest.
@@ -44,6 +86,14 @@ pub enum ChangeReason {
     ComponentUpgraded,
     /// Environment variables were changed.
     VarsChanged,
+    /// Interface wiring between components was changed.
+    WiringChanged,
+    /// Interceptors were changed.
+    InterceptorsChanged,
+    /// MCP servers were changed.
+    McpServersChanged,
+    /// Another field of the manifest was changed.
+    ManifestChanged,
     /// No changes from the previous version.
     NoChange,
 }
@@ -57,6 +107,10 @@ impl ChangeReason {
             ChangeReason::ComponentRemoved => "componentRemoved",
             ChangeReason::ComponentUpgraded => "componentUpgraded",
             ChangeReason::VarsChanged => "varsChanged",
+            ChangeReason::WiringChanged => "wiringChanged",
+            ChangeReason::InterceptorsChanged => "interceptorsChanged",
+            ChangeReason::McpServersChanged => "mcpServersChanged",
+            ChangeReason::ManifestChanged => "manifestChanged",
             ChangeReason::NoChange => "noChange",
         }
     }
@@ -79,6 +133,10 @@ impl Environment {
             },
             components: HashMap::new(),
             vars: HashMap::new(),
+            wiring: HashMap::new(),
+            interceptors: HashMap::new(),
+            mcp_servers: HashMap::new(),
+            mocks: HashMap::new(),
         }
     }
 
@@ -109,12 +167,42 @@ impl Environment {
         self.components.insert(key, component.version().to_string());
     }
 
-    /// Remove a component from this environment.
+    /// Remove a component from this environment,
+    /// along with any wiring or interceptors that refer to it.
     pub fn remove_component(&mut self, namespace: &str, name: &str) -> bool {
         let key = format!("{}:{}", namespace, name);
+        self.wiring.remove(&key);
+        for providers in self.wiring.values_mut() {
+            providers.retain(|_, provider| *provider != key);
+        }
+        self.wiring.retain(|_, providers| !providers.is_empty());
+        for chain in self.interceptors.values_mut() {
+            chain.retain(|interceptor| *interceptor != key);
+        }
+        self.interceptors.retain(|_, chain| !chain.is_empty());
         self.components.remove(&key).is_some()
     }
 
+    /// Parse the explicit interface wiring of this environment.
+    pub fn interface_wiring(&self) -> eyre::Result<InterfaceWiring> {
+        InterfaceWiring::parse(&self.wiring)
+    }
+
+    /// Parse the interceptors of this environment.
+    pub fn interceptors(&self) -> eyre::Result<Interceptors> {
+        Interceptors::parse(&self.interceptors)
+    }
+
+    /// Parse the MCP servers of this environment.
+    pub fn mcp_servers(&self) -> eyre::Result<McpServers> {
+        McpServers::parse(&self.mcp_servers)
+    }
+
+    /// Load the mock providers of this environment.
+    pub fn mocks(&self) -> eyre::Result<Mocks> {
+        Mocks::load(&self.mocks)
+    }
+
     /// Set an environment variable.
     pub fn set_var(&mut self, key: String, value: String) {
         self.vars.insert(key, value);
@@ -159,6 +247,89 @@ impl Environment {
     /// in the set. These are dependencies that must be resolved before
     /// the environment can run.
     pub fn dependencies(&self, components: &[impl ComponentInterface]) -> Vec<ResourceId> {
-        deps::unsatisfied_import_packages(components)
+        deps::unsatisfied_import_packages(components, &self.mocked_interfaces())
+    }
+
+    /// Returns the MCP servers that are pushed to the registry,
+    /// which are those reached by URL.
+    pub fn shared_mcp_servers(&self) -> HashMap<String, McpServerConfig> {
+        self.mcp_servers
+            .iter()
+            .filter(|(_, config)| !config.is_local())
+            .map(|(name, config)| (name.clone(), config.clone()))
+            .collect()
+    }
+
+    /// Replaces the local settings of this environment, as pulled from
+    /// the registry, with those of `local`, the local version of it if
+    /// any: its MCP servers launched by a command, and its mocks.
+    /// Pulled MCP servers launched by a command are ignored with a warning.
+    pub fn keep_local_settings(&mut self, local: Option<&Environment>) {
+        let mut ignored: Vec<&String> = self
+            .mcp_servers
+            .iter()
+            .filter(|(_, config)| config.is_local())
+            .map(|(name, _)| name)
+            .collect();
+        ignored.sort();
+        for server in ignored {
+            eprintln!(
+                "warning: ignoring MCP server {server} of the pulled environment: \
+                 servers launched by a command are only configured locally"
+            );
+        }
+        self.mcp_servers.retain(|_, config| !config.is_local());
+        let Some(local) = local else {
+            return;
+        };
+        for (name, config) in &local.mcp_servers {
+            if config.is_local() {
+                self.mcp_servers.insert(name.clone(), config.clone());
+            }
+        }
+        self.mocks = local.mocks.clone();
+    }
+
+    /// Returns the unversioned names of the interfaces this environment mocks.
+    pub fn mocked_interfaces(&self) -> HashSet<String> {
+        self.mocks
+            .keys()
+            .map(|interface| unversioned_interface_name(interface).to_owned())
+            .collect()
+    }
+
+    /// Returns the config vars declared by `components` that are
+    /// missing or invalid in this environment.
+    pub fn config_issues(&self, components: &[ComponentBinary]) -> Vec<ConfigIssue> {
+        components
+            .iter()
+            .flat_map(|component| {
+                component.config().iter().filter_map(|var| {
+                    let problem = var.check(&self.vars)?;
+                    Some(ConfigIssue {
+                        component: component.component().id().to_string(),
+                        var: var.clone(),
+                        problem,
+                    })
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the vars of this environment, adding the defaults
+    /// of the config vars declared by `components` that are not set.
+    pub fn vars_with_config_defaults(
+        &self,
+        components: &[ComponentBinary],
+    ) -> HashMap<String, String> {
+        let mut vars = self.vars.clone();
+        let defaults = components
+            .iter()
+            .flat_map(|component| component.config())
+            .filter_map(|var| Some((var.name.clone(), var.default.clone()?)));
+        for (name, default) in defaults {
+            vars.entry(name).or_insert(default);
+        }
+        vars
     }
 }
@@ -0,0 +1,158 @@
+//! Explicit wiring of imported interfaces to provider components.
+use crate::component::ComponentId;
+use crate::component::binary::ComponentBinary;
+use crate::component::wit::ComponentInterface;
+use eyre::{bail, eyre};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Per-consumer provider choices for imported interfaces.
+///
+/// By default, an imported interface exported by multiple components
+/// is linked to the first provider in alphabetical order. Wiring
+/// overrides this for a given consumer, so that e.g. two LLM providers
+/// can coexist in one environment and be routed per consumer.
+#[derive(Debug, Clone, Default)]
+pub struct InterfaceWiring {
+    /// Consumer -> unversioned interface name -> provider.
+    wires: HashMap<ComponentId, HashMap<String, ComponentId>>,
+}
+
+impl InterfaceWiring {
+    /// Parses the `wiring` table of an environment manifest.
+    pub fn parse(wiring: &HashMap<String, HashMap<String, String>>) -> eyre::Result<Self> {
+        let mut wires = HashMap::new();
+        for (consumer, interfaces) in wiring {
+            let consumer_id = ComponentId::from_str(consumer)
+                .map_err(|e| eyre!("invalid component '{consumer}' in wiring: {e}"))?;
+            let mut providers = HashMap::new();
+            for (interface, provider) in interfaces {
+                if !interface.contains('/') {
+                    bail!(
+                        "invalid interface '{interface}' in wiring for {consumer}: \
+                         expected namespace:package/interface"
+                    );
+                }
+                let provider_id = ComponentId::from_str(provider)
+                    .map_err(|e| eyre!("invalid component '{provider}' in wiring: {e}"))?;
+                providers.insert(
+                    unversioned_interface_name(interface).to_owned(),
+                    provider_id,
+                );
+            }
+            wires.insert(consumer_id, providers);
+        }
+        Ok(Self { wires })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.wires.values().all(|providers| providers.is_empty())
+    }
+
+    /// Returns the provider wired for `consumer`'s import of `interface`.
+    /// Any version in `interface` is ignored.
+    pub fn provider(&self, consumer: &ComponentId, interface: &str) -> Option<&ComponentId> {
+        self.wires
+            .get(consumer)?
+            .get(unversioned_interface_name(interface))
+    }
+
+    /// Returns consumers along with their wired interfaces and providers.
+    pub fn consumers(&self) -> impl Iterator<Item = (&ComponentId, &HashMap<String, ComponentId>)> {
+        self.wires
+            .iter()
+            .filter(|(_, providers)| !providers.is_empty())
+    }
+
+    /// Checks that each wire refers to components in the set, that the
+    /// consumer imports the interface and that the provider exports it.
+    pub fn validate(&self, components: &[ComponentBinary]) -> eyre::Result<()> {
+        let find = |id: &ComponentId| components.iter().find(|c| c.component().id() == *id);
+        for (consumer, providers) in self.consumers() {
+            let Some(consumer_binary) = find(consumer) else {
+                bail!("wired component {consumer} is not in the environment");
+            };
+            for (interface, provider) in providers {
+                let is_imported = consumer_binary
+                    .imported_interfaces()
+                    .iter()
+                    .any(|i| unversioned_interface_name(&i.name) == interface);
+                if !is_imported {
+                    bail!("{consumer} does not import {interface}");
+                }
+                let Some(provider_binary) = find(provider) else {
+                    bail!(
+                        "{provider} (wired to {consumer} for {interface}) is not in the environment"
+                    );
+                };
+                let is_exported = provider_binary
+                    .exported_interfaces()
+                    .iter()
+                    .any(|e| unversioned_interface_name(&e.name) == interface);
+                if !is_exported {
+                    bail!("{provider} does not export {interface} (wired to {consumer})");
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Strips the version from an interface name,
+/// e.g. "asterai:llm/llm@1.0.0" becomes "asterai:llm/llm".
+pub fn unversioned_interface_name(name: &str) -> &str {
+    name.split_once('@').map(|(n, _)| n).unwrap_or(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wiring(entries: &[(&str, &str, &str)]) -> HashMap<String, HashMap<String, String>> {
+        let mut wiring: HashMap<String, HashMap<String, String>> = HashMap::new();
+        for (consumer, interface, provider) in entries {
+            wiring
+                .entry(consumer.to_string())
+                .or_default()
+                .insert(interface.to_string(), provider.to_string());
+        }
+        wiring
+    }
+
+    #[test]
+    fn test_provider_ignores_versions() {
+        let wiring = InterfaceWiring::parse(&wiring(&[(
+            "asterbot:agent",
+            "asterai:llm/llm@1.0.0",
+            "acme:llm-proxy",
+        )]))
+        .unwrap();
+        let consumer = ComponentId::from_str("asterbot:agent").unwrap();
+        let provider = wiring.provider(&consumer, "asterai:llm/llm@1.2.0").unwrap();
+        assert_eq!(provider.to_string(), "acme:llm-proxy");
+        assert!(wiring.provider(&consumer, "asterai:fs/fs@1.0.0").is_none());
+        let other = ComponentId::from_str("asterbot:other").unwrap();
+        assert!(wiring.provider(&other, "asterai:llm/llm").is_none());
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_entries() {
+        let invalid_interface = wiring(&[("asterbot:agent", "asterai:llm", "acme:llm-proxy")]);
+        assert!(InterfaceWiring::parse(&invalid_interface).is_err());
+        let invalid_provider = wiring(&[("asterbot:agent", "asterai:llm/llm", "llm-proxy")]);
+        assert!(InterfaceWiring::parse(&invalid_provider).is_err());
+        assert!(InterfaceWiring::parse(&HashMap::new()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_unversioned_interface_name() {
+        assert_eq!(
+            unversioned_interface_name("asterai:llm/llm@1.0.0"),
+            "asterai:llm/llm"
+        );
+        assert_eq!(
+            unversioned_interface_name("asterai:llm/llm"),
+            "asterai:llm/llm"
+        );
+    }
+}
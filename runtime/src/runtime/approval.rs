@@ -0,0 +1,318 @@
+//! Approval of calls between components.
+//!
+//! Each component or function has an approval policy: `auto` lets its
+//! calls proceed, `ask` asks the user of the runtime to approve each call
+//! through an approver channel, and `deny` fails its calls.
+//!
+//! Policies are written as comma-separated `target=policy` pairs, where
+//! a target is a component, e.g. `asterai:telegram`, or a function of
+//! it, e.g. `asterai:telegram/api/send-message`. The policy of a function
+//! takes precedence over that of its component, and calls of components
+//! without a policy proceed.
+//!
+//! Only calls between components are checked, i.e. those made with
+//! `call-component-function` and through imports of other components,
+//! so that the user of the runtime can call any function itself.
+use eyre::{bail, eyre};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use strum_macros::{Display, EnumString};
+use tokio::sync::{mpsc, oneshot};
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, EnumString, Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum ApprovalPolicy {
+    #[default]
+    Auto,
+    Ask,
+    Deny,
+}
+
+/// Approval policies by component or function.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ApprovalPolicies {
+    policies: BTreeMap<String, ApprovalPolicy>,
+}
+
+/// A call waiting for the user of the runtime to approve it.
+#[derive(Debug)]
+pub struct ApprovalRequest {
+    /// The ID of the called component, e.g. `asterai:telegram`.
+    pub component: String,
+    /// The called function, e.g. `api/send-message`.
+    pub function: String,
+    /// The args of the call, as a JSON array.
+    pub args_json: String,
+    reply: oneshot::Sender<bool>,
+}
+
+/// The approval policies of a runtime, and the channel to send
+/// requests for approval on. Clones share the policies and channel.
+#[derive(Clone, Default)]
+pub struct CallApprovals {
+    inner: Arc<RwLock<CallApprovalsInner>>,
+}
+
+#[derive(Default)]
+struct CallApprovalsInner {
+    policies: ApprovalPolicies,
+    approver_tx: Option<mpsc::Sender<ApprovalRequest>>,
+}
+
+impl ApprovalPolicies {
+    /// The policy of calls of `function` on `component`.
+    pub fn policy_for(&self, component: &str, function: &str) -> ApprovalPolicy {
+        self.policies
+            .get(&format!("{component}/{function}"))
+            .or_else(|| self.policies.get(component))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Sets the policy of `target`, a component or one of its functions.
+    /// Setting it to `auto` removes it, as calls proceed by default.
+    pub fn set(&mut self, target: &str, policy: ApprovalPolicy) {
+        match policy {
+            ApprovalPolicy::Auto => self.policies.remove(target),
+            _ => self.policies.insert(target.to_owned(), policy),
+        };
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, ApprovalPolicy)> {
+        self.policies.iter().map(|(t, p)| (t.as_str(), *p))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.policies.is_empty()
+    }
+}
+
+impl FromStr for ApprovalPolicies {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> eyre::Result<Self> {
+        let mut policies = Self::default();
+        for pair in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let Some((target, policy)) = pair.split_once('=') else {
+                bail!("invalid approval policy {pair}, expected target=policy");
+            };
+            let target = target.trim();
+            if !target.contains(':') {
+                bail!("invalid approval target {target}, expected a component or function");
+            }
+            let policy = ApprovalPolicy::from_str(policy.trim()).map_err(|_| {
+                eyre!("invalid approval policy {policy}, expected auto, ask or deny")
+            })?;
+            policies.set(target, policy);
+        }
+        Ok(policies)
+    }
+}
+
+impl Display for ApprovalPolicies {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let pairs: Vec<String> = self
+            .iter()
+            .map(|(target, policy)| format!("{target}={policy}"))
+            .collect();
+        write!(f, "{}", pairs.join(","))
+    }
+}
+
+impl ApprovalRequest {
+    pub fn approve(self) {
+        let _ = self.reply.send(true);
+    }
+
+    pub fn deny(self) {
+        let _ = self.reply.send(false);
+    }
+}
+
+impl CallApprovals {
+    pub fn set_policies(&self, policies: ApprovalPolicies) {
+        self.inner.write().unwrap().policies = policies;
+    }
+
+    /// Sets the channel to send requests for approval on, without
+    /// which calls with the `ask` policy fail.
+    pub fn set_approver(&self, approver_tx: Option<mpsc::Sender<ApprovalRequest>>) {
+        self.inner.write().unwrap().approver_tx = approver_tx;
+    }
+
+    /// Checks that a call may proceed, blocking until it is approved
+    /// if its policy is `ask`. `args_json` is only called in that case.
+    /// This blocks the thread, so it must run on a blocking thread.
+    pub(crate) fn check_blocking(
+        &self,
+        component: &str,
+        function: &str,
+        args_json: impl FnOnce() -> eyre::Result<String>,
+    ) -> eyre::Result<()> {
+        let Some((request, reply_rx, approver_tx)) =
+            self.request_for(component, function, args_json)?
+        else {
+            return Ok(());
+        };
+        approver_tx
+            .blocking_send(request)
+            .map_err(|_| no_approver(component, function))?;
+        check_reply(reply_rx.blocking_recv(), component, function)
+    }
+
+    /// Like [`Self::check_blocking`], awaiting the approval instead.
+    pub(crate) async fn check(
+        &self,
+        component: &str,
+        function: &str,
+        args_json: impl FnOnce() -> eyre::Result<String>,
+    ) -> eyre::Result<()> {
+        let Some((request, reply_rx, approver_tx)) =
+            self.request_for(component, function, args_json)?
+        else {
+            return Ok(());
+        };
+        approver_tx
+            .send(request)
+            .await
+            .map_err(|_| no_approver(component, function))?;
+        check_reply(reply_rx.await, component, function)
+    }
+
+    /// Returns the request for approval of a call, if it needs one,
+    /// or an error if it may not proceed.
+    #[allow(clippy::type_complexity)]
+    fn request_for(
+        &self,
+        component: &str,
+        function: &str,
+        args_json: impl FnOnce() -> eyre::Result<String>,
+    ) -> eyre::Result<
+        Option<(
+            ApprovalRequest,
+            oneshot::Receiver<bool>,
+            mpsc::Sender<ApprovalRequest>,
+        )>,
+    > {
+        let inner = self.inner.read().unwrap();
+        match inner.policies.policy_for(component, function) {
+            ApprovalPolicy::Auto => return Ok(None),
+            ApprovalPolicy::Deny => {
+                bail!("call of {component}/{function} denied by its approval policy")
+            }
+            ApprovalPolicy::Ask => {}
+        }
+        let approver_tx = inner
+            .approver_tx
+            .clone()
+            .ok_or_else(|| no_approver(component, function))?;
+        let (reply, reply_rx) = oneshot::channel();
+        let request = ApprovalRequest {
+            component: component.to_owned(),
+            function: function.to_owned(),
+            args_json: args_json()?,
+            reply,
+        };
+        Ok(Some((request, reply_rx, approver_tx)))
+    }
+}
+
+fn no_approver(component: &str, function: &str) -> eyre::Report {
+    eyre!("call of {component}/{function} needs approval, but nothing can approve it here")
+}
+
+/// Checks the reply to a request, which is denied if it was dropped.
+fn check_reply<E>(reply: Result<bool, E>, component: &str, function: &str) -> eyre::Result<()> {
+    match reply {
+        Ok(true) => Ok(()),
+        _ => bail!("call of {component}/{function} was not approved"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_approval_policies() {
+        let policies = ApprovalPolicies::from_str(
+            "asterai:telegram=ask, asterai:telegram/api/get-updates=auto,asterbot:fs=deny",
+        )
+        .unwrap();
+        assert_eq!(
+            policies.policy_for("asterai:telegram", "api/send-message"),
+            ApprovalPolicy::Ask
+        );
+        // Setting a function to `auto` removes its policy, so that of the component applies.
+        assert_eq!(
+            policies.policy_for("asterai:telegram", "api/get-updates"),
+            ApprovalPolicy::Ask
+        );
+        assert_eq!(
+            policies.policy_for("asterbot:fs", "fs/write"),
+            ApprovalPolicy::Deny
+        );
+        assert_eq!(
+            policies.policy_for("asterbot:memory", "memory/recall"),
+            ApprovalPolicy::Auto
+        );
+        assert_eq!(
+            policies.to_string(),
+            "asterai:telegram=ask,asterbot:fs=deny"
+        );
+        assert!(ApprovalPolicies::from_str("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_function_policy_overrides_component_policy() {
+        let policies =
+            ApprovalPolicies::from_str("asterbot:fs=ask,asterbot:fs/fs/read=deny").unwrap();
+        assert_eq!(
+            policies.policy_for("asterbot:fs", "fs/read"),
+            ApprovalPolicy::Deny
+        );
+        assert_eq!(
+            policies.policy_for("asterbot:fs", "fs/write"),
+            ApprovalPolicy::Ask
+        );
+    }
+
+    #[test]
+    fn test_parse_approval_policies_errors() {
+        let error = |s: &str| ApprovalPolicies::from_str(s).unwrap_err().to_string();
+        assert!(error("asterai:telegram").contains("expected target=policy"));
+        assert!(error("telegram=ask").contains("invalid approval target"));
+        assert!(error("asterai:telegram=maybe").contains("expected auto, ask or deny"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_check_call() {
+        let approvals = CallApprovals::default();
+        approvals.set_policies(ApprovalPolicies::from_str("a:b=ask,a:c=deny").unwrap());
+        let args = || Ok("[1]".to_owned());
+        assert!(approvals.check("a:d", "f", args).await.is_ok());
+        let denied = approvals.check("a:c", "f", args).await.unwrap_err();
+        assert!(denied.to_string().contains("denied by its approval policy"));
+        let unapproved = approvals.check("a:b", "f", args).await.unwrap_err();
+        assert!(unapproved.to_string().contains("nothing can approve it"));
+        let (approver_tx, mut approver_rx) = mpsc::channel(1);
+        approvals.set_approver(Some(approver_tx));
+        let checked = tokio::spawn({
+            let approvals = approvals.clone();
+            async move { approvals.check("a:b", "f", args).await }
+        });
+        let request = approver_rx.recv().await.unwrap();
+        assert_eq!(request.args_json, "[1]");
+        request.approve();
+        assert!(checked.await.unwrap().is_ok());
+        let checked = tokio::task::spawn_blocking({
+            let approvals = approvals.clone();
+            move || approvals.check_blocking("a:b", "f", args)
+        });
+        approver_rx.recv().await.unwrap().deny();
+        let rejected = checked.await.unwrap().unwrap_err();
+        assert!(rejected.to_string().contains("was not approved"));
+    }
+}
@@ -0,0 +1,286 @@
+//! Call tracing with spans across components.
+//!
+//! A span is recorded for each host-mediated call: direct calls,
+//! forwarding stubs, dynamic calls, cron ticks, WebSocket callbacks
+//! and HTTP requests. The span of the call a store is running is kept
+//! in [`HostEnv::span`], so that calls made from within it become its
+//! children, including across the fresh stores of the sync engine.
+//!
+//! Finished spans are passed to the exporter set with [`set_exporter`],
+//! and counted in the call metrics. Without an exporter, spans are
+//! only logged at trace level.
+use crate::runtime::env::HostEnv;
+use crate::runtime::metrics::{self, INVOCATION_FAILED, call_error_kind_name};
+use crate::runtime::wit_bindings::exports::asterai::host::api::CallError;
+use log::{trace, warn};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use strum_macros::{Display as StrumDisplay, EnumString};
+use uuid::Uuid;
+use wasmtime::AsContextMut;
+
+static EXPORTER: OnceLock<Box<dyn SpanExporter>> = OnceLock::new();
+
+/// Receives finished spans.
+pub trait SpanExporter: Send + Sync {
+    fn export(&self, span: &Span);
+}
+
+/// Sets the global span exporter.
+/// Returns false if an exporter was already set.
+pub fn set_exporter(exporter: Box<dyn SpanExporter>) -> bool {
+    EXPORTER.set(exporter).is_ok()
+}
+
+/// Whether spans are exported.
+pub fn is_enabled() -> bool {
+    EXPORTER.get().is_some()
+}
+
+/// What initiated a call.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, StrumDisplay, EnumString,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum SpanKind {
+    /// A call into a component from the host, e.g. `env call`.
+    Call,
+    /// A call through an imported interface of another component.
+    Stub,
+    /// A call through `call-component-function`.
+    DynamicCall,
+    /// A cron schedule tick.
+    Cron,
+    /// A WebSocket callback.
+    Ws,
+    /// An HTTP request to a component route.
+    Http,
+}
+
+/// Identifies a span and its trace, for parent/child propagation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpanContext {
+    pub trace_id: u128,
+    pub span_id: u64,
+}
+
+/// A finished span.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Span {
+    /// Trace ID, as 32 hex characters.
+    pub trace_id: String,
+    /// Span ID, as 16 hex characters.
+    pub span_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_span_id: Option<String>,
+    pub kind: SpanKind,
+    /// The called component, e.g. "asterai:fs".
+    pub component: String,
+    /// The called function, e.g. "fs/read".
+    pub function: String,
+    pub start_time_unix_nano: u64,
+    pub duration_nanos: u64,
+    /// The error message, if the call failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Span {
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// A span that has started and not yet ended.
+pub struct ActiveSpan {
+    context: SpanContext,
+    parent: Option<SpanContext>,
+    kind: SpanKind,
+    component: String,
+    function: String,
+    started_at: Instant,
+    start_time: SystemTime,
+}
+
+impl ActiveSpan {
+    /// Starts a span, as a child of `parent` or as the root of a new trace.
+    pub fn start(
+        kind: SpanKind,
+        component: impl Display,
+        function: impl Display,
+        parent: Option<SpanContext>,
+    ) -> Self {
+        let ids = Uuid::new_v4().as_u128();
+        let context = SpanContext {
+            trace_id: parent.map(|p| p.trace_id).unwrap_or(ids),
+            span_id: ids as u64,
+        };
+        Self {
+            context,
+            parent,
+            kind,
+            component: component.to_string(),
+            function: function.to_string(),
+            started_at: Instant::now(),
+            start_time: SystemTime::now(),
+        }
+    }
+
+    /// Starts a child of the span the store is running, if any.
+    pub fn start_in(
+        store: &mut impl AsContextMut<Data = HostEnv>,
+        kind: SpanKind,
+        component: impl Display,
+        function: impl Display,
+    ) -> Self {
+        let parent = store.as_context_mut().data().span;
+        Self::start(kind, component, function, parent)
+    }
+
+    pub fn context(&self) -> SpanContext {
+        self.context
+    }
+
+    /// Ends the span with the outcome of the call.
+    pub fn end<T, E: Display>(self, result: &Result<T, E>) {
+        let error = result.as_ref().err().map(|e| format!("{e:#}"));
+        let error_kind = error.as_ref().map(|_| INVOCATION_FAILED);
+        self.finish(error, error_kind);
+    }
+
+    /// Ends the span with the outcome of a dynamic call,
+    /// keeping the kind of error for metrics.
+    pub(crate) fn end_call(self, result: &Result<String, CallError>) {
+        let error = result.as_ref().err().map(|e| e.message.clone());
+        let error_kind = result.as_ref().err().map(|e| call_error_kind_name(e.kind));
+        self.finish(error, error_kind);
+    }
+
+    fn finish(self, error: Option<String>, error_kind: Option<&'static str>) {
+        let duration = self.started_at.elapsed();
+        metrics::record_call(
+            self.kind,
+            &self.component,
+            &self.function,
+            duration,
+            error_kind,
+        );
+        let start_time_unix_nano = self
+            .start_time
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default();
+        let span = Span {
+            trace_id: format!("{:032x}", self.context.trace_id),
+            span_id: format!("{:016x}", self.context.span_id),
+            parent_span_id: self.parent.map(|p| format!("{:016x}", p.span_id)),
+            kind: self.kind,
+            component: self.component,
+            function: self.function,
+            start_time_unix_nano,
+            duration_nanos: duration.as_nanos() as u64,
+            error,
+        };
+        trace!(
+            "span {} {} {} {}/{} in {}us",
+            span.trace_id,
+            span.span_id,
+            span.kind,
+            span.component,
+            span.function,
+            span.duration_nanos / 1000
+        );
+        if let Some(exporter) = EXPORTER.get() {
+            exporter.export(&span);
+        }
+    }
+}
+
+/// Runs `f` with `span` as the store's current span, restoring
+/// the previous one afterwards.
+pub fn in_span<S, R>(store: &mut S, span: SpanContext, f: impl FnOnce(&mut S) -> R) -> R
+where
+    S: AsContextMut<Data = HostEnv>,
+{
+    let previous = store.as_context_mut().data_mut().span.replace(span);
+    let result = f(store);
+    store.as_context_mut().data_mut().span = previous;
+    result
+}
+
+/// Appends spans as JSON lines to a file.
+pub struct JsonlExporter {
+    file: Mutex<File>,
+}
+
+impl JsonlExporter {
+    pub fn new(path: &Path) -> eyre::Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+}
+
+impl SpanExporter for JsonlExporter {
+    fn export(&self, span: &Span) {
+        let Ok(mut line) = serde_json::to_string(span) else {
+            return;
+        };
+        line.push('\n');
+        let mut file = self.file.lock().unwrap();
+        if let Err(e) = file.write_all(line.as_bytes()) {
+            warn!("failed to write span: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_child_span_shares_trace() {
+        let root = ActiveSpan::start(SpanKind::Call, "test:agent", "api/run", None);
+        let child = ActiveSpan::start(
+            SpanKind::Stub,
+            "test:llm",
+            "llm/complete",
+            Some(root.context()),
+        );
+        assert_eq!(child.context().trace_id, root.context().trace_id);
+        assert_ne!(child.context().span_id, root.context().span_id);
+        assert_eq!(child.parent, Some(root.context()));
+    }
+
+    #[test]
+    fn test_span_json_round_trip() {
+        let span = Span {
+            trace_id: format!("{:032x}", 1),
+            span_id: format!("{:016x}", 2),
+            parent_span_id: None,
+            kind: SpanKind::DynamicCall,
+            component: "asterai:fs".to_owned(),
+            function: "fs/read".to_owned(),
+            start_time_unix_nano: 3,
+            duration_nanos: 4,
+            error: Some("failed".to_owned()),
+        };
+        let json = serde_json::to_string(&span).unwrap();
+        assert!(json.contains(r#""kind":"dynamic-call""#));
+        assert!(!json.contains("parentSpanId"));
+        let parsed: Span = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.span_id, span.span_id);
+        assert!(!parsed.is_ok());
+    }
+}
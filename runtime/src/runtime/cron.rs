@@ -1,5 +1,7 @@
+use crate::runtime::call_trace::{ActiveSpan, SpanKind};
 use crate::runtime::entry::{execute_dynamic_call, resolve_call};
 use crate::runtime::env::HostEnvRuntimeData;
+use crate::runtime::handles::HandleTable;
 use log::{error, info};
 use std::collections::HashMap;
 use std::str::FromStr;
@@ -99,6 +101,7 @@ impl CronManager {
             &function_name,
             &args_json,
             rd.compiled_components.iter().map(|(b, _)| b),
+            &HandleTable::default(),
         )
         .map_err(|e| e.message)?;
         let id = self.next_id.fetch_add(1, Ordering::Relaxed);
@@ -145,6 +148,10 @@ impl CronManager {
             .collect()
     }
 
+    pub async fn schedule_count(&self) -> usize {
+        self.schedules.read().await.len()
+    }
+
     pub async fn cancel_all(&self) {
         let mut schedules = self.schedules.write().await;
         for (id, entry) in schedules.drain() {
@@ -193,12 +200,15 @@ async fn execute_cron_call(info: &ScheduleInfo, rd: &HostEnvRuntimeData) {
     let component_name = info.component_name.clone();
     let function_name = info.function_name.clone();
     let args_json = info.args_json.clone();
+    let span = ActiveSpan::start(SpanKind::Cron, &component_name, &function_name, None);
+    let span_context = span.context();
     let result = tokio::task::spawn_blocking(move || {
         let (comp_id, function, inputs) = resolve_call(
             &component_name,
             &function_name,
             &args_json,
             compiled_components.iter().map(|(b, _)| b),
+            &HandleTable::default(),
         )?;
         execute_dynamic_call(
             compiled_components,
@@ -208,9 +218,15 @@ async fn execute_cron_call(info: &ScheduleInfo, rd: &HostEnvRuntimeData) {
             env_vars,
             preopened_dirs,
             runtime_data,
+            span_context,
+            None,
         )
     })
     .await;
+    match &result {
+        Ok(call_result) => span.end_call(call_result),
+        Err(e) => span.end(&Err::<(), _>(e)),
+    }
     match result {
         Ok(Ok(output)) => {
             info!("cron schedule {} executed: {}", info.id, output);
@@ -3,20 +3,31 @@ use crate::component::ComponentId;
 use crate::component::binary::{ComponentBinary, WasmtimeComponent};
 use crate::component::function_interface::ComponentFunctionInterface;
 use crate::component::function_name::ComponentFunctionName;
-use crate::component::wit::ComponentInterface;
+use crate::component::wit::{ComponentInterface, short_interface_name};
+use crate::environment::mocks::Mocks;
+use crate::environment::wiring::InterfaceWiring;
+use crate::runtime::approval::CallApprovals;
+use crate::runtime::call_trace::{ActiveSpan, SpanContext, SpanKind, in_span};
 use crate::runtime::env::{HostEnv, HostEnvRuntimeData, create_fresh_store, create_sync_linker};
+use crate::runtime::handles::HandleTable;
+use crate::runtime::interceptor::call_intercepted;
 use crate::runtime::link_components::{register_component_stubs_sync, resolve_component_stubs};
-use crate::runtime::parsing::{ValExt, json_value_to_val_typedef};
+use crate::runtime::mcp_client::McpComponent;
+use crate::runtime::output::OutputEvent;
+use crate::runtime::parsing::{ValExt, json_args_to_vals};
 use crate::runtime::wasm_instance::SYNC_ENGINE;
 use crate::runtime::wit_bindings::exports::asterai::host::api::{
     CallError, CallErrorKind, ComponentInfo, FunctionInfo, ParamInfo, RuntimeInfo, TypeInfo,
 };
+use crate::runtime::wit_bindings::exports::asterai::host::schema::FunctionSchema;
 use std::collections::{HashMap, HashSet};
 use std::future::Future;
 use std::path::PathBuf;
 use std::str::FromStr;
-use wasmtime::StoreContextMut;
+use std::sync::Arc;
+use tokio::sync::mpsc;
 use wasmtime::component::{Linker, Val};
+use wasmtime::{AsContextMut, StoreContextMut};
 
 type HostFuture<'a, T> = Box<dyn Future<Output = Result<T, wasmtime::Error>> + Send + 'a>;
 
@@ -44,6 +55,15 @@ pub fn add_asterai_host_to_sync_linker(linker: &mut Linker<HostEnv>) -> eyre::Re
     instance
         .func_wrap("call-component-function", call_component_function_sync)
         .map_err(|e| eyre::eyre!("{e:#?}"))?;
+    instance
+        .func_wrap("emit-output", emit_output_sync)
+        .map_err(|e| eyre::eyre!("{e:#?}"))?;
+    let mut instance = linker
+        .instance("asterai:host/schema@1.0.0")
+        .map_err(|e| eyre::eyre!("{e:#?}"))?;
+    instance
+        .func_wrap("get-json-schemas", get_json_schemas_sync)
+        .map_err(|e| eyre::eyre!("{e:#?}"))?;
     Ok(())
 }
 
@@ -69,6 +89,15 @@ pub fn add_asterai_host_to_linker(linker: &mut Linker<HostEnv>) -> eyre::Result<
     instance
         .func_wrap_async("call-component-function", call_component_function)
         .map_err(|e| eyre::eyre!("{e:#?}"))?;
+    instance
+        .func_wrap_async("emit-output", emit_output)
+        .map_err(|e| eyre::eyre!("{e:#?}"))?;
+    let mut instance = linker
+        .instance("asterai:host/schema@1.0.0")
+        .map_err(|e| eyre::eyre!("{e:#?}"))?;
+    instance
+        .func_wrap_async("get-json-schemas", get_json_schemas)
+        .map_err(|e| eyre::eyre!("{e:#?}"))?;
     Ok(())
 }
 
@@ -100,6 +129,13 @@ fn get_component<'a>(
     Box::new(async move { get_component_sync(store, params) })
 }
 
+fn get_json_schemas<'a>(
+    store: StoreContextMut<'a, HostEnv>,
+    params: (String,),
+) -> HostFuture<'a, (Option<Vec<FunctionSchema>>,)> {
+    Box::new(async move { get_json_schemas_sync(store, params) })
+}
+
 fn component_implements<'a>(
     store: StoreContextMut<'a, HostEnv>,
     params: (String, String),
@@ -123,12 +159,80 @@ fn call_component_function<'a>(
     })
 }
 
+fn emit_output<'a>(store: StoreContextMut<'a, HostEnv>, (chunk,): (String,)) -> HostFuture<'a, ()> {
+    Box::new(async move {
+        // The call goes on if no one receives its output.
+        let _ = store
+            .data()
+            .component_output_tx
+            .send(OutputEvent::Chunk(chunk))
+            .await;
+        Ok(())
+    })
+}
+
 async fn call_component_function_inner(
     store: &mut StoreContextMut<'_, HostEnv>,
     component_name: &str,
     function_name_str: &str,
     args_json: &str,
 ) -> Result<String, CallError> {
+    let output_tx = store.data().component_output_tx.clone();
+    let [started, finished] = call_events(component_name, function_name_str);
+    let _ = output_tx.send(started).await;
+    let span = ActiveSpan::start_in(
+        store,
+        SpanKind::DynamicCall,
+        component_name,
+        function_name_str,
+    );
+    let result = spawn_dynamic_call(
+        store,
+        component_name,
+        function_name_str,
+        args_json,
+        span.context(),
+    )
+    .await;
+    span.end_call(&result);
+    let _ = output_tx.send(finished).await;
+    result
+}
+
+/// Output events of a call of `function_name` on `component_name`,
+/// sent before and after the call.
+fn call_events(component_name: &str, function_name: &str) -> [OutputEvent; 2] {
+    let component = component_name.to_owned();
+    let function = function_name.to_owned();
+    [
+        OutputEvent::CallStarted {
+            component: component.clone(),
+            function: function.clone(),
+        },
+        OutputEvent::CallFinished {
+            component,
+            function,
+        },
+    ]
+}
+
+async fn spawn_dynamic_call(
+    store: &mut StoreContextMut<'_, HostEnv>,
+    component_name: &str,
+    function_name_str: &str,
+    args_json: &str,
+    span: SpanContext,
+) -> Result<String, CallError> {
+    if let Some(mcp_component) = find_mcp_component(store, component_name) {
+        call_approvals(store)
+            .check(component_name, function_name_str, || {
+                Ok(args_json.to_owned())
+            })
+            .await
+            .map_err(not_approved)?;
+        return mcp_component.call(function_name_str, args_json).await;
+    }
+    let output_tx = store.data().component_output_tx.clone();
     let (compiled_components, env_vars, preopened_dirs, runtime_data) = {
         let rd = store.data().runtime_data.as_ref().ok_or(CallError {
             kind: CallErrorKind::InvocationFailed,
@@ -141,11 +245,13 @@ async fn call_component_function_inner(
             rd.clone(),
         )
     };
+    // The call runs in a fresh store, which handles of this store are not valid in.
     let (comp_id, function, inputs) = resolve_call(
         component_name,
         function_name_str,
         args_json,
         compiled_components.iter().map(|(b, _)| b),
+        &HandleTable::default(),
     )?;
     // Run on a blocking thread with a sync engine to avoid the nested
     // `run_concurrent` assertion. The sync engine's `Func::call` bypasses
@@ -160,16 +266,21 @@ async fn call_component_function_inner(
             env_vars,
             preopened_dirs,
             runtime_data,
+            span,
+            Some(output_tx),
         )
     })
     .await
     .map_err(|e| CallError {
         kind: CallErrorKind::InvocationFailed,
         message: format!("{e}"),
-    })?
+    })
+    .and_then(|result| result)
 }
 
-/// Runs on a blocking thread with a sync engine.
+/// Runs on a blocking thread with a sync engine, as a child of `span`,
+/// sending output events to `output_tx` if given.
+#[allow(clippy::too_many_arguments)]
 pub(super) fn execute_dynamic_call(
     compiled_components: Vec<(ComponentBinary, WasmtimeComponent)>,
     comp_id: ComponentId,
@@ -178,16 +289,26 @@ pub(super) fn execute_dynamic_call(
     env_vars: HashMap<String, String>,
     preopened_dirs: Vec<PathBuf>,
     runtime_data: HostEnvRuntimeData,
+    span: SpanContext,
+    output_tx: Option<mpsc::Sender<OutputEvent>>,
 ) -> Result<String, CallError> {
     let engine = &*SYNC_ENGINE;
+    let wiring = runtime_data.wiring.clone();
+    let mocks = runtime_data.mocks.clone();
     let mut store = create_fresh_store(engine, &env_vars, &preopened_dirs);
+    if let Some(output_tx) = output_tx {
+        store.data_mut().component_output_tx = output_tx;
+    }
     store.data_mut().runtime_data = Some(runtime_data);
+    store.data_mut().span = Some(span);
     let mut linker = create_sync_linker(engine).map_err(|e| CallError {
         kind: CallErrorKind::InvocationFailed,
         message: format!("failed to set up linker: {e}"),
     })?;
     let (all_instances, target) = instantiate_all_sync(
         &compiled_components,
+        &wiring,
+        &mocks,
         engine,
         &mut linker,
         &mut store,
@@ -202,22 +323,37 @@ pub(super) fn execute_dynamic_call(
             message: format!("failed to get function: {e}"),
         })?;
     let mut results = function.new_results_vec();
-    func.call(&mut store, &inputs, &mut results)
-        .map_err(|e| CallError {
-            kind: CallErrorKind::InvocationFailed,
-            message: format!("{e:#}"),
-        })?;
-    func.post_return(&mut store).map_err(|e| CallError {
+    call_intercepted(
+        store.as_context_mut(),
+        &function,
+        func,
+        &inputs,
+        &mut results,
+    )
+    .map_err(|e| CallError {
         kind: CallErrorKind::InvocationFailed,
         message: format!("{e:#}"),
     })?;
-    serialize_call_results(results)
+    // Handles would not outlive this store, so they cannot be returned.
+    let handles = HandleTable::default();
+    let output = serialize_call_results(results, &handles)?;
+    if !handles.is_empty() {
+        return Err(CallError {
+            kind: CallErrorKind::SerializationFailed,
+            message: "resources, futures and streams cannot be returned \
+                      to a component in another store"
+                .to_owned(),
+        });
+    }
+    Ok(output)
 }
 
 /// Compiles, instantiates, and links all components with the sync engine.
 /// Returns (all instances, target instance for `target_id`).
 pub(crate) fn instantiate_all_sync(
     compiled_components: &[(ComponentBinary, WasmtimeComponent)],
+    wiring: &InterfaceWiring,
+    mocks: &Mocks,
     engine: &wasmtime::Engine,
     linker: &mut Linker<HostEnv>,
     store: &mut wasmtime::Store<HostEnv>,
@@ -230,10 +366,11 @@ pub(crate) fn instantiate_all_sync(
     CallError,
 > {
     let binaries: Vec<_> = compiled_components.iter().map(|(b, _)| b.clone()).collect();
-    let stubs = register_component_stubs_sync(&binaries, linker).map_err(|e| CallError {
-        kind: CallErrorKind::InvocationFailed,
-        message: format!("failed to register stubs: {e}"),
-    })?;
+    let stubs =
+        register_component_stubs_sync(&binaries, wiring, mocks, linker).map_err(|e| CallError {
+            kind: CallErrorKind::InvocationFailed,
+            message: format!("failed to register stubs: {e}"),
+        })?;
     let mut all_instances = Vec::new();
     let mut target_instance = None;
     for (binary, _) in compiled_components {
@@ -243,7 +380,8 @@ pub(crate) fn instantiate_all_sync(
                 kind: CallErrorKind::InvocationFailed,
                 message: format!("failed to compile '{}': {e}", binary.component().id()),
             })?;
-        let instance = linker
+        let instance = stubs
+            .linker_for(&binary.component().id(), linker)
             .instantiate(&mut *store, &compiled)
             .map_err(|e| CallError {
                 kind: CallErrorKind::InvocationFailed,
@@ -307,6 +445,13 @@ fn get_component_sync(
     Ok((info,))
 }
 
+fn get_json_schemas_sync(
+    store: StoreContextMut<HostEnv>,
+    (name,): (String,),
+) -> wasmtime::Result<(Option<Vec<FunctionSchema>>,)> {
+    Ok((build_function_schemas(&store, &name),))
+}
+
 fn component_implements_sync(
     store: StoreContextMut<HostEnv>,
     (component_name, interface_name): (String, String),
@@ -331,6 +476,15 @@ fn call_component_function_sync(
     Ok((result,))
 }
 
+/// Runs on a blocking thread, so it can block on sending the output.
+fn emit_output_sync(store: StoreContextMut<HostEnv>, (chunk,): (String,)) -> wasmtime::Result<()> {
+    let _ = store
+        .data()
+        .component_output_tx
+        .blocking_send(OutputEvent::Chunk(chunk));
+    Ok(())
+}
+
 /// Sync implementation of `call-component-function` for the sync engine.
 /// Uses `Func::call` which is naturally reentrant in sync mode.
 fn call_component_function_sync_inner(
@@ -339,11 +493,50 @@ fn call_component_function_sync_inner(
     function_name_str: &str,
     args_json: &str,
 ) -> Result<String, CallError> {
+    let span = ActiveSpan::start_in(
+        store,
+        SpanKind::DynamicCall,
+        component_name,
+        function_name_str,
+    );
+    let output_tx = store.data().component_output_tx.clone();
+    let [started, finished] = call_events(component_name, function_name_str);
+    let _ = output_tx.blocking_send(started);
+    let result = in_span(store, span.context(), |store| {
+        call_sync_instance(store, component_name, function_name_str, args_json)
+    });
+    span.end_call(&result);
+    let _ = output_tx.blocking_send(finished);
+    result
+}
+
+/// Calls a function of an instance in the sync store.
+fn call_sync_instance(
+    store: &mut StoreContextMut<'_, HostEnv>,
+    component_name: &str,
+    function_name_str: &str,
+    args_json: &str,
+) -> Result<String, CallError> {
+    if let Some(mcp_component) = find_mcp_component(store, component_name) {
+        // This runs on a blocking thread of the runtime, so it can block on the call.
+        call_approvals(store)
+            .check_blocking(component_name, function_name_str, || {
+                Ok(args_json.to_owned())
+            })
+            .map_err(not_approved)?;
+        let handle = tokio::runtime::Handle::try_current().map_err(|e| CallError {
+            kind: CallErrorKind::InvocationFailed,
+            message: format!("no async runtime to call MCP server: {e}"),
+        })?;
+        return handle.block_on(mcp_component.call(function_name_str, args_json));
+    }
+    let handles = store.data().handles.clone();
     let (comp_id, function, inputs) = resolve_call(
         component_name,
         function_name_str,
         args_json,
         store.data().sync_instances.iter().map(|(b, _)| b),
+        &handles,
     )?;
     let (_, instance) = store
         .data()
@@ -362,16 +555,18 @@ fn call_component_function_sync_inner(
             message: format!("failed to get function: {e}"),
         })?;
     let mut results = function.new_results_vec();
-    func.call(&mut *store, &inputs, &mut results)
-        .map_err(|e| CallError {
-            kind: CallErrorKind::InvocationFailed,
-            message: format!("{e:#}"),
-        })?;
-    func.post_return(&mut *store).map_err(|e| CallError {
+    call_intercepted(
+        store.as_context_mut(),
+        &function,
+        func,
+        &inputs,
+        &mut results,
+    )
+    .map_err(|e| CallError {
         kind: CallErrorKind::InvocationFailed,
         message: format!("{e:#}"),
     })?;
-    serialize_call_results(results)
+    serialize_call_results(results, &handles)
 }
 
 /// Resolves a component call: parses the target, finds the function,
@@ -381,6 +576,7 @@ pub(super) fn resolve_call<'a>(
     function_name_str: &str,
     args_json: &str,
     mut binaries: impl Iterator<Item = &'a ComponentBinary>,
+    handles: &HandleTable,
 ) -> Result<(ComponentId, ComponentFunctionInterface, Vec<Val>), CallError> {
     let comp_id = ComponentId::from_str(component_name).map_err(|e| CallError {
         kind: CallErrorKind::ComponentNotFound,
@@ -405,7 +601,7 @@ pub(super) fn resolve_call<'a>(
             ),
         })?;
     let resolve = binary.wit().resolve().clone();
-    let inputs = parse_call_args(args_json, &function, &resolve)?;
+    let inputs = parse_call_args(args_json, &function, &resolve, handles)?;
     Ok((comp_id, function, inputs))
 }
 
@@ -415,38 +611,33 @@ pub(super) fn parse_call_args(
     args_json: &str,
     function: &ComponentFunctionInterface,
     resolve: &wit_parser::Resolve,
+    handles: &HandleTable,
 ) -> Result<Vec<Val>, CallError> {
+    function
+        .check_is_json_callable(resolve)
+        .map_err(|e| CallError {
+            kind: CallErrorKind::InvalidArgs,
+            message: format!("{e:#}"),
+        })?;
     let json_args: Vec<serde_json::Value> =
         serde_json::from_str(args_json).map_err(|e| CallError {
             kind: CallErrorKind::InvalidArgs,
             message: format!("invalid JSON args: {e}"),
         })?;
-    if json_args.len() != function.inputs.len() {
-        return Err(CallError {
-            kind: CallErrorKind::InvalidArgs,
-            message: format!(
-                "expected {} arg(s), got {}",
-                function.inputs.len(),
-                json_args.len()
-            ),
-        });
-    }
-    json_args
-        .iter()
-        .zip(function.inputs.iter())
-        .map(|(arg, (_name, type_def))| json_value_to_val_typedef(arg, type_def, resolve))
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| CallError {
-            kind: CallErrorKind::InvalidArgs,
-            message: format!("failed to convert args: {e}"),
-        })
+    json_args_to_vals(&json_args, &function.inputs, resolve, handles).map_err(|e| CallError {
+        kind: CallErrorKind::InvalidArgs,
+        message: format!("{e:#}"),
+    })
 }
 
 /// Serializes call results to a JSON string.
-pub(super) fn serialize_call_results(results: Vec<Val>) -> Result<String, CallError> {
+pub(super) fn serialize_call_results(
+    results: Vec<Val>,
+    handles: &HandleTable,
+) -> Result<String, CallError> {
     let output_val = results.into_iter().next();
     let json_output = output_val
-        .and_then(|v| v.try_into_json_value())
+        .map(|v| v.into_json_value(handles))
         .unwrap_or(serde_json::Value::Null);
     serde_json::to_string(&json_output).map_err(|e| CallError {
         kind: CallErrorKind::SerializationFailed,
@@ -458,6 +649,10 @@ fn build_all_component_infos(store: &StoreContextMut<HostEnv>) -> Vec<ComponentI
     let Some(runtime_data) = store.data().runtime_data.as_ref() else {
         return Vec::new();
     };
+    let mcp_infos = runtime_data
+        .mcp_components
+        .iter()
+        .map(|c| c.component_info());
     runtime_data
         .instances
         .iter()
@@ -477,7 +672,7 @@ fn build_all_component_infos(store: &StoreContextMut<HostEnv>) -> Vec<ComponentI
             let functions = exported
                 .iter()
                 .flat_map(|iface| {
-                    let short_iface_name = extract_short_interface_name(&iface.name);
+                    let short_iface_name = short_interface_name(&iface.name);
                     iface.functions.iter().map(move |f| FunctionInfo {
                         name: f.name.clone(),
                         interface_name: Some(short_iface_name.clone()),
@@ -506,9 +701,41 @@ fn build_all_component_infos(store: &StoreContextMut<HostEnv>) -> Vec<ComponentI
                 functions,
             }
         })
+        .chain(mcp_infos)
         .collect()
 }
 
+/// Builds the JSON Schemas of the functions of the component `name`,
+/// in the order of its functions in [`build_all_component_infos`].
+fn build_function_schemas(
+    store: &StoreContextMut<HostEnv>,
+    name: &str,
+) -> Option<Vec<FunctionSchema>> {
+    let runtime_data = store.data().runtime_data.as_ref()?;
+    let instance = runtime_data
+        .instances
+        .iter()
+        .find(|i| i.component_interface.component().id().to_string() == name);
+    let Some(instance) = instance else {
+        return find_mcp_component(store, name).map(|c| c.function_schemas());
+    };
+    let schemas = instance
+        .component_interface
+        .exported_interfaces()
+        .iter()
+        .flat_map(|iface| {
+            let short_iface_name = short_interface_name(&iface.name);
+            iface.functions.iter().map(move |f| FunctionSchema {
+                name: f.name.clone(),
+                interface_name: Some(short_iface_name.clone()),
+                inputs: f.params.iter().map(|p| p.json_schema.to_string()).collect(),
+                output: f.return_json_schema.as_ref().map(|s| s.to_string()),
+            })
+        })
+        .collect();
+    Some(schemas)
+}
+
 fn build_component_description(
     world_docs: Option<String>,
     exported: &[crate::component::wit::ExportedInterface],
@@ -519,7 +746,7 @@ fn build_component_description(
     }
     for iface in exported {
         if let Some(docs) = &iface.docs {
-            let name = extract_short_interface_name(&iface.name);
+            let name = short_interface_name(&iface.name);
             parts.push(format!("{name}: {}", docs.trim()));
         }
     }
@@ -529,14 +756,34 @@ fn build_component_description(
     }
 }
 
-/// Extract the short interface name from a fully qualified name.
-/// e.g. "asterai:llm/llm@1.0.0" => "llm".
-fn extract_short_interface_name(fq_name: &str) -> String {
-    let name = fq_name.rsplit_once('/').map(|(_, n)| n).unwrap_or(fq_name);
-    name.split_once('@')
-        .map(|(n, _)| n)
-        .unwrap_or(name)
-        .to_owned()
+/// Finds the MCP server served as the component `component_name`, if any.
+fn find_mcp_component(
+    store: &StoreContextMut<HostEnv>,
+    component_name: &str,
+) -> Option<Arc<McpComponent>> {
+    let runtime_data = store.data().runtime_data.as_ref()?;
+    runtime_data
+        .mcp_components
+        .iter()
+        .find(|c| c.id().to_string() == component_name)
+        .cloned()
+}
+
+/// The approval policies of calls made from the store.
+fn call_approvals(store: &StoreContextMut<HostEnv>) -> CallApprovals {
+    store
+        .data()
+        .runtime_data
+        .as_ref()
+        .map(|rd| rd.call_approvals.clone())
+        .unwrap_or_default()
+}
+
+fn not_approved(e: eyre::Report) -> CallError {
+    CallError {
+        kind: CallErrorKind::InvocationFailed,
+        message: format!("{e:#}"),
+    }
 }
 
 fn get_last_component_id(store: &StoreContextMut<HostEnv>) -> Option<String> {
@@ -1,9 +1,17 @@
 use crate::component::Component;
 use crate::component::binary::{ComponentBinary, WasmtimeComponent};
+use crate::environment::interceptors::Interceptors;
+use crate::environment::mocks::Mocks;
+use crate::environment::wiring::InterfaceWiring;
+use crate::runtime::approval::CallApprovals;
+use crate::runtime::call_trace::SpanContext;
 use crate::runtime::cron::CronManager;
 use crate::runtime::cron_entry::{add_asterai_cron_to_linker, add_asterai_cron_to_sync_linker};
 use crate::runtime::entry::{add_asterai_host_to_linker, add_asterai_host_to_sync_linker};
-use crate::runtime::output::ComponentOutput;
+use crate::runtime::handles::HandleTable;
+use crate::runtime::mcp_client::McpComponent;
+use crate::runtime::metrics::StoreMemory;
+use crate::runtime::output::OutputEvent;
 use crate::runtime::std_out_err::{ComponentStderr, ComponentStdout};
 use crate::runtime::wasm_instance::ComponentRuntimeInstance;
 use crate::runtime::ws::WsManager;
@@ -31,10 +39,21 @@ pub struct HostEnv {
     pub wasi_ctx: WasiCtx,
     pub http_ctx: WasiHttpCtx,
     pub runtime_data: Option<HostEnvRuntimeData>,
-    pub component_output_tx: mpsc::Sender<ComponentOutput>,
+    pub component_output_tx: mpsc::Sender<OutputEvent>,
     /// Instances in the sync engine context for dynamic calls.
     /// Populated by `execute_dynamic_call` before calling the target.
     pub sync_instances: Vec<(ComponentBinary, wasmtime::component::Instance)>,
+    /// Whether an interceptor hook is running in this store,
+    /// in which case calls between components are not intercepted.
+    pub is_intercepting: bool,
+    /// The span of the call this store is running, if any.
+    pub span: Option<SpanContext>,
+    /// Linear memory allocated by this store, for metrics.
+    pub memory: StoreMemory,
+    /// Values of handles passed to and returned by JSON calls between
+    /// components in this store, which is a store of the sync engine
+    /// living for one call, see [`handles`](crate::runtime::handles).
+    pub handles: HandleTable,
 }
 
 #[derive(Clone)]
@@ -56,6 +75,20 @@ pub struct HostEnvRuntimeData {
     pub ws_manager: Option<Arc<WsManager>>,
     /// Shared cron schedule manager.
     pub cron_manager: Option<Arc<CronManager>>,
+    /// Explicit interface wiring, applied when linking components
+    /// in fresh stores for dynamic calls.
+    pub wiring: InterfaceWiring,
+    /// Interceptors for calls between components,
+    /// applied in stores of the sync engine.
+    pub interceptors: Interceptors,
+    /// Mock providers of imported interfaces, linked in place
+    /// of components in stores of both engines.
+    pub mocks: Mocks,
+    /// MCP servers served as virtual components.
+    pub mcp_components: Vec<Arc<McpComponent>>,
+    /// Approval policies of calls between components,
+    /// shared with stores of both engines.
+    pub call_approvals: CallApprovals,
 }
 
 /// Create a Store with an externally provided app ID and output channel.
@@ -64,7 +97,7 @@ pub fn create_store(
     env_vars: &HashMap<String, String>,
     preopened_dirs: &[PathBuf],
     app_id: Uuid,
-    component_output_tx: mpsc::Sender<ComponentOutput>,
+    component_output_tx: mpsc::Sender<OutputEvent>,
 ) -> Store<HostEnv> {
     let mut wasi_ctx = WasiCtxBuilder::new();
     wasi_ctx
@@ -107,8 +140,14 @@ pub fn create_store(
         table: ResourceTable::new(),
         component_output_tx,
         sync_instances: Vec::new(),
+        is_intercepting: false,
+        span: None,
+        memory: StoreMemory::default(),
+        handles: HandleTable::default(),
     };
-    Store::new(engine, host_env)
+    let mut store = Store::new(engine, host_env);
+    store.limiter(|host_env| &mut host_env.memory);
+    store
 }
 
 /// Create a disposable Store with a new app ID and a drain output channel.
@@ -0,0 +1,86 @@
+//! Host table of values without a JSON encoding.
+//!
+//! Resources and error contexts only have meaning within the store they
+//! were created in, so they cross the JSON boundary as `{"handle": <id>}`,
+//! where the ID refers to the value in a table of the caller. A handle
+//! returned by one call can then be passed to later calls of the same
+//! caller in the same store, e.g. a resource returned by a constructor
+//! to functions that take it as `borrow` or `own`.
+//!
+//! Each caller has its own table, so that handles are only valid for
+//! the caller they were returned to: a session of the call API of
+//! `env run`, a client of `env mcp`, or a component making calls in a
+//! store of the sync engine.
+//!
+//! Passing a handle as `own` moves the value into the callee, which
+//! removes it from the table. Callers release handles they no longer
+//! need, which drops owned resources, see [`ComponentRuntime::release_handles`](crate::runtime::ComponentRuntime::release_handles).
+//!
+//! Futures and streams are not supported: wasmtime has no operations
+//! on dynamically typed futures and streams yet, so the host can
+//! neither read, write nor drop them. Functions taking or returning
+//! them are rejected before they are called with JSON, see
+//! [`ComponentFunctionInterface::check_is_json_callable`](crate::component::function_interface::ComponentFunctionInterface::check_is_json_callable),
+//! and are left out of MCP tools and the OpenAPI document.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use wasmtime::component::Val;
+
+/// Handles of a store, shared by every call in it.
+#[derive(Clone, Default)]
+pub struct HandleTable {
+    inner: Arc<Mutex<HandleTableInner>>,
+}
+
+#[derive(Default)]
+struct HandleTableInner {
+    last_id: u64,
+    values: HashMap<u64, Val>,
+}
+
+impl HandleTable {
+    /// Holds a value, returning its handle ID.
+    pub fn insert(&self, val: Val) -> u64 {
+        let mut inner = self.inner.lock().unwrap();
+        inner.last_id += 1;
+        let id = inner.last_id;
+        inner.values.insert(id, val);
+        id
+    }
+
+    pub fn get(&self, id: u64) -> Option<Val> {
+        self.inner.lock().unwrap().values.get(&id).cloned()
+    }
+
+    pub fn remove(&self, id: u64) -> Option<Val> {
+        self.inner.lock().unwrap().values.remove(&id)
+    }
+
+    /// Removes all values, returning them.
+    pub fn drain(&self) -> Vec<Val> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.values.drain().map(|(_, val)| val).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.lock().unwrap().values.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ids_are_not_reused() {
+        let handles = HandleTable::default();
+        let first = handles.insert(Val::Bool(true));
+        assert_eq!(handles.remove(first), Some(Val::Bool(true)));
+        let second = handles.insert(Val::Bool(false));
+        assert_ne!(first, second);
+        assert!(handles.get(first).is_none());
+        assert_eq!(handles.get(second), Some(Val::Bool(false)));
+        assert_eq!(handles.drain(), vec![Val::Bool(false)]);
+        assert!(handles.is_empty());
+    }
+}
@@ -1,5 +1,7 @@
 use crate::component::Component;
+use crate::runtime::call_trace::{ActiveSpan, SpanKind};
 use crate::runtime::env::{HostEnv, HostEnvRuntimeData, create_fresh_store};
+use crate::runtime::metrics;
 use crate::runtime::wasm_instance::ENGINE;
 use bytes::Bytes;
 use eyre::eyre;
@@ -72,10 +74,18 @@ pub async fn handle_http_request<B>(
 where
     B: Body<Data = Bytes, Error = hyper::Error> + Send + 'static,
 {
+    let method = req.method().to_string();
+    let span = ActiveSpan::start(
+        SpanKind::Http,
+        route.component.id(),
+        format!("{method} {}", req.uri().path()),
+        None,
+    );
     let engine = &*ENGINE;
     let mut store =
         create_fresh_store(engine, &runtime_data.env_vars, &runtime_data.preopened_dirs);
     store.data_mut().runtime_data = Some(runtime_data.clone());
+    store.data_mut().span = Some(span.context());
     let (sender, receiver) = tokio::sync::oneshot::channel();
     let req = store
         .data_mut()
@@ -94,20 +104,20 @@ where
             .await?;
         Ok::<(), anyhow::Error>(())
     });
-    match receiver.await {
+    let result = match receiver.await {
         Ok(Ok(resp)) => Ok(resp),
         Ok(Err(e)) => Err(eyre!("{e:?}")),
-        Err(_) => {
-            let e = match task.await {
-                Ok(Ok(())) => {
-                    return Err(eyre!("guest never invoked response-outparam::set"));
-                }
-                Ok(Err(e)) => e,
-                Err(e) => e.into(),
-            };
-            Err(eyre!(e).wrap_err("guest never invoked response-outparam::set"))
-        }
-    }
+        Err(_) => match task.await {
+            Ok(Ok(())) => Err(eyre!("guest never invoked response-outparam::set")),
+            Ok(Err(e)) => Err(eyre!(e).wrap_err("guest never invoked response-outparam::set")),
+            Err(e) => Err(eyre!(anyhow::Error::from(e))
+                .wrap_err("guest never invoked response-outparam::set")),
+        },
+    };
+    span.end(&result);
+    let status = result.as_ref().ok().map(|resp| resp.status().as_u16());
+    metrics::record_http_request(&route.component.id().to_string(), &method, status);
+    result
 }
 
 pub fn strip_path_prefix(
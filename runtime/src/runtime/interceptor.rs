@@ -0,0 +1,323 @@
+//! Runs interceptor hooks around calls between components.
+//!
+//! Interceptors are called on the sync engine, in the same store
+//! as the intercepted call. Calls made by an interceptor while one
+//! of its hooks runs are not intercepted, so that an interceptor can
+//! call the interface it intercepts without recursing into itself.
+//!
+//! Hooks see resources, futures and streams as handles of a table
+//! local to the intercepted call, so that they can be passed through.
+use crate::component::ComponentId;
+use crate::component::function_interface::ComponentFunctionInterface;
+use crate::component::wit::ComponentInterface;
+use crate::environment::interceptors::INTERCEPTOR_INTERFACE;
+use crate::environment::wiring::unversioned_interface_name;
+use crate::runtime::entry::parse_call_args;
+use crate::runtime::env::HostEnv;
+use crate::runtime::handles::HandleTable;
+use crate::runtime::parsing::{ValExt, json_value_to_val_typedef};
+use eyre::{bail, eyre};
+use serde_json::Value;
+use wasmtime::StoreContextMut;
+use wasmtime::component::{ComponentType, Func, Instance, Lift, Lower, TypedFunc, Val};
+
+#[derive(ComponentType, Lower, Clone)]
+#[component(record)]
+struct CallInfo {
+    #[component(name = "component-name")]
+    component_name: String,
+    #[component(name = "interface-name")]
+    interface_name: String,
+    #[component(name = "function-name")]
+    function_name: String,
+}
+
+#[derive(ComponentType, Lift)]
+#[component(variant)]
+enum BeforeAction {
+    #[component(name = "proceed")]
+    Proceed(String),
+    #[component(name = "respond")]
+    Respond(String),
+    #[component(name = "reject")]
+    Reject(String),
+}
+
+/// An interceptor instance in the sync store.
+struct Interceptor {
+    id: ComponentId,
+    instance: Instance,
+    /// Versioned export name of the interceptor interface.
+    export_name: String,
+}
+
+/// Calls `func`, the export `function` of an instance in the sync
+/// store, through the interceptors configured for its interface,
+/// once its approval policy lets it proceed.
+pub(crate) fn call_intercepted(
+    mut store: StoreContextMut<'_, HostEnv>,
+    function: &ComponentFunctionInterface,
+    func: Func,
+    params: &[Val],
+    results: &mut [Val],
+) -> eyre::Result<()> {
+    if let Some(rd) = store.data().runtime_data.as_ref() {
+        rd.call_approvals.check_blocking(
+            &function.component.id().to_string(),
+            &function.name.to_string(),
+            || args_to_json(params),
+        )?;
+    }
+    let Some(interface_name) = function.get_instance_export_name() else {
+        return call(&mut store, func, params, results);
+    };
+    let chain = find_interceptors(&store, function, &interface_name);
+    if chain.is_empty() {
+        return call(&mut store, func, params, results);
+    }
+    let call_info = CallInfo {
+        component_name: function.component.id().to_string(),
+        interface_name,
+        function_name: function.name.name.clone(),
+    };
+    let handles = HandleTable::default();
+    let original_args_json = encode_json(
+        params
+            .iter()
+            .map(|v| v.clone().into_json_value(&handles))
+            .collect(),
+    )?;
+    let mut args_json = original_args_json.clone();
+    let mut responded = None;
+    let mut entered = 0;
+    for interceptor in &chain {
+        entered += 1;
+        match before_call(&mut store, interceptor, &call_info, &args_json)? {
+            BeforeAction::Proceed(args) => args_json = args,
+            BeforeAction::Respond(result) => {
+                responded = Some(result);
+                break;
+            }
+            BeforeAction::Reject(message) => {
+                bail!("call rejected by interceptor {}: {message}", interceptor.id)
+            }
+        }
+    }
+    let produced_result_json = match &responded {
+        Some(_) => None,
+        None => {
+            let args = match args_json == original_args_json {
+                true => params.to_vec(),
+                false => decode_args(&store, function, &args_json, &handles)?,
+            };
+            call(&mut store, func, &args, results)?;
+            let result = results
+                .first()
+                .map(|v| v.clone().into_json_value(&handles))
+                .unwrap_or(Value::Null);
+            Some(encode_json(result)?)
+        }
+    };
+    let mut result_json = responded
+        .clone()
+        .or_else(|| produced_result_json.clone())
+        .unwrap_or_default();
+    for interceptor in chain[..entered].iter().rev() {
+        result_json = after_call(
+            &mut store,
+            interceptor,
+            &call_info,
+            &args_json,
+            &result_json,
+        )?
+        .map_err(|message| eyre!("call failed in interceptor {}: {message}", interceptor.id))?;
+    }
+    if produced_result_json.as_ref() != Some(&result_json) {
+        decode_result(&store, function, &result_json, results, &handles)?;
+    }
+    Ok(())
+}
+
+fn call(
+    store: &mut StoreContextMut<'_, HostEnv>,
+    func: Func,
+    params: &[Val],
+    results: &mut [Val],
+) -> eyre::Result<()> {
+    func.call(&mut *store, params, results)
+        .map_err(|e| eyre!("{e:#}"))?;
+    func.post_return(&mut *store).map_err(|e| eyre!("{e:#}"))?;
+    Ok(())
+}
+
+/// Returns the interceptors for `interface_name`, unless the call is
+/// made from within an interceptor hook.
+fn find_interceptors(
+    store: &StoreContextMut<'_, HostEnv>,
+    function: &ComponentFunctionInterface,
+    interface_name: &str,
+) -> Vec<Interceptor> {
+    let data = store.data();
+    if data.is_intercepting {
+        return Vec::new();
+    }
+    let Some(runtime_data) = data.runtime_data.as_ref() else {
+        return Vec::new();
+    };
+    let target_id = function.component.id();
+    runtime_data
+        .interceptors
+        .for_interface(interface_name)
+        .iter()
+        .filter(|id| **id != target_id)
+        .filter_map(|id| {
+            let (binary, instance) = data
+                .sync_instances
+                .iter()
+                .find(|(b, _)| b.component().id() == *id)?;
+            let export_name = binary
+                .exported_interfaces()
+                .into_iter()
+                .find(|e| unversioned_interface_name(&e.name) == INTERCEPTOR_INTERFACE)?
+                .name;
+            Some(Interceptor {
+                id: id.clone(),
+                instance: *instance,
+                export_name,
+            })
+        })
+        .collect()
+}
+
+fn before_call(
+    store: &mut StoreContextMut<'_, HostEnv>,
+    interceptor: &Interceptor,
+    call_info: &CallInfo,
+    args_json: &str,
+) -> eyre::Result<BeforeAction> {
+    let func: TypedFunc<(CallInfo, String), (BeforeAction,)> =
+        get_hook(store, interceptor, "before-call")?;
+    let params = (call_info.clone(), args_json.to_owned());
+    let (action,) = call_hook(store, interceptor, func, params)?;
+    Ok(action)
+}
+
+fn after_call(
+    store: &mut StoreContextMut<'_, HostEnv>,
+    interceptor: &Interceptor,
+    call_info: &CallInfo,
+    args_json: &str,
+    result_json: &str,
+) -> eyre::Result<Result<String, String>> {
+    let func: TypedFunc<(CallInfo, String, String), (Result<String, String>,)> =
+        get_hook(store, interceptor, "after-call")?;
+    let params = (
+        call_info.clone(),
+        args_json.to_owned(),
+        result_json.to_owned(),
+    );
+    let (result,) = call_hook(store, interceptor, func, params)?;
+    Ok(result)
+}
+
+/// Calls a hook with interception disabled for nested calls.
+fn call_hook<Params, Results>(
+    store: &mut StoreContextMut<'_, HostEnv>,
+    interceptor: &Interceptor,
+    func: TypedFunc<Params, Results>,
+    params: Params,
+) -> eyre::Result<Results>
+where
+    Params: wasmtime::component::ComponentNamedList + Lower,
+    Results: wasmtime::component::ComponentNamedList + Lift,
+{
+    store.data_mut().is_intercepting = true;
+    let result = func
+        .call(&mut *store, params)
+        .and_then(|results| func.post_return(&mut *store).map(|_| results));
+    store.data_mut().is_intercepting = false;
+    result.map_err(|e| eyre!("interceptor {} failed: {e:#}", interceptor.id))
+}
+
+fn get_hook<Params, Results>(
+    store: &mut StoreContextMut<'_, HostEnv>,
+    interceptor: &Interceptor,
+    func_name: &str,
+) -> eyre::Result<TypedFunc<Params, Results>>
+where
+    Params: wasmtime::component::ComponentNamedList + Lower,
+    Results: wasmtime::component::ComponentNamedList + Lift,
+{
+    let instance = interceptor.instance;
+    let export_name = &interceptor.export_name;
+    let (_, iface_export) = instance
+        .get_export(&mut *store, None, export_name)
+        .ok_or_else(|| eyre!("export '{export_name}' not found"))?;
+    let (_, func_export) = instance
+        .get_export(&mut *store, Some(&iface_export), func_name)
+        .ok_or_else(|| eyre!("function '{func_name}' not found in '{export_name}'"))?;
+    instance
+        .get_typed_func::<Params, Results>(&mut *store, &func_export)
+        .map_err(|e| eyre!("failed to get typed func '{func_name}': {e:#}"))
+}
+
+/// Encodes args as a JSON array for approval, with handles
+/// local to the encoding as they are only displayed.
+fn args_to_json(params: &[Val]) -> eyre::Result<String> {
+    let handles = HandleTable::default();
+    encode_json(
+        params
+            .iter()
+            .map(|v| v.clone().into_json_value(&handles))
+            .collect(),
+    )
+}
+
+fn encode_json(value: Value) -> eyre::Result<String> {
+    serde_json::to_string(&value).map_err(|e| eyre!(e))
+}
+
+/// Converts args modified by an interceptor back into `Val`s.
+fn decode_args(
+    store: &StoreContextMut<'_, HostEnv>,
+    function: &ComponentFunctionInterface,
+    args_json: &str,
+    handles: &HandleTable,
+) -> eyre::Result<Vec<Val>> {
+    let resolve = target_resolve(store, function)?;
+    parse_call_args(args_json, function, &resolve, handles)
+        .map_err(|e| eyre!("interceptor returned invalid args: {}", e.message))
+}
+
+/// Converts a result returned by an interceptor back into `results`.
+fn decode_result(
+    store: &StoreContextMut<'_, HostEnv>,
+    function: &ComponentFunctionInterface,
+    result_json: &str,
+    results: &mut [Val],
+    handles: &HandleTable,
+) -> eyre::Result<()> {
+    let (Some(type_def), Some(slot)) = (&function.output_type, results.first_mut()) else {
+        return Ok(());
+    };
+    let resolve = target_resolve(store, function)?;
+    let value: Value = serde_json::from_str(result_json)
+        .map_err(|e| eyre!("interceptor returned invalid JSON result: {e}"))?;
+    *slot = json_value_to_val_typedef(&value, type_def, &resolve, handles)
+        .map_err(|e| eyre!("interceptor returned invalid result: {e:#}"))?;
+    Ok(())
+}
+
+fn target_resolve(
+    store: &StoreContextMut<'_, HostEnv>,
+    function: &ComponentFunctionInterface,
+) -> eyre::Result<wit_parser::Resolve> {
+    let target_id = function.component.id();
+    let (binary, _) = store
+        .data()
+        .sync_instances
+        .iter()
+        .find(|(b, _)| b.component().id() == target_id)
+        .ok_or_else(|| eyre!("component '{target_id}' not found"))?;
+    Ok(binary.wit().resolve().clone())
+}
@@ -1,13 +1,18 @@
 //! Pre-registers forwarding stubs in the linker for component-to-component
 //! exports, allowing instantiation in any order (including cycles).
-use crate::component::Component;
 use crate::component::binary::ComponentBinary;
 use crate::component::function_interface::ComponentFunctionInterface;
+use crate::component::{Component, ComponentId};
+use crate::environment::mocks::Mocks;
+use crate::environment::wiring::{InterfaceWiring, unversioned_interface_name};
+use crate::runtime::call_trace::{ActiveSpan, SpanContext, SpanKind, in_span};
 use crate::runtime::entry::instantiate_all_sync;
 use crate::runtime::env::{HostEnv, create_fresh_store, create_sync_linker};
+use crate::runtime::interceptor::call_intercepted;
+use crate::runtime::link_components::mock::{register_mocks, register_mocks_sync};
 use crate::runtime::wasm_instance::SYNC_ENGINE;
 use eyre::eyre;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::sync::OnceLock;
 use wasmtime::AsContextMut;
@@ -22,67 +27,102 @@ struct ResolvedFunc {
 
 type FuncSlot = Arc<OnceLock<ResolvedFunc>>;
 
-/// Key for a function slot: (instance_export_name, function_name).
-type SlotKey = (String, String);
+/// Key for a function slot: (provider, instance_export_name, function_name).
+type SlotKey = (ComponentId, String, String);
 
 /// Opaque handle returned by [`register_component_stubs`],
 /// passed to [`resolve_component_stubs`] after each instantiation.
 pub struct ComponentStubs {
     slots: HashMap<SlotKey, FuncSlot>,
+    /// Linkers for consumers with explicitly wired imports.
+    consumer_linkers: HashMap<ComponentId, Linker<HostEnv>>,
+}
+
+impl ComponentStubs {
+    /// Returns the linker to instantiate `consumer` with:
+    /// its own linker if it has wired imports, otherwise `default`.
+    pub fn linker_for<'a>(
+        &'a self,
+        consumer: &ComponentId,
+        default: &'a Linker<HostEnv>,
+    ) -> &'a Linker<HostEnv> {
+        self.consumer_linkers.get(consumer).unwrap_or(default)
+    }
 }
 
 /// Pre-registers forwarding stubs in the linker for every function
 /// exported by any component. Each stub delegates to the sync engine
 /// on a blocking thread to avoid the nested `run_concurrent` assertion
 /// that occurs when `call_async` is used inside an active guest thread.
+/// Imports of mocked interfaces are linked to their mocks instead.
 pub fn register_component_stubs(
     components: &[ComponentBinary],
+    wiring: &InterfaceWiring,
+    mocks: &Mocks,
     linker: &mut Linker<HostEnv>,
 ) -> eyre::Result<ComponentStubs> {
-    for_each_stub(components, linker, |inst_builder, f, slot| {
-        inst_builder
-            .func_new_async(&f.name.name, move |store, _, params, results| {
-                let slot = slot.clone();
-                Box::new(async move {
-                    let resolved = slot
-                        .get()
-                        .ok_or_else(|| wasmtime::Error::msg("unresolved component function"))?;
-                    let rd = store
-                        .data()
-                        .runtime_data
-                        .as_ref()
-                        .ok_or_else(|| wasmtime::Error::msg("runtime not initialized"))?;
-                    let compiled_components = rd.compiled_components.clone();
-                    let env_vars = rd.env_vars.clone();
-                    let preopened_dirs = rd.preopened_dirs.clone();
-                    let runtime_data = rd.clone();
-                    let comp_id = resolved.component.id().clone();
-                    let function = resolved.function_info.clone();
-                    let inputs: Vec<Val> = params.to_vec();
-                    let sync_results = tokio::task::spawn_blocking(move || {
-                        execute_stub_call(
-                            &compiled_components,
+    register_mocks(components, mocks, linker)?;
+    for_each_stub(
+        components,
+        wiring,
+        mocks,
+        linker,
+        |inst_builder, f, slot| {
+            inst_builder
+                .func_new_async(&f.name.name, move |mut store, _, params, results| {
+                    let slot = slot.clone();
+                    Box::new(async move {
+                        let resolved = slot
+                            .get()
+                            .ok_or_else(|| wasmtime::Error::msg("unresolved component function"))?;
+                        let rd = store
+                            .data()
+                            .runtime_data
+                            .as_ref()
+                            .ok_or_else(|| wasmtime::Error::msg("runtime not initialized"))?;
+                        let compiled_components = rd.compiled_components.clone();
+                        let env_vars = rd.env_vars.clone();
+                        let preopened_dirs = rd.preopened_dirs.clone();
+                        let runtime_data = rd.clone();
+                        let comp_id = resolved.component.id().clone();
+                        let function = resolved.function_info.clone();
+                        let inputs: Vec<Val> = params.to_vec();
+                        let span = ActiveSpan::start_in(
+                            &mut store,
+                            SpanKind::Stub,
                             &comp_id,
-                            &function,
-                            &inputs,
-                            &env_vars,
-                            &preopened_dirs,
-                            runtime_data,
-                        )
-                    })
-                    .await
-                    .map_err(|e| wasmtime::Error::msg(format!("{e}")))?
-                    .map_err(|e| wasmtime::Error::msg(format!("{e:#}")))?;
-                    for (i, val) in sync_results.into_iter().enumerate() {
-                        if i < results.len() {
-                            results[i] = val;
+                            &function.name,
+                        );
+                        let span_context = span.context();
+                        let result = tokio::task::spawn_blocking(move || {
+                            execute_stub_call(
+                                &compiled_components,
+                                &comp_id,
+                                &function,
+                                &inputs,
+                                &env_vars,
+                                &preopened_dirs,
+                                runtime_data,
+                                span_context,
+                            )
+                        })
+                        .await
+                        .map_err(|e| eyre!("{e}"))
+                        .and_then(|result| result);
+                        span.end(&result);
+                        let sync_results =
+                            result.map_err(|e| wasmtime::Error::msg(format!("{e:#}")))?;
+                        for (i, val) in sync_results.into_iter().enumerate() {
+                            if i < results.len() {
+                                results[i] = val;
+                            }
                         }
-                    }
-                    Ok(())
+                        Ok(())
+                    })
                 })
-            })
-            .map_err(|e| eyre!("{e:#?}"))
-    })
+                .map_err(|e| eyre!("{e:#?}"))
+        },
+    )
 }
 
 /// Sync variant of [`register_component_stubs`] for the sync engine.
@@ -90,27 +130,57 @@ pub fn register_component_stubs(
 /// the nested `run_concurrent` assertion.
 pub fn register_component_stubs_sync(
     components: &[ComponentBinary],
+    wiring: &InterfaceWiring,
+    mocks: &Mocks,
     linker: &mut Linker<HostEnv>,
 ) -> eyre::Result<ComponentStubs> {
-    for_each_stub(components, linker, |inst_builder, f, slot| {
-        inst_builder
-            .func_new(&f.name.name, move |mut store, _, params, results| {
-                let resolved = slot
-                    .get()
-                    .ok_or_else(|| wasmtime::Error::msg("unresolved component function"))?;
-                resolved.func.call(&mut store, params, results)?;
-                resolved.func.post_return(&mut store)?;
-                Ok(())
-            })
-            .map_err(|e| eyre!("{e:#?}"))
-    })
+    register_mocks_sync(components, mocks, linker)?;
+    for_each_stub(
+        components,
+        wiring,
+        mocks,
+        linker,
+        |inst_builder, f, slot| {
+            inst_builder
+                .func_new(&f.name.name, move |mut store, _, params, results| {
+                    let resolved = slot
+                        .get()
+                        .ok_or_else(|| wasmtime::Error::msg("unresolved component function"))?;
+                    let span = ActiveSpan::start_in(
+                        &mut store,
+                        SpanKind::Stub,
+                        resolved.component.id(),
+                        &resolved.function_info.name,
+                    );
+                    let result = in_span(&mut store, span.context(), |store| {
+                        call_intercepted(
+                            store.as_context_mut(),
+                            &resolved.function_info,
+                            resolved.func,
+                            params,
+                            results,
+                        )
+                    });
+                    span.end(&result);
+                    result.map_err(|e| wasmtime::Error::msg(format!("{e:#}")))
+                })
+                .map_err(|e| eyre!("{e:#?}"))
+        },
+    )
 }
 
 /// Iterates all exported functions grouped by instance, creates an
-/// `OnceLock` slot for each, and delegates the actual linker
-/// registration to `register`.
+/// `OnceLock` slot for each provider's function, and delegates the
+/// actual linker registration to `register`.
+///
+/// Each function is linked to its first provider in `components` order,
+/// except for consumers with wired imports, which get a clone of the
+/// linker where the wired instances only hold the wired provider's stubs.
+/// Mocked interfaces get no stubs, as they are linked to their mocks.
 fn for_each_stub<F>(
     components: &[ComponentBinary],
+    wiring: &InterfaceWiring,
+    mocks: &Mocks,
     linker: &mut Linker<HostEnv>,
     mut register: F,
 ) -> eyre::Result<ComponentStubs>
@@ -121,29 +191,67 @@ where
         FuncSlot,
     ) -> eyre::Result<()>,
 {
+    let mut exports = group_exports_by_instance(components);
+    exports.retain(|inst_name, _| mocks.for_interface(inst_name).is_none());
     let mut slots: HashMap<SlotKey, FuncSlot> = HashMap::new();
-    for (inst_name, funcs) in group_exports_by_instance(components) {
-        let mut inst_builder = linker.instance(&inst_name).map_err(|e| eyre!("{e:#?}"))?;
-        for f in funcs {
-            let key = (inst_name.clone(), f.name.name.clone());
-            let slot: FuncSlot = Arc::new(OnceLock::new());
-            register(&mut inst_builder, &f, slot.clone())?;
-            slots.insert(key, slot);
+    for (inst_name, funcs) in &exports {
+        for (provider, f) in funcs {
+            let key = (provider.clone(), inst_name.clone(), f.name.name.clone());
+            slots.insert(key, Arc::new(OnceLock::new()));
         }
     }
-    Ok(ComponentStubs { slots })
+    let slot_for = |provider: &ComponentId, inst_name: &str, f: &ComponentFunctionInterface| {
+        let key = (provider.clone(), inst_name.to_owned(), f.name.name.clone());
+        slots[&key].clone()
+    };
+    for (inst_name, funcs) in &exports {
+        let mut inst_builder = linker.instance(inst_name).map_err(|e| eyre!("{e:#?}"))?;
+        let mut registered = HashSet::new();
+        for (provider, f) in funcs {
+            if !registered.insert(&f.name.name) {
+                continue;
+            }
+            register(&mut inst_builder, f, slot_for(provider, inst_name, f))?;
+        }
+    }
+    let mut consumer_linkers = HashMap::new();
+    for (consumer, providers) in wiring.consumers() {
+        let mut consumer_linker = linker.clone();
+        for (inst_name, funcs) in &exports {
+            let Some(wired) = providers.get(unversioned_interface_name(inst_name)) else {
+                continue;
+            };
+            // Redefining the instance replaces the default stubs.
+            let mut inst_builder = consumer_linker
+                .instance(inst_name)
+                .map_err(|e| eyre!("{e:#?}"))?;
+            for (provider, f) in funcs.iter().filter(|(p, _)| p == wired) {
+                register(&mut inst_builder, f, slot_for(provider, inst_name, f))?;
+            }
+        }
+        consumer_linkers.insert(consumer.clone(), consumer_linker);
+    }
+    Ok(ComponentStubs {
+        slots,
+        consumer_linkers,
+    })
 }
 
 /// Groups all exported functions by their instance export name
-/// across all components.
+/// across all components, along with the component exporting them.
 fn group_exports_by_instance(
     components: &[ComponentBinary],
-) -> HashMap<String, Vec<ComponentFunctionInterface>> {
-    let mut by_instance: HashMap<String, Vec<ComponentFunctionInterface>> = HashMap::new();
+) -> HashMap<String, Vec<(ComponentId, ComponentFunctionInterface)>> {
+    let mut by_instance: HashMap<String, Vec<(ComponentId, ComponentFunctionInterface)>> =
+        HashMap::new();
     for comp in components {
+        let comp_id = comp.component().id();
         for f in comp.get_functions() {
             if let Some(inst) = f.get_instance_export_name() {
-                by_instance.entry(inst).or_default().push(f);
+                by_instance
+                    .entry(inst)
+                    .or_default()
+                    .push((comp_id.clone(), f));
             }
         }
     }
@@ -153,6 +261,7 @@ fn group_exports_by_instance(
 /// Runs a cross-component call on a blocking thread with the sync engine.
 /// This avoids the nested `run_concurrent` assertion by using `Func::call`
 /// (sync) instead of `Func::call_async`.
+#[allow(clippy::too_many_arguments)]
 fn execute_stub_call(
     compiled_components: &[(ComponentBinary, wasmtime::component::Component)],
     comp_id: &crate::component::ComponentId,
@@ -161,13 +270,19 @@ fn execute_stub_call(
     env_vars: &HashMap<String, String>,
     preopened_dirs: &[std::path::PathBuf],
     runtime_data: crate::runtime::env::HostEnvRuntimeData,
+    span: SpanContext,
 ) -> eyre::Result<Vec<Val>> {
     let engine = &*SYNC_ENGINE;
+    let wiring = runtime_data.wiring.clone();
+    let mocks = runtime_data.mocks.clone();
     let mut store = create_fresh_store(engine, env_vars, preopened_dirs);
     store.data_mut().runtime_data = Some(runtime_data);
+    store.data_mut().span = Some(span);
     let mut linker = create_sync_linker(engine)?;
     let (all_instances, target) = instantiate_all_sync(
         compiled_components,
+        &wiring,
+        &mocks,
         engine,
         &mut linker,
         &mut store,
@@ -179,9 +294,7 @@ fn execute_stub_call(
         .get_func(&mut store, &target)
         .map_err(|e| eyre!("{e:#}"))?;
     let mut results = function.new_results_vec();
-    func.call(&mut store, inputs, &mut results)
-        .map_err(|e| eyre!("{e:#}"))?;
-    func.post_return(&mut store).map_err(|e| eyre!("{e:#}"))?;
+    call_intercepted(store.as_context_mut(), function, func, inputs, &mut results)?;
     Ok(results)
 }
 
@@ -193,11 +306,12 @@ pub fn resolve_component_stubs(
     stubs: &ComponentStubs,
 ) -> eyre::Result<()> {
     let functions = binary.get_functions();
+    let comp_id = binary.component().id();
     for f in functions {
         let Some(inst_name) = f.get_instance_export_name() else {
             continue;
         };
-        let key = (inst_name, f.name.name.clone());
+        let key = (comp_id.clone(), inst_name, f.name.name.clone());
         let Some(slot) = stubs.slots.get(&key) else {
             continue;
         };
@@ -0,0 +1,136 @@
+//! Registers host functions in the linker for imports of mocked interfaces,
+//! answering calls with the responses of their mock files.
+use crate::component::binary::ComponentBinary;
+use crate::component::function_interface::ComponentFunctionInterface;
+use crate::environment::mocks::{MockResponses, Mocks};
+use crate::runtime::env::HostEnv;
+use crate::runtime::handles::HandleTable;
+use crate::runtime::parsing::{ValExt, json_value_to_val_typedef};
+use eyre::{bail, eyre};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use wasmtime::component::{Linker, LinkerInstance, Val};
+use wit_parser::Resolve;
+
+/// A function of a mocked interface, typed by the WIT of its importer.
+struct MockFunc {
+    interface: String,
+    responses: Arc<MockResponses>,
+    function: ComponentFunctionInterface,
+    resolve: Arc<Resolve>,
+}
+
+/// Defines a host function for each function of a mocked interface
+/// imported by any component, in a linker of the async engine.
+pub fn register_mocks(
+    components: &[ComponentBinary],
+    mocks: &Mocks,
+    linker: &mut Linker<HostEnv>,
+) -> eyre::Result<()> {
+    for_each_mock(components, mocks, linker, |inst_builder, mock| {
+        inst_builder
+            .func_new_async(
+                &mock.function.name.name.clone(),
+                move |_, _, params, results| {
+                    let mock = mock.clone();
+                    Box::new(async move { mock.call(params, results) })
+                },
+            )
+            .map_err(|e| eyre!("{e:#?}"))
+    })
+}
+
+/// Sync variant of [`register_mocks`] for the sync engine.
+pub fn register_mocks_sync(
+    components: &[ComponentBinary],
+    mocks: &Mocks,
+    linker: &mut Linker<HostEnv>,
+) -> eyre::Result<()> {
+    for_each_mock(components, mocks, linker, |inst_builder, mock| {
+        inst_builder
+            .func_new(
+                &mock.function.name.name.clone(),
+                move |_, _, params, results| mock.call(params, results),
+            )
+            .map_err(|e| eyre!("{e:#?}"))
+    })
+}
+
+/// Creates a [`MockFunc`] for each mocked function imported by any
+/// component, typed by the WIT of its first importer, and delegates
+/// the actual linker registration to `register`.
+fn for_each_mock<F>(
+    components: &[ComponentBinary],
+    mocks: &Mocks,
+    linker: &mut Linker<HostEnv>,
+    mut register: F,
+) -> eyre::Result<()>
+where
+    F: FnMut(&mut LinkerInstance<'_, HostEnv>, Arc<MockFunc>) -> eyre::Result<()>,
+{
+    let mut by_instance: HashMap<String, Vec<MockFunc>> = HashMap::new();
+    let mut registered = HashSet::new();
+    for binary in components {
+        let resolve = Arc::new(binary.wit().resolve().clone());
+        for function in binary.get_imported_functions() {
+            let Some(inst_name) = function.get_instance_export_name() else {
+                continue;
+            };
+            let Some(responses) = mocks.for_interface(&inst_name) else {
+                continue;
+            };
+            if !registered.insert((inst_name.clone(), function.name.name.clone())) {
+                continue;
+            }
+            let mock = MockFunc {
+                interface: inst_name.clone(),
+                responses: responses.clone(),
+                function,
+                resolve: resolve.clone(),
+            };
+            by_instance.entry(inst_name).or_default().push(mock);
+        }
+    }
+    // Defining an instance replaces any previous one of that name,
+    // so each is defined once with all its functions.
+    for (inst_name, funcs) in by_instance {
+        let mut inst_builder = linker.instance(&inst_name).map_err(|e| eyre!("{e:#?}"))?;
+        for mock in funcs {
+            register(&mut inst_builder, Arc::new(mock))?;
+        }
+    }
+    Ok(())
+}
+
+impl MockFunc {
+    fn call(&self, params: &[Val], results: &mut [Val]) -> wasmtime::Result<()> {
+        self.respond(params, results)
+            .map_err(|e| wasmtime::Error::msg(format!("mock of {}: {e:#}", self.interface)))
+    }
+
+    /// Answers a call with the output of its matching response.
+    fn respond(&self, params: &[Val], results: &mut [Val]) -> eyre::Result<()> {
+        let function = &self.function;
+        let handles = HandleTable::default();
+        let args: Vec<serde_json::Value> = params
+            .iter()
+            .map(|v| v.clone().into_json_value(&handles))
+            .collect();
+        let param_names: Vec<&str> = function.inputs.iter().map(|(n, _)| n.as_str()).collect();
+        let output = self
+            .responses
+            .respond(&function.name.name, &param_names, &args)?;
+        let Some(output_type) = &function.output_type else {
+            return Ok(());
+        };
+        let Some(output) = output else {
+            bail!(
+                "mock response of function {} has no output",
+                function.name.name
+            );
+        };
+        results[0] = json_value_to_val_typedef(output, output_type, &self.resolve, &handles)
+            .map_err(|e| eyre!("invalid output of function {}: {e:#}", function.name.name))?;
+        Ok(())
+    }
+}
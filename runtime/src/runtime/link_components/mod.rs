@@ -1,5 +1,7 @@
 pub use dep_stub::{
-    register_component_stubs, register_component_stubs_sync, resolve_component_stubs,
+    ComponentStubs, register_component_stubs, register_component_stubs_sync,
+    resolve_component_stubs,
 };
 
 mod dep_stub;
+mod mock;
@@ -0,0 +1,553 @@
+//! Client of external MCP servers, whose tools are called by components
+//! as functions of virtual components.
+//!
+//! A tool's arguments are an object, while component functions take
+//! positional args, so each top-level property of a tool's input schema
+//! is a parameter. Required properties come first, in the order of the
+//! schema's `required` list, followed by the others in alphabetical order.
+//! The order does not depend on that of the schema's properties, which
+//! JSON objects do not preserve.
+use crate::component::ComponentId;
+use crate::environment::mcp_servers::{McpServerConfig, McpServers, expand_vars};
+use crate::runtime::wit_bindings::exports::asterai::host::api::{
+    CallError, CallErrorKind, ComponentInfo, FunctionInfo, ParamInfo, TypeInfo,
+};
+use crate::runtime::wit_bindings::exports::asterai::host::schema::FunctionSchema;
+use eyre::{Context, OptionExt, bail, eyre};
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::Mutex;
+
+const PROTOCOL_VERSION: &str = "2025-06-18";
+
+/// How long to wait for a response from a server.
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// An MCP server served as a component.
+pub struct McpComponent {
+    id: ComponentId,
+    version: String,
+    instructions: Option<String>,
+    tools: Vec<McpTool>,
+    client: Mutex<McpClient>,
+}
+
+struct McpTool {
+    name: String,
+    description: Option<String>,
+    input_schema: Value,
+    output_schema: Option<Value>,
+}
+
+impl McpTool {
+    /// Returns the tool's parameters and their schemas, in call order,
+    /// see the module docs.
+    fn params(&self) -> Vec<(&str, &Value)> {
+        let Some(properties) = self.input_schema["properties"].as_object() else {
+            return Vec::new();
+        };
+        let required: Vec<&str> = self.input_schema["required"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .collect();
+        let mut params: Vec<(&str, &Value)> =
+            properties.iter().map(|(k, v)| (k.as_str(), v)).collect();
+        params.sort_by_key(|(name, _)| {
+            let index = required.iter().position(|r| r == name);
+            (index.unwrap_or(usize::MAX), *name)
+        });
+        params
+    }
+}
+
+/// Connects to the MCP servers of an environment.
+pub(crate) async fn connect_mcp_servers(
+    servers: &McpServers,
+    vars: &HashMap<String, String>,
+) -> eyre::Result<Vec<McpComponent>> {
+    let mut components = Vec::new();
+    for (id, config) in servers.iter() {
+        let component = McpComponent::connect(id.clone(), config, vars)
+            .await
+            .wrap_err_with(|| format!("failed to connect to MCP server {id}"))?;
+        eprintln!(
+            "connected to MCP server {id} ({} tool(s))",
+            component.tools.len()
+        );
+        components.push(component);
+    }
+    Ok(components)
+}
+
+impl McpComponent {
+    async fn connect(
+        id: ComponentId,
+        config: &McpServerConfig,
+        vars: &HashMap<String, String>,
+    ) -> eyre::Result<Self> {
+        let mut client = McpClient::start(config, vars)?;
+        let init = client
+            .request(
+                "initialize",
+                json!({
+                    "protocolVersion": PROTOCOL_VERSION,
+                    "capabilities": {},
+                    "clientInfo": { "name": "asterai", "version": env!("CARGO_PKG_VERSION") },
+                }),
+            )
+            .await?;
+        if let McpTransport::Http(http) = &mut client.transport {
+            http.protocol_version = init["protocolVersion"].as_str().map(str::to_owned);
+        }
+        client.notify("notifications/initialized").await?;
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let params = match &cursor {
+                Some(cursor) => json!({ "cursor": cursor }),
+                None => json!({}),
+            };
+            let page = client.request("tools/list", params).await?;
+            for tool in page["tools"].as_array().into_iter().flatten() {
+                tools.push(McpTool {
+                    name: tool["name"]
+                        .as_str()
+                        .ok_or_eyre("tool without a name")?
+                        .to_owned(),
+                    description: tool["description"].as_str().map(str::to_owned),
+                    input_schema: tool["inputSchema"].clone(),
+                    output_schema: tool.get("outputSchema").cloned(),
+                });
+            }
+            cursor = page["nextCursor"].as_str().map(str::to_owned);
+            if cursor.is_none() {
+                break;
+            }
+        }
+        Ok(Self {
+            id,
+            version: init["serverInfo"]["version"]
+                .as_str()
+                .unwrap_or("0.0.0")
+                .to_owned(),
+            instructions: init["instructions"].as_str().map(str::to_owned),
+            tools,
+            client: Mutex::new(client),
+        })
+    }
+
+    pub fn id(&self) -> &ComponentId {
+        &self.id
+    }
+
+    pub(crate) fn component_info(&self) -> ComponentInfo {
+        let functions = self
+            .tools
+            .iter()
+            .map(|tool| FunctionInfo {
+                name: tool.name.clone(),
+                interface_name: None,
+                description: tool.description.clone(),
+                inputs: tool
+                    .params()
+                    .into_iter()
+                    .map(|(name, schema)| ParamInfo {
+                        name: name.to_owned(),
+                        type_name: json_schema_type_name(schema),
+                        type_schema: json_schema_type_name(schema),
+                    })
+                    .collect(),
+                output: Some(match &tool.output_schema {
+                    Some(schema) => TypeInfo {
+                        type_name: json_schema_type_name(schema),
+                        type_schema: json_schema_type_name(schema),
+                    },
+                    None => TypeInfo {
+                        type_name: "string".to_owned(),
+                        type_schema: "string".to_owned(),
+                    },
+                }),
+            })
+            .collect();
+        ComponentInfo {
+            name: self.id.to_string(),
+            version: self.version.clone(),
+            interfaces: Vec::new(),
+            description: self.instructions.clone(),
+            functions,
+        }
+    }
+
+    /// The JSON Schemas of the tools, in the order of [`Self::component_info`].
+    pub(crate) fn function_schemas(&self) -> Vec<FunctionSchema> {
+        self.tools
+            .iter()
+            .map(|tool| FunctionSchema {
+                name: tool.name.clone(),
+                interface_name: None,
+                inputs: tool
+                    .params()
+                    .into_iter()
+                    .map(|(_, schema)| schema.to_string())
+                    .collect(),
+                output: Some(match &tool.output_schema {
+                    Some(schema) => schema.to_string(),
+                    None => json!({ "type": "string" }).to_string(),
+                }),
+            })
+            .collect()
+    }
+
+    /// Calls a tool with positional JSON args, returning its output as JSON:
+    /// the structured content if the tool has any, or else its text.
+    pub(crate) async fn call(
+        &self,
+        function_name: &str,
+        args_json: &str,
+    ) -> Result<String, CallError> {
+        let tool = self
+            .tools
+            .iter()
+            .find(|t| t.name == function_name)
+            .ok_or(CallError {
+                kind: CallErrorKind::FunctionNotFound,
+                message: format!("function '{function_name}' not found on '{}'", self.id),
+            })?;
+        let args: Vec<Value> = serde_json::from_str(args_json).map_err(|e| CallError {
+            kind: CallErrorKind::InvalidArgs,
+            message: format!("invalid JSON args: {e}"),
+        })?;
+        let params = tool.params();
+        if args.len() != params.len() {
+            return Err(CallError {
+                kind: CallErrorKind::InvalidArgs,
+                message: format!("expected {} arg(s), got {}", params.len(), args.len()),
+            });
+        }
+        // Null args are left out, as optional properties may not be null.
+        let arguments: Map<String, Value> = params
+            .iter()
+            .zip(args)
+            .filter(|(_, arg)| !arg.is_null())
+            .map(|((name, _), arg)| (name.to_string(), arg))
+            .collect();
+        let result = self
+            .client
+            .lock()
+            .await
+            .request(
+                "tools/call",
+                json!({ "name": tool.name, "arguments": arguments }),
+            )
+            .await
+            .map_err(|e| CallError {
+                kind: CallErrorKind::InvocationFailed,
+                message: format!("{e:#}"),
+            })?;
+        let text = result["content"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|c| c["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        if result["isError"].as_bool() == Some(true) {
+            return Err(CallError {
+                kind: CallErrorKind::InvocationFailed,
+                message: text,
+            });
+        }
+        let output = match result.get("structuredContent") {
+            Some(structured) => structured.clone(),
+            None => Value::String(text),
+        };
+        serde_json::to_string(&output).map_err(|e| CallError {
+            kind: CallErrorKind::SerializationFailed,
+            message: format!("{e}"),
+        })
+    }
+}
+
+/// Returns a display name of the type a JSON Schema describes,
+/// e.g. "string" or "array".
+fn json_schema_type_name(schema: &Value) -> String {
+    match &schema["type"] {
+        Value::String(ty) => ty.clone(),
+        Value::Array(types) => types
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join(" | "),
+        _ => "any".to_owned(),
+    }
+}
+
+struct McpClient {
+    transport: McpTransport,
+    next_id: u64,
+}
+
+enum McpTransport {
+    Stdio(Box<StdioTransport>),
+    Http(HttpTransport),
+}
+
+struct StdioTransport {
+    /// Kept so that the server is killed when the client is dropped.
+    _child: Child,
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+}
+
+struct HttpTransport {
+    client: reqwest::Client,
+    url: String,
+    headers: Vec<(String, String)>,
+    session_id: Option<String>,
+    protocol_version: Option<String>,
+}
+
+impl McpClient {
+    fn start(config: &McpServerConfig, vars: &HashMap<String, String>) -> eyre::Result<Self> {
+        let transport = match &config.url {
+            Some(url) => McpTransport::Http(HttpTransport {
+                client: reqwest::Client::new(),
+                url: expand_vars(url, vars),
+                headers: config
+                    .headers
+                    .iter()
+                    .map(|(k, v)| (k.clone(), expand_vars(v, vars)))
+                    .collect(),
+                session_id: None,
+                protocol_version: None,
+            }),
+            None => {
+                let (program, args) = config.command.split_first().ok_or_eyre("missing command")?;
+                let mut child = Command::new(program)
+                    .args(args)
+                    .envs(config.env.iter().map(|(k, v)| (k, expand_vars(v, vars))))
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::inherit())
+                    .kill_on_drop(true)
+                    .spawn()
+                    .wrap_err_with(|| format!("failed to start {program}"))?;
+                let stdin = child.stdin.take().ok_or_eyre("no stdin")?;
+                let stdout = child.stdout.take().ok_or_eyre("no stdout")?;
+                McpTransport::Stdio(Box::new(StdioTransport {
+                    _child: child,
+                    stdin,
+                    stdout: BufReader::new(stdout).lines(),
+                }))
+            }
+        };
+        Ok(Self {
+            transport,
+            next_id: 1,
+        })
+    }
+
+    /// Sends a request, returning its result.
+    async fn request(&mut self, method: &str, params: Value) -> eyre::Result<Value> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        let response = match &mut self.transport {
+            McpTransport::Stdio(stdio) => stdio.request(&message, id).await?,
+            McpTransport::Http(http) => http.request(&message, id).await?,
+        };
+        if let Some(error) = response.get("error") {
+            bail!(
+                "{method} failed: {}",
+                error["message"].as_str().unwrap_or("unknown error")
+            );
+        }
+        Ok(response["result"].clone())
+    }
+
+    async fn notify(&mut self, method: &str) -> eyre::Result<()> {
+        let message = json!({ "jsonrpc": "2.0", "method": method });
+        match &mut self.transport {
+            McpTransport::Stdio(stdio) => stdio.send(&message).await,
+            McpTransport::Http(http) => http.post(&message).await.map(|_| ()),
+        }
+    }
+}
+
+impl StdioTransport {
+    async fn send(&mut self, message: &Value) -> eyre::Result<()> {
+        let mut line = message.to_string();
+        line.push('\n');
+        self.stdin.write_all(line.as_bytes()).await?;
+        self.stdin.flush().await?;
+        Ok(())
+    }
+
+    /// Sends a request and reads messages until its response,
+    /// answering requests from the server meanwhile.
+    async fn request(&mut self, message: &Value, id: u64) -> eyre::Result<Value> {
+        self.send(message).await?;
+        loop {
+            let line = tokio::time::timeout(RESPONSE_TIMEOUT, self.stdout.next_line())
+                .await
+                .map_err(|_| eyre!("timed out waiting for the MCP server"))??
+                .ok_or_eyre("MCP server exited")?;
+            let Ok(incoming) = serde_json::from_str::<Value>(&line) else {
+                continue;
+            };
+            if incoming.get("method").is_none() {
+                if incoming["id"].as_u64() == Some(id) {
+                    return Ok(incoming);
+                }
+                continue;
+            }
+            // Requests from the server have an ID, notifications do not.
+            let Some(request_id) = incoming.get("id") else {
+                continue;
+            };
+            let reply = match incoming["method"].as_str() {
+                Some("ping") => json!({ "jsonrpc": "2.0", "id": request_id, "result": {} }),
+                _ => json!({
+                    "jsonrpc": "2.0",
+                    "id": request_id,
+                    "error": { "code": -32601, "message": "method not supported" },
+                }),
+            };
+            self.send(&reply).await?;
+        }
+    }
+}
+
+impl HttpTransport {
+    async fn post(&mut self, message: &Value) -> eyre::Result<reqwest::Response> {
+        let mut request = self
+            .client
+            .post(&self.url)
+            .header("Content-Type", "application/json")
+            .header("Accept", "application/json, text/event-stream")
+            .body(message.to_string());
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        if let Some(session_id) = &self.session_id {
+            request = request.header("Mcp-Session-Id", session_id);
+        }
+        if let Some(protocol_version) = &self.protocol_version {
+            request = request.header("MCP-Protocol-Version", protocol_version);
+        }
+        let response = tokio::time::timeout(RESPONSE_TIMEOUT, request.send())
+            .await
+            .map_err(|_| eyre!("timed out waiting for the MCP server"))??;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            bail!("MCP server responded with {status}: {body}");
+        }
+        if let Some(session_id) = response.headers().get("Mcp-Session-Id") {
+            self.session_id = session_id.to_str().ok().map(str::to_owned);
+        }
+        Ok(response)
+    }
+
+    /// Sends a request, reading its response from the JSON body
+    /// or from the event stream the server responds with.
+    async fn request(&mut self, message: &Value, id: u64) -> eyre::Result<Value> {
+        let response = self.post(message).await?;
+        let is_event_stream = response
+            .headers()
+            .get("Content-Type")
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("text/event-stream"));
+        let body = response.text().await?;
+        if !is_event_stream {
+            return serde_json::from_str(&body).wrap_err("invalid response from MCP server");
+        }
+        parse_event_stream(&body)
+            .into_iter()
+            .find(|event| event.get("method").is_none() && event["id"].as_u64() == Some(id))
+            .ok_or_eyre("MCP server closed the event stream without a response")
+    }
+}
+
+/// Parses the JSON messages of a server-sent event stream.
+fn parse_event_stream(body: &str) -> Vec<Value> {
+    let mut messages = Vec::new();
+    let mut data = String::new();
+    for line in body.lines().chain(std::iter::once("")) {
+        if let Some(value) = line.strip_prefix("data:") {
+            data.push_str(value.strip_prefix(' ').unwrap_or(value));
+            continue;
+        }
+        if !line.is_empty() || data.is_empty() {
+            continue;
+        }
+        if let Ok(message) = serde_json::from_str(&data) {
+            messages.push(message);
+        }
+        data.clear();
+    }
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_params_order() {
+        let tool = McpTool {
+            name: "search".to_owned(),
+            description: None,
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "limit": { "type": "integer" },
+                    "query": { "type": "string" },
+                    "after": { "type": "string" },
+                    "repo": { "type": "string" },
+                },
+                "required": ["repo", "query"],
+            }),
+            output_schema: None,
+        };
+        let names: Vec<&str> = tool.params().into_iter().map(|(n, _)| n).collect();
+        assert_eq!(names, ["repo", "query", "after", "limit"]);
+    }
+
+    #[test]
+    fn test_parse_event_stream() {
+        let body = "event: message\n\
+                    data: {\"jsonrpc\":\"2.0\",\"method\":\"notifications/progress\"}\n\
+                    \n\
+                    id: 2\n\
+                    data: {\"jsonrpc\":\"2.0\",\"id\":1,\"result\":{}}\n";
+        let messages = parse_event_stream(body);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1]["id"], 1);
+    }
+
+    #[test]
+    fn test_tool_params_follow_input_schema() {
+        let tool = McpTool {
+            name: "search".to_owned(),
+            description: None,
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "limit": { "type": "integer" },
+                    "query": { "type": ["string", "null"] },
+                },
+            }),
+            output_schema: None,
+        };
+        let params = tool.params();
+        assert_eq!(params[0].0, "limit");
+        assert_eq!(json_schema_type_name(params[1].1), "string | null");
+    }
+}
@@ -0,0 +1,339 @@
+//! Prometheus metrics of a running environment.
+//!
+//! Calls are counted when their span ends (see [`call_trace`]), so
+//! each kind of call traced is also measured. Recording is off until
+//! [`enable`] is called, except for store memory, which is tracked by
+//! the resource limiter of each store.
+//!
+//! [`call_trace`]: crate::runtime::call_trace
+use crate::runtime::call_trace::SpanKind;
+use crate::runtime::cron::CronManager;
+use crate::runtime::wit_bindings::exports::asterai::host::api::CallErrorKind;
+use crate::runtime::ws::WsManager;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+use wasmtime::ResourceLimiter;
+
+static IS_ENABLED: AtomicBool = AtomicBool::new(false);
+static REGISTRY: LazyLock<Mutex<Registry>> = LazyLock::new(Default::default);
+static STORE_MEMORY_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// Upper bounds of the call duration histogram buckets, in seconds.
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Error kind of failed calls that are not dynamic calls.
+pub(crate) const INVOCATION_FAILED: &str = "invocation-failed";
+
+/// Starts recording metrics for this process.
+pub fn enable() {
+    IS_ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    IS_ENABLED.load(Ordering::Relaxed)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CallKey {
+    kind: SpanKind,
+    component: String,
+    function: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct HttpKey {
+    component: String,
+    method: String,
+    status: String,
+}
+
+#[derive(Debug, Default)]
+struct Histogram {
+    /// Observations per bucket, not cumulative.
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        if let Some(i) = LATENCY_BUCKETS.iter().position(|b| seconds <= *b) {
+            self.buckets[i] += 1;
+        }
+        self.sum += seconds;
+        self.count += 1;
+    }
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    calls: HashMap<CallKey, Histogram>,
+    errors: HashMap<(CallKey, &'static str), u64>,
+    http_requests: HashMap<HttpKey, u64>,
+}
+
+/// Records a finished call, with the error kind if it failed.
+pub(crate) fn record_call(
+    kind: SpanKind,
+    component: &str,
+    function: &str,
+    duration: Duration,
+    error_kind: Option<&'static str>,
+) {
+    if !is_enabled() {
+        return;
+    }
+    let key = CallKey {
+        kind,
+        component: component.to_owned(),
+        function: function.to_owned(),
+    };
+    let mut registry = REGISTRY.lock().unwrap();
+    if let Some(error_kind) = error_kind {
+        *registry
+            .errors
+            .entry((key.clone(), error_kind))
+            .or_default() += 1;
+    }
+    registry
+        .calls
+        .entry(key)
+        .or_default()
+        .observe(duration.as_secs_f64());
+}
+
+/// Records a request to a component's HTTP route.
+/// `status` is `None` if the component failed to respond.
+pub(crate) fn record_http_request(component: &str, method: &str, status: Option<u16>) {
+    if !is_enabled() {
+        return;
+    }
+    let key = HttpKey {
+        component: component.to_owned(),
+        method: method.to_owned(),
+        status: status
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| "error".to_owned()),
+    };
+    *REGISTRY
+        .lock()
+        .unwrap()
+        .http_requests
+        .entry(key)
+        .or_default() += 1;
+}
+
+pub(crate) fn call_error_kind_name(kind: CallErrorKind) -> &'static str {
+    match kind {
+        CallErrorKind::ComponentNotFound => "component-not-found",
+        CallErrorKind::FunctionNotFound => "function-not-found",
+        CallErrorKind::InvalidArgs => "invalid-args",
+        CallErrorKind::InvocationFailed => INVOCATION_FAILED,
+        CallErrorKind::SerializationFailed => "serialization-failed",
+    }
+}
+
+/// Tracks the linear memory of a store in the store memory gauge.
+#[derive(Debug, Default)]
+pub struct StoreMemory {
+    bytes: usize,
+}
+
+impl ResourceLimiter for StoreMemory {
+    fn memory_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        _maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        let grown = desired.saturating_sub(current);
+        self.bytes += grown;
+        STORE_MEMORY_BYTES.fetch_add(grown as u64, Ordering::Relaxed);
+        Ok(true)
+    }
+
+    fn table_growing(
+        &mut self,
+        _current: usize,
+        _desired: usize,
+        _maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        Ok(true)
+    }
+}
+
+impl Drop for StoreMemory {
+    fn drop(&mut self) {
+        STORE_MEMORY_BYTES.fetch_sub(self.bytes as u64, Ordering::Relaxed);
+    }
+}
+
+/// Renders all metrics in the Prometheus text exposition format.
+pub async fn render(ws_manager: Option<&WsManager>, cron_manager: Option<&CronManager>) -> String {
+    let ws_connections = match ws_manager {
+        Some(manager) => manager.connection_count().await,
+        None => 0,
+    };
+    let cron_schedules = match cron_manager {
+        Some(manager) => manager.schedule_count().await,
+        None => 0,
+    };
+    let mut out = String::new();
+    render_registry(&mut out, &REGISTRY.lock().unwrap());
+    render_gauge(
+        &mut out,
+        "asterai_ws_connections",
+        "Open WebSocket connections.",
+        ws_connections as u64,
+    );
+    render_gauge(
+        &mut out,
+        "asterai_cron_schedules",
+        "Active cron schedules.",
+        cron_schedules as u64,
+    );
+    render_gauge(
+        &mut out,
+        "asterai_store_memory_bytes",
+        "Linear memory allocated by component stores.",
+        STORE_MEMORY_BYTES.load(Ordering::Relaxed),
+    );
+    out
+}
+
+fn render_registry(out: &mut String, registry: &Registry) {
+    let mut calls: Vec<_> = registry.calls.iter().collect();
+    calls.sort_by_key(|(key, _)| call_labels(key));
+    out.push_str("# HELP asterai_calls_total Calls to component functions.\n");
+    out.push_str("# TYPE asterai_calls_total counter\n");
+    for (key, histogram) in &calls {
+        let labels = call_labels(key);
+        writeln!(out, "asterai_calls_total{{{labels}}} {}", histogram.count).unwrap();
+    }
+    let mut errors: Vec<_> = registry.errors.iter().collect();
+    errors.sort_by_key(|((key, error_kind), _)| (call_labels(key), *error_kind));
+    out.push_str("# HELP asterai_call_errors_total Failed calls by error kind.\n");
+    out.push_str("# TYPE asterai_call_errors_total counter\n");
+    for ((key, error_kind), count) in errors {
+        let labels = call_labels(key);
+        writeln!(
+            out,
+            "asterai_call_errors_total{{{labels},error_kind=\"{error_kind}\"}} {count}"
+        )
+        .unwrap();
+    }
+    out.push_str("# HELP asterai_call_duration_seconds Duration of calls.\n");
+    out.push_str("# TYPE asterai_call_duration_seconds histogram\n");
+    for (key, histogram) in &calls {
+        let labels = call_labels(key);
+        let mut cumulative = 0;
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+            cumulative += count;
+            writeln!(
+                out,
+                "asterai_call_duration_seconds_bucket{{{labels},le=\"{bound}\"}} {cumulative}"
+            )
+            .unwrap();
+        }
+        writeln!(
+            out,
+            "asterai_call_duration_seconds_bucket{{{labels},le=\"+Inf\"}} {}",
+            histogram.count
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "asterai_call_duration_seconds_sum{{{labels}}} {}",
+            histogram.sum
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "asterai_call_duration_seconds_count{{{labels}}} {}",
+            histogram.count
+        )
+        .unwrap();
+    }
+    let mut http_requests: Vec<_> = registry.http_requests.iter().collect();
+    http_requests.sort_by_key(|(key, _)| (&key.component, &key.method, &key.status));
+    out.push_str("# HELP asterai_http_requests_total Requests to component HTTP routes.\n");
+    out.push_str("# TYPE asterai_http_requests_total counter\n");
+    for (key, count) in http_requests {
+        writeln!(
+            out,
+            "asterai_http_requests_total{{component=\"{}\",method=\"{}\",status=\"{}\"}} {count}",
+            escape_label(&key.component),
+            escape_label(&key.method),
+            key.status
+        )
+        .unwrap();
+    }
+}
+
+fn render_gauge(out: &mut String, name: &str, help: &str, value: u64) {
+    writeln!(out, "# HELP {name} {help}").unwrap();
+    writeln!(out, "# TYPE {name} gauge").unwrap();
+    writeln!(out, "{name} {value}").unwrap();
+}
+
+fn call_labels(key: &CallKey) -> String {
+    format!(
+        "kind=\"{}\",component=\"{}\",function=\"{}\"",
+        key.kind,
+        escape_label(&key.component),
+        escape_label(&key.function)
+    )
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets_are_cumulative() {
+        let mut registry = Registry::default();
+        let key = CallKey {
+            kind: SpanKind::Stub,
+            component: "test:llm".to_owned(),
+            function: "llm/complete".to_owned(),
+        };
+        let histogram = registry.calls.entry(key.clone()).or_default();
+        histogram.observe(0.003);
+        histogram.observe(0.2);
+        histogram.observe(60.0);
+        registry.errors.insert((key, INVOCATION_FAILED), 1);
+        let mut out = String::new();
+        render_registry(&mut out, &registry);
+        let labels = r#"kind="stub",component="test:llm",function="llm/complete""#;
+        assert!(out.contains(&format!("asterai_calls_total{{{labels}}} 3\n")));
+        assert!(out.contains(&format!(
+            "asterai_call_errors_total{{{labels},error_kind=\"invocation-failed\"}} 1\n"
+        )));
+        assert!(out.contains(&format!(
+            "asterai_call_duration_seconds_bucket{{{labels},le=\"0.005\"}} 1\n"
+        )));
+        assert!(out.contains(&format!(
+            "asterai_call_duration_seconds_bucket{{{labels},le=\"0.25\"}} 2\n"
+        )));
+        assert!(out.contains(&format!(
+            "asterai_call_duration_seconds_bucket{{{labels},le=\"+Inf\"}} 3\n"
+        )));
+    }
+
+    #[test]
+    fn test_escape_label() {
+        assert_eq!(escape_label(r#"a"b\c"#), r#"a\"b\\c"#);
+    }
+}
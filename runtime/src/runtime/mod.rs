@@ -3,10 +3,16 @@ use crate::component::binary::ComponentBinary;
 use crate::component::function_interface::ComponentFunctionInterface;
 use crate::component::function_name::ComponentFunctionName;
 use crate::component::wit::ComponentInterface;
+use crate::environment::interceptors::Interceptors;
+use crate::environment::mcp_servers::McpServers;
+use crate::environment::mocks::Mocks;
+use crate::environment::wiring::InterfaceWiring;
+use crate::runtime::approval::CallApprovals;
+use crate::runtime::call_trace::{ActiveSpan, SpanKind};
 use crate::runtime::cron::CronManager;
 use crate::runtime::env::HostEnvRuntimeData;
 use crate::runtime::http::{HttpRoute, HttpRouteTable};
-use crate::runtime::output::{ComponentFunctionOutput, ComponentOutput};
+use crate::runtime::output::{ComponentFunctionOutput, ComponentOutput, OutputEvent};
 use crate::runtime::wasm_instance::{
     ComponentRuntimeEngine, call_wasm_component_function_concurrent,
 };
@@ -25,16 +31,22 @@ pub use wasmtime::component::Val;
 use wasmtime_wasi_http::bindings::ProxyPre;
 use wit_parser::PackageName;
 
+pub mod approval;
+pub mod call_trace;
 pub mod cron;
 mod cron_entry;
 mod entry;
 pub mod env;
+pub mod handles;
 pub mod http;
+mod interceptor;
 mod link_components;
+pub mod mcp_client;
+pub mod metrics;
 pub mod output;
 pub mod parsing;
 pub(crate) mod std_out_err;
-mod wasm_instance;
+pub(crate) mod wasm_instance;
 mod wit_bindings;
 pub mod ws;
 mod ws_entry;
@@ -65,16 +77,23 @@ pub struct ComponentRuntime {
     ws_manager: Option<Arc<WsManager>>,
     #[getter(skip)]
     cron_manager: Option<Arc<CronManager>>,
+    #[getter(skip)]
+    call_approvals: CallApprovals,
 }
 
 impl ComponentRuntime {
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         components: Vec<ComponentBinary>,
         // TODO: change app ID for resource ID?
         app_id: Uuid,
-        component_output_tx: mpsc::Sender<ComponentOutput>,
+        component_output_tx: mpsc::Sender<OutputEvent>,
         env_vars: &HashMap<String, String>,
         preopened_dirs: &[PathBuf],
+        wiring: InterfaceWiring,
+        interceptors: Interceptors,
+        mcp_servers: McpServers,
+        mocks: Mocks,
         env_namespace: &str,
         env_name: &str,
     ) -> eyre::Result<Self> {
@@ -84,6 +103,10 @@ impl ComponentRuntime {
             component_output_tx,
             env_vars,
             preopened_dirs,
+            wiring,
+            interceptors,
+            mcp_servers,
+            mocks,
         )
         .await?;
         let (ws_manager, cron_manager, runtime_data) = {
@@ -96,6 +119,7 @@ impl ComponentRuntime {
             )
         };
         let runtime_data = runtime_data.expect("runtime data not initialized");
+        let call_approvals = runtime_data.call_approvals.clone();
         let http_route_table =
             build_http_route_table(&engine, env_namespace, env_name, runtime_data)?;
         Ok(Self {
@@ -104,6 +128,7 @@ impl ComponentRuntime {
             http_route_table: Arc::new(http_route_table),
             ws_manager,
             cron_manager,
+            call_approvals,
         })
     }
 
@@ -119,6 +144,26 @@ impl ComponentRuntime {
         self.cron_manager.clone()
     }
 
+    /// Releases the values of a caller's handles that it no longer
+    /// uses, dropping resources. See [`handles`] for futures and streams.
+    pub async fn release_handles(&mut self, vals: Vec<Val>) -> eyre::Result<()> {
+        let mut store = self.engine.store.lock().await;
+        for val in vals {
+            if let Val::Resource(resource) = val {
+                resource
+                    .resource_drop_async(&mut *store)
+                    .await
+                    .map_err(|e| eyre!(e))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Approval policies of calls between components on this runtime.
+    pub fn call_approvals(&self) -> &CallApprovals {
+        &self.call_approvals
+    }
+
     pub fn component_interfaces(&self) -> Vec<ComponentBinary> {
         self.engine
             .instances()
@@ -148,6 +193,19 @@ impl ComponentRuntime {
         Ok(output_opt)
     }
 
+    /// Calls a function, streaming its output events, such as chunks
+    /// emitted with `asterai:host/api.emit-output`, to `output_tx`.
+    pub async fn call_function_streaming(
+        &mut self,
+        component_manifest_function: ComponentFunctionInterface,
+        inputs: &[Val],
+        output_tx: mpsc::Sender<OutputEvent>,
+    ) -> eyre::Result<Option<ComponentOutput>> {
+        self.engine
+            .call_with_output(component_manifest_function, inputs, output_tx)
+            .await
+    }
+
     pub fn find_function(
         &self,
         component_id: &ComponentId,
@@ -272,9 +330,11 @@ impl ComponentRuntime {
                 .last_component
                 .clone();
             *last_component.lock().unwrap() = Some(component.clone());
-            store
+            let span = ActiveSpan::start(SpanKind::Call, component.id(), &func_name, None);
+            store.data_mut().span = Some(span.context());
+            let result = store
                 .run_concurrent(async |a| {
-                    let result = call_wasm_component_function_concurrent(
+                    call_wasm_component_function_concurrent(
                         &func,
                         &func_name,
                         a,
@@ -282,13 +342,22 @@ impl ComponentRuntime {
                         &mut [Val::Bool(false)],
                         component,
                     )
-                    .await;
-                    if let Err(e) = result {
-                        error!("{e:#?}");
-                    }
+                    .await
                 })
                 .await
-                .map_err(|e| eyre!(e))?;
+                .map_err(|e| eyre!(e));
+            store.data_mut().span = None;
+            let call_result = match result {
+                Ok(call_result) => call_result,
+                Err(e) => {
+                    span.end(&Err::<(), _>(&e));
+                    return Err(e);
+                }
+            };
+            span.end(&call_result);
+            if let Err(e) = call_result {
+                error!("{e:#?}");
+            }
         }
         Ok(())
     }
@@ -345,7 +414,8 @@ fn build_http_route_table(
             component.name()
         );
         let instance_pre = engine
-            .linker
+            .stubs
+            .linker_for(&component.id(), &engine.linker)
             .instantiate_pre(&entry.component)
             .map_err(|e| eyre!("{e:#?}"))?;
         let proxy_pre = ProxyPre::new(instance_pre).map_err(|e| eyre!("{e:#?}"))?;
@@ -1,9 +1,10 @@
 use crate::component::function_interface::ComponentFunctionInterface;
 use crate::runtime::SerializableVal;
+use crate::runtime::handles::HandleTable;
+use crate::runtime::parsing::ValExt;
 use derive_getters::Getters;
-use serde::ser::{SerializeSeq, SerializeStruct};
+use serde::ser::SerializeStruct;
 use serde::{Serialize, Serializer};
-use wasmtime::component::Val;
 use wit_parser::TypeDef;
 
 #[derive(Getters, Clone)]
@@ -17,6 +18,19 @@ pub struct ComponentOutput {
     pub component_response_to_agent_opt: Option<String>,
 }
 
+/// Progress of a running call, sent on the output channel
+/// of the store ahead of the call's result.
+#[derive(Clone, Debug, PartialEq)]
+pub enum OutputEvent {
+    /// A chunk of output emitted with `asterai:host/api.emit-output`.
+    Chunk(String),
+    /// A function of another component was called
+    /// with `call-component-function`.
+    CallStarted { component: String, function: String },
+    /// The call of a `CallStarted` event returned.
+    CallFinished { component: String, function: String },
+}
+
 #[derive(Clone)]
 pub struct ComponentFunctionOutput {
     pub type_def: TypeDef,
@@ -41,93 +55,21 @@ impl Serialize for ComponentFunctionOutput {
     }
 }
 
+/// Serializes values with the JSON mapping of [`crate::runtime::parsing`].
 impl Serialize for SerializableVal {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        match &self.val {
-            Val::Bool(v) => serializer.serialize_bool(*v),
-            Val::S8(v) => serializer.serialize_i8(*v),
-            Val::U8(v) => serializer.serialize_u8(*v),
-            Val::S16(v) => serializer.serialize_i16(*v),
-            Val::U16(v) => serializer.serialize_u16(*v),
-            Val::S32(v) => serializer.serialize_i32(*v),
-            Val::U32(v) => serializer.serialize_u32(*v),
-            Val::S64(v) => serializer.serialize_i64(*v),
-            Val::U64(v) => serializer.serialize_u64(*v),
-            Val::Float32(v) => serializer.serialize_f32(*v),
-            Val::Float64(v) => serializer.serialize_f64(*v),
-            Val::Char(v) => serializer.serialize_char(*v),
-            Val::String(v) => serializer.serialize_str(v),
-            Val::List(v) | Val::Tuple(v) => {
-                let mut state = serializer.serialize_seq(Some(v.len()))?;
-                for child in v.iter().cloned() {
-                    state.serialize_element(&SerializableVal {
-                        name: None,
-                        val: child,
-                    })?;
-                }
-                state.end()
-            }
-            Val::Record(v) => {
-                let name = self
-                    .name
-                    .clone()
-                    .unwrap_or_else(|| "ComponentOutput".to_owned());
-                // TODO fix leak
-                let mut state =
-                    serializer.serialize_struct(Box::leak(name.into_boxed_str()), v.len())?;
-                for (key, val) in v.iter().cloned() {
-                    state.serialize_field(
-                        // TODO fix leak
-                        Box::leak(key.into_boxed_str()),
-                        &SerializableVal {
-                            name: None,
-                            val: val.clone(),
-                        },
-                    )?;
-                }
-                state.end()
-            }
-            Val::Enum(v) => serializer.serialize_str(v),
-            Val::Option(v_opt) => {
-                if let Some(v) = v_opt.clone() {
-                    SerializableVal {
-                        name: None,
-                        val: *v,
-                    }
-                    .serialize(serializer)
-                } else {
-                    serializer.serialize_none()
-                }
-            }
-            Val::Result(result) => match result {
-                Ok(v_opt) => {
-                    if let Some(v) = v_opt.clone() {
-                        SerializableVal {
-                            name: None,
-                            val: *v,
-                        }
-                        .serialize(serializer)
-                    } else {
-                        serializer.serialize_none()
-                    }
-                }
-                Err(v_opt) => {
-                    if let Some(v) = v_opt.clone() {
-                        SerializableVal {
-                            name: None,
-                            val: *v,
-                        }
-                        .serialize(serializer)
-                    } else {
-                        serializer.serialize_none()
-                    }
-                }
-            },
-            _ => Err(serde::ser::Error::custom("unsupported Val variant")),
+        // Handles could not be referred to without the store's table.
+        let handles = HandleTable::default();
+        let value = self.val.clone().into_json_value(&handles);
+        if !handles.is_empty() {
+            return Err(serde::ser::Error::custom(
+                "resources, futures and streams cannot be serialized",
+            ));
         }
+        value.serialize(serializer)
     }
 }
 
@@ -137,6 +79,7 @@ mod test {
     use crate::component::Component;
     use crate::component::function_name::ComponentFunctionName;
     use std::str::FromStr;
+    use wasmtime::component::Val;
     use wit_parser::{TypeDefKind, TypeOwner};
 
     #[test]
@@ -202,4 +145,17 @@ mod test {
         let expected = r#"{"component":"namespace:component","version":"0.1.0","function":"important_function","value":1337}"#;
         assert_eq!(serialized, expected);
     }
+
+    #[test]
+    fn test_serialize_val_uses_json_mapping() {
+        let serialize =
+            |val: Val| serde_json::to_string(&SerializableVal { name: None, val }).unwrap();
+        assert_eq!(serialize(Val::Float64(f64::NAN)), r#""NaN""#);
+        assert_eq!(serialize(Val::Float32(0.1)), "0.1");
+        assert_eq!(
+            serialize(Val::List(vec![Val::U8(104), Val::U8(105)])),
+            r#""aGk=""#
+        );
+        assert_eq!(serialize(Val::U64(u64::MAX)), u64::MAX.to_string());
+    }
 }
@@ -1,88 +1,151 @@
-use eyre::bail;
-use serde_json::Value;
+//! The JSON mapping of WIT values, used by the call API, the CLI,
+//! MCP tools and `call-component-function`.
+//!
+//! Version 2 of the mapping, see [`JSON_MAPPING_VERSION`]:
+//! - integers are numbers, range-checked on input, where decimal
+//!   strings are also accepted; `u64` and `s64` are optionally output
+//!   as strings, see [`JsonOptions`],
+//! - floats are numbers, or `"NaN"`, `"Infinity"` and `"-Infinity"`,
+//! - `list<u8>` is a base64 string (standard alphabet, padded), except
+//!   that empty lists are output as `[]`, as a list value does not know
+//!   its element type; arrays of numbers are also accepted on input,
+//! - chars are single-char strings, enums are case name strings and
+//!   flags are arrays of flag names,
+//! - records are objects, tuples are arrays and options are the inner
+//!   value or `null`,
+//! - results are the ok value, or `{"error": <err>}`,
+//! - variants are `{"tag": <case>, "value": <payload>}`,
+//! - resources, futures, streams and error contexts are
+//!   `{"handle": <id>}`, see [`HandleTable`].
+use crate::component::json_schema::is_optional_param;
+use crate::runtime::handles::HandleTable;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use eyre::{bail, eyre};
+use serde_json::{Value, json};
 use wasmtime::component::Val;
-use wit_parser::{Resolve, Type, TypeDef, TypeDefKind};
+use wit_parser::{Handle, Resolve, Type, TypeDef, TypeDefKind};
+
+/// Version of the JSON mapping described in the module docs,
+/// incremented when the encoding of any type changes.
+pub const JSON_MAPPING_VERSION: u32 = 2;
+
+/// Options of the JSON output of values.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonOptions {
+    /// Whether to output `u64` and `s64` values as decimal strings,
+    /// for clients that parse JSON numbers as doubles, e.g. JavaScript.
+    pub is_int64_as_string: bool,
+}
 
 pub trait ValExt {
-    fn try_into_json_value(self) -> Option<Value>;
+    /// Converts a Val to JSON, holding values without a JSON encoding
+    /// in `handles` and encoding them as `{"handle": <id>}`.
+    fn into_json_value(self, handles: &HandleTable) -> Value;
+
+    /// Like [`ValExt::into_json_value`], with non-default options.
+    fn into_json_value_with(self, handles: &HandleTable, options: JsonOptions) -> Value;
 }
 
 impl ValExt for Val {
-    fn try_into_json_value(self) -> Option<Value> {
-        let value = match self {
+    fn into_json_value(self, handles: &HandleTable) -> Value {
+        self.into_json_value_with(handles, JsonOptions::default())
+    }
+
+    fn into_json_value_with(self, handles: &HandleTable, options: JsonOptions) -> Value {
+        let convert = |val: Val| val.into_json_value_with(handles, options);
+        match self {
             Val::Bool(v) => Value::Bool(v),
-            Val::S8(v) => Value::Number(v.into()),
-            Val::U8(v) => Value::Number(v.into()),
-            Val::S16(v) => Value::Number(v.into()),
-            Val::U16(v) => Value::Number(v.into()),
-            Val::S32(v) => Value::Number(v.into()),
-            Val::U32(v) => Value::Number(v.into()),
-            Val::S64(v) => Value::Number(v.into()),
-            Val::U64(v) => Value::Number(v.into()),
-            Val::Float32(v) => Value::Number(serde_json::Number::from_f64(v as f64).unwrap()),
-            Val::Float64(v) => Value::Number(serde_json::Number::from_f64(v).unwrap()),
+            Val::S8(v) => json!(v),
+            Val::U8(v) => json!(v),
+            Val::S16(v) => json!(v),
+            Val::U16(v) => json!(v),
+            Val::S32(v) => json!(v),
+            Val::U32(v) => json!(v),
+            Val::S64(v) => match options.is_int64_as_string {
+                true => Value::String(v.to_string()),
+                false => json!(v),
+            },
+            Val::U64(v) => match options.is_int64_as_string {
+                true => Value::String(v.to_string()),
+                false => json!(v),
+            },
+            // Going through the shortest decimal representation keeps
+            // e.g. 0.1 from being output as 0.10000000149011612.
+            Val::Float32(v) => float_to_json(v.to_string().parse().unwrap_or(v as f64)),
+            Val::Float64(v) => float_to_json(v),
             Val::Char(v) => Value::String(v.to_string()),
             Val::String(v) => Value::String(v),
-            Val::List(v) => Value::Array(
-                v.iter()
-                    .filter_map(|val| val.clone().try_into_json_value())
-                    .collect(),
-            ),
-            Val::Tuple(v) => Value::Array(
-                v.iter()
-                    .filter_map(|val| val.clone().try_into_json_value())
-                    .collect(),
-            ),
-            Val::Option(v) => v
-                .as_deref()
-                .and_then(|v| v.clone().try_into_json_value())
-                .unwrap_or(Value::Null),
-            Val::Result(v) => match v {
-                Ok(val) => val
-                    .as_deref()
-                    .and_then(|v| v.clone().try_into_json_value())
-                    .unwrap_or(Value::Null),
-                Err(val) => {
-                    let err = val
-                        .as_deref()
-                        .and_then(|v| v.clone().try_into_json_value())
-                        .unwrap_or(Value::String("unknown error".into()));
-                    serde_json::json!({ "error": err })
-                }
+            Val::List(v) => match bytes_of(&v) {
+                Some(bytes) => Value::String(BASE64.encode(bytes)),
+                None => Value::Array(v.into_iter().map(convert).collect()),
             },
+            Val::Tuple(v) => Value::Array(v.into_iter().map(convert).collect()),
+            Val::Option(v) => v.map(|v| convert(*v)).unwrap_or(Value::Null),
+            Val::Result(Ok(v)) => v.map(|v| convert(*v)).unwrap_or(Value::Null),
+            Val::Result(Err(v)) => {
+                let err = v
+                    .map(|v| convert(*v))
+                    .unwrap_or(Value::String("unknown error".into()));
+                json!({ "error": err })
+            }
             Val::Variant(discriminant, payload) => {
                 let mut map = serde_json::Map::new();
                 map.insert("tag".into(), Value::String(discriminant));
-                if let Some(val) = payload.and_then(|v| v.try_into_json_value()) {
-                    map.insert("value".into(), val);
+                if let Some(val) = payload {
+                    map.insert("value".into(), convert(*val));
                 }
                 Value::Object(map)
             }
             Val::Enum(s) => Value::String(s),
-            Val::Record(fields) => {
-                let map = fields
+            Val::Record(fields) => Value::Object(
+                fields
                     .into_iter()
-                    .filter_map(|(name, val)| {
-                        val.try_into_json_value().map(|v| (name, v))
-                    })
-                    .collect::<serde_json::Map<String, Value>>();
-                Value::Object(map)
-            }
-            Val::Flags(names) => Value::Array(
-                names.into_iter().map(Value::String).collect(),
+                    .map(|(name, val)| (name, convert(val)))
+                    .collect(),
             ),
-            Val::Resource(_) => return None,
-            Val::Future(_) => return None,
-            Val::Stream(_) => return None,
-            Val::ErrorContext(_) => return None,
-        };
-        Some(value)
+            Val::Flags(names) => Value::Array(names.into_iter().map(Value::String).collect()),
+            Val::Resource(_) | Val::Future(_) | Val::Stream(_) | Val::ErrorContext(_) => {
+                json!({ "handle": handles.insert(self) })
+            }
+        }
+    }
+}
+
+/// Returns the bytes of a non-empty list of `u8`s.
+fn bytes_of(list: &[Val]) -> Option<Vec<u8>> {
+    if list.is_empty() {
+        return None;
     }
+    list.iter()
+        .map(|v| match v {
+            Val::U8(byte) => Some(*byte),
+            _ => None,
+        })
+        .collect()
+}
+
+fn float_to_json(v: f64) -> Value {
+    if let Some(number) = serde_json::Number::from_f64(v) {
+        return Value::Number(number);
+    }
+    let name = match (v.is_nan(), v.is_sign_positive()) {
+        (true, _) => "NaN",
+        (false, true) => "Infinity",
+        (false, false) => "-Infinity",
+    };
+    Value::String(name.to_owned())
 }
 
 /// Converts a serde_json Value to a wasmtime Val based on the expected WIT type.
-/// The `resolve` is needed to look up `Type::Id` references for nested types.
-pub fn json_value_to_val(value: &Value, ty: &Type, resolve: &Resolve) -> eyre::Result<Val> {
+/// The `resolve` is needed to look up `Type::Id` references for nested types,
+/// and `handles` to look up the values of handles.
+pub fn json_value_to_val(
+    value: &Value,
+    ty: &Type,
+    resolve: &Resolve,
+    handles: &HandleTable,
+) -> eyre::Result<Val> {
     match ty {
         Type::String => match value {
             Value::String(s) => Ok(Val::String(s.clone())),
@@ -92,15 +155,15 @@ pub fn json_value_to_val(value: &Value, ty: &Type, resolve: &Resolve) -> eyre::R
             Value::Bool(b) => Ok(Val::Bool(*b)),
             _ => bail!("expected bool"),
         },
-        Type::U8 => Ok(Val::U8(json_to_u64(value)? as u8)),
-        Type::U16 => Ok(Val::U16(json_to_u64(value)? as u16)),
-        Type::U32 => Ok(Val::U32(json_to_u64(value)? as u32)),
-        Type::U64 => Ok(Val::U64(json_to_u64(value)?)),
-        Type::S8 => Ok(Val::S8(json_to_i64(value)? as i8)),
-        Type::S16 => Ok(Val::S16(json_to_i64(value)? as i16)),
-        Type::S32 => Ok(Val::S32(json_to_i64(value)? as i32)),
-        Type::S64 => Ok(Val::S64(json_to_i64(value)?)),
-        Type::F32 => Ok(Val::Float32(json_to_f64(value)? as f32)),
+        Type::U8 => Ok(Val::U8(json_to_int(value, "u8")?)),
+        Type::U16 => Ok(Val::U16(json_to_int(value, "u16")?)),
+        Type::U32 => Ok(Val::U32(json_to_int(value, "u32")?)),
+        Type::U64 => Ok(Val::U64(json_to_int(value, "u64")?)),
+        Type::S8 => Ok(Val::S8(json_to_int(value, "s8")?)),
+        Type::S16 => Ok(Val::S16(json_to_int(value, "s16")?)),
+        Type::S32 => Ok(Val::S32(json_to_int(value, "s32")?)),
+        Type::S64 => Ok(Val::S64(json_to_int(value, "s64")?)),
+        Type::F32 => Ok(Val::Float32(json_to_f32(value)?)),
         Type::F64 => Ok(Val::Float64(json_to_f64(value)?)),
         Type::Char => match value {
             Value::String(s) => {
@@ -118,68 +181,98 @@ pub fn json_value_to_val(value: &Value, ty: &Type, resolve: &Resolve) -> eyre::R
                 .types
                 .get(*type_id)
                 .ok_or_else(|| eyre::eyre!("unknown type id"))?;
-            json_value_to_val_typedef(value, type_def, resolve)
+            json_value_to_val_typedef(value, type_def, resolve, handles)
         }
-        Type::ErrorContext => bail!("error-context not supported in JSON"),
+        Type::ErrorContext => take_handle(value, handles, "error-context", false),
     }
 }
 
-/// Parses a string into a wasmtime Val based on the expected WIT primitive type.
-pub fn parse_primitive(arg: &str, ty: &Type) -> eyre::Result<Val> {
-    match ty {
-        Type::String => Ok(Val::String(arg.to_owned())),
-        Type::Bool => {
-            let v: bool = arg.parse().map_err(|_| eyre::eyre!("expected bool"))?;
-            Ok(Val::Bool(v))
-        }
-        Type::U8 => Ok(Val::U8(arg.parse()?)),
-        Type::U16 => Ok(Val::U16(arg.parse()?)),
-        Type::U32 => Ok(Val::U32(arg.parse()?)),
-        Type::U64 => Ok(Val::U64(arg.parse()?)),
-        Type::S8 => Ok(Val::S8(arg.parse()?)),
-        Type::S16 => Ok(Val::S16(arg.parse()?)),
-        Type::S32 => Ok(Val::S32(arg.parse()?)),
-        Type::S64 => Ok(Val::S64(arg.parse()?)),
-        Type::F32 => Ok(Val::Float32(arg.parse()?)),
-        Type::F64 => Ok(Val::Float64(arg.parse()?)),
-        Type::Char => {
-            let mut chars = arg.chars();
-            let c = chars.next().ok_or_else(|| eyre::eyre!("expected char"))?;
-            if chars.next().is_some() {
-                bail!("expected single char, got multiple");
-            }
-            Ok(Val::Char(c))
-        }
-        Type::Id(_) => bail!("unresolved type reference"),
-        Type::ErrorContext => bail!("error-context not supported"),
-    }
+/// Converts a JSON number, or a decimal string, to an integer
+/// of the WIT type `type_name`, checking that it is in range.
+fn json_to_int<T: TryFrom<i128>>(value: &Value, type_name: &str) -> eyre::Result<T> {
+    let n = match value {
+        Value::Number(n) => n
+            .as_i64()
+            .map(i128::from)
+            .or_else(|| n.as_u64().map(i128::from)),
+        Value::String(s) => s.trim().parse::<i128>().ok(),
+        _ => None,
+    };
+    let Some(n) = n else {
+        bail!("expected integer for {type_name}, got {value}");
+    };
+    T::try_from(n).map_err(|_| eyre!("{n} is out of range for {type_name}"))
 }
 
-fn json_to_u64(value: &Value) -> eyre::Result<u64> {
-    value
-        .as_u64()
-        .ok_or_else(|| eyre::eyre!("expected unsigned integer"))
+/// Converts a JSON number, a decimal string, or one of
+/// `"NaN"`, `"Infinity"` and `"-Infinity"` to a float.
+fn json_to_f64(value: &Value) -> eyre::Result<f64> {
+    let v = match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => match s.as_str() {
+            "NaN" => Some(f64::NAN),
+            "Infinity" => Some(f64::INFINITY),
+            "-Infinity" => Some(f64::NEG_INFINITY),
+            s => s.trim().parse().ok(),
+        },
+        _ => None,
+    };
+    v.ok_or_else(|| eyre!("expected number, got {value}"))
 }
 
-fn json_to_i64(value: &Value) -> eyre::Result<i64> {
-    value
-        .as_i64()
-        .ok_or_else(|| eyre::eyre!("expected integer"))
+fn json_to_f32(value: &Value) -> eyre::Result<f32> {
+    let v = json_to_f64(value)?;
+    let v32 = v as f32;
+    if v.is_finite() && v32.is_infinite() {
+        bail!("{v} is out of range for f32");
+    }
+    Ok(v32)
 }
 
-fn json_to_f64(value: &Value) -> eyre::Result<f64> {
-    value.as_f64().ok_or_else(|| eyre::eyre!("expected number"))
+/// Returns whether `ty` is `u8`, or an alias of it.
+pub(crate) fn is_u8(ty: &Type, resolve: &Resolve) -> bool {
+    match ty {
+        Type::U8 => true,
+        Type::Id(id) => match resolve.types.get(*id).map(|t| &t.kind) {
+            Some(TypeDefKind::Type(ty)) => is_u8(ty, resolve),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Returns whether a type is or contains a future or stream.
+pub(crate) fn has_future_or_stream(type_def: &TypeDef, resolve: &Resolve) -> bool {
+    let has = |ty: &Type| match ty {
+        Type::Id(id) => has_future_or_stream(&resolve.types[*id], resolve),
+        _ => false,
+    };
+    match &type_def.kind {
+        TypeDefKind::Future(_) | TypeDefKind::Stream(_) => true,
+        TypeDefKind::Type(ty) | TypeDefKind::List(ty) | TypeDefKind::Option(ty) => has(ty),
+        TypeDefKind::Record(record) => record.fields.iter().any(|f| has(&f.ty)),
+        TypeDefKind::Tuple(tuple) => tuple.types.iter().any(has),
+        TypeDefKind::Variant(variant) => {
+            variant.cases.iter().any(|c| c.ty.as_ref().is_some_and(has))
+        }
+        TypeDefKind::Result(result) => {
+            result.ok.as_ref().is_some_and(has) || result.err.as_ref().is_some_and(has)
+        }
+        _ => false,
+    }
 }
 
 /// Converts a JSON value to a wasmtime Val based on the expected WIT TypeDef.
-/// The `resolve` is needed to look up `Type::Id` references for nested types.
+/// The `resolve` is needed to look up `Type::Id` references for nested types,
+/// and `handles` to look up the values of handles.
 pub fn json_value_to_val_typedef(
     value: &Value,
     type_def: &TypeDef,
     resolve: &Resolve,
+    handles: &HandleTable,
 ) -> eyre::Result<Val> {
     match &type_def.kind {
-        TypeDefKind::Type(ty) => json_value_to_val(value, ty, resolve),
+        TypeDefKind::Type(ty) => json_value_to_val(value, ty, resolve, handles),
         TypeDefKind::Record(record) => {
             let Value::Object(map) = value else {
                 bail!("expected JSON object for record");
@@ -191,19 +284,31 @@ pub fn json_value_to_val_typedef(
                     let v = map
                         .get(&field.name)
                         .ok_or_else(|| eyre::eyre!("missing field '{}'", field.name))?;
-                    let val = json_value_to_val(v, &field.ty, resolve)?;
+                    let val = json_value_to_val(v, &field.ty, resolve, handles)
+                        .map_err(at(format!(".{}", field.name)))?;
                     Ok((field.name.clone(), val))
                 })
                 .collect::<eyre::Result<Vec<_>>>()?;
             Ok(Val::Record(fields))
         }
         TypeDefKind::List(ty) => {
+            if let Value::String(encoded) = value
+                && is_u8(ty, resolve)
+            {
+                let bytes = BASE64
+                    .decode(encoded)
+                    .map_err(|e| eyre!("expected base64 for list<u8>: {e}"))?;
+                return Ok(Val::List(bytes.into_iter().map(Val::U8).collect()));
+            }
             let Value::Array(arr) = value else {
                 bail!("expected JSON array for list");
             };
             let vals = arr
                 .iter()
-                .map(|v| json_value_to_val(v, ty, resolve))
+                .enumerate()
+                .map(|(i, v)| {
+                    json_value_to_val(v, ty, resolve, handles).map_err(at(format!("[{i}]")))
+                })
                 .collect::<eyre::Result<Vec<_>>>()?;
             Ok(Val::List(vals))
         }
@@ -221,7 +326,10 @@ pub fn json_value_to_val_typedef(
             let vals = arr
                 .iter()
                 .zip(tuple.types.iter())
-                .map(|(v, ty)| json_value_to_val(v, ty, resolve))
+                .enumerate()
+                .map(|(i, (v, ty))| {
+                    json_value_to_val(v, ty, resolve, handles).map_err(at(format!("[{i}]")))
+                })
                 .collect::<eyre::Result<Vec<_>>>()?;
             Ok(Val::Tuple(vals))
         }
@@ -239,7 +347,7 @@ pub fn json_value_to_val_typedef(
             if value.is_null() {
                 return Ok(Val::Option(None));
             }
-            let inner = json_value_to_val(value, ty, resolve)?;
+            let inner = json_value_to_val(value, ty, resolve, handles)?;
             Ok(Val::Option(Some(Box::new(inner))))
         }
         TypeDefKind::Flags(flags) => {
@@ -248,30 +356,33 @@ pub fn json_value_to_val_typedef(
             };
             let names = arr
                 .iter()
-                .map(|v| match v {
+                .enumerate()
+                .map(|(i, v)| match v {
                     Value::String(s) => {
                         if !flags.flags.iter().any(|f| f.name == *s) {
-                            bail!("invalid flag '{s}'");
+                            return Err(at(format!("[{i}]"))(eyre!("invalid flag '{s}'")));
                         }
                         Ok(s.clone())
                     }
-                    _ => bail!("expected string for flag name"),
+                    _ => Err(at(format!("[{i}]"))(eyre!("expected string for flag name"))),
                 })
                 .collect::<eyre::Result<Vec<_>>>()?;
             Ok(Val::Flags(names))
         }
         TypeDefKind::Result(result_) => {
-            if let Value::Object(map) = value {
-                if let Some(err_val) = map.get("error") {
-                    let inner = match &result_.err {
-                        Some(ty) => Some(Box::new(json_value_to_val(err_val, ty, resolve)?)),
-                        None => None,
-                    };
-                    return Ok(Val::Result(Err(inner)));
-                }
+            if let Value::Object(map) = value
+                && let Some(err_val) = map.get("error")
+            {
+                let inner = match &result_.err {
+                    Some(ty) => Some(Box::new(
+                        json_value_to_val(err_val, ty, resolve, handles).map_err(at(".error"))?,
+                    )),
+                    None => None,
+                };
+                return Ok(Val::Result(Err(inner)));
             }
             let inner = match &result_.ok {
-                Some(ty) => Some(Box::new(json_value_to_val(value, ty, resolve)?)),
+                Some(ty) => Some(Box::new(json_value_to_val(value, ty, resolve, handles)?)),
                 None => None,
             };
             Ok(Val::Result(Ok(inner)))
@@ -294,12 +405,303 @@ pub fn json_value_to_val_typedef(
                     let v = map
                         .get("value")
                         .ok_or_else(|| eyre::eyre!("missing 'value' for variant case '{tag}'"))?;
-                    Some(Box::new(json_value_to_val(v, ty, resolve)?))
+                    Some(Box::new(
+                        json_value_to_val(v, ty, resolve, handles).map_err(at(".value"))?,
+                    ))
                 }
                 None => None,
             };
             Ok(Val::Variant(tag.to_string(), payload))
         }
+        TypeDefKind::Handle(Handle::Own(_)) => take_handle(value, handles, "resource", true),
+        TypeDefKind::Handle(Handle::Borrow(_)) => take_handle(value, handles, "resource", false),
+        TypeDefKind::Future(_) | TypeDefKind::Stream(_) => {
+            bail!("futures and streams cannot be passed as JSON")
+        }
         _ => bail!("unsupported type: {:#?}", type_def.kind),
     }
 }
+
+/// Looks up the value of a `{"handle": <id>}` (or bare ID) in `handles`,
+/// removing it from the table if it is moved into the callee.
+fn take_handle(
+    value: &Value,
+    handles: &HandleTable,
+    kind: &str,
+    is_moved: bool,
+) -> eyre::Result<Val> {
+    let id = match value {
+        Value::Object(map) => map.get("handle").and_then(Value::as_u64),
+        other => other.as_u64(),
+    };
+    let Some(id) = id else {
+        bail!("expected {{\"handle\": <id>}} for {kind}");
+    };
+    let val = handles
+        .get(id)
+        .ok_or_else(|| eyre::eyre!("unknown handle {id}"))?;
+    let is_kind = matches!(
+        (kind, &val),
+        ("resource", Val::Resource(_)) | ("error-context", Val::ErrorContext(_))
+    );
+    if !is_kind {
+        bail!("handle {id} is not a {kind}");
+    }
+    if is_moved {
+        handles.remove(id);
+    }
+    Ok(val)
+}
+
+/// Error of a value nested in the JSON of an argument,
+/// with the path of the value, e.g. `.items[2].name`.
+#[derive(Debug)]
+pub struct ValueError {
+    pub path: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for ValueError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "at ${}: {}", self.path, self.message)
+    }
+}
+
+impl std::error::Error for ValueError {}
+
+/// Prefixes the path of an error with the segment of the value it is in.
+fn at(segment: impl std::fmt::Display) -> impl FnOnce(eyre::Report) -> eyre::Report {
+    move |e| match e.downcast::<ValueError>() {
+        Ok(mut e) => {
+            e.path = format!("{segment}{}", e.path);
+            eyre::Report::new(e)
+        }
+        Err(e) => eyre::Report::new(ValueError {
+            path: segment.to_string(),
+            message: format!("{e:#}"),
+        }),
+    }
+}
+
+/// Converts the JSON args of a call to the values of the params of the
+/// function, with errors naming the param and the path within it that
+/// failed, e.g. `invalid argument at 'user.tags[1]': expected string`.
+pub fn json_args_to_vals(
+    args: &[Value],
+    params: &[(String, TypeDef)],
+    resolve: &Resolve,
+    handles: &HandleTable,
+) -> eyre::Result<Vec<Val>> {
+    if args.len() != params.len() {
+        bail!("expected {} argument(s), got {}", params.len(), args.len());
+    }
+    args.iter()
+        .zip(params)
+        .map(|(arg, (name, type_def))| {
+            json_value_to_val_typedef(arg, type_def, resolve, handles).map_err(|e| match e
+                .downcast::<ValueError>()
+            {
+                Ok(e) => eyre!("invalid argument at '{name}{}': {}", e.path, e.message),
+                Err(e) => eyre!("invalid argument '{name}': {e:#}"),
+            })
+        })
+        .collect()
+}
+
+/// Orders named JSON args by the params of the function.
+/// Missing options are passed as `null`.
+pub fn named_json_args(
+    args: &serde_json::Map<String, Value>,
+    params: &[(String, TypeDef)],
+) -> eyre::Result<Vec<Value>> {
+    if let Some(unknown) = args
+        .keys()
+        .find(|k| !params.iter().any(|(name, _)| name == *k))
+    {
+        let names: Vec<&str> = params.iter().map(|(name, _)| name.as_str()).collect();
+        bail!("unknown argument '{unknown}', expected one of: {names:?}");
+    }
+    params
+        .iter()
+        .map(|(name, type_def)| match args.get(name) {
+            Some(arg) => Ok(arg.clone()),
+            None if is_optional_param(type_def) => Ok(Value::Null),
+            None => bail!("missing argument '{name}'"),
+        })
+        .collect()
+}
+
+/// Converts a command line argument to JSON for the param type `type_def`.
+/// Arguments are JSON, except that strings, chars and enums, and options
+/// of them, may be given without quotes. Other arguments that are not
+/// valid JSON, such as base64 for `list<u8>`, are taken as strings.
+pub fn cli_arg_to_json(arg: &str, type_def: &TypeDef, resolve: &Resolve) -> Value {
+    match &type_def.kind {
+        TypeDefKind::Type(ty) => cli_arg_to_json_type(arg, ty, resolve),
+        TypeDefKind::Enum(_) => unquoted_string(arg),
+        TypeDefKind::Option(_) if arg == "null" => Value::Null,
+        TypeDefKind::Option(ty) => cli_arg_to_json_type(arg, ty, resolve),
+        _ => serde_json::from_str(arg).unwrap_or_else(|_| Value::String(arg.to_owned())),
+    }
+}
+
+fn cli_arg_to_json_type(arg: &str, ty: &Type, resolve: &Resolve) -> Value {
+    match ty {
+        Type::String | Type::Char => unquoted_string(arg),
+        Type::Id(id) => match resolve.types.get(*id) {
+            Some(type_def) => cli_arg_to_json(arg, type_def, resolve),
+            None => Value::String(arg.to_owned()),
+        },
+        _ => serde_json::from_str(arg).unwrap_or_else(|_| Value::String(arg.to_owned())),
+    }
+}
+
+/// Takes a JSON string literal as its contents and anything else as is.
+fn unquoted_string(arg: &str) -> Value {
+    match serde_json::from_str::<String>(arg) {
+        Ok(s) => Value::String(s),
+        Err(_) => Value::String(arg.to_owned()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wit_parser::TypeOwner;
+
+    fn from_json(value: Value, ty: Type) -> eyre::Result<Val> {
+        json_value_to_val(&value, &ty, &Resolve::default(), &HandleTable::default())
+    }
+
+    #[test]
+    fn test_integers_are_range_checked() {
+        assert_eq!(from_json(json!(255), Type::U8).unwrap(), Val::U8(255));
+        assert!(from_json(json!(256), Type::U8).is_err());
+        assert!(from_json(json!(-1), Type::U32).is_err());
+        assert_eq!(
+            from_json(json!("18446744073709551615"), Type::U64).unwrap(),
+            Val::U64(u64::MAX)
+        );
+        assert!(from_json(json!(1.5), Type::S32).is_err());
+    }
+
+    #[test]
+    fn test_non_finite_floats() {
+        let handles = HandleTable::default();
+        assert_eq!(
+            Val::Float64(f64::INFINITY).into_json_value(&handles),
+            "Infinity"
+        );
+        assert_eq!(Val::Float32(f32::NAN).into_json_value(&handles), "NaN");
+        let val = from_json(json!("-Infinity"), Type::F64).unwrap();
+        assert_eq!(val, Val::Float64(f64::NEG_INFINITY));
+        assert!(from_json(json!(1e300), Type::F32).is_err());
+    }
+
+    #[test]
+    fn test_int64_as_string() {
+        let options = JsonOptions {
+            is_int64_as_string: true,
+        };
+        let handles = HandleTable::default();
+        let value = Val::Tuple(vec![Val::S64(-9007199254740993), Val::U32(7)])
+            .into_json_value_with(&handles, options);
+        assert_eq!(value, json!(["-9007199254740993", 7]));
+    }
+
+    #[test]
+    fn test_bytes_are_base64() {
+        let list = TypeDef {
+            name: None,
+            kind: TypeDefKind::List(Type::U8),
+            owner: TypeOwner::None,
+            docs: Default::default(),
+            stability: Default::default(),
+        };
+        let parse = |value: Value| {
+            json_value_to_val_typedef(&value, &list, &Resolve::default(), &HandleTable::default())
+        };
+        let bytes = Val::List(vec![Val::U8(0), Val::U8(255)]);
+        assert_eq!(parse(json!("AP8=")).unwrap(), bytes);
+        assert_eq!(parse(json!([0, 255])).unwrap(), bytes);
+        assert!(parse(json!("not base64!")).is_err());
+        assert_eq!(bytes.into_json_value(&HandleTable::default()), "AP8=");
+    }
+
+    /// Returns the resolve and params of `f` in a WIT interface.
+    fn params_of(wit: &str) -> (Resolve, Vec<(String, TypeDef)>) {
+        let mut resolve = Resolve::default();
+        resolve.push_str("test.wit", wit).unwrap();
+        let interface = resolve.interfaces.iter().next().unwrap().1;
+        let params = interface.functions["f"]
+            .params
+            .iter()
+            .map(|(name, ty)| {
+                let kind = match ty {
+                    Type::Id(id) => resolve.types[*id].kind.clone(),
+                    ty => TypeDefKind::Type(*ty),
+                };
+                let type_def = TypeDef {
+                    name: None,
+                    kind,
+                    owner: TypeOwner::None,
+                    docs: Default::default(),
+                    stability: Default::default(),
+                };
+                (name.clone(), type_def)
+            })
+            .collect();
+        (resolve, params)
+    }
+
+    #[test]
+    fn test_arg_errors_point_at_the_failing_value() {
+        let (resolve, params) = params_of(
+            "package a:b; interface i { record user { tags: list<string>, age: u8 } \
+             f: func(user: user, n: option<u32>); }",
+        );
+        let handles = HandleTable::default();
+        let parse = |args: Value| {
+            let args = named_json_args(args.as_object().unwrap(), &params)?;
+            json_args_to_vals(&args, &params, &resolve, &handles)
+        };
+        let err = parse(json!({"user": {"tags": ["a", 1], "age": 3}})).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid argument at 'user.tags[1]': expected string"
+        );
+        let err = parse(json!({"user": {"tags": [], "age": 300}})).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid argument at 'user.age': 300 is out of range for u8"
+        );
+        let err = parse(json!({"n": 1})).unwrap_err();
+        assert_eq!(err.to_string(), "missing argument 'user'");
+        let vals = parse(json!({"user": {"tags": [], "age": 3}})).unwrap();
+        assert_eq!(vals[1], Val::Option(None));
+    }
+
+    #[test]
+    fn test_cli_args() {
+        let (resolve, params) = params_of(
+            "package a:b; interface i { enum color { red } \
+             f: func(s: string, c: color, o: option<string>, n: u32, l: list<u8>); }",
+        );
+        let args: Vec<Value> = ["hi", "\"red\"", "null", "5", "AP8="]
+            .iter()
+            .zip(&params)
+            .map(|(arg, (_, type_def))| cli_arg_to_json(arg, type_def, &resolve))
+            .collect();
+        assert_eq!(
+            args,
+            vec![
+                json!("hi"),
+                json!("red"),
+                json!(null),
+                json!(5),
+                json!("AP8=")
+            ]
+        );
+        assert_eq!(cli_arg_to_json("5", &params[0].1, &resolve), json!("5"));
+    }
+}
@@ -2,20 +2,32 @@ use crate::component::Component;
 use crate::component::binary::ComponentBinary;
 use crate::component::function_interface::ComponentFunctionInterface;
 use crate::component::function_name::ComponentFunctionName;
+use crate::environment::interceptors::Interceptors;
+use crate::environment::mcp_servers::McpServers;
+use crate::environment::mocks::Mocks;
+use crate::environment::wiring::InterfaceWiring;
+use crate::runtime::approval::CallApprovals;
+use crate::runtime::call_trace::{ActiveSpan, SpanKind};
 use crate::runtime::cron::CronManager;
 use crate::runtime::env::{HostEnv, HostEnvRuntimeData, create_linker, create_store};
-use crate::runtime::link_components::{register_component_stubs, resolve_component_stubs};
-use crate::runtime::output::ComponentOutput;
+use crate::runtime::link_components::{
+    ComponentStubs, register_component_stubs, resolve_component_stubs,
+};
+use crate::runtime::mcp_client::connect_mcp_servers;
+use crate::runtime::output::{ComponentOutput, OutputEvent};
 use crate::runtime::ws::WsManager;
 use eyre::{Context, eyre};
+use futures::future::try_join_all;
 use log::trace;
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
 use std::fmt::{Debug, Formatter};
-use std::io::Write;
+use std::io::{IsTerminal, Write};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
-use tokio::sync::mpsc;
+use std::time::Instant;
+use tokio::sync::{Semaphore, mpsc};
 use uuid::Uuid;
 use wasmtime::component::*;
 use wasmtime::{
@@ -47,6 +59,7 @@ pub struct ComponentRuntimeEngine {
     pub(super) store: SharedStore,
     pub(super) instances: Vec<ComponentRuntimeInstance>,
     pub(super) linker: Linker<StoreState>,
+    pub(super) stubs: ComponentStubs,
     pub(super) compiled_components: Vec<CompiledComponentEntry>,
 }
 
@@ -69,15 +82,28 @@ pub struct ComponentRuntimeInstance {
 pub type StoreState = HostEnv;
 
 impl ComponentRuntimeEngine {
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         mut components: Vec<ComponentBinary>,
         app_id: Uuid,
-        component_output_tx: mpsc::Sender<ComponentOutput>,
+        component_output_tx: mpsc::Sender<OutputEvent>,
         env_vars: &HashMap<String, String>,
         preopened_dirs: &[PathBuf],
+        wiring: InterfaceWiring,
+        interceptors: Interceptors,
+        mcp_servers: McpServers,
+        mocks: Mocks,
     ) -> eyre::Result<Self> {
         // Sort for deterministic instantiation order (source is a HashMap).
         components.sort_by_key(|c| c.component().to_string());
+        wiring.validate(&components)?;
+        interceptors.validate(&components)?;
+        mcp_servers.validate(&components)?;
+        let mcp_components = connect_mcp_servers(&mcp_servers, env_vars)
+            .await?
+            .into_iter()
+            .map(Arc::new)
+            .collect();
         let engine = &ENGINE;
         let last_component = Arc::new(Mutex::new(None));
         let mut store = create_store(
@@ -94,18 +120,18 @@ impl ComponentRuntimeEngine {
         // Each stub holds an Arc<OnceLock<Func>> that gets filled after
         // instantiation. This allows components to import each other's
         // interfaces regardless of instantiation order (including cycles).
-        let func_slots = register_component_stubs(&components, &mut linker)?;
-        for interface in components.into_iter() {
+        // Consumers with explicitly wired imports get their own linker,
+        // and imports of mocked interfaces are linked to their mocks.
+        let stubs = register_component_stubs(&components, &wiring, &mocks, &mut linker)?;
+        let compiled = compile_components(&components, engine).await?;
+        for (interface, component) in components.into_iter().zip(compiled) {
             trace!("@ interface {}", interface.component().id());
-            print!("compiling {}...", interface.component());
-            std::io::stdout().flush().ok();
-            let component = interface.fetch_compiled_component(engine).await?;
-            println!(" done.");
             compiled_components.push(CompiledComponentEntry {
                 component: component.clone(),
                 component_binary: interface.clone(),
             });
-            let instance = linker
+            let instance = stubs
+                .linker_for(&interface.component().id(), &linker)
                 .instantiate_async(&mut store, &component)
                 .await
                 .map_err(|e| eyre!("{e:#?}"))
@@ -113,7 +139,7 @@ impl ComponentRuntimeEngine {
                     format!("failed to initiate component: {}", interface.component())
                 })?;
             // Resolve stubs: fill the OnceLock slots with real Func handles.
-            resolve_component_stubs(&interface, &instance, &mut store, &func_slots)?;
+            resolve_component_stubs(&interface, &instance, &mut store, &stubs)?;
             let instance = ComponentRuntimeInstance {
                 component_interface: interface,
                 app_id,
@@ -137,6 +163,11 @@ impl ComponentRuntimeEngine {
             preopened_dirs: preopened_dirs.to_vec(),
             ws_manager: Some(Arc::clone(&ws_manager)),
             cron_manager: Some(Arc::clone(&cron_manager)),
+            wiring,
+            interceptors,
+            mocks,
+            mcp_components,
+            call_approvals: CallApprovals::default(),
         };
         store.data_mut().runtime_data = Some(runtime_data.clone());
         let store = Arc::new(tokio::sync::Mutex::new(store));
@@ -146,6 +177,7 @@ impl ComponentRuntimeEngine {
             store,
             instances,
             linker,
+            stubs,
             compiled_components,
         })
     }
@@ -167,6 +199,23 @@ impl ComponentRuntimeEngine {
         Ok(output_opt)
     }
 
+    /// Like [`Self::call`], sending the output events of the call
+    /// to `output_tx` instead of the output channel of the store.
+    pub async fn call_with_output(
+        &mut self,
+        function_interface: ComponentFunctionInterface,
+        inputs: &[Val],
+        output_tx: mpsc::Sender<OutputEvent>,
+    ) -> eyre::Result<Option<ComponentOutput>> {
+        let previous_tx = std::mem::replace(
+            &mut self.store.lock().await.data_mut().component_output_tx,
+            output_tx,
+        );
+        let result = self.call(function_interface, inputs).await;
+        self.store.lock().await.data_mut().component_output_tx = previous_tx;
+        result
+    }
+
     /// Makes a function call to a WASM component.
     async fn call_raw(
         &mut self,
@@ -193,7 +242,9 @@ impl ComponentRuntimeEngine {
         let mut store = self.store.lock().await;
         let func = function.get_func(&mut *store, &instance.instance)?;
         let component = function.component.clone();
-        call_wasm_component_function(
+        let span = ActiveSpan::start(SpanKind::Call, component.id(), &function.name, None);
+        store.data_mut().span = Some(span.context());
+        let result = call_wasm_component_function(
             &func,
             &function.name,
             store.as_context_mut(),
@@ -201,29 +252,126 @@ impl ComponentRuntimeEngine {
             results,
             component,
         )
-        .await?;
-        Ok(())
+        .await;
+        store.data_mut().span = None;
+        span.end(&result);
+        result
+    }
+}
+
+/// Compiles all components concurrently, bounded by the number of
+/// available cores, and returns them in the order of `components`.
+/// Unlike instantiation, compilation is independent per component.
+async fn compile_components(
+    components: &[ComponentBinary],
+    engine: &Engine,
+) -> eyre::Result<Vec<wasmtime::component::Component>> {
+    let concurrency = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+    let semaphore = Arc::new(Semaphore::new(concurrency));
+    let progress = Arc::new(CompileProgress::new(components.len()));
+    let handles: Vec<_> = components
+        .iter()
+        .cloned()
+        .map(|binary| {
+            let semaphore = Arc::clone(&semaphore);
+            let progress = Arc::clone(&progress);
+            let engine = engine.clone();
+            tokio::spawn(async move {
+                let _permit = semaphore.acquire_owned().await?;
+                let component = binary
+                    .fetch_compiled_component(&engine)
+                    .await
+                    .with_context(|| {
+                        format!("failed to compile component: {}", binary.component())
+                    })?;
+                progress.advance(binary.component());
+                Ok::<_, eyre::Report>(component)
+            })
+        })
+        .collect();
+    let compiled = try_join_all(handles)
+        .await
+        .map_err(|e| eyre!(e))
+        .and_then(|results| results.into_iter().collect::<eyre::Result<Vec<_>>>());
+    progress.finish(compiled.is_ok());
+    compiled
+}
+
+/// Progress display for component compilation.
+/// Updates a single line on terminals, and otherwise
+/// prints one line per compiled component.
+struct CompileProgress {
+    total: usize,
+    done: AtomicUsize,
+    started_at: Instant,
+    is_terminal: bool,
+}
+
+impl CompileProgress {
+    fn new(total: usize) -> Self {
+        let progress = Self {
+            total,
+            done: AtomicUsize::new(0),
+            started_at: Instant::now(),
+            is_terminal: std::io::stdout().is_terminal(),
+        };
+        if progress.is_terminal && total > 0 {
+            print!("compiling components [0/{total}]");
+            std::io::stdout().flush().ok();
+        }
+        progress
+    }
+
+    fn advance(&self, component: &Component) {
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+        match self.is_terminal {
+            true => {
+                print!(
+                    "\r\x1b[2Kcompiling components [{done}/{}] {component}",
+                    self.total
+                );
+                std::io::stdout().flush().ok();
+            }
+            false => println!("compiled {component} [{done}/{}]", self.total),
+        }
+    }
+
+    fn finish(&self, is_ok: bool) {
+        if self.total == 0 {
+            return;
+        }
+        if self.is_terminal {
+            print!("\r\x1b[2K");
+            std::io::stdout().flush().ok();
+        }
+        if !is_ok {
+            return;
+        }
+        println!(
+            "compiled {} component(s) in {:.2}s",
+            self.total,
+            self.started_at.elapsed().as_secs_f64()
+        );
     }
 }
 
 pub(super) async fn call_wasm_component_function<'a>(
     func: &Func,
-    _func_name: &ComponentFunctionName,
+    func_name: &ComponentFunctionName,
     mut store: StoreContextMut<'a, HostEnv>,
     args: &[Val],
     results: &mut [Val],
     component: Component,
 ) -> eyre::Result<()> {
     let component_id = component.id().clone();
-    trace!("calling function' from component '{}'", component.id());
+    trace!("calling function '{func_name}' from component '{component_id}'");
     set_last_component(component, &mut store);
     func.call_async(&mut store, args, results)
         .await
         .map_err(|e| {
-            eyre!(
-                "failed to call func' from component '{}': {e:#?}",
-                component_id,
-            )
+            eyre!("failed to call function '{func_name}' from component '{component_id}': {e:#?}")
         })?;
     func.post_return_async(&mut store)
         .await
@@ -233,21 +381,18 @@ pub(super) async fn call_wasm_component_function<'a>(
 
 pub async fn call_wasm_component_function_concurrent(
     func: &Func,
-    _func_name: &ComponentFunctionName,
+    func_name: &ComponentFunctionName,
     accessor: &Accessor<HostEnv>,
     args: &[Val],
     results: &mut [Val],
     component: Component,
 ) -> eyre::Result<()> {
     let component_id = component.id().clone();
-    trace!("calling function' from component '{}'", component.id());
+    trace!("calling function '{func_name}' from component '{component_id}'");
     func.call_concurrent(accessor, args, results)
         .await
         .map_err(|e| {
-            eyre!(
-                "failed to call func' from component '{}': {e:#?}",
-                component_id,
-            )
+            eyre!("failed to call function '{func_name}' from component '{component_id}': {e:#?}")
         })?;
     Ok(())
 }
@@ -1,4 +1,5 @@
 use crate::component::binary::ComponentBinary;
+use crate::runtime::call_trace::{ActiveSpan, SpanKind};
 use crate::runtime::wasm_instance::SharedStore;
 use eyre::eyre;
 use futures::stream::{SplitSink, SplitStream};
@@ -131,6 +132,10 @@ impl WsManager {
         !self.connections.read().await.is_empty()
     }
 
+    pub async fn connection_count(&self) -> usize {
+        self.connections.read().await.len()
+    }
+
     pub async fn close_all(&self) {
         let ids: Vec<ConnectionId> = self.connections.read().await.keys().copied().collect();
         for id in ids {
@@ -300,24 +305,47 @@ async fn dispatch_export<Params>(
 ) where
     Params: ComponentNamedList + Lower + Send + Sync + 'static,
 {
+    let span = ActiveSpan::start(
+        SpanKind::Ws,
+        owner_binary.component().id(),
+        format!("incoming-handler/{func_name}"),
+        None,
+    );
+    let span_context = span.context();
     let result = dispatch_callback(owner_binary, manager, |store, instance| {
         Box::pin(async move {
-            let func: TypedFunc<Params, ()> = get_export_func(store, instance, func_name)?;
-            func.call_async(&mut *store, params)
-                .await
-                .map_err(|e| eyre!("{e:#}"))?;
-            func.post_return_async(&mut *store)
-                .await
-                .map_err(|e| eyre!("{e:#}"))?;
-            Ok(())
+            store.data_mut().span = Some(span_context);
+            let result = call_export(store, instance, func_name, params).await;
+            store.data_mut().span = None;
+            result
         })
     })
     .await;
+    span.end(&result);
     if let Err(e) = result {
         error!("ws {func_name} dispatch failed: {e:#}");
     }
 }
 
+async fn call_export<Params>(
+    store: &mut wasmtime::Store<crate::runtime::env::HostEnv>,
+    instance: &wasmtime::component::Instance,
+    func_name: &str,
+    params: Params,
+) -> eyre::Result<()>
+where
+    Params: ComponentNamedList + Lower + Send + Sync + 'static,
+{
+    let func: TypedFunc<Params, ()> = get_export_func(store, instance, func_name)?;
+    func.call_async(&mut *store, params)
+        .await
+        .map_err(|e| eyre!("{e:#}"))?;
+    func.post_return_async(&mut *store)
+        .await
+        .map_err(|e| eyre!("{e:#}"))?;
+    Ok(())
+}
+
 /// Lock the shared store, find the existing instance for the owning component,
 /// and call a callback function.
 /// This preserves component state across calls.